@@ -0,0 +1,713 @@
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    commands::encode::{
+        EncodeOptions, build_encoder, check_readability, is_customised, text_payload,
+        warn_low_contrast,
+    },
+    qr::{encoder::QrEncoder, output::OutputFormat, symbol::Symbol},
+    utils::error::{Result, RqrError},
+};
+
+/// Options for `rqr encode --batch`
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// CSV (with a header row) or JSON Lines file with one code per row
+    pub input: PathBuf,
+    /// Template for the encoded content, e.g. `{url}`
+    pub content: String,
+    /// Where the manifest is written, next to the outputs when unset
+    pub manifest: Option<PathBuf>,
+}
+
+/// A `{column}` template for batch content and file names
+///
+/// `{{` and `}}` stand for literal braces.
+///
+/// # Examples
+/// ```rust
+/// use std::collections::HashMap;
+///
+/// use rqr::commands::batch::Template;
+///
+/// let template: Template = "https://example.com/p/{sku}?ref={{qr}}".parse()?;
+/// let row = HashMap::from([("sku".to_string(), "A-42".to_string())]);
+/// assert_eq!(template.render(&row)?, "https://example.com/p/A-42?ref={qr}");
+/// # Ok::<(), rqr::utils::error::RqrError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<TemplatePart>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplatePart {
+    Literal(String),
+    Column(String),
+}
+
+impl FromStr for Template {
+    type Err = RqrError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid =
+            |reason: &str| RqrError::InvalidInput(format!("Invalid template '{}': {}", s, reason));
+
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some('{') | None => return Err(invalid("unclosed '{'")),
+                            Some(c) => name.push(c),
+                        }
+                    }
+                    let name = name.trim();
+                    if name.is_empty() {
+                        return Err(invalid("empty column name"));
+                    }
+                    if !literal.is_empty() {
+                        parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(TemplatePart::Column(name.to_string()));
+                }
+                '}' => return Err(invalid("unmatched '}', use '}}' for a literal brace")),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Literal(literal));
+        }
+
+        Ok(Self { parts })
+    }
+}
+
+impl Template {
+    /// Fill in the template from a row
+    ///
+    /// # Arguments
+    /// * `row` - Column values by name
+    ///
+    /// # Returns
+    /// Returns the rendered text, or an error naming a missing column
+    pub fn render(&self, row: &HashMap<String, String>) -> Result<String> {
+        self.render_with(row, |value| value.to_string())
+    }
+
+    /// Fill in the template, passing each column value through `escape`
+    fn render_with(
+        &self,
+        row: &HashMap<String, String>,
+        escape: impl Fn(&str) -> String,
+    ) -> Result<String> {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                TemplatePart::Literal(text) => out.push_str(text),
+                TemplatePart::Column(name) => {
+                    let value = row.get(name).ok_or_else(|| {
+                        RqrError::InvalidInput(format!("Row has no column '{}'", name))
+                    })?;
+                    out.push_str(&escape(value));
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// The text before the first column, which is the same for every row
+    fn static_prefix(&self) -> &str {
+        match self.parts.first() {
+            Some(TemplatePart::Literal(text)) => text,
+            _ => "",
+        }
+    }
+}
+
+/// Make a column value safe to use inside a file name
+///
+/// Path separators and characters that are invalid in file names on common
+/// platforms become `_`, so a value can never escape the output directory.
+fn file_name_safe(value: &str) -> String {
+    let safe: String = value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    match safe.as_str() {
+        "" | "." | ".." => "_".to_string(),
+        _ => safe,
+    }
+}
+
+/// Read the rows of a CSV or JSON Lines batch file
+///
+/// CSV files need a header row naming the columns. In JSON Lines files every
+/// non-empty line is an object; strings are used as-is, other values in
+/// their JSON form and `null` as an empty string.
+///
+/// # Arguments
+/// * `path` - A `.csv`, `.jsonl` or `.ndjson` file
+///
+/// # Returns
+/// Returns the rows in file order, or an error if the file cannot be parsed
+pub fn read_rows(path: &Path) -> Result<Vec<HashMap<String, String>>> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+    let file = File::open(path).map_err(|e| {
+        RqrError::InvalidInput(format!("Cannot read batch file {}: {}", path.display(), e))
+    })?;
+
+    match extension.as_deref() {
+        Some("csv") => {
+            let mut reader = csv::Reader::from_reader(file);
+            let headers = reader.headers().map_err(csv_error)?.clone();
+            reader
+                .records()
+                .map(|record| {
+                    let record = record.map_err(csv_error)?;
+                    Ok(headers
+                        .iter()
+                        .zip(record.iter())
+                        .map(|(name, value)| (name.to_string(), value.to_string()))
+                        .collect())
+                })
+                .collect()
+        }
+        Some("jsonl") | Some("ndjson") => {
+            let mut rows = Vec::new();
+            for (i, line) in BufReader::new(file).lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let object: serde_json::Map<String, serde_json::Value> =
+                    serde_json::from_str(&line).map_err(|e| {
+                        RqrError::InvalidInput(format!("Invalid JSON on line {}: {}", i + 1, e))
+                    })?;
+                rows.push(
+                    object
+                        .into_iter()
+                        .map(|(name, value)| {
+                            let value = match value {
+                                serde_json::Value::String(s) => s,
+                                serde_json::Value::Null => String::new(),
+                                other => other.to_string(),
+                            };
+                            (name, value)
+                        })
+                        .collect(),
+                );
+            }
+            Ok(rows)
+        }
+        _ => Err(RqrError::UnsupportedFormat(format!(
+            "Batch file must be .csv, .jsonl or .ndjson: {}",
+            path.display()
+        ))),
+    }
+}
+
+/// Describe a CSV parsing error
+fn csv_error(e: csv::Error) -> RqrError {
+    RqrError::InvalidInput(format!("Invalid CSV: {}", e))
+}
+
+/// One line of the batch manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ManifestEntry {
+    /// Data row number in the batch file, starting at 1
+    pub row: usize,
+    /// `ok` or `error`
+    pub status: &'static str,
+    /// The file written for this row
+    pub output: String,
+    /// The encoded content
+    pub content: String,
+    /// Symbol version of the written code
+    pub version: Option<String>,
+    /// Error correction level of the written code
+    pub error_correction: Option<String>,
+    /// Mask pattern of the written code
+    pub mask: Option<u8>,
+    /// Why the row failed
+    pub error: Option<String>,
+}
+
+impl ManifestEntry {
+    fn failed(row: usize, output: String, content: String, error: &RqrError) -> Self {
+        Self {
+            row,
+            status: "error",
+            output,
+            content,
+            version: None,
+            error_correction: None,
+            mask: None,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// Run the encode command for every row of a batch file
+///
+/// All rows share the encoder settings in `options`; `options.output` is a
+/// template naming each file (column values are made file-name safe). Rows
+/// are rendered in parallel, and every row, written or failed, is recorded
+/// in a manifest (CSV, or JSON Lines when its extension is `.jsonl`).
+///
+/// # Arguments
+/// * `batch` - The batch file, content template and manifest path
+/// * `options` - Output template and shared rendering settings
+///
+/// # Returns
+/// Returns `Ok(())` when every row was written, or an error if the batch
+/// cannot be read or any row failed (the manifest is written either way)
+///
+/// # Examples
+/// ```rust,no_run
+/// use std::path::PathBuf;
+///
+/// use rqr::commands::{
+///     batch::{BatchOptions, run},
+///     encode::EncodeOptions,
+/// };
+///
+/// run(
+///     BatchOptions {
+///         input: PathBuf::from("items.csv"),
+///         content: "{url}".to_string(),
+///         manifest: None,
+///     },
+///     EncodeOptions {
+///         output: PathBuf::from("out/{sku}.png"),
+///         ..Default::default()
+///     },
+/// )?;
+/// # Ok::<(), rqr::utils::error::RqrError>(())
+/// ```
+pub fn run(batch: BatchOptions, options: EncodeOptions) -> Result<()> {
+    let content_template: Template = batch.content.parse()?;
+    let output_template: Template = options
+        .output
+        .to_str()
+        .ok_or_else(|| RqrError::InvalidInput("Output template is not valid UTF-8".to_string()))?
+        .parse()?;
+    let manifest_path = batch
+        .manifest
+        .clone()
+        .unwrap_or_else(|| default_manifest_path(&output_template));
+
+    let rows = read_rows(&batch.input)?;
+    let encoder = build_encoder(&options)?;
+    if is_customised(&options) {
+        warn_low_contrast(&options);
+    }
+
+    // Render names first so clashing outputs fail instead of overwriting;
+    // every clash is blamed on the first row to claim the output
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let jobs: Vec<_> = rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let number = i + 1;
+            let content = content_template.render(row);
+            let output = output_template.render_with(row, file_name_safe);
+            let clash = output
+                .as_ref()
+                .ok()
+                .and_then(|output| match seen.entry(output.clone()) {
+                    Entry::Occupied(first) => Some(*first.get()),
+                    Entry::Vacant(slot) => {
+                        slot.insert(number);
+                        None
+                    }
+                });
+            (number, content, output, clash)
+        })
+        .collect();
+
+    let entries: Vec<ManifestEntry> = jobs
+        .into_par_iter()
+        .map(|(row, content, output, clash)| {
+            let output = match output {
+                Ok(output) => output,
+                Err(e) => {
+                    return ManifestEntry::failed(
+                        row,
+                        String::new(),
+                        content.unwrap_or_default(),
+                        &e,
+                    );
+                }
+            };
+            let content = match content {
+                Ok(content) => content,
+                Err(e) => return ManifestEntry::failed(row, output, String::new(), &e),
+            };
+            if let Some(first) = clash {
+                let e = RqrError::InvalidInput(format!(
+                    "Output {} is also written by row {}",
+                    output, first
+                ));
+                return ManifestEntry::failed(row, output, content, &e);
+            }
+
+            match encode_row(&encoder, &options, &content, Path::new(&output)) {
+                Ok(qr_code) => ManifestEntry {
+                    row,
+                    status: "ok",
+                    version: Some(qr_code.version_label()),
+                    mask: Some(qr_code.mask()),
+                    error_correction: Some(qr_code.ec_summary()),
+                    output,
+                    content,
+                    error: None,
+                },
+                Err(e) => ManifestEntry::failed(row, output, content, &e),
+            }
+        })
+        .collect();
+
+    write_manifest(&manifest_path, &entries)?;
+
+    let failures: Vec<_> = entries.iter().filter(|e| e.status != "ok").collect();
+    for entry in &failures {
+        eprintln!(
+            "Row {}: {}",
+            entry.row,
+            entry.error.as_deref().unwrap_or_default()
+        );
+    }
+    println!(
+        "Encoded {} of {} rows from {}",
+        entries.len() - failures.len(),
+        entries.len(),
+        batch.input.display()
+    );
+    println!("Manifest written to: {}", manifest_path.display());
+
+    if !failures.is_empty() {
+        return Err(RqrError::EncodingError(format!(
+            "{} of {} rows failed",
+            failures.len(),
+            entries.len()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Encode one row and write it to `output`
+fn encode_row(
+    encoder: &QrEncoder,
+    options: &EncodeOptions,
+    content: &str,
+    output: &Path,
+) -> Result<Symbol> {
+    let payload = text_payload(content, options)?;
+    let qr_code = encoder.encode_bytes(&payload)?;
+    let format = OutputFormat::from_path(output)?;
+    if options.logo.is_some() && !format.is_raster() {
+        return Err(RqrError::UnsupportedFormat(
+            "Logo overlay is only supported for raster output".to_string(),
+        ));
+    }
+    if is_customised(options) {
+        check_readability(encoder, &qr_code, &payload, options)?;
+    }
+
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    match format {
+        OutputFormat::Svg => encoder.save_to_svg(&qr_code, output)?,
+        OutputFormat::Pdf => encoder.save_to_pdf(&qr_code, output)?,
+        _ => encoder.save_to_file(&qr_code, output)?,
+    }
+
+    Ok(qr_code)
+}
+
+/// `manifest.csv` in the directory every output is written under
+fn default_manifest_path(output: &Template) -> PathBuf {
+    let prefix = output.static_prefix();
+    let dir = match prefix.rfind(['/', '\\']) {
+        Some(i) => &prefix[..=i],
+        None => "",
+    };
+    Path::new(dir).join("manifest.csv")
+}
+
+/// Write the manifest as JSON Lines for `.jsonl`/`.ndjson`, CSV otherwise
+fn write_manifest(path: &Path, entries: &[ManifestEntry]) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("jsonl") || ext.eq_ignore_ascii_case("ndjson"));
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    if json {
+        for entry in entries {
+            serde_json::to_writer(&mut writer, entry).map_err(std::io::Error::from)?;
+            writeln!(writer)?;
+        }
+    } else {
+        let mut csv = csv::Writer::from_writer(writer);
+        for entry in entries {
+            csv.serialize(entry).map_err(csv_error)?;
+        }
+        csv.flush()?;
+        return Ok(());
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{qr::decoder::QrDecoder, utils::test_utils::temp_dir};
+
+    fn row(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_template_render() {
+        let template: Template = "{ sku }-{name}.png".parse().unwrap();
+        assert_eq!(
+            template
+                .render(&row(&[("sku", "A1"), ("name", "Bolt")]))
+                .unwrap(),
+            "A1-Bolt.png"
+        );
+        assert!(template.render(&row(&[("sku", "A1")])).is_err());
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_template_escapes_and_errors() {
+        let template: Template = "{{literal}}".parse().unwrap();
+        assert_eq!(template.render(&row(&[])).unwrap(), "{literal}");
+
+        assert!("{unclosed".parse::<Template>().is_err());
+        assert!("stray}".parse::<Template>().is_err());
+        assert!("{}".parse::<Template>().is_err());
+    }
+
+    #[test]
+    fn test_file_name_safe() {
+        assert_eq!(file_name_safe("A/B\\C:D"), "A_B_C_D");
+        assert_eq!(file_name_safe(".."), "_");
+        assert_eq!(file_name_safe(""), "_");
+        assert_eq!(file_name_safe("ok name.1"), "ok name.1");
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_default_manifest_path() {
+        let template: Template = "out/tags/{sku}.png".parse().unwrap();
+        assert_eq!(
+            default_manifest_path(&template),
+            PathBuf::from("out/tags/manifest.csv")
+        );
+        let template: Template = "{sku}.png".parse().unwrap();
+        assert_eq!(
+            default_manifest_path(&template),
+            PathBuf::from("manifest.csv")
+        );
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_read_rows_csv_and_jsonl() {
+        let temp = temp_dir();
+        let csv = temp.path().join("items.csv");
+        std::fs::write(
+            &csv,
+            "sku,url\nA1,https://a.example\n\"B,2\",https://b.example\n",
+        )
+        .unwrap();
+        let rows = read_rows(&csv).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1]["sku"], "B,2");
+
+        let jsonl = temp.path().join("items.jsonl");
+        std::fs::write(&jsonl, "{\"sku\": \"A1\", \"qty\": 3, \"note\": null}\n\n").unwrap();
+        let rows = read_rows(&jsonl).unwrap();
+        assert_eq!(
+            rows,
+            vec![row(&[("sku", "A1"), ("qty", "3"), ("note", "")])]
+        );
+
+        let txt = temp.path().join("items.txt");
+        std::fs::write(&txt, "").unwrap();
+        assert!(read_rows(&txt).is_err());
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_batch_run_writes_files_and_manifest() {
+        let temp = temp_dir();
+        let items = temp.path().join("items.csv");
+        std::fs::write(
+            &items,
+            "sku,url\nA1,https://a.example\nB/2,https://b.example\n",
+        )
+        .unwrap();
+
+        let output = temp.path().join("out").join("{sku}.png");
+        run(
+            BatchOptions {
+                input: items,
+                content: "{url}".to_string(),
+                manifest: None,
+            },
+            EncodeOptions {
+                output,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let decoder = QrDecoder::new();
+        let first = decoder
+            .decode_from_file(temp.path().join("out").join("A1.png"))
+            .unwrap();
+        assert_eq!(first, vec!["https://a.example"]);
+        assert!(temp.path().join("out").join("B_2.png").exists());
+
+        let manifest =
+            std::fs::read_to_string(temp.path().join("out").join("manifest.csv")).unwrap();
+        let mut lines = manifest.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            "row,status,output,content,version,error_correction,mask,error"
+        );
+        assert!(lines.next().unwrap().starts_with("1,ok,"));
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_batch_run_records_failures() {
+        let temp = temp_dir();
+        let items = temp.path().join("items.jsonl");
+        std::fs::write(
+            &items,
+            "{\"sku\": \"A1\", \"url\": \"one\"}\n{\"sku\": \"A1\", \"url\": \"two\"}\n{\"url\": \"three\"}\n",
+        )
+        .unwrap();
+        let manifest = temp.path().join("manifest.jsonl");
+
+        let result = run(
+            BatchOptions {
+                input: items,
+                content: "{url}".to_string(),
+                manifest: Some(manifest.clone()),
+            },
+            EncodeOptions {
+                output: temp.path().join("{sku}.svg"),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(result, Err(RqrError::EncodingError(ref m)) if m == "2 of 3 rows failed"));
+
+        let entries: Vec<serde_json::Value> = std::fs::read_to_string(&manifest)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries[0]["status"], "ok");
+        assert!(
+            entries[1]["error"]
+                .as_str()
+                .unwrap()
+                .contains("also written by row 1")
+        );
+        assert!(
+            entries[2]["error"]
+                .as_str()
+                .unwrap()
+                .contains("no column 'sku'")
+        );
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_batch_run_blames_first_row_for_every_clash() {
+        let temp = temp_dir();
+        let items = temp.path().join("items.csv");
+        std::fs::write(&items, "sku,url\nA,one\nA,two\nA,three\nA,four\n").unwrap();
+        let manifest = temp.path().join("manifest.jsonl");
+
+        let result = run(
+            BatchOptions {
+                input: items,
+                content: "{url}".to_string(),
+                manifest: Some(manifest.clone()),
+            },
+            EncodeOptions {
+                output: temp.path().join("{sku}.png"),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(result, Err(RqrError::EncodingError(ref m)) if m == "3 of 4 rows failed"));
+
+        let entries: Vec<serde_json::Value> = std::fs::read_to_string(&manifest)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries[0]["status"], "ok");
+        for entry in &entries[1..] {
+            assert!(
+                entry["error"]
+                    .as_str()
+                    .unwrap()
+                    .ends_with("also written by row 1")
+            );
+        }
+        let decoded = QrDecoder::new()
+            .decode_from_file(temp.path().join("A.png"))
+            .unwrap();
+        assert_eq!(decoded, vec!["one"]);
+    }
+}
@@ -1,57 +1,492 @@
-use std::path::PathBuf;
+use std::{
+    fmt,
+    io::{self, Write},
+    str::FromStr,
+};
+
+use serde::Serialize;
+
+use crate::{
+    qr::{
+        decoded::{DecodedQr, Point},
+        decoder::QrDecoder,
+        source::{ExpandError, Expanded, ImageSource},
+        symbol::Symbology,
+        wifi::{self, REDACTED, WifiNetwork},
+    },
+    utils::error::{Result, RqrError},
+};
+
+/// How `rqr decode` prints its results
+///
+/// Progress messages always go to stderr, so stdout only ever holds the
+/// results in the chosen format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DecodeFormat {
+    /// Human-readable listing of each payload
+    #[default]
+    Text,
+    /// Payload bytes only, exactly as encoded, separated by newlines
+    Raw,
+    /// A JSON array with one object per code
+    Json,
+    /// One JSON object per line and code
+    Ndjson,
+}
+
+impl FromStr for DecodeFormat {
+    type Err = RqrError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(DecodeFormat::Text),
+            "raw" => Ok(DecodeFormat::Raw),
+            "json" => Ok(DecodeFormat::Json),
+            "ndjson" => Ok(DecodeFormat::Ndjson),
+            _ => Err(RqrError::InvalidInput(format!(
+                "Decode format must be text, raw, json or ndjson, got '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for DecodeFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DecodeFormat::Text => "text",
+            DecodeFormat::Raw => "raw",
+            DecodeFormat::Json => "json",
+            DecodeFormat::Ndjson => "ndjson",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Axis-aligned box around the corners of a code, in image pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+struct BoundingBox {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+impl BoundingBox {
+    fn from_corners(corners: &[Point; 4]) -> Self {
+        let min_x = corners.iter().map(|p| p.x).min().unwrap_or(0);
+        let max_x = corners.iter().map(|p| p.x).max().unwrap_or(0);
+        let min_y = corners.iter().map(|p| p.y).min().unwrap_or(0);
+        let max_y = corners.iter().map(|p| p.y).max().unwrap_or(0);
+        Self {
+            x: min_x,
+            y: min_y,
+            width: max_x - min_x,
+            height: max_y - min_y,
+        }
+    }
+}
+
+/// The JSON shape of a WiFi network read from a `WIFI:` payload
+#[derive(Debug, Serialize)]
+struct WifiRecord {
+    ssid: String,
+    security: String,
+    password: Option<String>,
+    hidden: bool,
+}
+
+impl WifiRecord {
+    fn new(network: WifiNetwork, show_secrets: bool) -> Self {
+        Self {
+            ssid: network.ssid,
+            security: network.auth.to_string(),
+            password: network.password.map(|password| {
+                if show_secrets {
+                    password
+                } else {
+                    REDACTED.to_string()
+                }
+            }),
+            hidden: network.hidden,
+        }
+    }
+}
+
+/// The JSON shape of one decoded code
+///
+/// UTF-8 payloads are written as text with `"encoding": "utf-8"`, anything
+/// else as lowercase hex with `"encoding": "hex"`. `WIFI:` payloads also
+/// get a `wifi` object, and their password is redacted in both unless
+/// secrets are shown; a payload that was changed that way is marked with
+/// `"redacted": true`.
+#[derive(Debug, Serialize)]
+struct DecodeRecord {
+    source: String,
+    payload: String,
+    encoding: &'static str,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    redacted: bool,
+    symbology: Symbology,
+    version: u8,
+    error_correction: String,
+    mask: u8,
+    eci: Option<u32>,
+    bounding_box: BoundingBox,
+    corners: [Point; 4],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    wifi: Option<WifiRecord>,
+}
+
+/// The JSON shape of an image that failed in batch mode
+#[derive(Debug, Serialize)]
+struct FailureRecord {
+    source: String,
+    error: String,
+}
+
+/// One entry of the batch JSON array: a decoded code or a failed image
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum Record {
+    Ok(DecodeRecord),
+    Err(FailureRecord),
+}
+
+impl DecodeRecord {
+    fn new(source: &str, decoded: &DecodedQr, show_secrets: bool) -> Self {
+        let (payload, encoding) = match &decoded.text {
+            Some(text) if show_secrets => (text.clone(), "utf-8"),
+            Some(text) => (wifi::redact(text), "utf-8"),
+            None => (hex::encode(&decoded.payload), "hex"),
+        };
+        Self {
+            source: source.to_string(),
+            redacted: decoded.text.as_ref().is_some_and(|text| *text != payload),
+            payload,
+            encoding,
+            symbology: decoded.symbology,
+            version: decoded.version,
+            error_correction: match decoded.symbology {
+                Symbology::DataMatrix => "ECC200".to_string(),
+                Symbology::Aztec => format!("{}%", decoded.ec_percent.unwrap_or_default()),
+                Symbology::Pdf417 => {
+                    format!("level {}", decoded.security_level.unwrap_or_default())
+                }
+                symbology if symbology.is_linear() => "none".to_string(),
+                _ => format!("{:?}", decoded.error_correction),
+            },
+            mask: decoded.mask,
+            eci: decoded.eci,
+            bounding_box: BoundingBox::from_corners(&decoded.corners),
+            corners: decoded.corners,
+            wifi: decoded
+                .text
+                .as_deref()
+                .and_then(WifiNetwork::parse)
+                .map(|network| WifiRecord::new(network, show_secrets)),
+        }
+    }
+}
+
+/// Options for the decode command, matching the CLI defaults
+#[derive(Debug, Clone, Default)]
+pub struct DecodeOptions {
+    /// How the results are printed
+    pub format: DecodeFormat,
+    /// Scan directories recursively
+    pub recursive: bool,
+    /// Only report codes of this symbology
+    pub symbology: Option<Symbology>,
+    /// Print WiFi passwords instead of redacting them
+    pub show_secrets: bool,
+}
 
-use crate::{qr::decoder::QrDecoder, utils::error::Result};
+/// Totals printed after a batch decode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BatchSummary {
+    /// Images that were read
+    pub files: usize,
+    /// Codes decoded across all images
+    pub codes: usize,
+    /// Images that could not be read or held no readable code
+    pub failures: usize,
+}
 
-/// Run the decode command to extract text from a QR code image
+impl fmt::Display for BatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Scanned {} files: {} codes found, {} failed",
+            self.files, self.codes, self.failures
+        )
+    }
+}
+
+/// Run the decode command to extract text from QR code images
 ///
 /// This function handles the complete QR code decoding workflow:
 /// 1. Creates a QR decoder
-/// 2. Loads and processes the image file or URL
-/// 3. Extracts and displays the decoded content
+/// 2. Loads each image from a file, URL, `data:` URI or stdin (`-`)
+/// 3. Prints the decoded codes to stdout in the requested format
+///
+/// A single image is decoded as before and any error is returned. Several
+/// inputs, directories or glob patterns switch to batch mode: every image
+/// is decoded, failures (including inputs and directories that cannot be
+/// read) are reported per file on stderr and in JSON output, and a summary
+/// is printed at the end.
 ///
 /// # Arguments
-/// * `input` - Path to the image file or URL containing the QR code
+/// * `inputs` - Image paths, directories, glob patterns, `file://` or
+///   `http(s)://` URLs, `data:` URIs, or `-` for stdin
+/// * `options` - Output format, directory scanning, the symbology to
+///   report and whether to show WiFi passwords
 ///
 /// # Returns
-/// Returns `Ok(())` on success, or an error if decoding fails
+/// Returns `Ok(())` on success, or an error if decoding fails (in batch
+/// mode, if any image failed)
 ///
 /// # Examples
 /// ```rust,no_run
-/// use rqr::commands::decode::run;
+/// use rqr::commands::decode::{DecodeFormat, DecodeOptions, run};
 ///
-/// run("qr_code.png".to_string())?;
-/// run("https://example.com/qr.png".to_string())?;
+/// run(vec!["qr_code.png".to_string()], DecodeOptions::default())?;
+///
+/// let options = DecodeOptions {
+///     format: DecodeFormat::Ndjson,
+///     recursive: true,
+///     ..Default::default()
+/// };
+/// run(vec!["photos".to_string(), "scans/*.jpg".to_string()], options)?;
 /// # Ok::<(), rqr::utils::error::RqrError>(())
 /// ```
-pub fn run(input: String) -> Result<()> {
-    let decoder = QrDecoder::new();
-
-    println!("Decoding QR code from: {}", input);
+pub fn run(inputs: Vec<String>, options: DecodeOptions) -> Result<()> {
+    let mut sources = Vec::new();
+    for input in &inputs {
+        match ImageSource::expand(input, options.recursive) {
+            Ok(expanded) => sources.extend(expanded),
+            Err(e) if inputs.len() == 1 => return Err(e),
+            Err(error) => sources.push(Err(ExpandError {
+                input: input.clone(),
+                error,
+            })),
+        }
+    }
 
-    // 判断是否是 URL
-    let results = if input.starts_with("http://") || input.starts_with("https://") {
-        println!("Detected URL input, fetching from web...");
-        decoder.decode_from_url(&input)?
-    } else {
-        println!("Detected file input, reading from disk...");
-        let path = PathBuf::from(&input);
-        decoder.decode_from_file(&path)?
+    let decoder = match options.symbology {
+        Some(symbology) => QrDecoder::new().with_symbology(symbology),
+        None => QrDecoder::new(),
     };
+    match (inputs.as_slice(), sources.as_slice()) {
+        ([input], [Ok(source)]) if input.parse::<ImageSource>()? == *source => {
+            run_single(&decoder, source, &options)
+        }
+        _ => {
+            let summary = run_batch(&decoder, sources, &options)?;
+            eprintln!("{}", summary);
+            if summary.failures > 0 {
+                return Err(RqrError::DecodingError(format!(
+                    "{} of {} files failed",
+                    summary.failures, summary.files
+                )));
+            }
+            Ok(())
+        }
+    }
+}
 
-    if results.len() == 1 {
-        println!("\nDecoded content:");
-        println!("{}", results[0]);
-    } else {
-        println!("\nFound {} QR codes:", results.len());
-        for (i, content) in results.iter().enumerate() {
-            println!("\nQR Code #{}:", i + 1);
-            println!("{}", content);
+/// Decode one image, returning its error directly
+fn run_single(decoder: &QrDecoder, source: &ImageSource, options: &DecodeOptions) -> Result<()> {
+    eprintln!("Decoding QR code from: {}", source);
+
+    match source {
+        ImageSource::File(_) => eprintln!("Detected file input, reading from disk..."),
+        ImageSource::Url(_) => eprintln!("Detected URL input, fetching from web..."),
+        ImageSource::Stdin => eprintln!("Detected stdin input, reading image bytes..."),
+        ImageSource::Data(_) => eprintln!("Detected data URI input..."),
+    }
+    let results = decoder.decode_detailed_from_source(source)?;
+
+    // Data URIs are summarised rather than echoed back in full
+    let mut stdout = io::stdout().lock();
+    write_results(
+        &mut stdout,
+        &source.to_string(),
+        &results,
+        options.format,
+        options.show_secrets,
+    )?;
+    stdout.flush()?;
+
+    Ok(())
+}
+
+/// Decode every image, streaming results to stdout as they come in
+///
+/// Entries that could not be expanded are reported as failures alongside
+/// the images that could not be decoded.
+fn run_batch(
+    decoder: &QrDecoder,
+    sources: Vec<Expanded>,
+    options: &DecodeOptions,
+) -> Result<BatchSummary> {
+    let (format, show_secrets) = (options.format, options.show_secrets);
+    let mut stdout = io::stdout().lock();
+    let mut summary = BatchSummary::default();
+    // JSON output is a single array, so it is written once at the end
+    let mut records = Vec::new();
+
+    for entry in sources {
+        let (name, decoded) = match entry {
+            Ok(source) => (
+                source.to_string(),
+                decoder.decode_detailed_from_source(&source),
+            ),
+            Err(ExpandError { input, error }) => (input, Err(error)),
+        };
+        summary.files += 1;
+
+        match decoded {
+            Ok(results) => {
+                summary.codes += results.len();
+                match format {
+                    DecodeFormat::Json => records.extend(results.iter().map(|decoded| {
+                        Record::Ok(DecodeRecord::new(&name, decoded, show_secrets))
+                    })),
+                    DecodeFormat::Text => {
+                        writeln!(stdout, "==> {} <==", name)?;
+                        write_results(&mut stdout, &name, &results, format, show_secrets)?;
+                        writeln!(stdout)?;
+                    }
+                    DecodeFormat::Raw => {
+                        write_results(&mut stdout, &name, &results, format, show_secrets)?;
+                        writeln!(stdout)?;
+                    }
+                    DecodeFormat::Ndjson => {
+                        write_results(&mut stdout, &name, &results, format, show_secrets)?;
+                    }
+                }
+            }
+            Err(e) => {
+                summary.failures += 1;
+                eprintln!("Failed to decode {}: {}", name, e);
+                let failure = FailureRecord {
+                    source: name,
+                    error: e.to_string(),
+                };
+                match format {
+                    DecodeFormat::Json => records.push(Record::Err(failure)),
+                    DecodeFormat::Ndjson => {
+                        serde_json::to_writer(&mut stdout, &failure).map_err(io::Error::from)?;
+                        writeln!(stdout)?;
+                    }
+                    DecodeFormat::Text | DecodeFormat::Raw => {}
+                }
+            }
+        }
+        stdout.flush()?;
+    }
+
+    if format == DecodeFormat::Json {
+        serde_json::to_writer_pretty(&mut stdout, &records).map_err(io::Error::from)?;
+        writeln!(stdout)?;
+    }
+
+    Ok(summary)
+}
+
+/// Write decoded codes in the given format
+///
+/// # Arguments
+/// * `out` - Where the results are written
+/// * `source` - Where the codes were read from, as shown to the user
+/// * `results` - The decoded codes
+/// * `format` - How the results are printed
+/// * `show_secrets` - Print WiFi passwords instead of redacting them; raw
+///   output always holds the payload exactly
+///
+/// # Returns
+/// Returns `Ok(())` on success, or an error if writing fails
+pub fn write_results<W: Write>(
+    out: &mut W,
+    source: &str,
+    results: &[DecodedQr],
+    format: DecodeFormat,
+    show_secrets: bool,
+) -> Result<()> {
+    match format {
+        DecodeFormat::Text => {
+            let content = |decoded: &DecodedQr| match &decoded.text {
+                Some(text) => match WifiNetwork::parse(text) {
+                    Some(network) => wifi_text(&network, show_secrets),
+                    None => text.clone(),
+                },
+                // If not valid UTF-8, show as hex
+                None => format!("Binary data: {}", hex::encode(&decoded.payload)),
+            };
+            if let [decoded] = results {
+                writeln!(out, "Decoded content:")?;
+                writeln!(out, "{}", content(decoded))?;
+            } else {
+                writeln!(out, "Found {} QR codes:", results.len())?;
+                for (i, decoded) in results.iter().enumerate() {
+                    writeln!(out, "\nQR Code #{}:", i + 1)?;
+                    writeln!(out, "{}", content(decoded))?;
+                }
+            }
+        }
+        DecodeFormat::Raw => {
+            for (i, decoded) in results.iter().enumerate() {
+                if i > 0 {
+                    out.write_all(b"\n")?;
+                }
+                out.write_all(&decoded.payload)?;
+            }
+        }
+        DecodeFormat::Json => {
+            let records: Vec<_> = results
+                .iter()
+                .map(|decoded| DecodeRecord::new(source, decoded, show_secrets))
+                .collect();
+            serde_json::to_writer_pretty(&mut *out, &records).map_err(io::Error::from)?;
+            writeln!(out)?;
+        }
+        DecodeFormat::Ndjson => {
+            for decoded in results {
+                serde_json::to_writer(&mut *out, &DecodeRecord::new(source, decoded, show_secrets))
+                    .map_err(io::Error::from)?;
+                writeln!(out)?;
+            }
         }
     }
 
     Ok(())
 }
 
+/// The fields of a WiFi network, one per line, for text output
+fn wifi_text(network: &WifiNetwork, show_secrets: bool) -> String {
+    let mut text = format!(
+        "WiFi network\n  SSID: {}\n  Security: {}",
+        network.ssid, network.auth
+    );
+    if let Some(password) = &network.password {
+        if show_secrets {
+            text.push_str(&format!("\n  Password: {}", password));
+        } else {
+            text.push_str(&format!(
+                "\n  Password: {} (--show-secrets to reveal)",
+                REDACTED
+            ));
+        }
+    }
+    text.push_str(if network.hidden {
+        "\n  Hidden: yes"
+    } else {
+        "\n  Hidden: no"
+    });
+    text
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::Path;
@@ -73,7 +508,10 @@ mod tests {
 
         create_test_qr_file(&image_path, "Hello from decode");
 
-        let result = run(image_path.to_str().unwrap().to_string());
+        let result = run(
+            vec![image_path.to_str().unwrap().to_string()],
+            DecodeOptions::default(),
+        );
         assert!(result.is_ok());
     }
 
@@ -85,7 +523,10 @@ mod tests {
 
         create_test_qr_file(&image_path, "");
 
-        let result = run(image_path.to_str().unwrap().to_string());
+        let result = run(
+            vec![image_path.to_str().unwrap().to_string()],
+            DecodeOptions::default(),
+        );
         assert!(result.is_ok());
     }
 
@@ -98,7 +539,10 @@ mod tests {
         let content = "你好世界 🌍 Привет мир";
         create_test_qr_file(&image_path, content);
 
-        let result = run(image_path.to_str().unwrap().to_string());
+        let result = run(
+            vec![image_path.to_str().unwrap().to_string()],
+            DecodeOptions::default(),
+        );
         assert!(result.is_ok());
     }
 
@@ -111,7 +555,10 @@ mod tests {
         let long_text = "a".repeat(200);
         create_test_qr_file(&image_path, &long_text);
 
-        let result = run(image_path.to_str().unwrap().to_string());
+        let result = run(
+            vec![image_path.to_str().unwrap().to_string()],
+            DecodeOptions::default(),
+        );
         assert!(result.is_ok());
     }
 
@@ -124,14 +571,20 @@ mod tests {
         let special = "!@#$%^&*()_+-=[]{}|;':\",./<>?";
         create_test_qr_file(&image_path, special);
 
-        let result = run(image_path.to_str().unwrap().to_string());
+        let result = run(
+            vec![image_path.to_str().unwrap().to_string()],
+            DecodeOptions::default(),
+        );
         assert!(result.is_ok());
     }
 
     #[test]
     #[allow(clippy::unwrap_used)]
     fn test_decode_command_file_not_found() {
-        let result = run("/nonexistent/path/qr.png".to_string());
+        let result = run(
+            vec!["/nonexistent/path/qr.png".to_string()],
+            DecodeOptions::default(),
+        );
         assert!(result.is_err());
     }
 
@@ -143,17 +596,61 @@ mod tests {
 
         std::fs::write(&invalid_path, "This is not an image").unwrap();
 
-        let result = run(invalid_path.to_str().unwrap().to_string());
+        let result = run(
+            vec![invalid_path.to_str().unwrap().to_string()],
+            DecodeOptions::default(),
+        );
         assert!(result.is_err());
     }
 
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_decode_command_data_uri() {
+        use base64::Engine;
+
+        let temp = temp_dir();
+        let image_path = temp.path().join("inline.png");
+        create_test_qr_file(&image_path, "Inline image");
+
+        let png = std::fs::read(&image_path).unwrap();
+        let uri = format!(
+            "data:image/png;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(png)
+        );
+        let options = DecodeOptions {
+            format: DecodeFormat::Raw,
+            ..Default::default()
+        };
+        assert!(run(vec![uri], options).is_ok());
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_decode_command_file_url() {
+        let temp = temp_dir();
+        let image_path = temp.path().join("file url.png");
+        create_test_qr_file(&image_path, "File URL");
+
+        let url = format!(
+            "file://{}",
+            image_path.to_str().unwrap().replace(' ', "%20")
+        );
+        assert!(run(vec![url], DecodeOptions::default()).is_ok());
+    }
+
     #[test]
     #[allow(clippy::unwrap_used)]
     fn test_decode_command_url_format() {
-        let result_http = run("http://example.com/qr.png".to_string());
+        let result_http = run(
+            vec!["http://example.com/qr.png".to_string()],
+            DecodeOptions::default(),
+        );
         assert!(result_http.is_err());
 
-        let result_https = run("https://example.com/qr.png".to_string());
+        let result_https = run(
+            vec!["https://example.com/qr.png".to_string()],
+            DecodeOptions::default(),
+        );
         assert!(result_https.is_err());
     }
 
@@ -169,7 +666,10 @@ mod tests {
             let qr_code = encoder.encode("Size test").unwrap();
             encoder.save_to_file(&qr_code, &image_path).unwrap();
 
-            let result = run(image_path.to_str().unwrap().to_string());
+            let result = run(
+                vec![image_path.to_str().unwrap().to_string()],
+                DecodeOptions::default(),
+            );
             assert!(result.is_ok(), "Failed with size {}", size);
         }
     }
@@ -186,7 +686,10 @@ mod tests {
             let qr_code = encoder.encode("EC test").unwrap();
             encoder.save_to_file(&qr_code, &image_path).unwrap();
 
-            let result = run(image_path.to_str().unwrap().to_string());
+            let result = run(
+                vec![image_path.to_str().unwrap().to_string()],
+                DecodeOptions::default(),
+            );
             assert!(result.is_ok(), "Failed with level {}", level);
         }
     }
@@ -200,7 +703,10 @@ mod tests {
         let url = "https://example.com/path?query=value&foo=bar";
         create_test_qr_file(&image_path, url);
 
-        let result = run(image_path.to_str().unwrap().to_string());
+        let result = run(
+            vec![image_path.to_str().unwrap().to_string()],
+            DecodeOptions::default(),
+        );
         assert!(result.is_ok());
     }
 
@@ -213,7 +719,10 @@ mod tests {
         let content = "This is a test with   multiple   spaces";
         create_test_qr_file(&image_path, content);
 
-        let result = run(image_path.to_str().unwrap().to_string());
+        let result = run(
+            vec![image_path.to_str().unwrap().to_string()],
+            DecodeOptions::default(),
+        );
         assert!(result.is_ok());
     }
 
@@ -226,7 +735,215 @@ mod tests {
         let content = "Line 1\nLine 2\nLine 3";
         create_test_qr_file(&image_path, content);
 
-        let result = run(image_path.to_str().unwrap().to_string());
+        let result = run(
+            vec![image_path.to_str().unwrap().to_string()],
+            DecodeOptions::default(),
+        );
+        assert!(result.is_ok());
+    }
+
+    fn decode_results(content: &[u8]) -> Vec<DecodedQr> {
+        let encoder = QrEncoder::new(200, 4, "M").unwrap();
+        let image = encoder
+            .to_image(&encoder.encode_bytes(content).unwrap())
+            .unwrap();
+        QrDecoder::new().decode_detailed_from_image(image).unwrap()
+    }
+
+    fn write_to_string(results: &[DecodedQr], format: DecodeFormat) -> String {
+        let mut out = Vec::new();
+        write_results(&mut out, "code.png", results, format, false).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_decode_format_from_str() {
+        assert_eq!("text".parse::<DecodeFormat>().unwrap(), DecodeFormat::Text);
+        assert_eq!("RAW".parse::<DecodeFormat>().unwrap(), DecodeFormat::Raw);
+        assert_eq!("json".parse::<DecodeFormat>().unwrap(), DecodeFormat::Json);
+        assert_eq!(
+            "ndjson".parse::<DecodeFormat>().unwrap(),
+            DecodeFormat::Ndjson
+        );
+        assert!("yaml".parse::<DecodeFormat>().is_err());
+        assert_eq!(DecodeFormat::Ndjson.to_string(), "ndjson");
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_write_results_text() {
+        let results = decode_results(b"Hello");
+        assert_eq!(
+            write_to_string(&results, DecodeFormat::Text),
+            "Decoded content:\nHello\n"
+        );
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_write_results_raw_is_exact() {
+        let data = [0x00, 0xff, 0x0a, 0x80];
+        let results = decode_results(&data);
+
+        let mut out = Vec::new();
+        write_results(&mut out, "code.png", &results, DecodeFormat::Raw, false).unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_write_results_json() {
+        let results = decode_results(b"Hello");
+        let json: serde_json::Value =
+            serde_json::from_str(&write_to_string(&results, DecodeFormat::Json)).unwrap();
+
+        let record = &json[0];
+        assert_eq!(record["source"], "code.png");
+        assert_eq!(record["payload"], "Hello");
+        assert_eq!(record["encoding"], "utf-8");
+        assert_eq!(record["symbology"], "qr");
+        assert_eq!(record["version"], 1);
+        assert_eq!(record["error_correction"], "M");
+        assert!(record["eci"].is_null());
+        assert!(record["bounding_box"]["width"].as_i64().unwrap() > 0);
+        assert_eq!(record["corners"].as_array().unwrap().len(), 4);
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_write_results_ndjson_binary_as_hex() {
+        let results = decode_results(&[0xde, 0xad, 0xbe, 0xef]);
+        let output = write_to_string(&results, DecodeFormat::Ndjson);
+
+        assert_eq!(output.lines().count(), 1);
+        let record: serde_json::Value = serde_json::from_str(output.trim_end()).unwrap();
+        assert_eq!(record["payload"], "deadbeef");
+        assert_eq!(record["encoding"], "hex");
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_write_results_wifi_redacts_password() {
+        let results = decode_results(br"WIFI:T:WPA;S:Home\;Net;P:hunter22;H:true;;");
+        assert_eq!(
+            write_to_string(&results, DecodeFormat::Text),
+            "Decoded content:\nWiFi network\n  SSID: Home;Net\n  Security: WPA\n  \
+             Password: ******** (--show-secrets to reveal)\n  Hidden: yes\n"
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&write_to_string(&results, DecodeFormat::Json)).unwrap();
+        assert_eq!(
+            json[0]["payload"],
+            r"WIFI:T:WPA;S:Home\;Net;P:********;H:true;;"
+        );
+        assert_eq!(json[0]["redacted"], true);
+        assert_eq!(json[0]["wifi"]["ssid"], "Home;Net");
+        assert_eq!(json[0]["wifi"]["security"], "WPA");
+        assert_eq!(json[0]["wifi"]["password"], "********");
+        assert_eq!(json[0]["wifi"]["hidden"], true);
+
+        // Raw output keeps the payload exact
+        let mut out = Vec::new();
+        write_results(&mut out, "code.png", &results, DecodeFormat::Raw, false).unwrap();
+        assert_eq!(out, br"WIFI:T:WPA;S:Home\;Net;P:hunter22;H:true;;");
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_write_results_wifi_show_secrets() {
+        let results = decode_results(b"WIFI:T:nopass;S:Cafe;;");
+        let mut out = Vec::new();
+        write_results(&mut out, "code.png", &results, DecodeFormat::Text, true).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Decoded content:\nWiFi network\n  SSID: Cafe\n  Security: nopass\n  Hidden: no\n"
+        );
+
+        let results = decode_results(b"WIFI:T:SAE;S:Lab;P:correct horse;;");
+        let mut out = Vec::new();
+        write_results(&mut out, "code.png", &results, DecodeFormat::Ndjson, true).unwrap();
+        let record: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(record["payload"], "WIFI:T:SAE;S:Lab;P:correct horse;;");
+        assert!(record.get("redacted").is_none());
+        assert_eq!(record["wifi"]["security"], "WPA3");
+        assert_eq!(record["wifi"]["password"], "correct horse");
+
+        // Other codes have no wifi object
+        let results = decode_results(b"Hello");
+        let json: serde_json::Value =
+            serde_json::from_str(&write_to_string(&results, DecodeFormat::Json)).unwrap();
+        assert!(json[0].get("wifi").is_none());
+    }
+
+    #[test]
+    fn test_bounding_box_from_corners() {
+        let corners = [
+            Point { x: 10, y: 12 },
+            Point { x: 50, y: 8 },
+            Point { x: 54, y: 48 },
+            Point { x: 14, y: 52 },
+        ];
+        assert_eq!(
+            BoundingBox::from_corners(&corners),
+            BoundingBox {
+                x: 10,
+                y: 8,
+                width: 44,
+                height: 44
+            }
+        );
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_decode_command_batch_directory() {
+        let temp = temp_dir();
+        create_test_qr_file(&temp.path().join("a.png"), "First");
+        std::fs::create_dir(temp.path().join("nested")).unwrap();
+        create_test_qr_file(&temp.path().join("nested").join("b.png"), "Second");
+
+        let options = DecodeOptions {
+            format: DecodeFormat::Ndjson,
+            recursive: true,
+            ..Default::default()
+        };
+        let result = run(vec![temp.path().to_str().unwrap().to_string()], options);
         assert!(result.is_ok());
     }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_decode_command_batch_reports_failures() {
+        let temp = temp_dir();
+        let good = temp.path().join("good.png");
+        create_test_qr_file(&good, "Good");
+        let bad = temp.path().join("bad.png");
+        std::fs::write(&bad, "not an image").unwrap();
+
+        let result = run(
+            vec![
+                good.to_str().unwrap().to_string(),
+                bad.to_str().unwrap().to_string(),
+            ],
+            DecodeOptions::default(),
+        );
+        assert!(
+            matches!(result, Err(RqrError::DecodingError(ref m)) if m == "1 of 2 files failed")
+        );
+    }
+
+    #[test]
+    fn test_batch_summary_display() {
+        let summary = BatchSummary {
+            files: 3,
+            codes: 4,
+            failures: 1,
+        };
+        assert_eq!(
+            summary.to_string(),
+            "Scanned 3 files: 4 codes found, 1 failed"
+        );
+    }
 }
@@ -1,10 +1,230 @@
-use std::path::PathBuf;
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
 
 use crate::{
-    qr::{encoder::QrEncoder, output::OutputFormat},
-    utils::error::Result,
+    qr::{
+        datamatrix::gs1_payload,
+        encoder::{QrEncoder, pdf_number},
+        output::{FitMode, OutputFormat, PhysicalSize, RgbaColor},
+        style::Style,
+        symbol::{Symbol, Symbology},
+        terminal::{self, TerminalGraphics, TerminalMode},
+        wifi::WifiNetwork,
+    },
+    utils::error::{Result, RqrError},
 };
 
+/// Where the content to encode comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContentSource {
+    /// Text given directly on the command line
+    Text(String),
+    /// Everything read from standard input (`rqr encode -`)
+    Stdin,
+    /// The contents of a file (`--input-file`)
+    File(PathBuf),
+}
+
+impl ContentSource {
+    /// Build the source from the positional argument and `--input-file`
+    ///
+    /// A positional `-` means standard input.
+    pub fn from_args(content: Option<String>, input_file: Option<PathBuf>) -> Result<Self> {
+        match (content, input_file) {
+            (_, Some(path)) => Ok(ContentSource::File(path)),
+            (Some(content), None) if content == "-" => Ok(ContentSource::Stdin),
+            (Some(content), None) => Ok(ContentSource::Text(content)),
+            (None, None) => Err(RqrError::InvalidInput(
+                "No content given, pass text, - for stdin, or --input-file".to_string(),
+            )),
+        }
+    }
+
+    /// Read the content to encode
+    ///
+    /// Stdin and file input usually end with a newline that is not part of
+    /// the payload, so one trailing `\n` or `\r\n` is removed unless
+    /// `keep_newline` is set. Text arguments are used as given.
+    ///
+    /// # Arguments
+    /// * `keep_newline` - Keep a trailing newline from stdin or a file
+    ///
+    /// # Returns
+    /// Returns the content, or an error if it cannot be read or is not UTF-8
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// use std::path::PathBuf;
+    /// use rqr::commands::encode::ContentSource;
+    ///
+    /// let config = ContentSource::File(PathBuf::from("wg0.conf")).read(false)?;
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn read(self, keep_newline: bool) -> Result<String> {
+        match self {
+            ContentSource::Text(content) => Ok(content),
+            source => into_text(source.read_bytes()?, keep_newline),
+        }
+    }
+
+    /// Read the content to encode as raw bytes, exactly as given
+    ///
+    /// # Returns
+    /// Returns the bytes, or an error if the input cannot be read
+    pub fn read_bytes(self) -> Result<Vec<u8>> {
+        match self {
+            ContentSource::Text(content) => Ok(content.into_bytes()),
+            ContentSource::Stdin => read_all(std::io::stdin().lock()),
+            ContentSource::File(path) => read_all(open_input(&path)?),
+        }
+    }
+}
+
+/// Open an input file, naming it in the error
+fn open_input(path: &Path) -> Result<std::fs::File> {
+    std::fs::File::open(path).map_err(|e| {
+        RqrError::InvalidInput(format!("Cannot read input file {}: {}", path.display(), e))
+    })
+}
+
+/// Read everything from a reader
+fn read_all<R: Read>(mut reader: R) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Interpret input as UTF-8 text, optionally dropping one trailing newline
+fn into_text(bytes: Vec<u8>, keep_newline: bool) -> Result<String> {
+    let mut content = String::from_utf8(bytes).map_err(|_| {
+        RqrError::InvalidInput("Input is not valid UTF-8 text, use --binary".to_string())
+    })?;
+
+    if !keep_newline && content.ends_with('\n') {
+        content.pop();
+        if content.ends_with('\r') {
+            content.pop();
+        }
+    }
+
+    Ok(content)
+}
+
+/// Options for the encode command
+///
+/// Mirrors the flags accepted by `rqr encode`. The `Default` implementation
+/// uses the same defaults as the CLI.
+#[derive(Debug, Clone)]
+pub struct EncodeOptions {
+    /// Path where to save the code image
+    pub output: PathBuf,
+    /// Size of the output image in pixels
+    pub size: u32,
+    /// Error correction level ("L", "M", "Q", "H")
+    pub error_correction: String,
+    /// Margin around the code in modules, `symbology.default_margin()` when
+    /// unset
+    pub margin: Option<u32>,
+    /// If true, display in terminal instead of saving to file
+    pub terminal: bool,
+    /// How terminal output maps modules onto character cells
+    pub terminal_mode: TerminalMode,
+    /// Draw light modules in terminal output, for dark themes
+    pub invert: bool,
+    /// Send the rendered bitmap with a terminal graphics protocol instead
+    /// of drawing it with characters
+    pub terminal_graphics: Option<TerminalGraphics>,
+    /// How raster output reaches the requested size
+    pub fit: FitMode,
+    /// Module colour, black when unset
+    pub foreground: Option<RgbaColor>,
+    /// Background colour, white when unset
+    pub background: Option<RgbaColor>,
+    /// Image to composite over the centre of the code
+    pub logo: Option<PathBuf>,
+    /// Logo width as a fraction of the symbol width
+    pub logo_scale: f64,
+    /// Physical width of the code for PDF output, margin excluded
+    pub physical_size: Option<PhysicalSize>,
+    /// Module and finder pattern styling
+    pub style: Style,
+    /// Quality of lossy raster output (1-100), `DEFAULT_QUALITY` when unset
+    pub quality: Option<u8>,
+    /// Encode a QR code, a Micro QR, an rMQR, a Data Matrix, an Aztec or a
+    /// PDF417 symbol, or a Code 128, EAN-13, UPC-A, Code 39 or ITF barcode
+    pub symbology: Symbology,
+    /// Fixed symbol version (1-40, 1-4 for Micro QR, 1-32 for rMQR, 1-30
+    /// for Data Matrix, 1-36 for Aztec); content that does not fit is an
+    /// error. PDF417 is sized with `columns` and `rows` instead, and 1D
+    /// barcodes have no versions.
+    pub version: Option<u8>,
+    /// Smallest symbol version to use (1-40, 1-4 for Micro QR, 1-32 for
+    /// rMQR, 1-30 for Data Matrix, 1-36 for Aztec); not available for
+    /// PDF417 or 1D barcodes
+    pub min_version: Option<u8>,
+    /// Fixed mask pattern (0-7, 0-3 for Micro QR), the lowest-penalty one
+    /// when unset; rMQR has a single mask and the other symbologies none
+    pub mask: Option<u8>,
+    /// Content is GS1 element strings with bracketed AIs, encoded as a GS1
+    /// Data Matrix
+    pub gs1: bool,
+    /// Aztec error correction in percent of the data, set by the error
+    /// correction level when unset
+    pub ec_percent: Option<u8>,
+    /// PDF417 data columns (1-30), chosen to fit when unset
+    pub columns: Option<u8>,
+    /// PDF417 rows (3-90), chosen to fit when unset
+    pub rows: Option<u8>,
+    /// PDF417 security level (0-8), set by the error correction level when
+    /// unset
+    pub security_level: Option<u8>,
+}
+
+/// Contrast ratio below which custom colours trigger a warning
+const MIN_CONTRAST_RATIO: f64 = 3.0;
+
+impl EncodeOptions {
+    /// The margin in modules, falling back to the symbology's default
+    pub fn margin(&self) -> u32 {
+        self.margin
+            .unwrap_or_else(|| self.symbology.default_margin())
+    }
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self {
+            output: PathBuf::from("rqr.png"),
+            size: 200,
+            error_correction: "M".to_string(),
+            margin: None,
+            terminal: false,
+            terminal_mode: TerminalMode::default(),
+            invert: false,
+            terminal_graphics: None,
+            fit: FitMode::default(),
+            foreground: None,
+            background: None,
+            logo: None,
+            logo_scale: 0.2,
+            physical_size: None,
+            style: Style::default(),
+            quality: None,
+            symbology: Symbology::default(),
+            version: None,
+            min_version: None,
+            mask: None,
+            gs1: false,
+            ec_percent: None,
+            columns: None,
+            rows: None,
+            security_level: None,
+        }
+    }
+}
+
 /// Run the encode command to generate a QR code
 ///
 /// This function handles the complete QR code encoding workflow:
@@ -14,11 +234,7 @@ use crate::{
 ///
 /// # Arguments
 /// * `content` - The text content to encode
-/// * `output` - Path where to save the QR code image
-/// * `size` - Size of the output image in pixels
-/// * `error_correction` - Error correction level ("L", "M", "Q", "H")
-/// * `margin` - Margin around the QR code in modules
-/// * `terminal` - If true, display in terminal instead of saving to file
+/// * `options` - Output path, rendering and error correction settings
 ///
 /// # Returns
 /// Returns `Ok(())` on success, or an error if encoding fails
@@ -26,62 +242,446 @@ use crate::{
 /// # Examples
 /// ```rust,no_run
 /// use std::path::PathBuf;
-/// use rqr::commands::encode::run;
+/// use rqr::commands::encode::{EncodeOptions, run};
 ///
 /// run(
 ///     "Hello World".to_string(),
-///     PathBuf::from("hello.png"),
-///     200,
-///     "M".to_string(),
-///     10,
-///     false
+///     EncodeOptions {
+///         output: PathBuf::from("hello.png"),
+///         ..Default::default()
+///     },
 /// )?;
 /// # Ok::<(), rqr::utils::error::RqrError>(())
 /// ```
-pub fn run(
-    content: String,
-    output: PathBuf,
-    size: u32,
-    error_correction: String,
-    margin: u32,
-    terminal: bool,
-) -> Result<()> {
-    // Create encoder
-    let encoder = QrEncoder::new(size, margin, &error_correction)?;
+pub fn run(content: String, options: EncodeOptions) -> Result<()> {
+    run_payload(&text_payload(&content, &options)?, &content, options)
+}
+
+/// Run the encode command on raw bytes
+///
+/// Works like `run`, but the data is stored byte for byte and need not be
+/// text. The summary reports the payload length instead of the content.
+///
+/// # Arguments
+/// * `data` - The bytes to encode
+/// * `options` - Output path, rendering and error correction settings
+///
+/// # Returns
+/// Returns `Ok(())` on success, or an error if encoding fails
+///
+/// # Examples
+/// ```rust,no_run
+/// use std::path::PathBuf;
+/// use rqr::commands::encode::{EncodeOptions, run_binary};
+///
+/// run_binary(
+///     std::fs::read("key.bin")?,
+///     EncodeOptions {
+///         output: PathBuf::from("key.png"),
+///         ..Default::default()
+///     },
+/// )?;
+/// # Ok::<(), rqr::utils::error::RqrError>(())
+/// ```
+pub fn run_binary(data: Vec<u8>, options: EncodeOptions) -> Result<()> {
+    let description = format!("{} bytes of binary data", data.len());
+    run_payload(&data, &description, options)
+}
+
+/// Run the encode command on a WiFi network
+///
+/// Works like `run` with the network's `WIFI:` payload as the content. The
+/// summary describes the network without its password.
+///
+/// # Arguments
+/// * `network` - The network to join by scanning the code
+/// * `options` - Output path, rendering and error correction settings
+///
+/// # Returns
+/// Returns `Ok(())` on success, or an error if encoding fails
+///
+/// # Examples
+/// ```rust,no_run
+/// use std::path::PathBuf;
+/// use rqr::{
+///     commands::encode::{EncodeOptions, run_wifi},
+///     qr::wifi::WifiNetwork,
+/// };
+///
+/// run_wifi(
+///     WifiNetwork::new("Office;2F", Some("correct horse"), None, false)?,
+///     EncodeOptions {
+///         output: PathBuf::from("wifi.png"),
+///         ..Default::default()
+///     },
+/// )?;
+/// # Ok::<(), rqr::utils::error::RqrError>(())
+/// ```
+pub fn run_wifi(network: WifiNetwork, options: EncodeOptions) -> Result<()> {
+    run_payload(network.payload().as_bytes(), &network.to_string(), options)
+}
+
+/// The data to encode for text content: GS1 element strings become the
+/// data a scanner transmits, other text is encoded as is
+///
+/// GS1 content for other symbologies is left alone for the encoder to
+/// refuse.
+pub(crate) fn text_payload(content: &str, options: &EncodeOptions) -> Result<Vec<u8>> {
+    if options.gs1 && options.symbology == Symbology::DataMatrix {
+        gs1_payload(content)
+    } else {
+        Ok(content.as_bytes().to_vec())
+    }
+}
+
+/// Build the encoder shared by single and batch encoding
+pub(crate) fn build_encoder(options: &EncodeOptions) -> Result<QrEncoder> {
+    let mut encoder = QrEncoder::new(options.size, options.margin(), &options.error_correction)?
+        .with_fit(options.fit)
+        .with_style(options.style)
+        .with_symbology(options.symbology)
+        .with_gs1(options.gs1);
+    if let Some(physical_size) = options.physical_size {
+        encoder = encoder.with_physical_size(physical_size);
+    }
+    if options.foreground.is_some() || options.background.is_some() {
+        encoder = encoder.with_colors(
+            options.foreground.unwrap_or(RgbaColor::BLACK),
+            options.background.unwrap_or(RgbaColor::WHITE),
+        );
+    }
+    if let Some(logo) = &options.logo {
+        encoder = encoder.with_logo(image::open(logo)?, options.logo_scale)?;
+    }
+    if let Some(quality) = options.quality {
+        encoder = encoder.with_quality(quality)?;
+    }
+    if let Some(min_version) = options.min_version {
+        encoder = encoder.with_min_version(min_version)?;
+    }
+    if let Some(version) = options.version {
+        encoder = encoder.with_version(version)?;
+    }
+    if let Some(mask) = options.mask {
+        encoder = encoder.with_mask(mask)?;
+    }
+    if let Some(percent) = options.ec_percent {
+        encoder = encoder.with_ec_percent(percent)?;
+    }
+    if let Some(columns) = options.columns {
+        encoder = encoder.with_columns(columns)?;
+    }
+    if let Some(rows) = options.rows {
+        encoder = encoder.with_rows(rows)?;
+    }
+    if let Some(level) = options.security_level {
+        encoder = encoder.with_security_level(level)?;
+    }
+
+    Ok(encoder)
+}
+
+/// Whether colours, a logo or a style could make the code unreadable
+pub(crate) fn is_customised(options: &EncodeOptions) -> bool {
+    options.foreground.is_some()
+        || options.background.is_some()
+        || options.logo.is_some()
+        || !options.style.is_plain()
+}
+
+/// Encode a payload and write it out, describing it as `content` in the summary
+fn run_payload(payload: &[u8], content: &str, options: EncodeOptions) -> Result<()> {
+    let encoder = build_encoder(&options)?;
 
     // Encode QR code
-    let qr_code = encoder.encode(&content)?;
+    let qr_code = encoder.encode_bytes(payload)?;
 
     // Determine output format
-    let output_format = if terminal {
+    let output_format = if options.terminal {
         OutputFormat::Terminal
     } else {
-        OutputFormat::from_path(&output)?
+        OutputFormat::from_path(&options.output)?
     };
 
+    if options.logo.is_some() && !output_format.is_raster() {
+        return Err(RqrError::UnsupportedFormat(
+            "Logo overlay is only supported for raster output".to_string(),
+        ));
+    }
+    if options.quality.is_some() && !output_format.is_lossy() {
+        eprintln!("Warning: --quality only applies to JPEG output and is ignored");
+    }
+    if output_format.is_raster()
+        && !output_format.supports_alpha()
+        && options.background.is_some_and(|bg| bg.alpha() < 255)
+    {
+        eprintln!(
+            "Warning: {:?} has no transparency, the background is flattened onto white",
+            output_format
+        );
+    }
+
+    if is_customised(&options) && !matches!(output_format, OutputFormat::Terminal) {
+        check_readability(&encoder, &qr_code, payload, &options)?;
+        warn_low_contrast(&options);
+    }
+
     match output_format {
         OutputFormat::Terminal => {
-            let qr_string = encoder.to_terminal_string(&qr_code);
-            println!("{}", qr_string);
+            if let Some(protocol) = options.terminal_graphics {
+                let image = encoder.to_image(&qr_code)?;
+                let mut stdout = std::io::stdout().lock();
+                stdout.write_all(&terminal::render_graphics(&image, protocol)?)?;
+                writeln!(stdout)?;
+            } else {
+                let mode = options
+                    .terminal_mode
+                    .fit(&qr_code, terminal::terminal_width())?;
+                if mode != options.terminal_mode {
+                    eprintln!(
+                        "Warning: the code is too wide for {} mode, using {} mode",
+                        options.terminal_mode, mode
+                    );
+                }
+                let qr_string = encoder.to_terminal_string(&qr_code, mode, options.invert);
+                println!("{}", qr_string);
+            }
             println!("\nContent: {}", content);
         }
-        OutputFormat::Png => {
-            encoder.save_to_file(&qr_code, &output)?;
-            println!("QR code saved to: {}", output.display());
-            println!("Content: {}", content);
-            println!("Size: {}x{} pixels", size, size);
-            println!("Error correction: {}", error_correction);
-            println!("Margin: {} modules", margin);
+        OutputFormat::Svg => {
+            encoder.save_to_svg(&qr_code, &options.output)?;
+            let height = encoder.output_height(&qr_code, f64::from(options.size));
+            let size = format!("{}x{} pixels", options.size, pdf_number(height));
+            print_file_summary(content, &qr_code, &options, &size);
         }
+        OutputFormat::Pdf => {
+            encoder.save_to_pdf(&qr_code, &options.output)?;
+            let mut size = encoder.physical_size().to_string();
+            if qr_code.height() != qr_code.width() {
+                size.push_str(" wide");
+            }
+            print_file_summary(content, &qr_code, &options, &size);
+        }
+        raster => {
+            let image = encoder.to_image(&qr_code)?;
+            encoder.save_image(&image, &options.output)?;
+            let size = format!("{}x{} pixels", image.width(), image.height());
+            print_file_summary(content, &qr_code, &options, &size);
+            if options.logo.is_some() {
+                println!(
+                    "Logo scale: {} (safe maximum {:.2})",
+                    options.logo_scale,
+                    encoder.max_logo_scale(&qr_code)
+                );
+            }
+            if raster.is_lossy() {
+                println!("Quality: {}", encoder.quality());
+                check_lossy_output(&encoder, &qr_code, payload, &options)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Warn when compression artefacts make a written lossy file unreadable
+///
+/// The file is read back from disk, so the check sees exactly what a
+/// scanner would get.
+fn check_lossy_output(
+    encoder: &QrEncoder,
+    qr_code: &Symbol,
+    payload: &[u8],
+    options: &EncodeOptions,
+) -> Result<()> {
+    let written = image::open(&options.output)?;
+    if encoder.verify_image(&written, payload).is_err() {
+        let total_modules = qr_code.width() + options.margin() as usize * 2;
+        eprintln!(
+            "Warning: JPEG artefacts at quality {} with {} pixel modules make the code \
+             unreadable, raise --quality or --size or use PNG",
+            encoder.quality(),
+            written.width() as usize / total_modules
+        );
     }
 
     Ok(())
 }
 
+/// Refuse colours, logos or styles that make the code unreadable
+///
+/// The code is rendered and decoded before anything is written, so an
+/// unreadable result never reaches the disk.
+pub(crate) fn check_readability(
+    encoder: &QrEncoder,
+    qr_code: &Symbol,
+    payload: &[u8],
+    options: &EncodeOptions,
+) -> Result<()> {
+    let foreground = options.foreground.unwrap_or(RgbaColor::BLACK);
+    let background = options.background.unwrap_or(RgbaColor::WHITE);
+
+    let image = encoder.to_image(qr_code)?;
+    if encoder.verify_image(&image, payload).is_err() {
+        let message = if options.logo.is_some() {
+            format!(
+                "The logo at scale {} makes the QR code unreadable, try a smaller --logo-scale",
+                options.logo_scale
+            )
+        } else if !options.style.is_plain() {
+            format!(
+                "The {} module shape with {} eyes makes the QR code unreadable",
+                options.style.module_shape, options.style.eye_shape
+            )
+        } else {
+            format!(
+                "Colours {} on {} make the QR code unreadable",
+                foreground, background
+            )
+        };
+        return Err(RqrError::InvalidInput(message));
+    }
+
+    Ok(())
+}
+
+/// Warn about colours that decode here but may trouble other scanners
+pub(crate) fn warn_low_contrast(options: &EncodeOptions) {
+    let foreground = options.foreground.unwrap_or(RgbaColor::BLACK);
+    let background = options.background.unwrap_or(RgbaColor::WHITE);
+
+    for color in [Some(foreground), options.style.eye_color]
+        .into_iter()
+        .flatten()
+    {
+        let ratio = color.contrast_ratio(background);
+        if ratio < MIN_CONTRAST_RATIO {
+            eprintln!(
+                "Warning: low contrast between {} and {} ({:.2}:1), some scanners may fail",
+                color, background, ratio
+            );
+        }
+    }
+    if foreground.relative_luminance() > background.relative_luminance() {
+        eprintln!("Warning: light modules on a dark background are not supported by all scanners");
+    }
+}
+
+/// Print the summary shown after a code has been written to a file
+fn print_file_summary(content: &str, qr_code: &Symbol, options: &EncodeOptions, size: &str) {
+    println!(
+        "{} saved to: {}",
+        qr_code.symbology().display_name(),
+        options.output.display()
+    );
+    println!("Content: {}", content);
+    println!("Size: {}", size);
+    let raised = qr_code.error_correction_level().is_some_and(|level| {
+        !format!("{:?}", level).eq_ignore_ascii_case(&options.error_correction)
+    });
+    if raised {
+        println!(
+            "Error correction: {} (raised from {} for the logo)",
+            qr_code.ec_summary(),
+            options.error_correction.to_uppercase()
+        );
+    } else {
+        println!("Error correction: {}", qr_code.ec_summary());
+    }
+    println!("Version: {}", qr_code.version_summary());
+    println!("Margin: {} modules", options.margin());
+    if !options.style.is_plain() {
+        let style = &options.style;
+        match style.eye_color {
+            Some(eye_color) => println!(
+                "Style: {} modules, {} eyes in {}",
+                style.module_shape, style.eye_shape, eye_color
+            ),
+            None => println!(
+                "Style: {} modules, {} eyes",
+                style.module_shape, style.eye_shape
+            ),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::utils::test_utils::temp_dir;
+    use crate::{
+        qr::{
+            decoder::QrDecoder,
+            style::{EyeShape, ModuleShape},
+        },
+        utils::test_utils::temp_dir,
+    };
+
+    #[test]
+    fn test_content_source_from_args() {
+        assert_eq!(
+            ContentSource::from_args(Some("-".to_string()), None).unwrap(),
+            ContentSource::Stdin
+        );
+        assert_eq!(
+            ContentSource::from_args(Some("Hello".to_string()), None).unwrap(),
+            ContentSource::Text("Hello".to_string())
+        );
+        assert_eq!(
+            ContentSource::from_args(None, Some(PathBuf::from("wg0.conf"))).unwrap(),
+            ContentSource::File(PathBuf::from("wg0.conf"))
+        );
+        assert!(ContentSource::from_args(None, None).is_err());
+    }
+
+    #[test]
+    fn test_into_text_trailing_newline() {
+        let cases = [
+            ("line\n", false, "line"),
+            ("line\r\n", false, "line"),
+            ("line\n\n", false, "line\n"),
+            ("line", false, "line"),
+            ("line\n", true, "line\n"),
+            ("", false, ""),
+        ];
+
+        for (input, keep_newline, expected) in cases {
+            let content = into_text(input.as_bytes().to_vec(), keep_newline).unwrap();
+            assert_eq!(content, expected, "Failed for {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_into_text_rejects_invalid_utf8() {
+        let result = into_text(vec![0xff, 0xfe], false);
+        assert!(matches!(result, Err(RqrError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_content_source_file() {
+        let temp = temp_dir();
+        let path = temp.path().join("wg0.conf");
+        std::fs::write(&path, "[Interface]\nPrivateKey = abc\n").unwrap();
+
+        let source = ContentSource::File(path.clone());
+        assert_eq!(
+            source.clone().read(false).unwrap(),
+            "[Interface]\nPrivateKey = abc"
+        );
+        assert_eq!(
+            source.read(true).unwrap(),
+            "[Interface]\nPrivateKey = abc\n"
+        );
+
+        let binary = temp.path().join("key.bin");
+        std::fs::write(&binary, [0xff, 0x00, b'\n']).unwrap();
+        assert_eq!(
+            ContentSource::File(binary).read_bytes().unwrap(),
+            vec![0xff, 0x00, b'\n']
+        );
+
+        let missing = ContentSource::File(temp.path().join("missing.conf")).read(false);
+        assert!(missing.unwrap_err().to_string().contains("missing.conf"));
+    }
 
     #[test]
     #[allow(clippy::unwrap_used)]
@@ -91,17 +691,325 @@ mod tests {
 
         let result = run(
             "Test content".to_string(),
-            output_path.clone(),
-            200,
-            "M".to_string(),
-            10,
-            false,
+            EncodeOptions {
+                output: output_path.clone(),
+                ..Default::default()
+            },
         );
 
         assert!(result.is_ok());
         assert!(output_path.exists());
     }
 
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_encode_command_binary() {
+        let temp = temp_dir();
+        let output_path = temp.path().join("binary.png");
+        let data = vec![0x00, 0xff, 0x80, b'\n', 0xfe];
+
+        let result = run_binary(
+            data.clone(),
+            EncodeOptions {
+                output: output_path.clone(),
+                ..Default::default()
+            },
+        );
+
+        assert!(result.is_ok());
+        let payloads = crate::qr::decoder::QrDecoder::new()
+            .decode_bytes_from_file(&output_path)
+            .unwrap();
+        assert_eq!(payloads, vec![data]);
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_encode_command_wifi() {
+        let temp = temp_dir();
+        let output_path = temp.path().join("wifi.png");
+        let network = WifiNetwork::new("Office;2F", Some("pass:word,1"), None, true).unwrap();
+
+        let result = run_wifi(
+            network.clone(),
+            EncodeOptions {
+                output: output_path.clone(),
+                ..Default::default()
+            },
+        );
+
+        assert!(result.is_ok());
+        let decoded = crate::qr::decoder::QrDecoder::new()
+            .decode_from_file(&output_path)
+            .unwrap();
+        assert_eq!(decoded, vec![network.payload()]);
+        assert_eq!(WifiNetwork::parse(&decoded[0]), Some(network));
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_encode_command_svg_output() {
+        let temp = temp_dir();
+        let output_path = temp.path().join("output.svg");
+
+        let result = run(
+            "SVG content".to_string(),
+            EncodeOptions {
+                output: output_path.clone(),
+                margin: Some(4),
+                ..Default::default()
+            },
+        );
+
+        assert!(result.is_ok());
+        let svg = std::fs::read_to_string(&output_path).unwrap();
+        assert!(svg.contains("<svg"));
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_encode_command_pdf_output() {
+        let temp = temp_dir();
+        let output_path = temp.path().join("label.pdf");
+
+        let result = run(
+            "PDF content".to_string(),
+            EncodeOptions {
+                output: output_path.clone(),
+                margin: Some(4),
+                physical_size: Some("1in".parse().unwrap()),
+                ..Default::default()
+            },
+        );
+
+        assert!(result.is_ok());
+        let pdf = std::fs::read(&output_path).unwrap();
+        assert!(pdf.starts_with(b"%PDF-"));
+        // One inch of symbol, plus the four-module margin on both sides
+        assert!(String::from_utf8_lossy(&pdf).contains("/MediaBox [0 0 99.428571 99.428571]"));
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_encode_command_fit_modes() {
+        let temp = temp_dir();
+
+        for fit in [FitMode::Pad, FitMode::Scale] {
+            let output_path = temp.path().join(format!("fit_{}.png", fit));
+
+            let result = run(
+                "Fit test".to_string(),
+                EncodeOptions {
+                    output: output_path.clone(),
+                    fit,
+                    ..Default::default()
+                },
+            );
+
+            assert!(result.is_ok(), "Failed with fit {}", fit);
+            let image = image::open(&output_path).unwrap();
+            assert_eq!((image.width(), image.height()), (200, 200));
+        }
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_encode_command_exact_modules_rejects_size() {
+        let temp = temp_dir();
+        let output_path = temp.path().join("exact.png");
+
+        let result = run(
+            "Fit test".to_string(),
+            EncodeOptions {
+                output: output_path.clone(),
+                fit: FitMode::ExactModules,
+                ..Default::default()
+            },
+        );
+
+        assert!(result.is_err());
+        assert!(!output_path.exists());
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_encode_command_custom_colors() {
+        let temp = temp_dir();
+        let output_path = temp.path().join("colors.png");
+
+        let result = run(
+            "Color test".to_string(),
+            EncodeOptions {
+                output: output_path.clone(),
+                foreground: Some("#1e3a8a".parse().unwrap()),
+                background: Some(RgbaColor::TRANSPARENT),
+                ..Default::default()
+            },
+        );
+
+        assert!(result.is_ok());
+        let image = image::open(&output_path).unwrap();
+        assert!(image.color().has_alpha());
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_encode_command_rejects_unreadable_colors() {
+        let temp = temp_dir();
+        let output_path = temp.path().join("unreadable.png");
+
+        let result = run(
+            "Color test".to_string(),
+            EncodeOptions {
+                output: output_path.clone(),
+                foreground: Some("#ffffff".parse().unwrap()),
+                background: Some("#000000".parse().unwrap()),
+                ..Default::default()
+            },
+        );
+
+        assert!(result.unwrap_err().to_string().contains("unreadable"));
+        assert!(!output_path.exists());
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_encode_command_styled_vector_output() {
+        let temp = temp_dir();
+        let style = Style {
+            module_shape: ModuleShape::Liquid,
+            eye_shape: EyeShape::Circle,
+            eye_color: Some("#be123c".parse().unwrap()),
+        };
+
+        for name in ["styled.png", "styled.svg", "styled.pdf"] {
+            let output_path = temp.path().join(name);
+            let result = run(
+                "Style test".to_string(),
+                EncodeOptions {
+                    output: output_path.clone(),
+                    size: 400,
+                    style,
+                    ..Default::default()
+                },
+            );
+
+            assert!(result.is_ok(), "{}: {:?}", name, result);
+            assert!(output_path.exists());
+        }
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_encode_command_rejects_unreadable_style() {
+        let temp = temp_dir();
+        let output_path = temp.path().join("unreadable_style.svg");
+
+        let result = run(
+            "Style test".to_string(),
+            EncodeOptions {
+                output: output_path.clone(),
+                style: Style {
+                    module_shape: ModuleShape::Dot,
+                    eye_color: Some("#fafafa".parse().unwrap()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("dot module shape"), "{}", message);
+        assert!(!output_path.exists());
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_encode_command_with_logo() {
+        let temp = temp_dir();
+        let logo_path = temp.path().join("logo.png");
+        let output_path = temp.path().join("logo_qr.png");
+        image::RgbaImage::from_pixel(32, 32, image::Rgba([0, 128, 255, 255]))
+            .save(&logo_path)
+            .unwrap();
+
+        let result = run(
+            "https://example.com/product/12345".to_string(),
+            EncodeOptions {
+                output: output_path.clone(),
+                size: 400,
+                margin: Some(4),
+                logo: Some(logo_path),
+                logo_scale: 0.25,
+                ..Default::default()
+            },
+        );
+
+        assert!(result.is_ok());
+        let image = image::open(&output_path).unwrap().to_rgba8();
+        assert_eq!(image.get_pixel(200, 200).0, [0, 128, 255, 255]);
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_encode_command_logo_requires_png() {
+        let temp = temp_dir();
+        let logo_path = temp.path().join("logo.png");
+        let output_path = temp.path().join("logo_qr.svg");
+        image::RgbaImage::new(32, 32).save(&logo_path).unwrap();
+
+        let result = run(
+            "Logo test".to_string(),
+            EncodeOptions {
+                output: output_path.clone(),
+                logo: Some(logo_path),
+                ..Default::default()
+            },
+        );
+
+        assert!(matches!(result, Err(RqrError::UnsupportedFormat(_))));
+        assert!(!output_path.exists());
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_encode_command_jpeg_output() {
+        let temp = temp_dir();
+        let output_path = temp.path().join("output.jpg");
+
+        let result = run(
+            "JPEG content".to_string(),
+            EncodeOptions {
+                output: output_path.clone(),
+                quality: Some(80),
+                ..Default::default()
+            },
+        );
+
+        assert!(result.is_ok());
+        let image = image::open(&output_path).unwrap();
+        assert_eq!(image.width(), 200);
+    }
+
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_encode_command_invalid_quality() {
+        let temp = temp_dir();
+        let output_path = temp.path().join("output.jpg");
+
+        let result = run(
+            "JPEG content".to_string(),
+            EncodeOptions {
+                output: output_path.clone(),
+                quality: Some(0),
+                ..Default::default()
+            },
+        );
+
+        assert!(matches!(result, Err(RqrError::InvalidInput(_))));
+        assert!(!output_path.exists());
+    }
+
     #[test]
     #[allow(clippy::unwrap_used)]
     fn test_encode_command_different_sizes() {
@@ -112,11 +1020,11 @@ mod tests {
 
             let result = run(
                 "Size test".to_string(),
-                output_path.clone(),
-                size,
-                "M".to_string(),
-                10,
-                false,
+                EncodeOptions {
+                    output: output_path.clone(),
+                    size,
+                    ..Default::default()
+                },
             );
 
             assert!(result.is_ok(), "Failed with size {}", size);
@@ -134,11 +1042,11 @@ mod tests {
 
             let result = run(
                 "EC test".to_string(),
-                output_path.clone(),
-                200,
-                level.to_string(),
-                10,
-                false,
+                EncodeOptions {
+                    output: output_path.clone(),
+                    error_correction: level.to_string(),
+                    ..Default::default()
+                },
             );
 
             assert!(result.is_ok(), "Failed with level {}", level);
@@ -146,6 +1054,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_default_margin_follows_symbology() {
+        let options = |symbology| EncodeOptions {
+            symbology,
+            ..Default::default()
+        };
+        assert_eq!(options(Symbology::Qr).margin(), 10);
+        assert_eq!(options(Symbology::DataMatrix).margin(), 2);
+        assert_eq!(options(Symbology::Ean13).margin(), 11);
+        let options = EncodeOptions {
+            margin: Some(0),
+            ..options(Symbology::Aztec)
+        };
+        assert_eq!(options.margin(), 0);
+    }
+
     #[test]
     #[allow(clippy::unwrap_used)]
     fn test_encode_command_different_margins() {
@@ -156,11 +1080,11 @@ mod tests {
 
             let result = run(
                 "Margin test".to_string(),
-                output_path.clone(),
-                200,
-                "M".to_string(),
-                margin,
-                false,
+                EncodeOptions {
+                    output: output_path.clone(),
+                    margin: Some(margin),
+                    ..Default::default()
+                },
             );
 
             assert!(result.is_ok(), "Failed with margin {}", margin);
@@ -175,11 +1099,10 @@ mod tests {
 
         let result = run(
             "".to_string(),
-            output_path.clone(),
-            200,
-            "M".to_string(),
-            10,
-            false,
+            EncodeOptions {
+                output: output_path.clone(),
+                ..Default::default()
+            },
         );
 
         assert!(result.is_ok());
@@ -194,11 +1117,10 @@ mod tests {
 
         let result = run(
             "你好世界 🌍 Привет мир".to_string(),
-            output_path.clone(),
-            200,
-            "M".to_string(),
-            10,
-            false,
+            EncodeOptions {
+                output: output_path.clone(),
+                ..Default::default()
+            },
         );
 
         assert!(result.is_ok());
@@ -215,11 +1137,11 @@ mod tests {
 
         let result = run(
             long_content,
-            output_path.clone(),
-            400,
-            "M".to_string(),
-            10,
-            false,
+            EncodeOptions {
+                output: output_path.clone(),
+                size: 400,
+                ..Default::default()
+            },
         );
 
         assert!(result.is_ok());
@@ -234,11 +1156,11 @@ mod tests {
 
         let result = run(
             "Test".to_string(),
-            output_path.clone(),
-            200,
-            "X".to_string(),
-            10,
-            false,
+            EncodeOptions {
+                output: output_path.clone(),
+                error_correction: "X".to_string(),
+                ..Default::default()
+            },
         );
 
         assert!(result.is_err());
@@ -252,11 +1174,11 @@ mod tests {
 
         let result = run(
             "Test".to_string(),
-            output_path.clone(),
-            10,
-            "M".to_string(),
-            10,
-            false,
+            EncodeOptions {
+                output: output_path.clone(),
+                size: 10,
+                ..Default::default()
+            },
         );
 
         assert!(result.is_err());
@@ -270,17 +1192,50 @@ mod tests {
 
         let result = run(
             "Terminal test".to_string(),
-            output_path.clone(),
-            200,
-            "M".to_string(),
-            10,
-            true,
+            EncodeOptions {
+                output: output_path.clone(),
+                terminal: true,
+                ..Default::default()
+            },
         );
 
         assert!(result.is_ok());
         assert!(!output_path.exists());
     }
 
+    #[test]
+    #[allow(clippy::unwrap_used)]
+    fn test_encode_command_fixed_version_and_mask() {
+        let temp = temp_dir();
+        let output_path = temp.path().join("fixed.png");
+
+        run(
+            "Fixed".to_string(),
+            EncodeOptions {
+                output: output_path.clone(),
+                version: Some(6),
+                mask: Some(4),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let decoded = QrDecoder::new()
+            .decode_detailed_from_file(&output_path)
+            .unwrap();
+        assert_eq!((decoded[0].version, decoded[0].mask), (6, 4));
+
+        let result = run(
+            "x".repeat(100),
+            EncodeOptions {
+                output: temp.path().join("too-long.png"),
+                version: Some(1),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(result, Err(RqrError::EncodingError(_))));
+    }
+
     #[test]
     #[allow(clippy::unwrap_used)]
     fn test_encode_command_nested_output_path() {
@@ -289,11 +1244,10 @@ mod tests {
 
         let result = run(
             "Test".to_string(),
-            nested_path.clone(),
-            200,
-            "M".to_string(),
-            10,
-            false,
+            EncodeOptions {
+                output: nested_path.clone(),
+                ..Default::default()
+            },
         );
 
         assert!(result.is_err());
@@ -309,11 +1263,10 @@ mod tests {
 
         let result = run(
             special_content.to_string(),
-            output_path.clone(),
-            200,
-            "M".to_string(),
-            10,
-            false,
+            EncodeOptions {
+                output: output_path.clone(),
+                ..Default::default()
+            },
         );
 
         assert!(result.is_ok());
@@ -330,11 +1283,10 @@ mod tests {
 
         let result = run(
             url.to_string(),
-            output_path.clone(),
-            200,
-            "M".to_string(),
-            10,
-            false,
+            EncodeOptions {
+                output: output_path.clone(),
+                ..Default::default()
+            },
         );
 
         assert!(result.is_ok());
@@ -2,5 +2,6 @@
 //!
 //! Contains CLI command implementations for encoding and decoding QR codes.
 
+pub mod batch;
 pub mod decode;
 pub mod encode;
@@ -3,15 +3,27 @@
 //! Command-line interface for the rqr QR code tool.
 //! Provides easy-to-use commands for encoding and decoding QR codes.
 
-mod commands;
-mod qr;
-mod utils;
-
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
-use commands::{decode::run as decode, encode::run as encode};
-use utils::error::Result;
+use clap::{Args, Parser, Subcommand};
+use rqr::{
+    commands::{
+        batch::{BatchOptions, run as encode_batch},
+        decode::{DecodeFormat, DecodeOptions, run as decode},
+        encode::{
+            ContentSource, EncodeOptions, run as encode, run_binary as encode_binary,
+            run_wifi as encode_wifi,
+        },
+    },
+    qr::{
+        output::{FitMode, PhysicalSize, RgbaColor},
+        style::{EyeShape, ModuleShape, Style},
+        symbol::Symbology,
+        terminal::{TerminalGraphics, TerminalMode},
+        wifi::{WifiAuth, WifiNetwork},
+    },
+    utils::error::Result,
+};
 
 /// Main CLI structure for the rqr tool
 #[derive(Parser)]
@@ -24,50 +36,283 @@ struct Cli {
 
 /// Available subcommands
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 enum Commands {
     /// Encode a QR code from text
     Encode {
-        /// Text content to encode
-        content: String,
-        /// Output file path
-        #[arg(short, long, default_value = "rqr.png")]
-        output: PathBuf,
-        /// QR code size in pixels
-        #[arg(short, long, default_value = "200")]
-        size: u32,
-        /// Error correction level (L, M, Q, H)
-        #[arg(short, long, default_value = "M")]
-        error_correction: String,
-        /// Margin size
-        #[arg(short, long, default_value = "10")]
-        margin: u32,
-        /// Output to terminal instead of file
-        #[arg(short, long)]
-        terminal: bool,
+        /// Text content to encode, or - to read it from stdin
+        #[arg(
+            required_unless_present_any = ["input_file", "batch"],
+            conflicts_with_all = ["input_file", "batch"]
+        )]
+        content: Option<String>,
+        /// Read the content to encode from a file
+        #[arg(long)]
+        input_file: Option<PathBuf>,
+        /// Encode one code per row of a CSV or JSON Lines file; --output
+        /// names each file from columns, e.g. 'out/{sku}.png'
+        #[arg(long, requires = "content_template", conflicts_with_all = ["input_file", "terminal", "binary"])]
+        batch: Option<PathBuf>,
+        /// Content template for --batch, e.g. '{url}'
+        #[arg(long = "content", value_name = "TEMPLATE", requires = "batch")]
+        content_template: Option<String>,
+        /// Where --batch writes its manifest (.csv or .jsonl), next to the outputs by default
+        #[arg(long, requires = "batch")]
+        manifest: Option<PathBuf>,
+        /// Keep a trailing newline when reading from stdin or a file
+        #[arg(long)]
+        keep_newline: bool,
+        /// Encode the input as raw bytes, exactly as read
+        #[arg(long, conflicts_with_all = ["keep_newline", "gs1"])]
+        binary: bool,
+        #[command(flatten)]
+        args: EncodeArgs,
+    },
+    /// Encode a WiFi network for phones to join, escaping special characters
+    Wifi {
+        /// Network name (SSID)
+        #[arg(long)]
+        ssid: String,
+        /// Network password
+        #[arg(long)]
+        password: Option<String>,
+        /// Security (WPA, WPA3, nopass) [default: WPA with a password,
+        /// nopass without]
+        #[arg(long)]
+        auth: Option<WifiAuth>,
+        /// The network does not broadcast its name
+        #[arg(long)]
+        hidden: bool,
+        #[command(flatten)]
+        args: EncodeArgs,
     },
     /// Decode a QR code from an image file or URL
     Decode {
-        /// Path to the image file or URL
-        input: String,
+        /// Image paths, directories, glob patterns, http(s):// or file://
+        /// URLs, data: URIs, or - for stdin
+        #[arg(required = true)]
+        inputs: Vec<String>,
+        /// Scan directories recursively
+        #[arg(short, long)]
+        recursive: bool,
+        /// Output format (text, raw, json, ndjson)
+        #[arg(short, long, default_value = "text")]
+        format: DecodeFormat,
+        /// Only report codes of this symbology (qr, micro, rmqr, datamatrix,
+        /// aztec, pdf417, code128, ean13, upca, code39, itf)
+        #[arg(long)]
+        symbology: Option<Symbology>,
+        /// Print WiFi passwords instead of redacting them (raw output is
+        /// never redacted)
+        #[arg(long)]
+        show_secrets: bool,
     },
 }
 
+/// Rendering and symbol options shared by `encode` and `wifi`
+#[derive(Args)]
+struct EncodeArgs {
+    /// Output file path (format is chosen by extension: png, jpg, webp, bmp, gif, tiff, pnm, svg, pdf)
+    #[arg(short, long, default_value = "rqr.png")]
+    output: PathBuf,
+    /// QR code size in pixels
+    #[arg(short, long, default_value = "200")]
+    size: u32,
+    /// Error correction level (L, M, Q, H); Data Matrix has a fixed level,
+    /// Aztec adds 10, 23, 36 or 50% of the data, PDF417 raises its
+    /// recommended security level by 0 to 3, 1D barcodes have none
+    #[arg(short, long, default_value = "M")]
+    error_correction: String,
+    /// Margin size in modules [default: 10, or 2 for Micro QR, rMQR, Data
+    /// Matrix, Aztec and PDF417, 11 for EAN-13 and UPC-A]
+    #[arg(short, long)]
+    margin: Option<u32>,
+    /// Output to terminal instead of file
+    #[arg(short, long)]
+    terminal: bool,
+    /// Terminal drawing: half (two modules per cell) or full (two cells per module)
+    #[arg(long, default_value = "half", requires = "terminal")]
+    terminal_mode: TerminalMode,
+    /// Draw light modules in the terminal, for dark themes
+    #[arg(long, requires = "terminal")]
+    invert: bool,
+    /// Show the rendered image inline with a terminal graphics protocol
+    /// (sixel, kitty, iterm)
+    #[arg(long, requires = "terminal", conflicts_with_all = ["invert", "terminal_mode"])]
+    terminal_graphics: Option<TerminalGraphics>,
+    /// How raster output reaches the requested size (pad, scale, exact-modules)
+    #[arg(long, default_value = "pad")]
+    fit: FitMode,
+    /// Module colour as hex RGB or RGBA (e.g. #1e3a8a)
+    #[arg(long)]
+    fg: Option<RgbaColor>,
+    /// Background colour as hex RGB or RGBA, or "transparent"
+    #[arg(long)]
+    bg: Option<RgbaColor>,
+    /// Image to place over the centre of the code (raster output only)
+    #[arg(long)]
+    logo: Option<PathBuf>,
+    /// Logo width as a fraction of the code width
+    #[arg(long, default_value = "0.2")]
+    logo_scale: f64,
+    /// Physical width of the code for PDF output, margin not included (e.g. 25mm, 1in, 72pt)
+    #[arg(long)]
+    physical_size: Option<PhysicalSize>,
+    /// Quality of JPEG output (1-100)
+    #[arg(long)]
+    quality: Option<u8>,
+    /// Shape of the data modules (square, dot, rounded, liquid)
+    #[arg(long, default_value = "square")]
+    module_shape: ModuleShape,
+    /// Shape inside the three corner eyes (square, rounded, circle)
+    #[arg(long, default_value = "square")]
+    eye_shape: EyeShape,
+    /// Colour of the three corner eyes, the module colour by default
+    #[arg(long)]
+    eye_color: Option<RgbaColor>,
+    /// Kind of code to encode: qr, micro for Micro QR (M1-M4), rmqr for
+    /// rectangular Micro QR (R7x43-R17x139, error correction M or H),
+    /// datamatrix for Data Matrix ECC 200 (10x10-144x144, 8x18-16x48),
+    /// aztec for Aztec (15x15-27x27 compact, 19x19-151x151 full-range),
+    /// pdf417 for PDF417 stacked barcodes (1-30 columns, 3-90 rows), or
+    /// the 1D barcodes code128 (ASCII), ean13 (12 or 13 digits), upca (11
+    /// or 12 digits), code39 (0-9, A-Z, space and - . $ / + %) and itf
+    /// (pairs of digits), drawn with their text beneath
+    #[arg(long, default_value = "qr")]
+    symbology: Symbology,
+    /// Always use this symbol version (1-40, 1-4 for Micro QR, 1-32 for
+    /// rMQR flattest first, 1-24 for square and 25-30 for rectangular
+    /// Data Matrix, 1-4 for compact and 5-36 for full-range Aztec),
+    /// failing if the content doesn't fit; PDF417 uses --columns and
+    /// --rows
+    #[arg(long, conflicts_with = "min_version")]
+    version: Option<u8>,
+    /// Never use a symbol version below this one (1-40, 1-4 for Micro QR,
+    /// 1-32 for rMQR, 1-30 for Data Matrix; 25 picks a rectangle, 1-36
+    /// for Aztec)
+    #[arg(long)]
+    min_version: Option<u8>,
+    /// Always use this mask pattern (0-7, 0-3 for Micro QR) instead of
+    /// the lowest-penalty one; rMQR has a single mask, Data Matrix, Aztec
+    /// and PDF417 none
+    #[arg(long)]
+    mask: Option<u8>,
+    /// Encode GS1 element strings such as (01)09521234543213(10)ABC123
+    /// as a GS1 Data Matrix
+    #[arg(long)]
+    gs1: bool,
+    /// Error correction to add to an Aztec symbol, in percent of the
+    /// data (5-95), instead of the share set by --error-correction
+    #[arg(long)]
+    ec_percent: Option<u8>,
+    /// Number of PDF417 data columns (1-30), chosen with the rows for a
+    /// symbol about three times as wide as high when unset
+    #[arg(long)]
+    columns: Option<u8>,
+    /// Number of PDF417 rows (3-90), as many as the data needs when unset
+    #[arg(long)]
+    rows: Option<u8>,
+    /// PDF417 security level (0-8), adding 2 to 512 error correction
+    /// codewords, instead of the level set by --error-correction
+    #[arg(long)]
+    security_level: Option<u8>,
+}
+
+impl EncodeArgs {
+    fn into_options(self) -> EncodeOptions {
+        EncodeOptions {
+            output: self.output,
+            size: self.size,
+            error_correction: self.error_correction,
+            margin: self.margin,
+            terminal: self.terminal,
+            terminal_mode: self.terminal_mode,
+            invert: self.invert,
+            terminal_graphics: self.terminal_graphics,
+            fit: self.fit,
+            foreground: self.fg,
+            background: self.bg,
+            logo: self.logo,
+            logo_scale: self.logo_scale,
+            physical_size: self.physical_size,
+            style: Style {
+                module_shape: self.module_shape,
+                eye_shape: self.eye_shape,
+                eye_color: self.eye_color,
+            },
+            quality: self.quality,
+            symbology: self.symbology,
+            version: self.version,
+            min_version: self.min_version,
+            mask: self.mask,
+            gs1: self.gs1,
+            ec_percent: self.ec_percent,
+            columns: self.columns,
+            rows: self.rows,
+            security_level: self.security_level,
+        }
+    }
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
         Commands::Encode {
             content,
-            output,
-            size,
-            error_correction,
-            margin,
-            terminal,
+            input_file,
+            batch,
+            content_template,
+            manifest,
+            keep_newline,
+            binary,
+            args,
         } => {
-            encode(content, output, size, error_correction, margin, terminal)?;
+            let options = args.into_options();
+            if let Some(input) = batch {
+                let content = content_template.unwrap_or_default();
+                encode_batch(
+                    BatchOptions {
+                        input,
+                        content,
+                        manifest,
+                    },
+                    options,
+                )?;
+            } else if binary {
+                let source = ContentSource::from_args(content, input_file)?;
+                encode_binary(source.read_bytes()?, options)?;
+            } else {
+                let source = ContentSource::from_args(content, input_file)?;
+                encode(source.read(keep_newline)?, options)?;
+            }
         }
-        Commands::Decode { input } => {
-            decode(input)?;
+        Commands::Wifi {
+            ssid,
+            password,
+            auth,
+            hidden,
+            args,
+        } => {
+            let network = WifiNetwork::new(&ssid, password.as_deref(), auth, hidden)?;
+            encode_wifi(network, args.into_options())?;
+        }
+        Commands::Decode {
+            inputs,
+            recursive,
+            format,
+            symbology,
+            show_secrets,
+        } => {
+            decode(
+                inputs,
+                DecodeOptions {
+                    format,
+                    recursive,
+                    symbology,
+                    show_secrets,
+                },
+            )?;
         }
     }
 
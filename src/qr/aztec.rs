@@ -0,0 +1,1515 @@
+use std::{ops::RangeInclusive, rc::Rc};
+
+use image::GrayImage;
+use qrcode::{Color, EcLevel};
+
+use crate::qr::{
+    decoded::{DecodedQr, Point},
+    grid::{self, Bitmap, ORIENTATIONS},
+    reed_solomon::{GaloisField, ReedSolomon},
+    segments::BitReader,
+    symbol::{ErrorCorrection, Symbol, SymbolVersion, Symbology},
+};
+
+/// Versions 1 to 4 are compact symbols of one to four layers, versions 5
+/// to 36 full-range symbols of one to 32 layers
+pub(crate) const LAST_COMPACT: u8 = 4;
+
+/// Full-range symbols with fewer layers hold less than the compact symbol
+/// of the same size, so they are only used when asked for
+const FIRST_FULL_LAYERS: usize = 4;
+
+/// Most data codewords the mode message of a compact symbol can count
+const MAX_COMPACT_WORDS: usize = 64;
+
+/// Punctuation mode characters with codes 6 to 30
+const PUNCTUATION: &[u8; 25] = b"!\"#$%&'()*+,-./:;<=>?[]{}";
+
+/// Group separator, transmitted for FLG(0)
+const GS: u8 = 0x1d;
+
+/// The character modes of the high-level encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Upper,
+    Lower,
+    Digit,
+    Mixed,
+    Punct,
+}
+
+const MODES: [Mode; 5] = [
+    Mode::Upper,
+    Mode::Lower,
+    Mode::Digit,
+    Mode::Mixed,
+    Mode::Punct,
+];
+
+/// The bits and value of the shortest latch from one mode to another,
+/// indexed by the modes' positions in `MODES`
+const LATCHES: [[(usize, u32); 5]; 5] = [
+    // Upper to L/L, D/L, M/L, and M/L P/L
+    [(0, 0), (5, 28), (5, 30), (5, 29), (10, 29 << 5 | 30)],
+    // Lower to D/L U/L, D/L, M/L, and M/L P/L
+    [
+        (9, 30 << 4 | 14),
+        (0, 0),
+        (5, 30),
+        (5, 29),
+        (10, 29 << 5 | 30),
+    ],
+    // Digit to U/L, U/L L/L, U/L M/L, and U/L M/L P/L
+    [
+        (4, 14),
+        (9, 14 << 5 | 28),
+        (0, 0),
+        (9, 14 << 5 | 29),
+        (14, 14 << 10 | 29 << 5 | 30),
+    ],
+    // Mixed to U/L, L/L, U/L D/L, and P/L
+    [(5, 29), (5, 28), (10, 29 << 5 | 30), (0, 0), (5, 30)],
+    // Punct to U/L, U/L L/L, U/L D/L, and U/L M/L
+    [
+        (5, 31),
+        (10, 31 << 5 | 28),
+        (10, 31 << 5 | 30),
+        (10, 31 << 5 | 29),
+        (0, 0),
+    ],
+];
+
+/// What a code means in one of the character modes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Code {
+    Char(u8),
+    /// CR LF, ". ", ", " or ": " in the punctuation mode
+    Pair(&'static [u8; 2]),
+    /// Switch mode for the next character only
+    Shift(Mode),
+    Latch(Mode),
+    /// B/S: a count of bytes follows, each in eight bits
+    BinaryShift,
+    /// FLG(n): FNC1, or an ECI designator of n digits
+    Flag,
+}
+
+impl Mode {
+    /// Bits in each code: four in the digit mode, five in the others
+    fn bits(self) -> usize {
+        if self == Mode::Digit { 4 } else { 5 }
+    }
+
+    /// The meaning of a code, from the tables in ISO/IEC 24778
+    fn code(self, value: u32) -> Code {
+        let offset = |first: u8| first + (value - 2) as u8;
+        match (self, value) {
+            (Mode::Punct, 0) => Code::Flag,
+            (Mode::Punct, 1) => Code::Char(b'\r'),
+            (Mode::Punct, 2) => Code::Pair(b"\r\n"),
+            (Mode::Punct, 3) => Code::Pair(b". "),
+            (Mode::Punct, 4) => Code::Pair(b", "),
+            (Mode::Punct, 5) => Code::Pair(b": "),
+            (Mode::Punct, 6..=30) => Code::Char(PUNCTUATION[value as usize - 6]),
+            (Mode::Punct, _) => Code::Latch(Mode::Upper),
+            (_, 0) => Code::Shift(Mode::Punct),
+            (_, 1) => Code::Char(b' '),
+            (Mode::Digit, 2..=11) => Code::Char(offset(b'0')),
+            (Mode::Digit, 12) => Code::Char(b','),
+            (Mode::Digit, 13) => Code::Char(b'.'),
+            (Mode::Digit, 14) => Code::Latch(Mode::Upper),
+            (Mode::Digit, _) => Code::Shift(Mode::Upper),
+            (_, 31) => Code::BinaryShift,
+            (Mode::Upper, 2..=27) => Code::Char(offset(b'A')),
+            (Mode::Lower, 2..=27) => Code::Char(offset(b'a')),
+            // Control characters 1 to 13, then 27 to 31
+            (Mode::Mixed, 2..=14) => Code::Char(value as u8 - 1),
+            (Mode::Mixed, 15..=19) => Code::Char(value as u8 + 12),
+            (Mode::Mixed, 20..=27) => Code::Char(b"@\\^_`|~\x7f"[value as usize - 20]),
+            (Mode::Upper | Mode::Mixed, 28) => Code::Latch(Mode::Lower),
+            (Mode::Lower, 28) => Code::Shift(Mode::Upper),
+            (Mode::Mixed, 29) => Code::Latch(Mode::Upper),
+            (Mode::Mixed, _) => Code::Latch(Mode::Punct),
+            (_, 29) => Code::Latch(Mode::Mixed),
+            (_, _) => Code::Latch(Mode::Digit),
+        }
+    }
+
+    /// The code of a shift from this mode to another, if there is one
+    fn shift(self, to: Mode) -> Option<u32> {
+        match (self, to) {
+            (Mode::Punct, _) | (_, Mode::Lower | Mode::Digit | Mode::Mixed) => None,
+            (_, Mode::Punct) => Some(0),
+            (Mode::Lower, Mode::Upper) => Some(28),
+            (Mode::Digit, Mode::Upper) => Some(15),
+            _ => None,
+        }
+    }
+}
+
+/// The code of every byte in each mode that has one, zero for none
+fn char_codes() -> [[u32; 256]; 5] {
+    let mut codes = [[0; 256]; 5];
+    for (i, mode) in MODES.into_iter().enumerate() {
+        for value in 1..1 << mode.bits() {
+            if let Code::Char(c) = mode.code(value) {
+                codes[i][usize::from(c)] = value;
+            }
+        }
+    }
+    codes
+}
+
+/// One step of an encoding, linked to the steps before it
+struct Token {
+    previous: Option<Rc<Token>>,
+    kind: TokenKind,
+}
+
+enum TokenKind {
+    Code {
+        value: u32,
+        bits: usize,
+    },
+    /// B/S with `count` bytes of the data from `start`
+    Binary {
+        start: usize,
+        count: usize,
+    },
+}
+
+/// A partial encoding: the mode it ends in, the bytes of a binary shift
+/// still open, and its length in bits
+#[derive(Clone)]
+struct State {
+    mode: Mode,
+    tokens: Option<Rc<Token>>,
+    binary_bytes: usize,
+    bit_count: usize,
+}
+
+impl State {
+    fn push(&self, kind: TokenKind) -> Option<Rc<Token>> {
+        Some(Rc::new(Token {
+            previous: self.tokens.clone(),
+            kind,
+        }))
+    }
+
+    /// Latch to a mode, unless already in it, and append a code
+    fn latch_and_append(&self, mode: Mode, value: u32) -> State {
+        let mut state = self.clone();
+        if mode != self.mode {
+            let (bits, latch) = LATCHES[self.mode as usize][mode as usize];
+            state.tokens = state.push(TokenKind::Code { value: latch, bits });
+            state.bit_count += bits;
+        }
+        state.tokens = state.push(TokenKind::Code {
+            value,
+            bits: mode.bits(),
+        });
+        state.mode = mode;
+        state.bit_count += mode.bits();
+        state
+    }
+
+    /// Append a code from another mode after a shift to it
+    fn shift_and_append(&self, mode: Mode, value: u32) -> State {
+        let mut state = self.clone();
+        let shift = self.mode.shift(mode).unwrap_or_default();
+        let bits = self.mode.bits();
+        state.tokens = state.push(TokenKind::Code { value: shift, bits });
+        state.tokens = state.push(TokenKind::Code { value, bits: 5 });
+        state.bit_count += bits + 5;
+        state
+    }
+
+    /// Add the byte at `index` to the binary shift, opening one if needed
+    fn add_binary_byte(&self, index: usize) -> State {
+        let mut state = self.clone();
+        // B/S is only in the upper, lower and mixed modes
+        if matches!(self.mode, Mode::Punct | Mode::Digit) {
+            let (bits, latch) = LATCHES[self.mode as usize][Mode::Upper as usize];
+            state.tokens = state.push(TokenKind::Code { value: latch, bits });
+            state.bit_count += bits;
+            state.mode = Mode::Upper;
+        }
+        state.bit_count += match self.binary_bytes {
+            0 | 31 => 18,
+            62 => 9,
+            _ => 8,
+        };
+        state.binary_bytes += 1;
+        if state.binary_bytes == 2047 + 31 {
+            state = state.end_binary_shift(index + 1);
+        }
+        state
+    }
+
+    /// Close the open binary shift, which ends before `index`
+    fn end_binary_shift(&self, index: usize) -> State {
+        if self.binary_bytes == 0 {
+            return self.clone();
+        }
+        let mut state = self.clone();
+        state.tokens = state.push(TokenKind::Binary {
+            start: index - self.binary_bytes,
+            count: self.binary_bytes,
+        });
+        state.binary_bytes = 0;
+        state
+    }
+
+    /// Whether this state can reach the other's mode and open binary
+    /// shift in no more bits than the other already has
+    fn is_better_than_or_equal_to(&self, other: &State) -> bool {
+        let mut bit_count = self.bit_count + LATCHES[self.mode as usize][other.mode as usize].0;
+        if self.binary_bytes < other.binary_bytes {
+            bit_count +=
+                binary_shift_cost(other.binary_bytes) - binary_shift_cost(self.binary_bytes);
+        } else if self.binary_bytes > other.binary_bytes && other.binary_bytes > 0 {
+            bit_count += 10;
+        }
+        bit_count <= other.bit_count
+    }
+
+    fn into_bits(self, data: &[u8]) -> Vec<bool> {
+        let mut tokens = Vec::new();
+        let mut next = self.end_binary_shift(data.len()).tokens;
+        while let Some(token) = next {
+            next = token.previous.clone();
+            tokens.push(token);
+        }
+
+        let mut bits = Vec::new();
+        for token in tokens.iter().rev() {
+            match token.kind {
+                TokenKind::Code { value, bits: count } => push_bits(&mut bits, value, count),
+                TokenKind::Binary { start, count } => {
+                    for (i, &byte) in data[start..start + count].iter().enumerate() {
+                        // Up to 62 bytes are sent as one or two short
+                        // shifts, more with an 11 bit length
+                        if i == 0 || (i == 31 && count <= 62) {
+                            push_bits(&mut bits, 31, 5);
+                            if count > 62 {
+                                push_bits(&mut bits, (count - 31) as u32, 16);
+                            } else if i == 0 {
+                                push_bits(&mut bits, count.min(31) as u32, 5);
+                            } else {
+                                push_bits(&mut bits, (count - 31) as u32, 5);
+                            }
+                        }
+                        push_bits(&mut bits, u32::from(byte), 8);
+                    }
+                }
+            }
+        }
+        bits
+    }
+}
+
+/// Bits of B/S codes and lengths for a binary shift of `count` bytes
+fn binary_shift_cost(count: usize) -> usize {
+    match count {
+        0 => 0,
+        1..=31 => 10,
+        32..=62 => 20,
+        _ => 21,
+    }
+}
+
+fn push_bits(bits: &mut Vec<bool>, value: u32, count: usize) {
+    bits.extend((0..count).rev().map(|i| value >> i & 1 == 1));
+}
+
+/// Encode data in the character modes, in as few bits as possible
+///
+/// Every prefix of the data keeps the shortest encodings ending in each
+/// mode, with and without an open binary shift; the shortest one for the
+/// whole data wins.
+fn high_level_encode(data: &[u8]) -> Vec<bool> {
+    let codes = char_codes();
+    let mut states = vec![State {
+        mode: Mode::Upper,
+        tokens: None,
+        binary_bytes: 0,
+        bit_count: 0,
+    }];
+
+    let mut index = 0;
+    while index < data.len() {
+        let pair = match (data[index], data.get(index + 1)) {
+            (b'\r', Some(b'\n')) => 2,
+            (b'.', Some(b' ')) => 3,
+            (b',', Some(b' ')) => 4,
+            (b':', Some(b' ')) => 5,
+            _ => 0,
+        };
+        let mut next = Vec::new();
+        for state in &states {
+            if pair > 0 {
+                add_pair(state, index, pair, &mut next);
+            } else {
+                add_char(state, data[index], index, &codes, &mut next);
+            }
+        }
+        states = simplify(next);
+        index += if pair > 0 { 2 } else { 1 };
+    }
+
+    states
+        .into_iter()
+        .min_by_key(|state| state.bit_count)
+        .map(|state| state.into_bits(data))
+        .unwrap_or_default()
+}
+
+/// The ways to extend an encoding by one byte
+fn add_char(state: &State, byte: u8, index: usize, codes: &[[u32; 256]; 5], next: &mut Vec<State>) {
+    let in_current = codes[state.mode as usize][usize::from(byte)] > 0;
+    let ended = state.end_binary_shift(index);
+    for mode in MODES {
+        let value = codes[mode as usize][usize::from(byte)];
+        if value == 0 {
+            continue;
+        }
+        // Latching away only pays when the current mode lacks the
+        // character, except into the compact digit mode
+        if !in_current || mode == state.mode || mode == Mode::Digit {
+            next.push(ended.latch_and_append(mode, value));
+        }
+        if !in_current && state.mode.shift(mode).is_some() {
+            next.push(ended.shift_and_append(mode, value));
+        }
+    }
+    if state.binary_bytes > 0 || !in_current {
+        next.push(state.add_binary_byte(index));
+    }
+}
+
+/// The ways to extend an encoding by a pair with a punctuation code
+fn add_pair(state: &State, index: usize, pair: u32, next: &mut Vec<State>) {
+    let ended = state.end_binary_shift(index);
+    next.push(ended.latch_and_append(Mode::Punct, pair));
+    if state.mode != Mode::Punct {
+        next.push(ended.shift_and_append(Mode::Punct, pair));
+    }
+    if pair == 3 || pair == 4 {
+        // A period or comma, then a space, in the digit mode
+        next.push(
+            ended
+                .latch_and_append(Mode::Digit, 16 - pair)
+                .latch_and_append(Mode::Digit, 1),
+        );
+    }
+    if state.binary_bytes > 0 {
+        next.push(state.add_binary_byte(index).add_binary_byte(index + 1));
+    }
+}
+
+/// Drop the states another state is at least as good as
+fn simplify(states: Vec<State>) -> Vec<State> {
+    let mut kept: Vec<State> = Vec::with_capacity(states.len());
+    for state in states {
+        if kept
+            .iter()
+            .any(|other| other.is_better_than_or_equal_to(&state))
+        {
+            continue;
+        }
+        kept.retain(|other| !state.is_better_than_or_equal_to(other));
+        kept.push(state);
+    }
+    kept
+}
+
+/// Split bits into codewords, so that none is all zeros or all ones
+///
+/// A word whose first `word_size - 1` bits are all the same gets the
+/// opposite bit added, and the bit it pushes out starts the next word. The
+/// last word is padded with ones.
+fn stuff_bits(bits: &[bool], word_size: usize) -> Vec<u16> {
+    let mask = (1u16 << word_size) - 2;
+    let mut words = Vec::new();
+    let mut i = 0;
+    while i < bits.len() {
+        let word = (0..word_size).fold(0u16, |word, j| {
+            word << 1 | u16::from(bits.get(i + j).copied().unwrap_or(true))
+        });
+        if word & mask == mask {
+            words.push(word & mask);
+            i += word_size - 1;
+        } else if word & mask == 0 {
+            words.push(word | 1);
+            i += word_size - 1;
+        } else {
+            words.push(word);
+            i += word_size;
+        }
+    }
+    words
+}
+
+/// Undo `stuff_bits`, or `None` for a word that cannot have been stuffed
+fn unstuff_bits(words: &[u16], word_size: usize) -> Option<Vec<bool>> {
+    let mask = (1u16 << word_size) - 1;
+    let mut bits = Vec::with_capacity(words.len() * word_size);
+    for &word in words {
+        if word == 0 || word == mask {
+            return None;
+        }
+        if word == 1 || word == mask - 1 {
+            bits.extend(std::iter::repeat_n(word > 1, word_size - 1));
+        } else {
+            bits.extend((0..word_size).rev().map(|i| word >> i & 1 == 1));
+        }
+    }
+    Some(bits)
+}
+
+/// The Galois field of the codewords of a word size
+fn field(word_size: usize) -> GaloisField {
+    match word_size {
+        4 => GaloisField::new(0x13, 16),
+        6 => GaloisField::new(0x43, 64),
+        8 => GaloisField::new(0x12d, 256),
+        10 => GaloisField::new(0x409, 1024),
+        _ => GaloisField::new(0x1069, 4096),
+    }
+}
+
+/// Append error correction words to fill `total` words
+fn add_check_words(words: &[u16], total: usize, word_size: usize) -> Vec<u16> {
+    let rs = ReedSolomon::new(field(word_size), 1);
+    let mut codewords = words.to_vec();
+    codewords.extend(rs.encode(words, total - words.len()));
+    codewords
+}
+
+/// Codewords as bits, most significant bit first
+fn word_bits(words: &[u16], word_size: usize) -> impl Iterator<Item = bool> + '_ {
+    words
+        .iter()
+        .flat_map(move |&word| (0..word_size).rev().map(move |i| word >> i & 1 == 1))
+}
+
+fn bits_value(bits: &[bool]) -> u16 {
+    bits.iter()
+        .fold(0, |value, &bit| value << 1 | u16::from(bit))
+}
+
+/// A symbol size: compact or full-range, and the number of data layers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Layout {
+    compact: bool,
+    layers: usize,
+}
+
+impl Layout {
+    fn from_version(version: u8) -> Option<Self> {
+        let layers = usize::from(version);
+        match version {
+            1..=LAST_COMPACT => Some(Self {
+                compact: true,
+                layers,
+            }),
+            5..=36 => Some(Self {
+                compact: false,
+                layers: layers - usize::from(LAST_COMPACT),
+            }),
+            _ => None,
+        }
+    }
+
+    fn version(self) -> u8 {
+        if self.compact {
+            self.layers as u8
+        } else {
+            self.layers as u8 + LAST_COMPACT
+        }
+    }
+
+    /// Modules across the symbol, leaving out the reference grid
+    fn base_size(self) -> usize {
+        (if self.compact { 11 } else { 14 }) + 4 * self.layers
+    }
+
+    /// Modules across the symbol
+    fn size(self) -> usize {
+        let base = self.base_size();
+        if self.compact {
+            base
+        } else {
+            base + 1 + 2 * ((base / 2 - 1) / 15)
+        }
+    }
+
+    /// Distance from the centre to the ring with the mode message
+    fn ring(self) -> isize {
+        if self.compact { 5 } else { 7 }
+    }
+
+    fn total_bits(self) -> usize {
+        (if self.compact { 88 } else { 112 } + 16 * self.layers) * self.layers
+    }
+
+    fn word_size(self) -> usize {
+        match self.layers {
+            1..=2 => 6,
+            3..=8 => 8,
+            9..=22 => 10,
+            _ => 12,
+        }
+    }
+
+    fn total_words(self) -> usize {
+        self.total_bits() / self.word_size()
+    }
+
+    /// Where each row and column of the base symbol is once the reference
+    /// grid lines are put in
+    fn alignment_map(self) -> Vec<usize> {
+        let base = self.base_size();
+        if self.compact {
+            return (0..base).collect();
+        }
+        let (middle, centre) = (base / 2, self.size() / 2);
+        let mut map = vec![0; base];
+        for i in 0..middle {
+            let offset = i + i / 15;
+            map[middle - i - 1] = centre - offset - 1;
+            map[middle + i] = centre + offset + 1;
+        }
+        map
+    }
+
+    /// The module of each data bit, as (x, y)
+    ///
+    /// The layers spiral in from the outside, each two modules deep and
+    /// read around the symbol counterclockwise from its top left corner.
+    fn data_positions(self) -> Vec<(usize, usize)> {
+        let map = self.alignment_map();
+        let base = self.base_size();
+        let mut positions = vec![(0, 0); self.total_bits()];
+        let mut offset = 0;
+        for i in 0..self.layers {
+            let row_size = (self.layers - i) * 4 + if self.compact { 9 } else { 12 };
+            let (low, high) = (i * 2, base - 1 - i * 2);
+            for j in 0..row_size {
+                for k in 0..2 {
+                    let at = offset + j * 2 + k;
+                    positions[at] = (map[low + k], map[low + j]);
+                    positions[at + row_size * 2] = (map[low + j], map[high - k]);
+                    positions[at + row_size * 4] = (map[high - k], map[high - j]);
+                    positions[at + row_size * 6] = (map[high - j], map[low + k]);
+                }
+            }
+            offset += row_size * 8;
+        }
+        positions
+    }
+
+    /// The module of each mode message bit, clockwise around the bullseye
+    fn mode_positions(self) -> Vec<(usize, usize)> {
+        let c = self.size() / 2;
+        let s = self.ring() as usize;
+        let side = if self.compact { 7 } else { 10 };
+        let mut positions = vec![(0, 0); side * 4];
+        for i in 0..side {
+            let offset = if self.compact {
+                c - 3 + i
+            } else {
+                // Skipping the reference grid line through the centre
+                c - 5 + i + i / 5
+            };
+            positions[i] = (offset, c - s);
+            positions[i + side] = (c + s, offset);
+            positions[side * 3 - 1 - i] = (offset, c + s);
+            positions[side * 4 - 1 - i] = (c - s, offset);
+        }
+        positions
+    }
+
+    /// The colour of a bullseye, orientation mark or reference grid
+    /// module, `None` for data and mode message modules
+    fn fixed(self, x: usize, y: usize) -> Option<bool> {
+        let c = (self.size() / 2) as isize;
+        let (dx, dy) = (x as isize - c, y as isize - c);
+        let (s, distance) = (self.ring(), dx.abs().max(dy.abs()));
+        if distance < s {
+            Some(distance % 2 == 0)
+        } else if distance == s && dx.abs().min(dy.abs()) >= s - 1 {
+            Some(is_orientation_mark(dx, dy, s))
+        } else if !self.compact && dx % 16 == 0 {
+            Some(dy % 2 == 0)
+        } else if !self.compact && dy % 16 == 0 {
+            Some(dx % 2 == 0)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether a module near a corner of the mode message ring is dark: three
+/// at the top left, two at the top right, one at the bottom right
+fn is_orientation_mark(dx: isize, dy: isize, s: isize) -> bool {
+    [
+        (-s, -s),
+        (1 - s, -s),
+        (-s, 1 - s),
+        (s, -s),
+        (s, 1 - s),
+        (s, s - 1),
+    ]
+    .contains(&(dx, dy))
+}
+
+/// The version as printed in summaries: modules across, e.g. `19x19
+/// compact` or `31x31` for a full-range symbol
+pub(crate) fn label(version: u8) -> String {
+    match Layout::from_version(version) {
+        Some(layout) if layout.compact => format!("{0}x{0} compact", layout.size()),
+        Some(layout) => format!("{0}x{0}", layout.size()),
+        None => version.to_string(),
+    }
+}
+
+/// The share of error correction used for an error correction level, in
+/// percent of the data: 10, 23 (the minimum ISO/IEC 24778 recommends),
+/// 36 or 50
+pub(crate) fn level_ec_percent(ec_level: EcLevel) -> u8 {
+    match ec_level {
+        EcLevel::L => 10,
+        EcLevel::M => 23,
+        EcLevel::Q => 36,
+        EcLevel::H => 50,
+    }
+}
+
+/// Encode data in the smallest allowed Aztec symbol that fits
+///
+/// The data is encoded in the character modes or, where shorter, as
+/// binary. The symbol keeps at least `ec_percent` percent of the data's
+/// size, plus three codewords, for error correction.
+///
+/// # Arguments
+/// * `data` - The bytes to encode
+/// * `ec_percent` - Error correction to add, in percent of the data
+/// * `versions` - Versions to try, from 1 to 4 for compact symbols of one
+///   to four layers and 5 to 36 for full-range symbols of one to 32
+///
+/// # Returns
+/// Returns the symbol, or `None` if the data fits none of the versions
+pub(crate) fn encode(data: &[u8], ec_percent: u8, versions: RangeInclusive<u8>) -> Option<Symbol> {
+    let bits = high_level_encode(data);
+    let ec_bits = bits.len() * usize::from(ec_percent) / 100 + 11;
+    let from_compact = *versions.start() <= LAST_COMPACT;
+
+    versions
+        .filter_map(Layout::from_version)
+        .filter(|layout| layout.compact || layout.layers >= FIRST_FULL_LAYERS || !from_compact)
+        .find_map(|layout| {
+            let total_bits = layout.total_bits();
+            if bits.len() + ec_bits > total_bits {
+                return None;
+            }
+            let word_size = layout.word_size();
+            // The mode message counts at least one data codeword, so empty
+            // data is padded with ones, which decode to nothing
+            let words = if bits.is_empty() {
+                stuff_bits(&[true], word_size)
+            } else {
+                stuff_bits(&bits, word_size)
+            };
+            if (layout.compact && words.len() > MAX_COMPACT_WORDS)
+                || words.len() * word_size + ec_bits > total_bits - total_bits % word_size
+            {
+                return None;
+            }
+            Some(draw(layout, &words, ec_percent))
+        })
+}
+
+/// The mode message: the layers and data codewords, with error correction
+fn mode_message(layout: Layout, data_words: usize) -> Vec<bool> {
+    let (value, count) = if layout.compact {
+        ((layout.layers - 1) << 6 | (data_words - 1), 2)
+    } else {
+        ((layout.layers - 1) << 11 | (data_words - 1), 4)
+    };
+    let words: Vec<u16> = (0..count)
+        .rev()
+        .map(|i| (value >> (i * 4) & 0xf) as u16)
+        .collect();
+    let total = if layout.compact { 7 } else { 10 };
+    word_bits(&add_check_words(&words, total, 4), 4).collect()
+}
+
+/// Read the layers and data codewords from a mode message
+fn read_mode_message(compact: bool, bits: &[bool]) -> Option<(usize, usize)> {
+    let mut words: Vec<u16> = bits.chunks(4).map(bits_value).collect();
+    let data_len = if compact { 2 } else { 4 };
+    let ec_len = words.len() - data_len;
+    ReedSolomon::new(field(4), 1)
+        .correct(&mut words, ec_len)
+        .ok()?;
+    let value = words[..data_len]
+        .iter()
+        .fold(0, |value, &word| value << 4 | usize::from(word));
+    Some(if compact {
+        ((value >> 6) + 1, (value & 0x3f) + 1)
+    } else {
+        ((value >> 11) + 1, (value & 0x7ff) + 1)
+    })
+}
+
+/// Lay out the data and mode message around the bullseye, filling the
+/// codewords the data leaves with error correction
+fn draw(layout: Layout, words: &[u16], ec_percent: u8) -> Symbol {
+    let word_size = layout.word_size();
+    let codewords = add_check_words(words, layout.total_words(), word_size);
+    // The layers hold a few bits more than whole codewords; they come first
+    let bits = std::iter::repeat_n(false, layout.total_bits() % word_size)
+        .chain(word_bits(&codewords, word_size));
+
+    let size = layout.size();
+    let mut dark = vec![false; size * size];
+    for (&(x, y), bit) in layout.data_positions().iter().zip(bits) {
+        dark[y * size + x] = bit;
+    }
+    let mode = mode_message(layout, words.len());
+    for (&(x, y), &bit) in layout.mode_positions().iter().zip(&mode) {
+        dark[y * size + x] = bit;
+    }
+
+    let colors = (0..size)
+        .flat_map(|y| (0..size).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            if layout.fixed(x, y).unwrap_or(dark[y * size + x]) {
+                Color::Dark
+            } else {
+                Color::Light
+            }
+        })
+        .collect();
+    Symbol::from_modules(
+        colors,
+        size,
+        size,
+        Symbology::Aztec,
+        SymbolVersion::Aztec {
+            compact: layout.compact,
+            layers: layout.layers as u8,
+        },
+        ErrorCorrection::Aztec {
+            percent: ec_percent,
+            data_words: words.len(),
+            ec_words: codewords.len() - words.len(),
+        },
+    )
+}
+
+/// Find and decode the Aztec symbols in a greyscale image
+///
+/// Symbols are located by the square rings of their bullseye and read in
+/// any of the eight axis-aligned orientations, which the marks at the
+/// corners of the mode message tell apart. Aztec symbols need no quiet
+/// zone, so they are found right up against other codes or the image edge.
+/// Symbols inside the codes in `skip` are not looked at.
+///
+/// # Arguments
+/// * `image` - The greyscale image to search
+/// * `skip` - Codes already decoded from the image
+///
+/// # Returns
+/// Returns a `DecodedQr` for each Aztec symbol that decodes
+///
+/// # Examples
+/// ```rust
+/// use rqr::qr::{aztec, encoder::QrEncoder, symbol::Symbology};
+///
+/// let encoder = QrEncoder::new(200, 2, "M")?.with_symbology(Symbology::Aztec);
+/// let image = encoder.to_image(&encoder.encode("TKT 0421 SEAT 12C")?)?;
+///
+/// let decoded = aztec::decode(&image.to_luma8(), &[]);
+/// assert_eq!(decoded[0].text.as_deref(), Some("TKT 0421 SEAT 12C"));
+/// assert_eq!(decoded[0].symbology, Symbology::Aztec);
+/// # Ok::<(), rqr::utils::error::RqrError>(())
+/// ```
+pub fn decode(image: &GrayImage, skip: &[DecodedQr]) -> Vec<DecodedQr> {
+    let Some(bitmap) = Bitmap::new(image) else {
+        return Vec::new();
+    };
+
+    let mut results: Vec<DecodedQr> = Vec::new();
+    for bullseye in find_bullseyes(&bitmap) {
+        if skip
+            .iter()
+            .chain(&results)
+            .any(|code| grid::contains(&code.corners, bullseye.x, bullseye.y))
+        {
+            continue;
+        }
+        results.extend(read_symbol(&bitmap, bullseye));
+    }
+    results
+}
+
+/// The centre of a bullseye and the size of its modules in pixels
+#[derive(Debug, Clone, Copy)]
+struct Bullseye {
+    x: f64,
+    y: f64,
+    module_x: f64,
+    module_y: f64,
+}
+
+/// Pixels of one colour in a row or column
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Run {
+    pub(super) dark: bool,
+    pub(super) start: usize,
+    pub(super) len: usize,
+}
+
+pub(super) fn runs(len: usize, dark: impl Fn(usize) -> bool) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+    for i in 0..len {
+        let dark = dark(i);
+        match runs.last_mut() {
+            Some(run) if run.dark == dark => run.len += 1,
+            _ => runs.push(Run {
+                dark,
+                start: i,
+                len: 1,
+            }),
+        }
+    }
+    runs
+}
+
+/// The centre and module size of bullseye rings around a dark run
+///
+/// The centre module and the rings around it are all a module wide, so
+/// seven runs across the middle of any bullseye have about the same
+/// length. The outer dark ring runs on into the dark modules beyond it and
+/// only has to be there; a full-range bullseye has two more even rings.
+fn rings(runs: &[Run], mid: usize) -> Option<(f64, f64)> {
+    if mid < 4 || !runs.get(mid)?.dark {
+        return None;
+    }
+    let span = |runs: &[Run]| {
+        runs.last()
+            .map_or(0.0, |last| (last.start + last.len - runs[0].start) as f64)
+    };
+    let inner = runs.get(mid - 3..=mid + 3)?;
+    let module = span(inner) / 7.0;
+    let even = |runs: &[Run]| {
+        runs.iter()
+            .all(|run| (run.len as f64 - module).abs() <= module / 2.0)
+    };
+    if !even(inner)
+        || [runs[mid - 4], *runs.get(mid + 4)?]
+            .iter()
+            .any(|run| (run.len as f64) < module / 2.0)
+    {
+        return None;
+    }
+    // Measure over the wider span when there is one
+    let module = match mid
+        .checked_sub(5)
+        .and_then(|start| runs.get(start..=mid + 5))
+    {
+        Some(outer) if even(outer) => span(outer) / 11.0,
+        _ => module,
+    };
+    Some((runs[mid].start as f64 + runs[mid].len as f64 / 2.0, module))
+}
+
+/// Find bullseyes: rings along a pixel row, confirmed down the column
+/// through their centre
+fn find_bullseyes(bitmap: &Bitmap) -> Vec<Bullseye> {
+    let run_at = |runs: &[Run], i: usize| runs.iter().position(|run| i < run.start + run.len);
+    let mut found: Vec<Bullseye> = Vec::new();
+    for y in 0..bitmap.height {
+        let row = runs(bitmap.width, |x| bitmap.get(x, y));
+        for mid in 0..row.len() {
+            let Some((x, module_x)) = rings(&row, mid) else {
+                continue;
+            };
+            if found.iter().any(|other| {
+                (other.x - x).abs() < other.module_x && (other.y - y as f64).abs() < other.module_y
+            }) {
+                continue;
+            }
+
+            let column = runs(bitmap.height, |y| bitmap.get(x as usize, y));
+            let Some((cy, module_y)) = run_at(&column, y).and_then(|at| rings(&column, at)) else {
+                continue;
+            };
+            if !(0.5..=2.0).contains(&(module_y / module_x)) {
+                continue;
+            }
+            // Centre along the row through the middle of the centre module
+            let row = runs(bitmap.width, |x| bitmap.get(x, cy as usize));
+            if let Some((cx, module_x)) = run_at(&row, x as usize).and_then(|at| rings(&row, at)) {
+                found.push(Bullseye {
+                    x: cx,
+                    y: cy,
+                    module_x,
+                    module_y,
+                });
+            }
+        }
+    }
+    found
+}
+
+/// The modules at a distance from the centre, clockwise from the top left
+fn ring(distance: isize) -> impl Iterator<Item = (isize, isize)> {
+    (-distance..distance).flat_map(move |i| {
+        [
+            (i, -distance),
+            (distance, i),
+            (-i, distance),
+            (-distance, -i),
+        ]
+    })
+}
+
+/// Read and decode the symbol around a bullseye
+fn read_symbol(bitmap: &Bitmap, bullseye: Bullseye) -> Option<DecodedQr> {
+    // A full-range bullseye has a light and a dark ring more
+    let upright = Sampler::new(bitmap, bullseye, ORIENTATIONS[0]);
+    let ring_errors = |distance, dark| {
+        ring(distance)
+            .filter(|&(dx, dy)| upright.dark(dx, dy) != dark)
+            .count()
+    };
+    let compact = ring_errors(5, false) > 2 || ring_errors(6, true) > 2;
+
+    ORIENTATIONS
+        .iter()
+        .find_map(|&axes| Sampler::new(bitmap, bullseye, axes).read_symbol(compact))
+}
+
+/// Reads an Aztec symbol outwards from its bullseye, whose centre module
+/// is the origin of both axes
+struct Sampler<'a> {
+    bitmap: &'a Bitmap,
+    centre: (f64, f64),
+    /// Image offset of one module along the symbol's rows and columns
+    ex: (f64, f64),
+    ey: (f64, f64),
+}
+
+impl<'a> Sampler<'a> {
+    fn new(bitmap: &'a Bitmap, bullseye: Bullseye, (ax, ay): ((f64, f64), (f64, f64))) -> Self {
+        let (mx, my) = (bullseye.module_x, bullseye.module_y);
+        Self {
+            bitmap,
+            centre: (bullseye.x, bullseye.y),
+            ex: (ax.0 * mx, ax.1 * my),
+            ey: (ay.0 * mx, ay.1 * my),
+        }
+    }
+
+    /// Image position `dx` modules right and `dy` modules down from the
+    /// centre of the bullseye, turned as the sampler's axes give
+    fn position(&self, dx: f64, dy: f64) -> (f64, f64) {
+        (
+            self.centre.0 + self.ex.0 * dx + self.ey.0 * dy,
+            self.centre.1 + self.ex.1 * dx + self.ey.1 * dy,
+        )
+    }
+
+    /// Whether the module `dx` columns and `dy` rows from the centre is dark
+    fn dark(&self, dx: isize, dy: isize) -> bool {
+        let (px, py) = self.position(dx as f64, dy as f64);
+        self.bitmap.dark_at(px, py)
+    }
+
+    /// Whether the module at column `x`, row `y` of a symbol with this
+    /// layout is dark, counted from its top left corner
+    fn module(&self, layout: Layout, (x, y): (usize, usize)) -> bool {
+        let c = (layout.size() / 2) as isize;
+        self.dark(x as isize - c, y as isize - c)
+    }
+
+    /// Check the orientation marks on the ring just outside a compact or
+    /// full-range bullseye, then read the mode message on the same ring and
+    /// the data layers it announces
+    ///
+    /// Returns `None` when the marks do not sit where this orientation puts
+    /// them.
+    fn read_symbol(&self, compact: bool) -> Option<DecodedQr> {
+        let probe = Layout { compact, layers: 1 };
+        let s = probe.ring();
+        let mark_errors = ring(s)
+            .filter(|&(dx, dy)| dx.abs().min(dy.abs()) >= s - 1)
+            .filter(|&(dx, dy)| self.dark(dx, dy) != is_orientation_mark(dx, dy, s))
+            .count();
+        if mark_errors > 1 {
+            return None;
+        }
+
+        let mode: Vec<bool> = probe
+            .mode_positions()
+            .into_iter()
+            .map(|position| self.module(probe, position))
+            .collect();
+        let (layers, data_words) = read_mode_message(compact, &mode)?;
+        let layout = Layout { compact, layers };
+        let (word_size, total_words) = (layout.word_size(), layout.total_words());
+        if data_words > total_words {
+            return None;
+        }
+
+        let bits: Vec<bool> = layout
+            .data_positions()
+            .into_iter()
+            .map(|position| self.module(layout, position))
+            .collect();
+        let mut words: Vec<u16> = bits[bits.len() % word_size..]
+            .chunks(word_size)
+            .map(bits_value)
+            .collect();
+        ReedSolomon::new(field(word_size), 1)
+            .correct(&mut words, total_words - data_words)
+            .ok()?;
+        let (payload, eci) =
+            unstuff_bits(&words[..data_words], word_size).and_then(|bits| parse_bits(&bits))?;
+
+        let half = (layout.size() / 2) as f64 + 0.5;
+        let corners = [(-half, -half), (half, -half), (half, half), (-half, half)].map(|(x, y)| {
+            let (px, py) = self.position(x, y);
+            Point {
+                x: px.round() as i32,
+                y: py.round() as i32,
+            }
+        });
+        Some(DecodedQr {
+            text: String::from_utf8(payload.clone()).ok(),
+            payload,
+            symbology: Symbology::Aztec,
+            version: layout.version(),
+            error_correction: EcLevel::M,
+            ec_percent: Some((100 * (total_words - data_words) / total_words) as u8),
+            security_level: None,
+            mask: 0,
+            eci,
+            corners,
+        })
+    }
+}
+
+/// Decode the character modes and binary shifts
+///
+/// FLG(0) is FNC1 and becomes a GS separator; the first ECI designator is
+/// returned along with the payload. Bits left over at the end, too few for
+/// another character, are padding.
+fn parse_bits(bits: &[bool]) -> Option<(Vec<u8>, Option<u32>)> {
+    let mut reader = BitReader::new(bits);
+    let mut payload = Vec::new();
+    let mut eci = None;
+    // The mode to return to, and the mode of the next code
+    let (mut latched, mut current) = (Mode::Upper, Mode::Upper);
+
+    'read: while let Some(value) = reader.read(current.bits()) {
+        match current.code(value) {
+            Code::Char(c) => payload.push(c),
+            Code::Pair(pair) => payload.extend_from_slice(pair),
+            Code::Shift(mode) => {
+                latched = current;
+                current = mode;
+                continue;
+            }
+            Code::Latch(mode) => {
+                (latched, current) = (mode, mode);
+                continue;
+            }
+            Code::BinaryShift => {
+                // A shift from a shifted mode returns to that mode
+                latched = current;
+                let Some(mut count) = reader.read(5) else {
+                    break;
+                };
+                if count == 0 {
+                    let Some(long) = reader.read(11) else {
+                        break;
+                    };
+                    count = long + 31;
+                }
+                for _ in 0..count {
+                    let Some(byte) = reader.read(8) else {
+                        break 'read;
+                    };
+                    payload.push(byte as u8);
+                }
+            }
+            Code::Flag => match reader.read(3) {
+                None => break,
+                Some(0) => payload.push(GS),
+                Some(7) => return None,
+                Some(digits) => {
+                    let mut designator = 0;
+                    for _ in 0..digits {
+                        let Some(digit) = reader.read(4) else {
+                            break 'read;
+                        };
+                        if !(2..=11).contains(&digit) {
+                            return None;
+                        }
+                        designator = designator * 10 + digit - 2;
+                    }
+                    eci.get_or_insert(designator);
+                }
+            },
+        }
+        current = latched;
+    }
+    Some((payload, eci))
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, Luma};
+
+    use super::*;
+    use crate::qr::encoder::QrEncoder;
+
+    fn aztec_image(symbol: &Symbol) -> DynamicImage {
+        QrEncoder::new(400, 2, "M")
+            .unwrap()
+            .to_image(symbol)
+            .unwrap()
+    }
+
+    /// Bits written as `X` and `.`, with spaces ignored
+    fn bits(text: &str) -> Vec<bool> {
+        text.chars()
+            .filter(|&c| c != ' ')
+            .map(|c| c == 'X')
+            .collect()
+    }
+
+    #[test]
+    fn test_stuff_bits() {
+        // From ZXing's encoder tests
+        for (word_size, input, expected) in [
+            (5, ".X.X. X.X.X .X.X.", ".X.X. X.X.X .X.X."),
+            (5, ".X.X. ..... .X.X", ".X.X. ....X ..X.X"),
+            (
+                3,
+                "XX. ... ... ..X XXX .X. ..",
+                "XX. ..X ..X ..X ..X .XX XX. .X. ..X",
+            ),
+            (6, ".X.X.. ...... ..X.XX", ".X.X.. .....X. ..X.XX XXXX."),
+            (
+                6,
+                ".X.X.. XXXXXX ...... ..X.XX",
+                ".X.X.. XXXXX. X..... ...X.X XXXXX.",
+            ),
+        ] {
+            let words = stuff_bits(&bits(input), word_size);
+            let stuffed: Vec<bool> = word_bits(&words, word_size).collect();
+            assert_eq!(stuffed, bits(expected), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_unstuff_bits() {
+        let input = bits("...... ..XXXX X..XX. .X.... .X.X.X .....X .X.... ...X.X");
+        let words = stuff_bits(&input, 6);
+        let mut unstuffed = unstuff_bits(&words, 6).unwrap();
+        // The last word is padded with ones
+        assert!(unstuffed[input.len()..].iter().all(|&bit| bit));
+        unstuffed.truncate(input.len());
+        assert_eq!(unstuffed, input);
+
+        assert_eq!(unstuff_bits(&[0b000000], 6), None);
+        assert_eq!(unstuff_bits(&[0b111111], 6), None);
+    }
+
+    #[test]
+    fn test_mode_message() {
+        let compact = |layers| Layout {
+            compact: true,
+            layers,
+        };
+        assert_eq!(
+            mode_message(compact(2), 29),
+            bits(".X .XXX.. ...X XX.. ..X .XX. .XX.X")
+        );
+        assert_eq!(
+            mode_message(compact(4), 64),
+            bits("XX XXXXXX .X.. ...X ..XX .X.. XX..")
+        );
+        let full = Layout {
+            compact: false,
+            layers: 21,
+        };
+        let message = mode_message(full, 660);
+        assert_eq!(
+            message,
+            bits("X.X.. .X.X..X..XX .XXX ..X.. .XXX. .X... ..XXX")
+        );
+        assert_eq!(read_mode_message(false, &message), Some((21, 660)));
+    }
+
+    #[test]
+    fn test_high_level_encode() {
+        // From ZXing's encoder tests, which pick the shortest encoding
+        for (text, expected) in [
+            // 'A' P/S '. ' L/L b D/L '.'
+            ("A. b.", "...X. ..... ...XX XXX.. ...XX XXXX. XX.X"),
+            (
+                "Lo. Test 123.",
+                ".XX.X XXX.. X.... ..... ...XX XXX.. X.X.X ..XX. X.X.. X.X.X \
+                 XXXX. ...X ..XX .X.. .X.X XX.X",
+            ),
+            (
+                "Lo...x",
+                ".XX.X XXX.. X.... XXXX. XX.X XX.X XX.X XXX. XXX.. XX..X",
+            ),
+            (
+                ". x://abc/.",
+                "..... ...XX XXX.. XX..X ..... X.X.X ..... X.X.. ..... X.X.. ...X. ...XX \
+                 ..X.. ..... X.X.. XXXX. XX.X",
+            ),
+            // B/S saves two bits over L/S
+            (
+                "ABCdEFG",
+                "...X. ...XX ..X.. XXXXX ....X .XX..X.. ..XX. ..XXX .X...",
+            ),
+        ] {
+            assert_eq!(
+                high_level_encode(text.as_bytes()),
+                bits(expected),
+                "{}",
+                text
+            );
+            let (payload, _) = parse_bits(&bits(expected)).unwrap();
+            assert_eq!(payload, text.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_high_level_encode_binary() {
+        for (data, expected) in [
+            (&b"N\0N"[..], ".XXXX XXXXX ....X ........ .XXXX"),
+            (b"N\0n", ".XXXX XXXXX ...X. ........ .XX.XXX."),
+            (
+                b"N\0\x80 A",
+                ".XXXX XXXXX ...X. ........ X....... ....X ...X.",
+            ),
+            (
+                b"1234\0",
+                "XXXX. ..XX .X.. .X.X .XX. XXX. XXXXX ....X ........",
+            ),
+        ] {
+            assert_eq!(high_level_encode(data), bits(expected));
+        }
+
+        // Long runs of binary: one B/S up to 31 bytes, two up to 62, then
+        // one with an 11 bit length
+        for (count, overhead) in [(31, 10), (32, 20), (62, 20), (63, 21), (2078, 21)] {
+            let data: Vec<u8> = (0..count).map(|i| 128 + (i % 30) as u8).collect();
+            let encoded = high_level_encode(&data);
+            assert_eq!(encoded.len(), count * 8 + overhead, "{}", count);
+            assert_eq!(parse_bits(&encoded).unwrap().0, data);
+        }
+    }
+
+    #[test]
+    fn test_encode_matches_reference() {
+        // ZXing's encoding of a Wikipedia example, at 33% error correction
+        let expected = [
+            "10011000100110010010000",
+            "10000100110010110110001",
+            "11011111011100000000100",
+            "11000000001101000111111",
+            "00111010100111100001100",
+            "01110111101001010011010",
+            "00001111100111101010010",
+            "10001011111111111001011",
+            "10100111000000011110110",
+            "10011011011111011010111",
+            "10100001010001011110001",
+            "10001001010101010110100",
+            "00010111010001010011100",
+            "00111111011111011111101",
+            "01101011000000011101111",
+            "01010001111111111110110",
+            "01101000111011100011000",
+            "01000000010110010011100",
+            "01011101101011110101111",
+            "00101011101010111100100",
+            "00001000000010000000010",
+            "00001100101101010100011",
+            "01010110001010000111001",
+        ];
+        let symbol = encode(
+            b"This is an example Aztec symbol for Wikipedia.",
+            33,
+            1..=36,
+        )
+        .unwrap();
+        assert_eq!(symbol.version_label(), "23x23 compact");
+        let colors = symbol.to_colors();
+        let rows: Vec<String> = colors
+            .chunks(23)
+            .map(|row| {
+                row.iter()
+                    .map(|&color| if color == Color::Dark { '1' } else { '0' })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(rows, expected);
+    }
+
+    #[test]
+    fn test_layout_covers_every_module() {
+        // Each module is exactly one of fixed, mode message or data
+        for version in 1..=36 {
+            let layout = Layout::from_version(version).unwrap();
+            let size = layout.size();
+            let mut uses = vec![0; size * size];
+            for (x, y) in layout
+                .data_positions()
+                .into_iter()
+                .chain(layout.mode_positions())
+            {
+                uses[y * size + x] += 1;
+            }
+            for y in 0..size {
+                for x in 0..size {
+                    let fixed = usize::from(layout.fixed(x, y).is_some());
+                    assert_eq!(
+                        uses[y * size + x] + fixed,
+                        1,
+                        "{} at {},{}",
+                        label(version),
+                        x,
+                        y
+                    );
+                }
+            }
+        }
+        assert_eq!(label(1), "15x15 compact");
+        assert_eq!(label(8), "31x31");
+        assert_eq!(label(36), "151x151");
+    }
+
+    #[test]
+    fn test_decode_every_size() {
+        for version in 1..=36 {
+            let symbol = encode(b"AZ-42", 23, version..=version).unwrap();
+            let image = QrEncoder::new(symbol.width() as u32 * 3 + 12, 2, "M")
+                .unwrap()
+                .to_image(&symbol)
+                .unwrap();
+            let decoded = decode(&image.to_luma8(), &[]);
+
+            assert_eq!(decoded.len(), 1, "{}", label(version));
+            assert_eq!(decoded[0].payload, b"AZ-42");
+            assert_eq!(decoded[0].version, version);
+            assert_eq!(decoded[0].symbology, Symbology::Aztec);
+        }
+    }
+
+    #[test]
+    fn test_decode_rotated_and_mirrored() {
+        let symbol = encode(b"Gate B22, seat 14F", 23, 1..=36).unwrap();
+        let image = aztec_image(&symbol);
+        for turned in [
+            image.rotate90(),
+            image.rotate180(),
+            image.rotate270(),
+            image.fliph(),
+            image.rotate90().flipv(),
+        ] {
+            let decoded = decode(&turned.to_luma8(), &[]);
+            assert_eq!(decoded.len(), 1);
+            assert_eq!(decoded[0].payload, b"Gate B22, seat 14F");
+        }
+    }
+
+    #[test]
+    fn test_decode_corrects_damage() {
+        let symbol = encode(b"Damaged boarding pass", 50, 1..=36).unwrap();
+        let (width, image) = (symbol.width(), aztec_image(&symbol));
+        let mut image = image.to_luma8();
+        // Blot out a 2x2 module block in the outer layer
+        let module = 400 / (width + 4);
+        let origin = (400 - module * (width + 4)) / 2 + 2 * module;
+        for y in origin + module..origin + module * 3 {
+            for x in origin + module * 4..origin + module * 6 {
+                image.put_pixel(x as u32, y as u32, Luma([0]));
+            }
+        }
+
+        let decoded = decode(&image, &[]);
+        assert_eq!(decoded[0].payload, b"Damaged boarding pass");
+        assert!(decoded[0].ec_percent.unwrap() >= 33);
+        assert!(symbol.max_allowed_errors() >= 4);
+    }
+
+    #[test]
+    fn test_encode_picks_smallest_size() {
+        let smallest = encode(b"12345", 23, 1..=36).unwrap();
+        assert_eq!(smallest.width(), 15);
+
+        // More error correction needs more layers
+        let text = b"https://tickets.example.com/t/8d0a4c9f31b7e2";
+        let low = encode(text, 10, 1..=36).unwrap();
+        let high = encode(text, 50, 1..=36).unwrap();
+        assert!(low.width() < high.width());
+        let ErrorCorrection::Aztec {
+            percent,
+            data_words,
+            ec_words,
+        } = high.error_correction()
+        else {
+            panic!("expected Aztec error correction");
+        };
+        let layout = Layout::from_version(high.version().number()).unwrap();
+        assert_eq!(percent, 50);
+        assert_eq!(data_words + ec_words, layout.total_words());
+        assert!(ec_words * 2 >= data_words);
+
+        // Full-range symbols start at four layers unless asked for
+        let long = [b'x'; 70];
+        let compact = encode(&long, 23, 1..=36).unwrap();
+        assert_eq!(compact.version_label(), "27x27 compact");
+        let full = encode(&long, 23, 5..=36).unwrap();
+        assert_eq!(full.version_label(), "27x27");
+        assert!(encode(&long, 23, 1..=3).is_none());
+        assert!(encode(&[0xff; 2000], 23, 1..=36).is_none());
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let data: Vec<u8> = (0..=255).collect();
+        let symbol = encode(&data, 23, 1..=36).unwrap();
+        let decoded = decode(&aztec_image(&symbol).to_luma8(), &[]);
+        assert_eq!(decoded[0].payload, data);
+        assert_eq!(decoded[0].text, None);
+    }
+
+    #[test]
+    fn test_empty_round_trip() {
+        let symbol = encode(b"", 23, 1..=36).unwrap();
+        let decoded = decode(&aztec_image(&symbol).to_luma8(), &[]);
+        assert_eq!(decoded[0].payload, b"");
+    }
+
+    #[test]
+    fn test_parse_bits_flags() {
+        // P/S FLG(0), 'A', P/S FLG(2) '2' '6'
+        let encoded = bits("..... ..... ... ...X. ..... ..... .X. .X.. X...");
+        assert_eq!(parse_bits(&encoded), Some((vec![GS, b'A'], Some(26))));
+        // FLG(7) is reserved
+        assert_eq!(parse_bits(&bits("..... ..... XXX")), None);
+    }
+}
@@ -0,0 +1,1297 @@
+use std::ops::RangeInclusive;
+
+use image::GrayImage;
+use qrcode::{Color, EcLevel};
+
+use crate::{
+    qr::{
+        decoded::DecodedQr,
+        grid::{self, Bitmap, Grid, ORIENTATIONS},
+        reed_solomon::{GaloisField, ReedSolomon},
+        symbol::{ErrorCorrection, Symbol, SymbolVersion, Symbology},
+    },
+    utils::error::{Result, RqrError},
+};
+
+/// An ECC 200 symbol size: the symbol's rows and columns, the rows and
+/// columns of data inside each region, the data codewords, and the error
+/// correction codewords in each of its blocks
+#[derive(Debug, Clone, Copy)]
+struct Size {
+    rows: usize,
+    cols: usize,
+    region_rows: usize,
+    region_cols: usize,
+    data: usize,
+    ec: usize,
+    blocks: usize,
+}
+
+const fn size(
+    rows: usize,
+    cols: usize,
+    region_rows: usize,
+    region_cols: usize,
+    data: usize,
+    ec: usize,
+    blocks: usize,
+) -> Size {
+    Size {
+        rows,
+        cols,
+        region_rows,
+        region_cols,
+        data,
+        ec,
+        blocks,
+    }
+}
+
+/// Versions 1 (10x10) to 24 (144x144) are the square sizes, 25 (8x18) to
+/// 30 (16x48) the rectangular ones
+const SIZES: [Size; 30] = [
+    size(10, 10, 8, 8, 3, 5, 1),
+    size(12, 12, 10, 10, 5, 7, 1),
+    size(14, 14, 12, 12, 8, 10, 1),
+    size(16, 16, 14, 14, 12, 12, 1),
+    size(18, 18, 16, 16, 18, 14, 1),
+    size(20, 20, 18, 18, 22, 18, 1),
+    size(22, 22, 20, 20, 30, 20, 1),
+    size(24, 24, 22, 22, 36, 24, 1),
+    size(26, 26, 24, 24, 44, 28, 1),
+    size(32, 32, 14, 14, 62, 36, 1),
+    size(36, 36, 16, 16, 86, 42, 1),
+    size(40, 40, 18, 18, 114, 48, 1),
+    size(44, 44, 20, 20, 144, 56, 1),
+    size(48, 48, 22, 22, 174, 68, 1),
+    size(52, 52, 24, 24, 204, 42, 2),
+    size(64, 64, 14, 14, 280, 56, 2),
+    size(72, 72, 16, 16, 368, 36, 4),
+    size(80, 80, 18, 18, 456, 48, 4),
+    size(88, 88, 20, 20, 576, 56, 4),
+    size(96, 96, 22, 22, 696, 68, 4),
+    size(104, 104, 24, 24, 816, 56, 6),
+    size(120, 120, 18, 18, 1050, 68, 6),
+    size(132, 132, 20, 20, 1304, 62, 8),
+    size(144, 144, 22, 22, 1558, 62, 10),
+    size(8, 18, 6, 16, 5, 7, 1),
+    size(8, 32, 6, 14, 10, 11, 1),
+    size(12, 26, 10, 24, 16, 14, 1),
+    size(12, 36, 10, 16, 22, 18, 1),
+    size(16, 36, 14, 16, 32, 24, 1),
+    size(16, 48, 14, 22, 49, 28, 1),
+];
+
+/// The last square version; higher versions are rectangular
+pub(crate) const LAST_SQUARE: u8 = 24;
+
+// Codewords of the ASCII encodation
+const PAD: u8 = 129;
+const LATCH_C40: u8 = 230;
+const LATCH_BASE256: u8 = 231;
+const FNC1: u8 = 232;
+const STRUCTURED_APPEND: u8 = 233;
+const READER_PROGRAMMING: u8 = 234;
+const UPPER_SHIFT: u8 = 235;
+const MACRO_05: u8 = 236;
+const MACRO_06: u8 = 237;
+const LATCH_X12: u8 = 238;
+const LATCH_TEXT: u8 = 239;
+const LATCH_EDIFACT: u8 = 240;
+const ECI: u8 = 241;
+/// Returns from C40, Text and X12 to ASCII
+const UNLATCH: u8 = 254;
+
+/// The group separator a scanner sends for FNC1 between GS1 elements
+const GS: u8 = 0x1d;
+
+/// The characters of the C40 and Text shift 2 set, before FNC1 and the
+/// upper shift
+const SHIFT2: &[u8; 27] = b"!\"#$%&'()*+,-./:;<=>?@[\\]^_";
+
+/// The smallest symbol edge, in pixels, the decoder looks for
+const MIN_SIDE: usize = 8;
+
+fn get_size(version: u8) -> Option<Size> {
+    SIZES.get(usize::from(version).checked_sub(1)?).copied()
+}
+
+/// The version as printed in summaries: rows by columns, e.g. `16x16`
+pub(crate) fn label(version: u8) -> String {
+    match get_size(version) {
+        Some(size) => format!("{}x{}", size.rows, size.cols),
+        None => version.to_string(),
+    }
+}
+
+/// Number of codewords that can be wrong before the data is lost
+pub(crate) fn max_allowed_errors(version: u8) -> usize {
+    get_size(version)
+        .map(|size| size.blocks * (size.ec / 2))
+        .unwrap_or_default()
+}
+
+impl Size {
+    /// Data regions across and down the symbol
+    fn regions(&self) -> (usize, usize) {
+        (
+            self.cols / (self.region_cols + 2),
+            self.rows / (self.region_rows + 2),
+        )
+    }
+
+    /// Rows and columns of the mapping matrix, the regions put together
+    /// without their borders
+    fn mapping(&self) -> (usize, usize) {
+        let (across, down) = self.regions();
+        (down * self.region_rows, across * self.region_cols)
+    }
+
+    /// The colour of a module of the finder and timing patterns around each
+    /// region, or `None` for a data module
+    ///
+    /// Every region has a solid line on its left and bottom, and a line of
+    /// alternating modules on its top and right.
+    fn border(&self, x: usize, y: usize) -> Option<bool> {
+        let (lx, ly) = (x % (self.region_cols + 2), y % (self.region_rows + 2));
+        if lx == 0 || ly == self.region_rows + 1 {
+            Some(true)
+        } else if ly == 0 {
+            Some(lx % 2 == 0)
+        } else if lx == self.region_cols + 1 {
+            Some(ly % 2 == 1)
+        } else {
+            None
+        }
+    }
+
+    /// Position in the mapping matrix of a data module, as (row, column)
+    fn mapping_position(&self, x: usize, y: usize) -> (usize, usize) {
+        (
+            y - 1 - 2 * (y / (self.region_rows + 2)),
+            x - 1 - 2 * (x / (self.region_cols + 2)),
+        )
+    }
+}
+
+/// Places codewords into the mapping matrix following the diagonal
+/// "utah" shapes and four corner shapes of ISO/IEC 16022 Annex F
+struct Placement {
+    rows: isize,
+    cols: isize,
+    filled: Vec<bool>,
+    codewords: Vec<[(usize, usize); 8]>,
+}
+
+impl Placement {
+    fn is_filled(&self, row: isize, col: isize) -> bool {
+        self.filled[(row * self.cols + col) as usize]
+    }
+
+    /// Place a codeword's eight bits, most significant first, wrapping
+    /// positions outside the matrix round to the other side
+    fn place(&mut self, cells: [(isize, isize); 8]) {
+        let cells = cells.map(|(mut row, mut col)| {
+            if row < 0 {
+                row += self.rows;
+                col += 4 - (self.rows + 4) % 8;
+            }
+            if col < 0 {
+                col += self.cols;
+                row += 4 - (self.cols + 4) % 8;
+            }
+            self.filled[(row * self.cols + col) as usize] = true;
+            (row as usize, col as usize)
+        });
+        self.codewords.push(cells);
+    }
+
+    fn utah(&mut self, row: isize, col: isize) {
+        self.place([
+            (row - 2, col - 2),
+            (row - 2, col - 1),
+            (row - 1, col - 2),
+            (row - 1, col - 1),
+            (row - 1, col),
+            (row, col - 2),
+            (row, col - 1),
+            (row, col),
+        ]);
+    }
+}
+
+/// The mapping matrix positions, as (row, column), of every codeword's
+/// bits, most significant first
+///
+/// Some sizes leave the bottom right 2x2 modules unused; the second value
+/// says so, and those modules are filled with a fixed pattern.
+fn placement(rows: usize, cols: usize) -> (Vec<[(usize, usize); 8]>, bool) {
+    let (nrow, ncol) = (rows as isize, cols as isize);
+    let mut p = Placement {
+        rows: nrow,
+        cols: ncol,
+        filled: vec![false; rows * cols],
+        codewords: Vec::new(),
+    };
+
+    let (mut row, mut col) = (4, 0);
+    loop {
+        if row == nrow && col == 0 {
+            p.place([
+                (nrow - 1, 0),
+                (nrow - 1, 1),
+                (nrow - 1, 2),
+                (0, ncol - 2),
+                (0, ncol - 1),
+                (1, ncol - 1),
+                (2, ncol - 1),
+                (3, ncol - 1),
+            ]);
+        }
+        if row == nrow - 2 && col == 0 && ncol % 4 != 0 {
+            p.place([
+                (nrow - 3, 0),
+                (nrow - 2, 0),
+                (nrow - 1, 0),
+                (0, ncol - 4),
+                (0, ncol - 3),
+                (0, ncol - 2),
+                (0, ncol - 1),
+                (1, ncol - 1),
+            ]);
+        }
+        if row == nrow - 2 && col == 0 && ncol % 8 == 4 {
+            p.place([
+                (nrow - 3, 0),
+                (nrow - 2, 0),
+                (nrow - 1, 0),
+                (0, ncol - 2),
+                (0, ncol - 1),
+                (1, ncol - 1),
+                (2, ncol - 1),
+                (3, ncol - 1),
+            ]);
+        }
+        if row == nrow + 4 && col == 2 && ncol % 8 == 0 {
+            p.place([
+                (nrow - 1, 0),
+                (nrow - 1, ncol - 1),
+                (0, ncol - 3),
+                (0, ncol - 2),
+                (0, ncol - 1),
+                (1, ncol - 3),
+                (1, ncol - 2),
+                (1, ncol - 1),
+            ]);
+        }
+
+        // Sweep up and to the right
+        loop {
+            if row < nrow && col >= 0 && !p.is_filled(row, col) {
+                p.utah(row, col);
+            }
+            row -= 2;
+            col += 2;
+            if row < 0 || col >= ncol {
+                break;
+            }
+        }
+        row += 1;
+        col += 3;
+
+        // Then down and to the left
+        loop {
+            if row >= 0 && col < ncol && !p.is_filled(row, col) {
+                p.utah(row, col);
+            }
+            row += 2;
+            col -= 2;
+            if row >= nrow || col < 0 {
+                break;
+            }
+        }
+        row += 3;
+        col += 1;
+
+        if row >= nrow && col >= ncol {
+            break;
+        }
+    }
+
+    let corner = !p.is_filled(nrow - 1, ncol - 1);
+    (p.codewords, corner)
+}
+
+/// Encode data in ASCII: digit pairs share a codeword, other ASCII
+/// characters take one and bytes above 127 two
+fn ascii_codewords(data: &[u8], gs1: bool) -> Vec<u8> {
+    let mut codewords = Vec::new();
+    if gs1 {
+        codewords.push(FNC1);
+    }
+    let mut i = 0;
+    while i < data.len() {
+        match (data[i], data.get(i + 1)) {
+            (a @ b'0'..=b'9', Some(&b @ b'0'..=b'9')) => {
+                codewords.push(130 + (a - b'0') * 10 + (b - b'0'));
+                i += 1;
+            }
+            (GS, _) if gs1 => codewords.push(FNC1),
+            (byte @ 0..=127, _) => codewords.push(byte + 1),
+            (byte, _) => codewords.extend([UPPER_SHIFT, byte - 127]),
+        }
+        i += 1;
+    }
+    codewords
+}
+
+/// Encode data as a single Base 256 field, a length followed by the bytes
+fn base256_codewords(data: &[u8]) -> Vec<u8> {
+    let mut field = if data.len() < 250 {
+        vec![data.len() as u8]
+    } else {
+        vec![(data.len() / 250 + 249) as u8, (data.len() % 250) as u8]
+    };
+    field.extend(data);
+
+    let mut codewords = vec![LATCH_BASE256];
+    for byte in field {
+        let position = codewords.len() + 1;
+        codewords.push(randomize_255(byte, position));
+    }
+    codewords
+}
+
+/// The 255-state randomising of Base 256 codewords, by their 1-based
+/// position in the symbol
+fn randomize_255(value: u8, position: usize) -> u8 {
+    let pseudo = (149 * position) % 255 + 1;
+    ((usize::from(value) + pseudo) % 256) as u8
+}
+
+fn unrandomize_255(value: u8, position: usize) -> u8 {
+    let pseudo = (149 * position) % 255 + 1;
+    ((usize::from(value) + 256 - pseudo) % 256) as u8
+}
+
+/// Fill the data codewords up to the symbol's capacity: one plain pad
+/// codeword, then pads randomised by the 253-state algorithm
+fn pad(codewords: &mut Vec<u8>, capacity: usize) {
+    if codewords.len() < capacity {
+        codewords.push(PAD);
+    }
+    while codewords.len() < capacity {
+        let position = codewords.len() + 1;
+        let value = usize::from(PAD) + (149 * position) % 253 + 1;
+        codewords.push(if value <= 254 { value } else { value - 254 } as u8);
+    }
+}
+
+/// Add the error correction codewords of each block and interleave them
+///
+/// Data codeword `i` belongs to block `i % blocks`, and the error
+/// correction codewords follow the data in the same round robin order.
+fn add_error_correction(data: &[u8], size: Size) -> Vec<u8> {
+    let rs = ReedSolomon::new(GaloisField::new(0x12d, 256), 1);
+    let mut codewords = data.to_vec();
+    codewords.resize(data.len() + size.ec * size.blocks, 0);
+    for block in 0..size.blocks {
+        let block_data: Vec<u16> = data
+            .iter()
+            .skip(block)
+            .step_by(size.blocks)
+            .map(|&codeword| u16::from(codeword))
+            .collect();
+        for (i, ec) in rs.encode(&block_data, size.ec).into_iter().enumerate() {
+            codewords[data.len() + i * size.blocks + block] = ec as u8;
+        }
+    }
+    codewords
+}
+
+/// Turn GS1 element strings written with their Application Identifiers in
+/// brackets into the data a scanner transmits
+///
+/// The AIs and values run together, with a GS character ending every value
+/// but the last whose AI does not have a fixed length. The encoder writes
+/// the GS characters as FNC1 when GS1 mode is on.
+///
+/// # Arguments
+/// * `text` - Element strings, e.g. `(01)09521234543213(10)ABC123`
+///
+/// # Returns
+/// Returns the data to encode, or an error naming the malformed element
+///
+/// # Examples
+/// ```rust
+/// use rqr::qr::datamatrix::gs1_payload;
+///
+/// let payload = gs1_payload("(01)09521234543213(10)ABC123(21)42")?;
+/// assert_eq!(payload, b"010952123454321310ABC123\x1d2142");
+/// # Ok::<(), rqr::utils::error::RqrError>(())
+/// ```
+pub fn gs1_payload(text: &str) -> Result<Vec<u8>> {
+    let invalid = |reason: &str| {
+        RqrError::InvalidInput(format!("Invalid GS1 element string '{}': {}", text, reason))
+    };
+
+    let mut payload = Vec::new();
+    let mut rest = text.trim();
+    if rest.is_empty() {
+        return Err(invalid("no elements"));
+    }
+    while !rest.is_empty() {
+        let element = rest
+            .strip_prefix('(')
+            .ok_or_else(|| invalid("expected '(' before an AI"))?;
+        let (ai, tail) = element
+            .split_once(')')
+            .ok_or_else(|| invalid("unclosed '('"))?;
+        if !(2..=4).contains(&ai.len()) || !ai.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid(&format!("AI '{}' must be 2 to 4 digits", ai)));
+        }
+        let end = tail.find('(').unwrap_or(tail.len());
+        let value = &tail[..end];
+        rest = &tail[end..];
+        if value.is_empty() || value.contains(')') || !value.bytes().all(|b| b.is_ascii_graphic()) {
+            return Err(invalid(&format!("AI ({}) needs a printable value", ai)));
+        }
+
+        let fixed = fixed_length(ai);
+        if let Some(len) = fixed
+            && ai.len() + value.len() != len
+        {
+            return Err(invalid(&format!(
+                "AI ({}) takes {} characters, got {}",
+                ai,
+                len - ai.len(),
+                value.len()
+            )));
+        }
+        payload.extend(ai.bytes().chain(value.bytes()));
+        if fixed.is_none() && !rest.is_empty() {
+            payload.push(GS);
+        }
+    }
+    Ok(payload)
+}
+
+/// Total length of the AI and value for the AIs whose first two digits
+/// fix the length, which need no separator after them
+fn fixed_length(ai: &str) -> Option<usize> {
+    match &ai[..2] {
+        "00" => Some(20),
+        "01" | "02" | "03" | "41" => Some(16),
+        "04" => Some(18),
+        "11" | "12" | "13" | "14" | "15" | "16" | "17" | "18" | "19" => Some(8),
+        "20" => Some(4),
+        "31" | "32" | "33" | "34" | "35" | "36" => Some(10),
+        _ => None,
+    }
+}
+
+/// Encode data in the first of `versions` it fits
+///
+/// The data is encoded in ASCII, or as one Base 256 field when that is
+/// shorter, as it is for binary data. In GS1 mode the symbol starts with
+/// FNC1 and GS characters are written as FNC1 separators.
+///
+/// # Arguments
+/// * `data` - The bytes to encode
+/// * `gs1` - Whether the data is GS1 element strings, as from `gs1_payload`
+/// * `versions` - Versions to try, from 1 (10x10) to 24 (144x144) for
+///   squares and 25 (8x18) to 30 (16x48) for rectangles
+///
+/// # Returns
+/// Returns the symbol, or `None` if the data fits none of the versions
+pub(crate) fn encode(data: &[u8], gs1: bool, versions: RangeInclusive<u8>) -> Option<Symbol> {
+    let ascii = ascii_codewords(data, gs1);
+    let codewords = if gs1 {
+        ascii
+    } else {
+        let base256 = base256_codewords(data);
+        if base256.len() < ascii.len() {
+            base256
+        } else {
+            ascii
+        }
+    };
+
+    versions.into_iter().find_map(|version| {
+        let size = get_size(version)?;
+        if codewords.len() > size.data {
+            return None;
+        }
+        let mut data = codewords.clone();
+        pad(&mut data, size.data);
+        Some(draw(version, size, &add_error_correction(&data, size)))
+    })
+}
+
+/// Lay out the codewords and the finder and timing patterns of each region
+fn draw(version: u8, size: Size, codewords: &[u8]) -> Symbol {
+    let (rows, cols) = size.mapping();
+    let mut mapping = vec![false; rows * cols];
+    let (positions, corner) = placement(rows, cols);
+    for (&codeword, cells) in codewords.iter().zip(&positions) {
+        for (i, &(row, col)) in cells.iter().enumerate() {
+            mapping[row * cols + col] = codeword >> (7 - i) & 1 == 1;
+        }
+    }
+    if corner {
+        mapping[rows * cols - 1] = true;
+        mapping[(rows - 1) * cols - 2] = true;
+    }
+
+    let colors = (0..size.rows)
+        .flat_map(|y| (0..size.cols).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let dark = size.border(x, y).unwrap_or_else(|| {
+                let (row, col) = size.mapping_position(x, y);
+                mapping[row * cols + col]
+            });
+            if dark { Color::Dark } else { Color::Light }
+        })
+        .collect();
+    Symbol::from_modules(
+        colors,
+        size.cols,
+        size.rows,
+        Symbology::DataMatrix,
+        SymbolVersion::DataMatrix(version),
+        ErrorCorrection::Ecc200,
+    )
+}
+
+/// Find and decode the Data Matrix symbols in a greyscale image
+///
+/// Symbols are located by the solid L of their left and bottom edges and
+/// read in any of the eight axis-aligned orientations; the alternating
+/// modules on the other two edges give the size. Symbols inside the codes
+/// in `skip` are not looked at.
+///
+/// # Arguments
+/// * `image` - The greyscale image to search
+/// * `skip` - Codes already decoded from the image
+///
+/// # Returns
+/// Returns a `DecodedQr` for each Data Matrix symbol that decodes
+///
+/// # Examples
+/// ```rust
+/// use rqr::qr::{datamatrix, encoder::QrEncoder, symbol::Symbology};
+///
+/// let encoder = QrEncoder::new(200, 2, "M")?.with_symbology(Symbology::DataMatrix);
+/// let image = encoder.to_image(&encoder.encode("PART-0042")?)?;
+///
+/// let decoded = datamatrix::decode(&image.to_luma8(), &[]);
+/// assert_eq!(decoded[0].text.as_deref(), Some("PART-0042"));
+/// assert_eq!(decoded[0].symbology, Symbology::DataMatrix);
+/// # Ok::<(), rqr::utils::error::RqrError>(())
+/// ```
+pub fn decode(image: &GrayImage, skip: &[DecodedQr]) -> Vec<DecodedQr> {
+    let Some(bitmap) = Bitmap::new(image) else {
+        return Vec::new();
+    };
+
+    let mut results: Vec<DecodedQr> = Vec::new();
+    for bounds in find_candidates(&bitmap) {
+        let (x, y) = bounds.centre();
+        if skip
+            .iter()
+            .chain(&results)
+            .any(|code| grid::contains(&code.corners, x, y))
+        {
+            continue;
+        }
+        let decoded = ORIENTATIONS
+            .iter()
+            .find_map(|&axes| Sampler::new(&bitmap, bounds, axes)?.read_symbol());
+        results.extend(decoded);
+    }
+    results
+}
+
+/// The pixel bounds of a possible symbol, right and bottom exclusive
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    left: usize,
+    top: usize,
+    right: usize,
+    bottom: usize,
+}
+
+impl Bounds {
+    fn centre(&self) -> (f64, f64) {
+        (
+            (self.left + self.right) as f64 / 2.0,
+            (self.top + self.bottom) as f64 / 2.0,
+        )
+    }
+
+    /// Whether two bounds are the same symbol, found on different pixel rows
+    fn matches(&self, other: &Bounds) -> bool {
+        self.left.abs_diff(other.left) <= 2
+            && self.top.abs_diff(other.top) <= 2
+            && self.right.abs_diff(other.right) <= 2
+            && self.bottom.abs_diff(other.bottom) <= 2
+    }
+}
+
+/// Find the solid L shapes that may be a symbol's edges: a dark run along a
+/// pixel row meeting, at one end, a dark run down a pixel column that
+/// starts or ends within a module or so of that row
+fn find_candidates(bitmap: &Bitmap) -> Vec<Bounds> {
+    let mut found: Vec<Bounds> = Vec::new();
+    for y in 0..bitmap.height {
+        let mut x = 0;
+        while x < bitmap.width {
+            if !bitmap.get(x, y) {
+                x += 1;
+                continue;
+            }
+            let start = x;
+            while x < bitmap.width && bitmap.get(x, y) {
+                x += 1;
+            }
+            let width = x - start;
+            if width < MIN_SIDE {
+                continue;
+            }
+
+            for column in [start, x - 1] {
+                let (top, bottom) = column_run(bitmap, column, y);
+                let height = bottom - top;
+                // The edges are at most 48 modules to 8, or 144 to 144
+                let thickness = width.min(height) / 6;
+                if height < MIN_SIDE
+                    || width.max(height) > width.min(height) * 6
+                    || (y - top).min(bottom - 1 - y) > thickness
+                {
+                    continue;
+                }
+                let bounds = Bounds {
+                    left: start,
+                    top,
+                    right: x,
+                    bottom,
+                };
+                if !found.iter().any(|other| other.matches(&bounds)) {
+                    found.push(bounds);
+                }
+            }
+        }
+    }
+    found
+}
+
+/// The dark run down column `x` through row `y`, as (top, bottom exclusive)
+fn column_run(bitmap: &Bitmap, x: usize, y: usize) -> (usize, usize) {
+    let mut top = y;
+    while top > 0 && bitmap.get(x, top - 1) {
+        top -= 1;
+    }
+    let mut bottom = y + 1;
+    while bottom < bitmap.height && bitmap.get(x, bottom) {
+        bottom += 1;
+    }
+    (top, bottom)
+}
+
+/// A symbol measured from its solid edges: its version and size, and the
+/// module grid laid over it
+struct Sampler<'a> {
+    version: u8,
+    size: Size,
+    grid: Grid<'a>,
+}
+
+impl<'a> Sampler<'a> {
+    /// Measure the symbol's size, if the bounds hold one in this orientation
+    ///
+    /// The solid edges are the symbol's left column and bottom row. Their
+    /// thinnest part gives the module size, and the dark modules half a
+    /// module in from the other two edges are counted for the columns and
+    /// rows.
+    fn new(bitmap: &'a Bitmap, bounds: Bounds, (ax, ay): ((f64, f64), (f64, f64))) -> Option<Self> {
+        let (px_width, px_height) = (
+            (bounds.right - bounds.left) as f64,
+            (bounds.bottom - bounds.top) as f64,
+        );
+        let (width, height) = if ax.0 != 0.0 {
+            (px_width, px_height)
+        } else {
+            (px_height, px_width)
+        };
+        let origin = (
+            if ax.0 < 0.0 || ay.0 < 0.0 {
+                bounds.right as f64
+            } else {
+                bounds.left as f64
+            },
+            if ax.1 < 0.0 || ay.1 < 0.0 {
+                bounds.bottom as f64
+            } else {
+                bounds.top as f64
+            },
+        );
+        let dark = |u: f64, v: f64| {
+            bitmap.dark_at(
+                origin.0 + ax.0 * u + ay.0 * v,
+                origin.1 + ax.1 * u + ay.1 * v,
+            )
+        };
+
+        // Both solid edges must be dark along their length
+        let steps = |len: f64| (0..len as usize).map(|i| i as f64 + 0.5);
+        if !steps(width).all(|u| dark(u, height - 0.5)) || !steps(height).all(|v| dark(0.5, v)) {
+            return None;
+        }
+        let depth = |dark_at: &dyn Fn(f64) -> bool, len: f64| {
+            steps(len).take_while(|&t| dark_at(t)).count() as f64
+        };
+        let module_y = steps(width)
+            .map(|u| depth(&|t| dark(u, height - t), height))
+            .fold(height, f64::min);
+        let module_x = steps(height)
+            .map(|v| depth(&|t| dark(t, v), width))
+            .fold(width, f64::min);
+
+        let runs = |samples: &mut dyn Iterator<Item = bool>| {
+            let mut count = 0;
+            let mut previous = false;
+            for dark in samples {
+                if dark && !previous {
+                    count += 1;
+                }
+                previous = dark;
+            }
+            count
+        };
+        let cols = 2 * runs(&mut steps(width).map(|u| dark(u, module_y / 2.0)));
+        let rows = 2 * runs(&mut steps(height).map(|v| dark(width - module_x / 2.0, v)));
+        let index = SIZES
+            .iter()
+            .position(|size| size.rows == rows && size.cols == cols)?;
+
+        let (mx, my) = (width / cols as f64, height / rows as f64);
+        Some(Self {
+            version: index as u8 + 1,
+            size: SIZES[index],
+            grid: Grid::new(
+                bitmap,
+                origin,
+                (ax.0 * mx, ax.1 * mx),
+                (ay.0 * my, ay.1 * my),
+            ),
+        })
+    }
+
+    /// Check the finder and timing patterns of every data region, then read
+    /// the codewords in their utah-shaped placement
+    fn read_symbol(&self) -> Option<DecodedQr> {
+        let (size, grid) = (self.size, self.grid);
+        let modules = (0..size.rows).flat_map(|y| (0..size.cols).map(move |x| (x, y)));
+
+        // The finder and timing patterns of every region
+        let (border, errors) = modules
+            .clone()
+            .filter_map(|(x, y)| Some(size.border(x, y)? != grid.dark(x, y)))
+            .fold((0, 0), |(total, errors), wrong| {
+                (total + 1, errors + usize::from(wrong))
+            });
+        if errors > border / 10 {
+            return None;
+        }
+
+        let (rows, cols) = size.mapping();
+        let mut mapping = vec![false; rows * cols];
+        for (x, y) in modules {
+            if size.border(x, y).is_none() {
+                let (row, col) = size.mapping_position(x, y);
+                mapping[row * cols + col] = grid.dark(x, y);
+            }
+        }
+        let codewords: Vec<u8> = placement(rows, cols)
+            .0
+            .iter()
+            .map(|cells| {
+                cells.iter().fold(0, |value, &(row, col)| {
+                    value << 1 | u8::from(mapping[row * cols + col])
+                })
+            })
+            .collect();
+        let (payload, eci) =
+            correct_errors(&codewords, size).and_then(|data| parse_codewords(&data))?;
+
+        Some(DecodedQr {
+            text: String::from_utf8(payload.clone()).ok(),
+            payload,
+            symbology: Symbology::DataMatrix,
+            version: self.version,
+            error_correction: EcLevel::M,
+            ec_percent: None,
+            security_level: None,
+            mask: 0,
+            eci,
+            corners: grid.corners(size.cols, size.rows),
+        })
+    }
+}
+
+/// Undo the interleaving, correct errors in each block and return the data
+/// codewords
+fn correct_errors(codewords: &[u8], size: Size) -> Option<Vec<u8>> {
+    let rs = ReedSolomon::new(GaloisField::new(0x12d, 256), 1);
+    let mut data = codewords.get(..size.data)?.to_vec();
+    for block in 0..size.blocks {
+        let mut indices: Vec<usize> = (block..size.data).step_by(size.blocks).collect();
+        let data_len = indices.len();
+        indices.extend((0..size.ec).map(|i| size.data + i * size.blocks + block));
+        let mut block_codewords = indices
+            .iter()
+            .map(|&i| codewords.get(i).map(|&codeword| u16::from(codeword)))
+            .collect::<Option<Vec<u16>>>()?;
+        rs.correct(&mut block_codewords, size.ec).ok()?;
+        for (&i, &codeword) in indices.iter().zip(&block_codewords[..data_len]) {
+            data[i] = codeword as u8;
+        }
+    }
+    Some(data)
+}
+
+/// Decode the data codewords up to the first pad
+///
+/// ASCII is the starting encodation, from which the C40, Text, X12,
+/// EDIFACT and Base 256 encodations are latched. FNC1 in the first position
+/// marks GS1 data and is dropped; later ones become GS separators. The
+/// first ECI designator is returned along with the payload.
+fn parse_codewords(data: &[u8]) -> Option<(Vec<u8>, Option<u32>)> {
+    let mut payload = Vec::new();
+    let mut eci = None;
+    let mut trailer: &[u8] = &[];
+    let mut upper_shift = false;
+
+    let mut i = 0;
+    while let Some(&codeword) = data.get(i) {
+        i += 1;
+        match codeword {
+            1..=128 => {
+                let byte = codeword - 1;
+                payload.push(if upper_shift { byte + 128 } else { byte });
+                upper_shift = false;
+            }
+            PAD => break,
+            130..=229 => payload.extend(format!("{:02}", codeword - 130).bytes()),
+            LATCH_C40 | LATCH_TEXT | LATCH_X12 => {
+                i = read_triples(data, i, codeword, &mut payload)?;
+            }
+            LATCH_BASE256 => i = read_base256(data, i, &mut payload)?,
+            FNC1 => {
+                if i > 1 {
+                    payload.push(GS);
+                }
+            }
+            // The position in a structured append sequence and file id
+            STRUCTURED_APPEND => i += 3,
+            READER_PROGRAMMING => {}
+            UPPER_SHIFT => upper_shift = true,
+            MACRO_05 | MACRO_06 => {
+                payload.extend(if codeword == MACRO_05 {
+                    b"[)>\x1e05\x1d"
+                } else {
+                    b"[)>\x1e06\x1d"
+                });
+                trailer = b"\x1e\x04";
+            }
+            LATCH_EDIFACT => i = read_edifact(data, i, &mut payload)?,
+            ECI => {
+                let first = usize::from(*data.get(i)?);
+                let (designator, len) = match first {
+                    1..=127 => (first - 1, 1),
+                    128..=191 => (
+                        (first - 128) * 254 + usize::from(*data.get(i + 1)?) - 1 + 127,
+                        2,
+                    ),
+                    192..=253 => (
+                        (first - 192) * 64516
+                            + (usize::from(*data.get(i + 1)?) - 1) * 254
+                            + usize::from(*data.get(i + 2)?)
+                            - 1
+                            + 16383,
+                        3,
+                    ),
+                    _ => return None,
+                };
+                i += len;
+                eci.get_or_insert(designator as u32);
+            }
+            _ => return None,
+        }
+    }
+
+    payload.extend(trailer);
+    Some((payload, eci))
+}
+
+/// Decode C40, Text or X12 codeword pairs, three values each, up to the
+/// unlatch or the end of the data, and return where ASCII resumes
+fn read_triples(data: &[u8], mut i: usize, latch: u8, payload: &mut Vec<u8>) -> Option<usize> {
+    let text = latch == LATCH_TEXT;
+    let mut shift = 0;
+    let mut upper_shift = false;
+    while i + 1 < data.len() {
+        if data[i] == UNLATCH {
+            return Some(i + 1);
+        }
+        let packed = (u16::from(data[i]) << 8 | u16::from(data[i + 1])).checked_sub(1)?;
+        i += 2;
+        for value in [packed / 1600, packed / 40 % 40, packed % 40] {
+            let value = value as u8;
+            if value >= 40 {
+                return None;
+            }
+            let byte = if latch == LATCH_X12 {
+                match value {
+                    0 => b'\r',
+                    1 => b'*',
+                    2 => b'>',
+                    3 => b' ',
+                    4..=13 => b'0' + value - 4,
+                    _ => b'A' + value - 14,
+                }
+            } else {
+                match std::mem::take(&mut shift) {
+                    0 => match value {
+                        0..=2 => {
+                            shift = value + 1;
+                            continue;
+                        }
+                        3 => b' ',
+                        4..=13 => b'0' + value - 4,
+                        _ if text => b'a' + value - 14,
+                        _ => b'A' + value - 14,
+                    },
+                    1 => value,
+                    2 => match value {
+                        0..=26 => SHIFT2[usize::from(value)],
+                        27 => GS,
+                        30 => {
+                            upper_shift = true;
+                            continue;
+                        }
+                        _ => return None,
+                    },
+                    _ if text => match value {
+                        0 => b'`',
+                        1..=26 => b'A' + value - 1,
+                        27..=31 => b'{' + value - 27,
+                        _ => return None,
+                    },
+                    _ => match value {
+                        0..=31 => 96 + value,
+                        _ => return None,
+                    },
+                }
+            };
+            payload.push(if std::mem::take(&mut upper_shift) {
+                byte + 128
+            } else {
+                byte
+            });
+        }
+    }
+    Some(i)
+}
+
+/// Decode EDIFACT, four six-bit values in every three codewords, up to the
+/// unlatch value, and return where ASCII resumes
+fn read_edifact(data: &[u8], mut i: usize, payload: &mut Vec<u8>) -> Option<usize> {
+    // Two codewords or fewer at the end of the symbol are in ASCII
+    while data.len() - i > 2 {
+        let bits = data[i..i + 3]
+            .iter()
+            .fold(0u32, |bits, &codeword| bits << 8 | u32::from(codeword));
+        for k in 0..4 {
+            let value = (bits >> (18 - 6 * k)) & 0x3f;
+            if value == 0x1f {
+                // The rest of the codeword is padding
+                return Some(i + (6 * k as usize + 6).div_ceil(8));
+            }
+            payload.push(if value & 0x20 == 0 {
+                value | 0x40
+            } else {
+                value
+            } as u8);
+        }
+        i += 3;
+    }
+    Some(i)
+}
+
+/// Decode a Base 256 field and return where ASCII resumes
+fn read_base256(data: &[u8], mut i: usize, payload: &mut Vec<u8>) -> Option<usize> {
+    let next = |i: &mut usize| {
+        let byte = unrandomize_255(*data.get(*i)?, *i + 1);
+        *i += 1;
+        Some(byte)
+    };
+    let len = match next(&mut i)? {
+        // The field runs to the end of the symbol
+        0 => data.len() - i,
+        len @ 1..=249 => usize::from(len),
+        high => 250 * (usize::from(high) - 249) + usize::from(next(&mut i)?),
+    };
+    for _ in 0..len {
+        payload.push(next(&mut i)?);
+    }
+    Some(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, Luma};
+
+    use super::*;
+    use crate::qr::encoder::QrEncoder;
+
+    fn datamatrix_image(symbol: &Symbol) -> DynamicImage {
+        QrEncoder::new(300, 2, "M")
+            .unwrap()
+            .to_image(symbol)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_encode_matches_reference_codewords() {
+        // "AIMAIM" in a 14x14 symbol, from ISO/IEC 16022 and other
+        // encoders' test suites
+        let mut data = ascii_codewords(b"AIMAIM", false);
+        pad(&mut data, 8);
+        assert_eq!(
+            add_error_correction(&data, SIZES[2]),
+            [
+                66, 74, 78, 66, 74, 78, 129, 56, 35, 102, 192, 96, 226, 100, 156, 1, 107, 221
+            ]
+        );
+
+        // Annex R: three data codewords in a 10x10 symbol
+        assert_eq!(
+            add_error_correction(&[142, 164, 186], SIZES[0]),
+            [142, 164, 186, 114, 25, 5, 88, 102]
+        );
+        let mut data = ascii_codewords(b"A", false);
+        pad(&mut data, 3);
+        assert_eq!(
+            add_error_correction(&data, SIZES[0]),
+            [66, 129, 70, 138, 234, 82, 82, 95]
+        );
+    }
+
+    #[test]
+    fn test_placement_matches_reference() {
+        let codewords = [
+            66, 74, 78, 66, 74, 78, 129, 56, 35, 102, 192, 96, 226, 100, 156, 1, 107, 221,
+        ];
+        let expected = [
+            "011100001111",
+            "001010101000",
+            "010001010100",
+            "001010100010",
+            "000111000100",
+            "011000010100",
+            "000100001101",
+            "011000010000",
+            "001100001101",
+            "100010010111",
+            "011101011010",
+            "001011001010",
+        ];
+        let symbol = draw(3, SIZES[2], &codewords);
+        let colors = symbol.to_colors();
+        let inner: Vec<String> = (1..13)
+            .map(|y| {
+                (1..13)
+                    .map(|x| match colors[y * 14 + x] {
+                        Color::Dark => '1',
+                        Color::Light => '0',
+                    })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(inner, expected);
+    }
+
+    #[test]
+    fn test_placement_fills_every_size() {
+        // Every codeword has a place, and only the fixed corner is left
+        for (i, size) in SIZES.iter().enumerate() {
+            let (rows, cols) = size.mapping();
+            let (positions, corner) = placement(rows, cols);
+            assert_eq!(
+                positions.len(),
+                size.data + size.ec * size.blocks,
+                "{}",
+                label(i as u8 + 1)
+            );
+            let placed = positions.len() * 8 + if corner { 4 } else { 0 };
+            assert_eq!(placed, rows * cols, "{}", label(i as u8 + 1));
+        }
+    }
+
+    #[test]
+    fn test_border_patterns() {
+        // 10x10: solid left column and bottom row, alternating top and right
+        let symbol = encode(b"1", false, 1..=1).unwrap();
+        let colors = symbol.to_colors();
+        for i in 0..10 {
+            assert_eq!(colors[i * 10], Color::Dark);
+            assert_eq!(colors[90 + i], Color::Dark);
+            let alternating = if i % 2 == 0 {
+                Color::Dark
+            } else {
+                Color::Light
+            };
+            assert_eq!(colors[i], alternating);
+            let alternating = if i % 2 == 1 {
+                Color::Dark
+            } else {
+                Color::Light
+            };
+            assert_eq!(colors[i * 10 + 9], alternating);
+        }
+    }
+
+    #[test]
+    fn test_decode_every_size() {
+        for version in 1..=30 {
+            let symbol = encode(b"DM42", false, version..=version).unwrap();
+            let decoded = decode(&datamatrix_image(&symbol).to_luma8(), &[]);
+
+            assert_eq!(decoded.len(), 1, "{}", label(version));
+            assert_eq!(decoded[0].payload, b"DM42");
+            assert_eq!(decoded[0].version, version);
+            assert_eq!(decoded[0].symbology, Symbology::DataMatrix);
+        }
+    }
+
+    #[test]
+    fn test_decode_rotated_and_mirrored() {
+        let symbol = encode(b"lot 7/12", false, 25..=30).unwrap();
+        let image = datamatrix_image(&symbol);
+        for turned in [
+            image.rotate90(),
+            image.rotate180(),
+            image.rotate270(),
+            image.fliph(),
+            image.rotate90().flipv(),
+        ] {
+            let decoded = decode(&turned.to_luma8(), &[]);
+            assert_eq!(decoded.len(), 1);
+            assert_eq!(decoded[0].payload, b"lot 7/12");
+        }
+    }
+
+    #[test]
+    fn test_decode_corrects_damage() {
+        let symbol = encode(b"Damaged label", false, 5..=5).unwrap();
+        let mut image = datamatrix_image(&symbol).to_luma8();
+        // 18x18 plus the margin is 22 modules of 13px after 7px of padding;
+        // blot out a 2x2 module block in the data area
+        let origin = 7 + 2 * 13;
+        for y in origin + 13 * 6..origin + 13 * 8 {
+            for x in origin + 13 * 6..origin + 13 * 8 {
+                image.put_pixel(x, y, Luma([0]));
+            }
+        }
+
+        let decoded = decode(&image, &[]);
+        assert_eq!(decoded[0].payload, b"Damaged label");
+    }
+
+    #[test]
+    fn test_encode_picks_smallest_size() {
+        // Six digits are three codewords, the capacity of 10x10
+        assert_eq!(encode(b"123456", false, 1..=30).unwrap().width(), 10);
+        assert_eq!(encode(b"1234567", false, 1..=30).unwrap().width(), 12);
+        let symbol = encode(b"12345678", false, 25..=30).unwrap();
+        assert_eq!((symbol.width(), symbol.height()), (18, 8));
+        assert!(encode(&[b'x'; 1600], false, 1..=30).is_none());
+    }
+
+    #[test]
+    fn test_binary_data_uses_base256() {
+        let data: Vec<u8> = (128..=255).collect();
+        assert_eq!(base256_codewords(&data).len(), 130);
+        assert_eq!(ascii_codewords(&data, false).len(), 256);
+
+        let symbol = encode(&data, false, 1..=30).unwrap();
+        let decoded = decode(&datamatrix_image(&symbol).to_luma8(), &[]);
+        assert_eq!(decoded[0].payload, data);
+        assert_eq!(decoded[0].text, None);
+    }
+
+    #[test]
+    fn test_gs1_round_trip() {
+        let payload = gs1_payload("(01)09521234543213(10)ABC123(21)42").unwrap();
+        assert_eq!(payload, b"010952123454321310ABC123\x1d2142");
+
+        let codewords = ascii_codewords(&payload, true);
+        assert_eq!(codewords[0], FNC1);
+        assert_eq!(codewords.iter().filter(|&&c| c == FNC1).count(), 2);
+
+        let symbol = encode(&payload, true, 1..=30).unwrap();
+        let decoded = decode(&datamatrix_image(&symbol).to_luma8(), &[]);
+        assert_eq!(decoded[0].payload, payload);
+    }
+
+    #[test]
+    fn test_gs1_payload_rejects_malformed_elements() {
+        for text in ["", "01095212", "(01)123", "(1)5", "(10", "(10)", "(10)A B"] {
+            assert!(
+                matches!(gs1_payload(text), Err(RqrError::InvalidInput(_))),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_codewords() {
+        // ASCII, a digit pair and an upper shifted byte
+        assert_eq!(
+            parse_codewords(&[66, 142, UPPER_SHIFT, 34, PAD, 55]),
+            Some((vec![b'A', b'1', b'2', 0xa1], None))
+        );
+        // C40 "AIM" then unlatch, from ISO/IEC 16022 Annex S
+        assert_eq!(
+            parse_codewords(&[LATCH_C40, 91, 11, UNLATCH, 66]),
+            Some((b"AIMA".to_vec(), None))
+        );
+        // Text "aim"
+        assert_eq!(
+            parse_codewords(&[LATCH_TEXT, 91, 11]),
+            Some((b"aim".to_vec(), None))
+        );
+        // X12 "AIM"
+        assert_eq!(
+            parse_codewords(&[LATCH_X12, 91, 11]),
+            Some((b"AIM".to_vec(), None))
+        );
+        // EDIFACT "AIM" then unlatch
+        assert_eq!(
+            parse_codewords(&[LATCH_EDIFACT, 4, 147, 95, 66]),
+            Some((b"AIMA".to_vec(), None))
+        );
+        // ECI 26 then ASCII, and a macro 05 header and trailer
+        assert_eq!(
+            parse_codewords(&[ECI, 27, 66]),
+            Some((b"A".to_vec(), Some(26)))
+        );
+        assert_eq!(
+            parse_codewords(&[MACRO_05, 66]),
+            Some((b"[)>\x1e05\x1dA\x1e\x04".to_vec(), None))
+        );
+        assert_eq!(parse_codewords(&[0]), None);
+    }
+}
@@ -0,0 +1,231 @@
+use qrcode::{EcLevel, Version as QrVersion, bits::Bits, ec::construct_codewords};
+use rqrr::{BitGrid, Grid, MetaData, SimpleGrid};
+use serde::Serialize;
+
+use crate::qr::symbol::Symbology;
+
+/// A corner of a decoded QR code, in image pixel coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl From<rqrr::Point> for Point {
+    fn from(point: rqrr::Point) -> Self {
+        Self {
+            x: point.x,
+            y: point.y,
+        }
+    }
+}
+
+/// A QR code decoded from an image, with the metadata read from its symbol
+///
+/// # Examples
+/// ```rust
+/// use qrcode::EcLevel;
+/// use rqr::qr::{decoder::QrDecoder, encoder::QrEncoder};
+///
+/// let encoder = QrEncoder::new(200, 4, "Q")?;
+/// let image = encoder.to_image(&encoder.encode("Hello")?)?;
+///
+/// let decoded = QrDecoder::new().decode_detailed_from_image(image)?;
+/// assert_eq!(decoded[0].text.as_deref(), Some("Hello"));
+/// assert_eq!(decoded[0].version, 1);
+/// assert_eq!(decoded[0].error_correction, EcLevel::Q);
+/// # Ok::<(), rqr::utils::error::RqrError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedQr {
+    /// The payload exactly as encoded
+    pub payload: Vec<u8>,
+    /// The payload as text, when it is valid UTF-8
+    pub text: Option<String>,
+    /// QR, Micro QR, rMQR, Data Matrix, Aztec or PDF417
+    pub symbology: Symbology,
+    /// Symbol version, 1 to 40, 1 to 4 for Micro QR, 1 (R7x43) to 32
+    /// (R17x139) for rMQR, 1 (10x10) to 30 (16x48) for Data Matrix, or 1 to
+    /// 4 (compact) and 5 to 36 (full-range) for Aztec, or the 1 to 30 data
+    /// columns of a PDF417 symbol
+    pub version: u8,
+    /// Error correction level read from the format information; always M
+    /// for Data Matrix, whose ECC 200 has no levels, and for Aztec and
+    /// PDF417
+    pub error_correction: EcLevel,
+    /// Share of the codewords used for error correction, in percent, for
+    /// Aztec symbols, which set it freely
+    pub ec_percent: Option<u8>,
+    /// Security level, 0 to 8, of PDF417 symbols
+    pub security_level: Option<u8>,
+    /// Data mask pattern, 0 to 7, 0 to 3 for Micro QR, always 0 for rMQR,
+    /// Data Matrix, Aztec and PDF417
+    pub mask: u8,
+    /// ECI designator at the start of the data, if any
+    pub eci: Option<u32>,
+    /// Corners in the image, clockwise from the top-left of the symbol
+    pub corners: [Point; 4],
+}
+
+impl DecodedQr {
+    /// Build the result for a decoded grid
+    pub(crate) fn new<G: BitGrid>(grid: &Grid<G>, meta: &MetaData, payload: Vec<u8>) -> Self {
+        let text = String::from_utf8(payload.clone()).ok();
+        Self {
+            payload,
+            text,
+            symbology: Symbology::Qr,
+            version: meta.version.0 as u8,
+            error_correction: ec_level(meta.ecc_level),
+            ec_percent: None,
+            security_level: None,
+            mask: meta.mask as u8,
+            eci: read_eci(&grid.grid, meta),
+            corners: grid.bounds.map(Point::from),
+        }
+    }
+}
+
+/// Map the two format information bits to the error correction level
+fn ec_level(bits: u16) -> EcLevel {
+    match bits {
+        0 => EcLevel::M,
+        1 => EcLevel::L,
+        2 => EcLevel::H,
+        _ => EcLevel::Q,
+    }
+}
+
+/// Read the ECI designator at the start of the data stream
+///
+/// rqrr consumes ECI headers without reporting them, so the first data
+/// codewords are read again from the unmasked grid. They are taken before
+/// error correction, which is enough for the intact symbols this is called
+/// on; a header that does not parse is reported as no ECI.
+fn read_eci(grid: &dyn BitGrid, meta: &MetaData) -> Option<u32> {
+    let version = meta.version.0;
+    let ec = ec_level(meta.ecc_level);
+
+    // rqrr retries mirrored codes transposed, so try both orientations
+    [false, true].into_iter().find_map(|mirrored| {
+        let size = grid.size();
+        let view = |y: usize, x: usize| {
+            if mirrored {
+                grid.bit(x, y)
+            } else {
+                grid.bit(y, x)
+            }
+        };
+        let unmasked = SimpleGrid::from_func(size, |x, y| {
+            view(y, x) ^ (!is_format_cell(size, y, x) && mask_bit(meta.mask, y, x))
+        });
+        let (raw_meta, raw) = Grid::new(unmasked).get_raw_data().ok()?;
+        if raw_meta.mask != meta.mask || raw_meta.ecc_level != meta.ecc_level {
+            return None;
+        }
+
+        let data_len = Bits::new(QrVersion::Normal(version as i16))
+            .max_len(ec)
+            .ok()?
+            / 8;
+        let blocks = block_count(version, ec, data_len)?;
+        let bytes: Vec<u8> = (0..4).map(|i| raw.data[i * blocks]).collect();
+        parse_eci(&bytes)
+    })?
+}
+
+/// Find how many blocks the data codewords are interleaved across
+fn block_count(version: usize, ec: EcLevel, data_len: usize) -> Option<usize> {
+    // The second byte of the first block lands right after the first byte of
+    // every other block
+    let mut marker = vec![0; data_len];
+    marker[1] = 1;
+    let (data, _) = construct_codewords(&marker, QrVersion::Normal(version as i16), ec).ok()?;
+    data.iter().position(|&b| b == 1)
+}
+
+/// Parse the mode indicator and ECI designator from the first data bytes
+///
+/// Returns `None` when the stream does not start with a valid mode,
+/// `Some(None)` when it starts with a mode other than ECI.
+fn parse_eci(bytes: &[u8]) -> Option<Option<u32>> {
+    match bytes[0] >> 4 {
+        0b0111 => {}
+        0b0001 | 0b0010 | 0b0100 | 0b1000 | 0b0000 => return Some(None),
+        _ => return None,
+    }
+    // The designator starts four bits into the stream
+    let byte = |i: usize| (bytes[i] << 4) | (bytes[i + 1] >> 4);
+    let first = u32::from(byte(0));
+    let eci = if first & 0x80 == 0 {
+        first
+    } else if first & 0xc0 == 0x80 {
+        ((first & 0x3f) << 8) | u32::from(byte(1))
+    } else if first & 0xe0 == 0xc0 {
+        ((first & 0x1f) << 16) | (u32::from(byte(1)) << 8) | u32::from(byte(2))
+    } else {
+        return None;
+    };
+    Some(Some(eci))
+}
+
+/// Whether a cell holds format information, which is never masked
+fn is_format_cell(size: usize, y: usize, x: usize) -> bool {
+    (x < 9 && (y < 9 || y + 8 >= size)) || (y < 9 && x + 8 >= size)
+}
+
+/// The data mask patterns from ISO/IEC 18004, row `y` and column `x`
+fn mask_bit(mask: u16, y: usize, x: usize) -> bool {
+    match mask {
+        0 => (y + x).is_multiple_of(2),
+        1 => y.is_multiple_of(2),
+        2 => x.is_multiple_of(3),
+        3 => (y + x).is_multiple_of(3),
+        4 => ((y / 2) + (x / 3)).is_multiple_of(2),
+        5 => (y * x) % 2 + (y * x) % 3 == 0,
+        6 => ((y * x) % 2 + (y * x) % 3).is_multiple_of(2),
+        _ => ((y * x) % 3 + (y + x) % 2).is_multiple_of(2),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_eci_short_designator() {
+        // 0111 | 00011010 | 0100 (byte mode)
+        assert_eq!(parse_eci(&[0x71, 0xa4, 0x00, 0x00]), Some(Some(26)));
+    }
+
+    #[test]
+    fn test_parse_eci_long_designators() {
+        // 0111 | 10 000011 11101000 (1000)
+        assert_eq!(parse_eci(&[0x78, 0x3e, 0x80, 0x00]), Some(Some(1000)));
+        // 0111 | 110 00001 10000110 10100000 (100000)
+        assert_eq!(parse_eci(&[0x7c, 0x18, 0x6a, 0x00]), Some(Some(100_000)));
+    }
+
+    #[test]
+    fn test_parse_eci_other_modes() {
+        assert_eq!(parse_eci(&[0x40, 0x00, 0x00, 0x00]), Some(None));
+        assert_eq!(parse_eci(&[0x20, 0x00, 0x00, 0x00]), Some(None));
+        assert_eq!(parse_eci(&[0xf0, 0x00, 0x00, 0x00]), None);
+    }
+
+    #[test]
+    fn test_ec_level_mapping() {
+        assert_eq!(ec_level(0), EcLevel::M);
+        assert_eq!(ec_level(1), EcLevel::L);
+        assert_eq!(ec_level(2), EcLevel::H);
+        assert_eq!(ec_level(3), EcLevel::Q);
+    }
+
+    #[test]
+    fn test_block_count() {
+        // Version 1-M has one block, 5-Q has four, 40-H has eighty-one
+        assert_eq!(block_count(1, EcLevel::M, 16), Some(1));
+        assert_eq!(block_count(5, EcLevel::Q, 62), Some(4));
+        assert_eq!(block_count(40, EcLevel::H, 1276), Some(81));
+    }
+}
@@ -1,17 +1,26 @@
-use std::{path::Path, process::Command};
+use std::path::Path;
 
 use image::{DynamicImage, open as open_image};
 use rqrr::PreparedImage;
 
-use crate::utils::error::{Result, RqrError};
+use crate::{
+    qr::{
+        aztec, datamatrix, decoded::DecodedQr, linear, micro, pdf417, rmqr, source::ImageSource,
+        symbol::Symbology,
+    },
+    utils::error::{Result, RqrError},
+};
 
 /// QR Code decoder for extracting text from images
 ///
 /// The `QrDecoder` handles the detection and decoding of QR codes
 /// from image files. It supports various image formats and can
-/// detect multiple QR codes in a single image.
+/// detect multiple QR codes in a single image, Micro QR, rMQR, Data
+/// Matrix, Aztec, PDF417 symbols and 1D barcodes included.
 #[derive(Default)]
-pub struct QrDecoder;
+pub struct QrDecoder {
+    symbology: Option<Symbology>,
+}
 
 impl QrDecoder {
     /// Create a new QR decoder
@@ -26,32 +35,88 @@ impl QrDecoder {
     /// let decoder = QrDecoder::new();
     /// ```
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Only report codes of one symbology
+    ///
+    /// The whole image is still searched, so codes of other symbologies
+    /// are not mistaken for this one, but only matching codes are returned
+    /// and an image without any fails to decode.
+    ///
+    /// # Arguments
+    /// * `symbology` - The symbology to report
+    ///
+    /// # Returns
+    /// Returns the decoder with the filter set
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::{decoder::QrDecoder, encoder::QrEncoder, symbol::Symbology};
+    ///
+    /// let encoder = QrEncoder::new(300, 10, "M")?.with_symbology(Symbology::Code128);
+    /// let image = encoder.to_image(&encoder.encode("SKU-4411")?)?;
+    ///
+    /// let decoder = QrDecoder::new().with_symbology(Symbology::Code128);
+    /// assert_eq!(decoder.decode_from_image(image.clone())?, vec!["SKU-4411"]);
+    ///
+    /// let decoder = QrDecoder::new().with_symbology(Symbology::Qr);
+    /// assert!(decoder.decode_from_image(image).is_err());
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn with_symbology(mut self, symbology: Symbology) -> Self {
+        self.symbology = Some(symbology);
+        self
     }
 
     /// Decode QR codes from an image url
     pub fn decode_from_url(&self, url: &str) -> Result<Vec<String>> {
-        let output = Command::new("curl")
-            .args([
-                "-s", // Silent mode
-                "-L", // Follow redirects
-                "-f", // Fail on HTTP error
-                "--max-time",
-                "30",
-                url,
-            ])
-            .output()
-            .map_err(|e| RqrError::DecodingError(format!("Failed to execute curl: {}", e)))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(RqrError::DecodingError(format!("curl failed: {}", stderr)));
-        }
-
-        let img = image::load_from_memory(&output.stdout)?;
+        let img = self.fetch_image(url)?;
         self.decode_from_image(img)
     }
 
+    /// Decode QR codes from an image url along with their metadata
+    pub fn decode_detailed_from_url(&self, url: &str) -> Result<Vec<DecodedQr>> {
+        let img = self.fetch_image(url)?;
+        self.decode_detailed_from_image(img)
+    }
+
+    /// Download and load the image at a url
+    fn fetch_image(&self, url: &str) -> Result<DynamicImage> {
+        ImageSource::Url(url.to_string()).load()
+    }
+
+    /// Decode QR codes from any image source
+    ///
+    /// # Arguments
+    /// * `source` - A file, URL, data URI or stdin
+    ///
+    /// # Returns
+    /// Returns a vector of decoded strings, or an error if decoding fails
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// use rqr::qr::{decoder::QrDecoder, source::ImageSource};
+    ///
+    /// let source: ImageSource = "-".parse()?;
+    /// let contents = QrDecoder::new().decode_from_source(&source)?;
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn decode_from_source(&self, source: &ImageSource) -> Result<Vec<String>> {
+        self.decode_from_image(source.load()?)
+    }
+
+    /// Decode QR codes from any image source along with their metadata
+    ///
+    /// # Arguments
+    /// * `source` - A file, URL, data URI or stdin
+    ///
+    /// # Returns
+    /// Returns a `DecodedQr` for each decoded code, or an error if decoding fails
+    pub fn decode_detailed_from_source(&self, source: &ImageSource) -> Result<Vec<DecodedQr>> {
+        self.decode_detailed_from_image(source.load()?)
+    }
+
     /// Decode QR codes from an image file
     ///
     /// # Arguments
@@ -78,12 +143,98 @@ impl QrDecoder {
 
     /// Decode QR codes from an image buffer
     ///
+    /// Payloads that are not valid UTF-8 are returned as
+    /// `"Binary data: <hex>"`; use `decode_bytes_from_image` to get them
+    /// back unchanged.
+    ///
     /// # Arguments
     /// * `img` - The image to decode
     ///
     /// # Returns
     /// Returns a vector of decoded strings, or an error if decoding fails
     pub fn decode_from_image(&self, img: DynamicImage) -> Result<Vec<String>> {
+        let results = self
+            .decode_bytes_from_image(img)?
+            .into_iter()
+            .map(|bytes| match String::from_utf8(bytes) {
+                Ok(s) => s,
+                // If not valid UTF-8, show as hex
+                Err(e) => format!("Binary data: {}", hex::encode(e.as_bytes())),
+            })
+            .collect();
+
+        Ok(results)
+    }
+
+    /// Decode the exact payload bytes of QR codes in an image file
+    ///
+    /// # Arguments
+    /// * `path` - Path to the image file
+    ///
+    /// # Returns
+    /// Returns the raw payload of each decoded code, or an error if decoding fails
+    pub fn decode_bytes_from_file<P: AsRef<Path>>(&self, path: P) -> Result<Vec<Vec<u8>>> {
+        let img = open_image(path)?;
+        self.decode_bytes_from_image(img)
+    }
+
+    /// Decode the exact payload bytes of QR codes in an image buffer
+    ///
+    /// Unlike `decode_from_image`, binary payloads are returned as the bytes
+    /// that were encoded, without any text conversion.
+    ///
+    /// # Arguments
+    /// * `img` - The image to decode
+    ///
+    /// # Returns
+    /// Returns the raw payload of each decoded code, or an error if decoding fails
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::{decoder::QrDecoder, encoder::QrEncoder};
+    ///
+    /// let data = [0x00, 0xff, 0x10, 0x80];
+    /// let encoder = QrEncoder::new(200, 4, "M")?;
+    /// let image = encoder.to_image(&encoder.encode_bytes(&data)?)?;
+    ///
+    /// let payloads = QrDecoder::new().decode_bytes_from_image(image)?;
+    /// assert_eq!(payloads, vec![data.to_vec()]);
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn decode_bytes_from_image(&self, img: DynamicImage) -> Result<Vec<Vec<u8>>> {
+        let results = self
+            .decode_detailed_from_image(img)?
+            .into_iter()
+            .map(|decoded| decoded.payload)
+            .collect();
+
+        Ok(results)
+    }
+
+    /// Decode QR codes in an image file along with their metadata
+    ///
+    /// # Arguments
+    /// * `path` - Path to the image file
+    ///
+    /// # Returns
+    /// Returns a `DecodedQr` for each decoded code, or an error if decoding fails
+    pub fn decode_detailed_from_file<P: AsRef<Path>>(&self, path: P) -> Result<Vec<DecodedQr>> {
+        let img = open_image(path)?;
+        self.decode_detailed_from_image(img)
+    }
+
+    /// Decode QR codes in an image buffer along with their metadata
+    ///
+    /// Each result carries the payload bytes, the text when the payload is
+    /// UTF-8, the version, error correction level, mask, ECI designator and
+    /// the corners of the code in the image.
+    ///
+    /// # Arguments
+    /// * `img` - The image to decode
+    ///
+    /// # Returns
+    /// Returns a `DecodedQr` for each decoded code, or an error if decoding fails
+    pub fn decode_detailed_from_image(&self, img: DynamicImage) -> Result<Vec<DecodedQr>> {
         // Convert to grayscale
         let luma_img = img.to_luma8();
 
@@ -100,26 +251,12 @@ impl QrDecoder {
         // Find and decode all QR codes in the image
         let grids = prepared_img.detect_grids();
 
-        if grids.is_empty() {
-            return Err(RqrError::DecodingError(
-                "No QR codes found in image".to_string(),
-            ));
-        }
-
         let mut results = Vec::new();
 
-        for grid in grids {
-            match grid.decode() {
-                Ok((_meta, content)) => {
-                    let content_str = match std::str::from_utf8(content.as_bytes()) {
-                        Ok(s) => s.to_string(),
-                        Err(_) => {
-                            // If not valid UTF-8, show as hex
-                            format!("Binary data: {}", hex::encode(content.as_bytes()))
-                        }
-                    };
-                    results.push(content_str);
-                }
+        for grid in &grids {
+            let mut payload = Vec::new();
+            match grid.decode_to(&mut payload) {
+                Ok(meta) => results.push(DecodedQr::new(grid, &meta, payload)),
                 Err(e) => {
                     eprintln!("Failed to decode QR code: {:?}", e);
                     continue;
@@ -127,10 +264,47 @@ impl QrDecoder {
             }
         }
 
+        // rqrr only knows QR codes, which have three finder patterns; look
+        // for rMQR and then Micro QR symbols around the finder patterns left
+        // over. rMQR goes first as its finder pattern would pass for a Micro
+        // QR one.
+        let rmqr = rmqr::decode(&luma_img, &results);
+        results.extend(rmqr);
+        let micro = micro::decode(&luma_img, &results);
+        results.extend(micro);
+        // Data Matrix symbols have no finder pattern and are found by the
+        // solid L of their edges
+        let data_matrix = datamatrix::decode(&luma_img, &results);
+        results.extend(data_matrix);
+        // Aztec symbols are found by their central bullseye, so they need
+        // no quiet zone
+        let aztec = aztec::decode(&luma_img, &results);
+        results.extend(aztec);
+        // PDF417 rows are read line by line between their start and stop
+        // patterns
+        let pdf417 = pdf417::decode(&luma_img, &results);
+        results.extend(pdf417);
+        // 1D barcodes go last, as scan lines through a 2D code could
+        // otherwise be read as bars
+        let linear = linear::decode(&luma_img, &results);
+        results.extend(linear);
+
+        if let Some(symbology) = self.symbology {
+            results.retain(|decoded| decoded.symbology == symbology);
+            if results.is_empty() {
+                return Err(RqrError::DecodingError(format!(
+                    "No {} codes found in image",
+                    symbology
+                )));
+            }
+        }
         if results.is_empty() {
-            return Err(RqrError::DecodingError(
-                "Found QR codes but failed to decode any".to_string(),
-            ));
+            let message = if grids.is_empty() {
+                "No codes found in image"
+            } else {
+                "Found QR codes but failed to decode any"
+            };
+            return Err(RqrError::DecodingError(message.to_string()));
         }
 
         Ok(results)
@@ -142,10 +316,14 @@ mod tests {
     use std::path::Path;
 
     use image::{DynamicImage, ImageBuffer, Luma};
+    use qrcode::{EcLevel, Version, bits::Bits};
     use tempfile::TempDir;
 
     use super::*;
-    use crate::qr::encoder::QrEncoder;
+    use crate::qr::{
+        encoder::QrEncoder,
+        symbol::{Symbol, SymbolVersion, Symbology},
+    };
 
     /// Helper: Create a test QR image file
     fn create_test_qr_image(content: &str, path: &Path) {
@@ -262,6 +440,34 @@ mod tests {
         assert_eq!(results[0], "Test from buffer");
     }
 
+    #[test]
+    fn test_decode_bytes_from_image_binary() {
+        let data: Vec<u8> = (0..=255).collect();
+        let encoder = QrEncoder::new(400, 4, "M").unwrap();
+        let image = encoder
+            .to_image(&encoder.encode_bytes(&data).unwrap())
+            .unwrap();
+
+        let decoder = QrDecoder::new();
+        let payloads = decoder.decode_bytes_from_image(image.clone()).unwrap();
+        assert_eq!(payloads, vec![data.clone()]);
+
+        let text = decoder.decode_from_image(image).unwrap();
+        assert_eq!(text, vec![format!("Binary data: {}", hex::encode(&data))]);
+    }
+
+    #[test]
+    fn test_decode_bytes_from_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let image_path = temp_dir.path().join("bytes.png");
+
+        create_test_qr_image("Plain text", &image_path);
+
+        let decoder = QrDecoder::new();
+        let payloads = decoder.decode_bytes_from_file(&image_path).unwrap();
+        assert_eq!(payloads, vec![b"Plain text".to_vec()]);
+    }
+
     #[test]
     fn test_decode_from_image_no_qr() {
         let blank_image =
@@ -272,7 +478,7 @@ mod tests {
 
         assert!(result.is_err());
         let err = result.unwrap_err();
-        assert!(err.to_string().contains("No QR codes found"));
+        assert!(err.to_string().contains("No codes found"));
     }
 
     #[test]
@@ -319,4 +525,272 @@ mod tests {
             assert_eq!(results[0], "EC test");
         }
     }
+
+    #[test]
+    fn test_decode_detailed_metadata() {
+        for (level, expected) in [
+            ("L", EcLevel::L),
+            ("M", EcLevel::M),
+            ("Q", EcLevel::Q),
+            ("H", EcLevel::H),
+        ] {
+            let encoder = QrEncoder::new(200, 4, level).unwrap();
+            let image = encoder
+                .to_image(&encoder.encode("Metadata").unwrap())
+                .unwrap();
+
+            let decoded = QrDecoder::new().decode_detailed_from_image(image).unwrap();
+            assert_eq!(decoded.len(), 1);
+            assert_eq!(decoded[0].payload, b"Metadata");
+            assert_eq!(decoded[0].text.as_deref(), Some("Metadata"));
+            assert!((1..=2).contains(&decoded[0].version));
+            assert_eq!(decoded[0].error_correction, expected);
+            assert!(decoded[0].mask < 8);
+            assert_eq!(decoded[0].eci, None);
+        }
+    }
+
+    #[test]
+    fn test_decode_detailed_large_version() {
+        let content = "v".repeat(400);
+        let encoder = QrEncoder::new(400, 4, "M").unwrap();
+        let qr_code = encoder.encode(&content).unwrap();
+        let image = encoder.to_image(&qr_code).unwrap();
+
+        let decoded = QrDecoder::new().decode_detailed_from_image(image).unwrap();
+        assert_eq!(SymbolVersion::Qr(decoded[0].version), qr_code.version());
+        assert_eq!(decoded[0].text.as_deref(), Some(content.as_str()));
+    }
+
+    #[test]
+    fn test_decode_detailed_corners() {
+        let encoder = QrEncoder::new(200, 4, "M").unwrap();
+        let image = encoder
+            .to_image(&encoder.encode("Corners").unwrap())
+            .unwrap();
+        let (width, height) = (image.width() as i32, image.height() as i32);
+
+        let decoded = QrDecoder::new().decode_detailed_from_image(image).unwrap();
+        let [top_left, top_right, bottom_right, bottom_left] = decoded[0].corners;
+        for corner in decoded[0].corners {
+            assert!((0..=width).contains(&corner.x) && (0..=height).contains(&corner.y));
+        }
+        assert!(top_left.x < top_right.x && top_left.y < bottom_left.y);
+        assert!(bottom_right.x > bottom_left.x && bottom_right.y > top_right.y);
+    }
+
+    #[test]
+    fn test_decode_detailed_binary_has_no_text() {
+        let data = [0xff, 0xfe, 0x00, 0x81];
+        let encoder = QrEncoder::new(200, 4, "M").unwrap();
+        let image = encoder
+            .to_image(&encoder.encode_bytes(&data).unwrap())
+            .unwrap();
+
+        let decoded = QrDecoder::new().decode_detailed_from_image(image).unwrap();
+        assert_eq!(decoded[0].payload, data);
+        assert_eq!(decoded[0].text, None);
+    }
+
+    #[test]
+    fn test_decode_detailed_eci() {
+        for (eci, version) in [(26, 1), (899, 3), (20_000, 8)] {
+            let mut bits = Bits::new(Version::Normal(version));
+            bits.push_eci_designator(eci).unwrap();
+            bits.push_byte_data("Grüße".as_bytes()).unwrap();
+            bits.push_terminator(EcLevel::Q).unwrap();
+            let qr_code = Symbol::from_bits(bits, EcLevel::Q, None).unwrap();
+
+            let encoder = QrEncoder::new(300, 4, "Q").unwrap();
+            let image = encoder.to_image(&qr_code).unwrap();
+
+            let decoded = QrDecoder::new().decode_detailed_from_image(image).unwrap();
+            assert_eq!(decoded[0].eci, Some(eci));
+            assert_eq!(decoded[0].text.as_deref(), Some("Grüße"));
+        }
+    }
+
+    #[test]
+    fn test_decode_detailed_from_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let image_path = temp_dir.path().join("detailed.png");
+
+        create_test_qr_image("Detailed", &image_path);
+
+        let decoded = QrDecoder::new()
+            .decode_detailed_from_file(&image_path)
+            .unwrap();
+        assert_eq!(decoded[0].text.as_deref(), Some("Detailed"));
+    }
+
+    #[test]
+    fn test_decode_detailed_micro_qr() {
+        let encoder = QrEncoder::new(200, 2, "M")
+            .unwrap()
+            .with_symbology(Symbology::Micro);
+        let image = encoder.to_image(&encoder.encode("Micro").unwrap()).unwrap();
+
+        let decoded = QrDecoder::new().decode_detailed_from_image(image).unwrap();
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].symbology, Symbology::Micro);
+        assert_eq!(decoded[0].text.as_deref(), Some("Micro"));
+        assert_eq!(decoded[0].error_correction, EcLevel::M);
+    }
+
+    #[test]
+    fn test_decode_qr_and_micro_qr_side_by_side() {
+        let qr = create_test_qr_image_buffer("Big brother").to_luma8();
+        let encoder = QrEncoder::new(200, 2, "L")
+            .unwrap()
+            .with_symbology(Symbology::Micro);
+        let micro = encoder
+            .to_image(&encoder.encode("SMALL").unwrap())
+            .unwrap()
+            .to_luma8();
+
+        let mut both = ImageBuffer::from_pixel(400, 200, Luma([255u8]));
+        image::imageops::overlay(&mut both, &qr, 0, 0);
+        image::imageops::overlay(&mut both, &micro, 200, 0);
+
+        let decoded = QrDecoder::new()
+            .decode_detailed_from_image(DynamicImage::ImageLuma8(both))
+            .unwrap();
+        let found: Vec<_> = decoded
+            .iter()
+            .map(|d| (d.symbology, d.text.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![(Symbology::Qr, "Big brother"), (Symbology::Micro, "SMALL")]
+        );
+    }
+
+    #[test]
+    fn test_decode_qr_and_aztec_side_by_side() {
+        let qr = create_test_qr_image_buffer("Boarding pass").to_luma8();
+        // Aztec symbols are drawn without a margin and still decode
+        let encoder = QrEncoder::new(200, 0, "M")
+            .unwrap()
+            .with_symbology(Symbology::Aztec);
+        let aztec = encoder
+            .to_image(&encoder.encode("TKT 0421 SEAT 12C").unwrap())
+            .unwrap()
+            .to_luma8();
+
+        let mut both = ImageBuffer::from_pixel(420, 200, Luma([255u8]));
+        image::imageops::overlay(&mut both, &qr, 0, 0);
+        image::imageops::overlay(&mut both, &aztec, 210, 0);
+
+        let decoded = QrDecoder::new()
+            .decode_detailed_from_image(DynamicImage::ImageLuma8(both))
+            .unwrap();
+        let found: Vec<_> = decoded
+            .iter()
+            .map(|d| (d.symbology, d.text.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Symbology::Qr, "Boarding pass"),
+                (Symbology::Aztec, "TKT 0421 SEAT 12C")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_qr_above_pdf417() {
+        let qr = create_test_qr_image_buffer("Manifest").to_luma8();
+        let encoder = QrEncoder::new(400, 2, "M")
+            .unwrap()
+            .with_symbology(Symbology::Pdf417);
+        let pdf417 = encoder
+            .to_image(&encoder.encode("PALLET 0042 OF 0100").unwrap())
+            .unwrap()
+            .to_luma8();
+
+        let mut both = ImageBuffer::from_pixel(400, 210 + pdf417.height(), Luma([255u8]));
+        image::imageops::overlay(&mut both, &qr, 0, 0);
+        image::imageops::overlay(&mut both, &pdf417, 0, 210);
+
+        let decoded = QrDecoder::new()
+            .decode_detailed_from_image(DynamicImage::ImageLuma8(both))
+            .unwrap();
+        let found: Vec<_> = decoded
+            .iter()
+            .map(|d| (d.symbology, d.text.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Symbology::Qr, "Manifest"),
+                (Symbology::Pdf417, "PALLET 0042 OF 0100")
+            ]
+        );
+        assert_eq!(decoded[1].security_level, Some(3));
+    }
+
+    #[test]
+    fn test_decode_label_with_symbology_filter() {
+        let qr = create_test_qr_image_buffer("https://example.com/item/4411").to_luma8();
+        let encoder = QrEncoder::new(400, 10, "M")
+            .unwrap()
+            .with_symbology(Symbology::Ean13);
+        let ean = encoder
+            .to_image(&encoder.encode("400638133393").unwrap())
+            .unwrap()
+            .to_luma8();
+
+        let mut label = ImageBuffer::from_pixel(600, 400, Luma([255u8]));
+        image::imageops::overlay(&mut label, &qr, 0, 0);
+        image::imageops::overlay(&mut label, &ean, 200, 100);
+        let label = DynamicImage::ImageLuma8(label);
+
+        let found: Vec<_> = QrDecoder::new()
+            .decode_detailed_from_image(label.clone())
+            .unwrap()
+            .into_iter()
+            .map(|d| (d.symbology, d.text.unwrap()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Symbology::Qr, "https://example.com/item/4411".to_string()),
+                (Symbology::Ean13, "4006381333931".to_string())
+            ]
+        );
+
+        let decoder = QrDecoder::new().with_symbology(Symbology::Ean13);
+        assert_eq!(
+            decoder.decode_from_image(label.clone()).unwrap(),
+            vec!["4006381333931"]
+        );
+        let err = QrDecoder::new()
+            .with_symbology(Symbology::Code128)
+            .decode_from_image(label)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "QR code decoding failed: No code128 codes found in image"
+        );
+    }
+
+    #[test]
+    fn test_decode_detailed_eci_mirrored() {
+        let mut bits = Bits::new(Version::Normal(2));
+        bits.push_eci_designator(26).unwrap();
+        bits.push_byte_data(b"Mirror").unwrap();
+        bits.push_terminator(EcLevel::M).unwrap();
+        let qr_code = Symbol::from_bits(bits, EcLevel::M, None).unwrap();
+
+        let encoder = QrEncoder::new(200, 4, "M").unwrap();
+        let image = encoder.to_image(&qr_code).unwrap();
+        // Transpose the image so it can only be read mirrored
+        let mirrored = image.rotate90().fliph();
+
+        let decoded = QrDecoder::new()
+            .decode_detailed_from_image(mirrored)
+            .unwrap();
+        assert_eq!(decoded[0].eci, Some(26));
+        assert_eq!(decoded[0].text.as_deref(), Some("Mirror"));
+    }
 }
@@ -1,9 +1,51 @@
-use std::path::Path;
+use std::{fs::File, io::BufWriter, path::Path};
 
-use image::{DynamicImage, ImageBuffer, Luma};
-use qrcode::{EcLevel, QrCode};
+use image::{
+    DynamicImage, ImageBuffer, Luma, Rgba, RgbaImage,
+    codecs::{
+        jpeg::JpegEncoder,
+        pnm::{PnmEncoder, PnmSubtype, SampleEncoding},
+    },
+    imageops::FilterType,
+};
+use qrcode::{
+    EcLevel, Version,
+    bits::{self, Bits},
+};
 
-use crate::utils::error::{Result, RqrError};
+use crate::{
+    qr::{
+        aztec, datamatrix,
+        decoder::QrDecoder,
+        linear,
+        output::{FitMode, LengthUnit, OutputFormat, PhysicalSize, RgbaColor},
+        pdf417, rmqr,
+        style::{self, ModuleShape, PathSyntax, Style},
+        symbol::{Symbol, Symbology},
+        terminal::{self, TerminalMode},
+    },
+    utils::error::{Result, RqrError},
+};
+
+/// Share of the correctable codewords a centre logo may use up
+///
+/// Covered modules rarely line up with codeword boundaries, so a logo damages
+/// more codewords than its area suggests. Keeping half of the error
+/// correction in reserve also leaves room for print and scan noise.
+const LOGO_ERROR_BUDGET: f64 = 0.5;
+
+/// Quality used for lossy output when none is configured
+///
+/// Higher than the usual photo default: JPEG blurs the hard module edges
+/// first, and smaller files are rarely worth an unreadable code.
+pub const DEFAULT_QUALITY: u8 = 90;
+
+/// An image composited over the centre of the QR code
+#[derive(Debug, Clone)]
+struct Logo {
+    image: DynamicImage,
+    scale: f64,
+}
 
 /// QR Code encoder with configurable parameters
 ///
@@ -14,6 +56,22 @@ pub struct QrEncoder {
     size: u32,
     margin: u32,
     error_correction: EcLevel,
+    fit: FitMode,
+    foreground: RgbaColor,
+    background: RgbaColor,
+    logo: Option<Logo>,
+    physical_size: Option<PhysicalSize>,
+    style: Style,
+    quality: u8,
+    symbology: Symbology,
+    min_version: u8,
+    max_version: u8,
+    mask: Option<u8>,
+    gs1: bool,
+    ec_percent: Option<u8>,
+    columns: Option<u8>,
+    rows: Option<u8>,
+    security_level: Option<u8>,
 }
 
 impl QrEncoder {
@@ -51,16 +109,503 @@ impl QrEncoder {
             size,
             margin,
             error_correction: ec_level,
+            fit: FitMode::default(),
+            foreground: RgbaColor::BLACK,
+            background: RgbaColor::WHITE,
+            logo: None,
+            physical_size: None,
+            style: Style::default(),
+            quality: DEFAULT_QUALITY,
+            symbology: Symbology::default(),
+            min_version: 1,
+            max_version: 40,
+            mask: None,
+            gs1: false,
+            ec_percent: None,
+            columns: None,
+            rows: None,
+            security_level: None,
+        })
+    }
+
+    /// Composite a logo over the centre of raster output
+    ///
+    /// The logo is fitted into a square whose side is `scale` times the
+    /// symbol width (margin excluded). While a logo is set, `encode` raises
+    /// the error correction level to Q or H when the configured level cannot
+    /// recover the covered area.
+    ///
+    /// # Arguments
+    /// * `logo` - The image to place in the centre
+    /// * `scale` - Logo width as a fraction of the symbol width, in `(0, 1)`
+    ///
+    /// # Returns
+    /// Returns the updated encoder or an error for an invalid scale
+    ///
+    /// # Examples
+    /// ```rust
+    /// use image::{DynamicImage, RgbaImage};
+    /// use rqr::qr::encoder::QrEncoder;
+    ///
+    /// let logo = DynamicImage::ImageRgba8(RgbaImage::new(32, 32));
+    /// let encoder = QrEncoder::new(400, 4, "M")?.with_logo(logo, 0.2)?;
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn with_logo(mut self, logo: DynamicImage, scale: f64) -> Result<Self> {
+        if !(scale > 0.0 && scale < 1.0) {
+            return Err(RqrError::InvalidInput(format!(
+                "Logo scale must be between 0 and 1, got {}",
+                scale
+            )));
+        }
+
+        self.logo = Some(Logo { image: logo, scale });
+        Ok(self)
+    }
+
+    /// The largest logo scale a QR code can recover from
+    ///
+    /// Derived from the number of codeword errors the code's version and
+    /// error correction level can correct, with half of that capacity kept
+    /// in reserve. The result is a fraction of the symbol width, or of the
+    /// height for rMQR.
+    ///
+    /// # Arguments
+    /// * `qr_code` - The QR code the logo would cover
+    pub fn max_logo_scale(&self, qr_code: &Symbol) -> f64 {
+        let budget_modules = qr_code.max_allowed_errors() as f64 * 8.0 * LOGO_ERROR_BUDGET;
+        (budget_modules.sqrt() / short_side(qr_code) as f64).min(1.0)
+    }
+
+    /// Set the module and background colours
+    ///
+    /// Raster output switches from grayscale to RGBA unless the colours are
+    /// the default opaque black on white. A fully transparent background
+    /// leaves the quiet zone and light modules see-through.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::{encoder::QrEncoder, output::RgbaColor};
+    ///
+    /// let encoder = QrEncoder::new(200, 4, "M")?
+    ///     .with_colors("#1e3a8a".parse()?, RgbaColor::TRANSPARENT);
+    /// let qr_code = encoder.encode("Hello")?;
+    /// assert!(encoder.to_image(&qr_code)?.color().has_alpha());
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn with_colors(mut self, foreground: RgbaColor, background: RgbaColor) -> Self {
+        self.foreground = foreground;
+        self.background = background;
+        self
+    }
+
+    /// Set the module and finder pattern styling
+    ///
+    /// The style applies to raster, SVG and PDF output alike. Non-square
+    /// shapes leave less ink per module, so callers should check the result
+    /// with `verify_image` before handing it out.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::{
+    ///     encoder::QrEncoder,
+    ///     style::{ModuleShape, Style},
+    /// };
+    ///
+    /// let encoder = QrEncoder::new(400, 4, "M")?.with_style(Style {
+    ///     module_shape: ModuleShape::Dot,
+    ///     ..Default::default()
+    /// });
+    /// let qr_code = encoder.encode("Hello")?;
+    /// encoder.verify_image(&encoder.to_image(&qr_code)?, "Hello")?;
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Set the quality of lossy raster output
+    ///
+    /// Only JPEG is affected; every other format is written losslessly.
+    ///
+    /// # Arguments
+    /// * `quality` - Encoder quality from 1 (smallest) to 100 (best)
+    ///
+    /// # Returns
+    /// Returns the updated encoder or an error for an out-of-range quality
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::encoder::QrEncoder;
+    ///
+    /// let encoder = QrEncoder::new(200, 4, "M")?.with_quality(75)?;
+    /// assert_eq!(encoder.quality(), 75);
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn with_quality(mut self, quality: u8) -> Result<Self> {
+        if !(1..=100).contains(&quality) {
+            return Err(RqrError::InvalidInput(format!(
+                "Quality must be between 1 and 100, got {}",
+                quality
+            )));
+        }
+
+        self.quality = quality;
+        Ok(self)
+    }
+
+    /// The quality used for lossy raster output
+    pub fn quality(&self) -> u8 {
+        self.quality
+    }
+
+    /// Encode Micro QR symbols instead of QR codes
+    ///
+    /// Micro QR has versions M1 (11x11 modules) to M4 (17x17) and a single
+    /// finder pattern, for labels too small for a QR code. It supports error
+    /// correction levels L, M and Q; M1 holds digits only and only detects
+    /// errors. `with_version` and `with_min_version` then count 1 to 4 and
+    /// `with_mask` takes 0 to 3.
+    ///
+    /// rMQR symbols are rectangles from R7x43 to R17x139 for long, narrow
+    /// labels, at error correction level M or H. Versions count 1 (R7x43)
+    /// to 32 (R17x139), from the flattest symbol up, and there is a single
+    /// mask pattern.
+    ///
+    /// Data Matrix ECC 200 symbols are squares from 10x10 to 144x144, with
+    /// no logo, mask or choice of error correction level. Versions count
+    /// the squares from 1 (10x10) to 24 (144x144), then the rectangles from
+    /// 25 (8x18) to 30 (16x48), so the smallest square is picked unless
+    /// `with_min_version(25)` asks for a rectangle.
+    ///
+    /// Aztec symbols are squares around a bullseye that need no quiet zone,
+    /// with no logo or mask. Versions 1 to 4 are compact symbols from 15x15
+    /// to 27x27 and versions 5 to 36 full-range symbols from 19x19 to
+    /// 151x151; a full-range symbol is only picked over a compact one once
+    /// the compact sizes are too small. The error correction level sets
+    /// how much error correction is added, 10, 23, 36 or 50 percent of the
+    /// data, unless `with_ec_percent` chooses the share directly.
+    ///
+    /// PDF417 symbols are stacked rows of 1 to 30 data columns, 3 to 90
+    /// rows high, sized with `with_columns` and `with_rows` rather than
+    /// versions, with no logo or mask. The error correction level raises the
+    /// recommended security level, unless `with_security_level` sets it.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::{
+    ///     encoder::QrEncoder,
+    ///     symbol::{SymbolVersion, Symbology},
+    /// };
+    ///
+    /// let encoder = QrEncoder::new(200, 2, "L")?.with_symbology(Symbology::Micro);
+    /// let symbol = encoder.encode("12345")?;
+    /// assert_eq!(symbol.version(), SymbolVersion::Micro(1));
+    /// assert_eq!(symbol.width(), 11);
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn with_symbology(mut self, symbology: Symbology) -> Self {
+        self.symbology = symbology;
+        self
+    }
+
+    /// Always encode with one symbol version
+    ///
+    /// Every code then has the same dimensions, whatever its content;
+    /// content that does not fit is an error rather than a larger code.
+    ///
+    /// # Arguments
+    /// * `version` - Symbol version from 1 (21x21 modules) to 40 (177x177)
+    ///
+    /// # Returns
+    /// Returns the updated encoder or an error for an out-of-range version
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::encoder::QrEncoder;
+    ///
+    /// let encoder = QrEncoder::new(200, 4, "M")?.with_version(5)?;
+    /// assert_eq!(encoder.encode("Hi")?.width(), 37);
+    /// assert!(QrEncoder::new(200, 4, "M")?.with_version(1)?.encode(&"x".repeat(100)).is_err());
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn with_version(mut self, version: u8) -> Result<Self> {
+        self.min_version = check_version(version)?;
+        self.max_version = version;
+        Ok(self)
+    }
+
+    /// Use the smallest version that fits, but never one below `version`
+    ///
+    /// # Arguments
+    /// * `version` - Smallest symbol version to use, from 1 to 40
+    ///
+    /// # Returns
+    /// Returns the updated encoder or an error for an out-of-range version
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::{encoder::QrEncoder, symbol::SymbolVersion};
+    ///
+    /// let encoder = QrEncoder::new(200, 4, "M")?.with_min_version(3)?;
+    /// assert_eq!(encoder.encode("Hi")?.version(), SymbolVersion::Qr(3));
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn with_min_version(mut self, version: u8) -> Result<Self> {
+        self.min_version = check_version(version)?;
+        self.max_version = 40;
+        Ok(self)
+    }
+
+    /// Always apply one mask pattern instead of the lowest-penalty one
+    ///
+    /// # Arguments
+    /// * `mask` - Mask pattern number from 0 to 7
+    ///
+    /// # Returns
+    /// Returns the updated encoder or an error for an out-of-range mask
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::encoder::QrEncoder;
+    ///
+    /// let encoder = QrEncoder::new(200, 4, "M")?.with_mask(3)?;
+    /// assert_eq!(encoder.encode("Hi")?.mask(), 3);
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn with_mask(mut self, mask: u8) -> Result<Self> {
+        if mask > 7 {
+            return Err(RqrError::InvalidInput(format!(
+                "Mask must be between 0 and 7, got {}",
+                mask
+            )));
+        }
+
+        self.mask = Some(mask);
+        Ok(self)
+    }
+
+    /// Encode GS1 element strings in a GS1 Data Matrix
+    ///
+    /// The symbol starts with FNC1, and each GS character in the data, as
+    /// `datamatrix::gs1_payload` puts after variable length values, is
+    /// written as an FNC1 separator. Other symbologies refuse to encode.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::{datamatrix::gs1_payload, encoder::QrEncoder, symbol::Symbology};
+    ///
+    /// let encoder = QrEncoder::new(200, 2, "M")?
+    ///     .with_symbology(Symbology::DataMatrix)
+    ///     .with_gs1(true);
+    /// let payload = gs1_payload("(01)09521234543213(10)ABC123")?;
+    /// assert_eq!(encoder.encode_bytes(&payload)?.version_label(), "18x18");
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn with_gs1(mut self, gs1: bool) -> Self {
+        self.gs1 = gs1;
+        self
+    }
+
+    /// Add a fixed share of error correction to Aztec symbols
+    ///
+    /// Aztec error correction is not limited to four levels: the symbol
+    /// keeps `percent` percent of the data's size, plus three codewords,
+    /// for error correction. Other symbologies refuse to encode.
+    ///
+    /// # Arguments
+    /// * `percent` - Error correction in percent of the data, from 5 to 95
+    ///
+    /// # Returns
+    /// Returns the updated encoder or an error for an out-of-range share
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::{encoder::QrEncoder, symbol::Symbology};
+    ///
+    /// let encoder = QrEncoder::new(200, 2, "M")?
+    ///     .with_symbology(Symbology::Aztec)
+    ///     .with_ec_percent(80)?;
+    /// assert!(encoder.encode("PNR X7K2QD")?.max_allowed_errors() >= 4);
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn with_ec_percent(mut self, percent: u8) -> Result<Self> {
+        if !(5..=95).contains(&percent) {
+            return Err(RqrError::InvalidInput(format!(
+                "Error correction percentage must be between 5 and 95, got {}",
+                percent
+            )));
+        }
+
+        self.ec_percent = Some(percent);
+        Ok(self)
+    }
+
+    /// Use a fixed number of data columns in PDF417 symbols
+    ///
+    /// The rows then grow with the data. Other symbologies refuse to
+    /// encode.
+    ///
+    /// # Arguments
+    /// * `columns` - Data columns, from 1 to 30
+    ///
+    /// # Returns
+    /// Returns the updated encoder or an error for an out-of-range count
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::{
+    ///     encoder::QrEncoder,
+    ///     symbol::{SymbolVersion, Symbology},
+    /// };
+    ///
+    /// let encoder = QrEncoder::new(400, 2, "M")?
+    ///     .with_symbology(Symbology::Pdf417)
+    ///     .with_columns(4)?;
+    /// let symbol = encoder.encode("DOCK 7")?;
+    /// assert!(matches!(
+    ///     symbol.version(),
+    ///     SymbolVersion::Pdf417 { columns: 4, .. }
+    /// ));
+    /// assert_eq!(symbol.width(), 4 * 17 + 69);
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn with_columns(mut self, columns: u8) -> Result<Self> {
+        if !(1..=pdf417::MAX_COLUMNS).contains(&columns) {
+            return Err(RqrError::InvalidInput(format!(
+                "Columns must be between 1 and {}, got {}",
+                pdf417::MAX_COLUMNS,
+                columns
+            )));
+        }
+
+        self.columns = Some(columns);
+        Ok(self)
+    }
+
+    /// Use a fixed number of rows in PDF417 symbols
+    ///
+    /// The columns then grow with the data, unless `with_columns` fixes
+    /// them too. Other symbologies refuse to encode.
+    ///
+    /// # Arguments
+    /// * `rows` - Rows, from 3 to 90
+    ///
+    /// # Returns
+    /// Returns the updated encoder or an error for an out-of-range count
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::{encoder::QrEncoder, symbol::Symbology};
+    ///
+    /// let encoder = QrEncoder::new(400, 2, "M")?
+    ///     .with_symbology(Symbology::Pdf417)
+    ///     .with_rows(6)?;
+    /// assert!(encoder.encode("DOCK 7")?.version_label().starts_with("6x"));
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn with_rows(mut self, rows: u8) -> Result<Self> {
+        if !(pdf417::MIN_ROWS..=pdf417::MAX_ROWS).contains(&rows) {
+            return Err(RqrError::InvalidInput(format!(
+                "Rows must be between {} and {}, got {}",
+                pdf417::MIN_ROWS,
+                pdf417::MAX_ROWS,
+                rows
+            )));
+        }
+
+        self.rows = Some(rows);
+        Ok(self)
+    }
+
+    /// Set the security level of PDF417 symbols
+    ///
+    /// Level `n` adds 2^(n+1) error correction codewords, from 2 at level
+    /// 0 to 512 at level 8, whatever the error correction level. Other
+    /// symbologies refuse to encode.
+    ///
+    /// # Arguments
+    /// * `level` - Security level, from 0 to 8
+    ///
+    /// # Returns
+    /// Returns the updated encoder or an error for an out-of-range level
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::{encoder::QrEncoder, symbol::Symbology};
+    ///
+    /// let encoder = QrEncoder::new(400, 2, "M")?
+    ///     .with_symbology(Symbology::Pdf417)
+    ///     .with_security_level(5)?;
+    /// assert_eq!(encoder.encode("DOCK 7")?.max_allowed_errors(), 31);
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn with_security_level(mut self, level: u8) -> Result<Self> {
+        if level > pdf417::MAX_SECURITY_LEVEL {
+            return Err(RqrError::InvalidInput(format!(
+                "Security level must be between 0 and {}, got {}",
+                pdf417::MAX_SECURITY_LEVEL,
+                level
+            )));
+        }
+
+        self.security_level = Some(level);
+        Ok(self)
+    }
+
+    /// Set how raster output reaches the requested size
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::{encoder::QrEncoder, output::FitMode};
+    ///
+    /// let encoder = QrEncoder::new(200, 4, "M")?.with_fit(FitMode::Scale);
+    /// let qr_code = encoder.encode("Hello")?;
+    /// assert_eq!(encoder.to_image(&qr_code)?.width(), 200);
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn with_fit(mut self, fit: FitMode) -> Self {
+        self.fit = fit;
+        self
+    }
+
+    /// Set the physical width of the code in PDF output
+    ///
+    /// The symbol itself is drawn this wide; the quiet zone is added
+    /// around it. Without it, PDF output treats `size` as points (72 DPI).
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::encoder::QrEncoder;
+    ///
+    /// let encoder = QrEncoder::new(200, 4, "M")?.with_physical_size("25mm".parse()?);
+    /// assert_eq!(encoder.physical_size().to_string(), "25mm");
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn with_physical_size(mut self, physical_size: PhysicalSize) -> Self {
+        self.physical_size = Some(physical_size);
+        self
+    }
+
+    /// The physical width of the code in PDF output, margin excluded
+    pub fn physical_size(&self) -> PhysicalSize {
+        self.physical_size.unwrap_or(PhysicalSize {
+            value: f64::from(self.size),
+            unit: LengthUnit::Pt,
         })
     }
 
     /// Encode text content into a QR code
     ///
+    /// With a logo set, the error correction level is raised to Q or H when
+    /// the configured level cannot recover the area the logo covers.
+    ///
     /// # Arguments
     /// * `content` - The text to encode
     ///
     /// # Returns
-    /// Returns a `QrCode` structure or an error if encoding fails
+    /// Returns a `Symbol` or an error if encoding fails
     ///
     /// # Examples
     /// ```rust
@@ -70,261 +615,2208 @@ impl QrEncoder {
     /// let qr_code = encoder.encode("Hello, World!")?;
     /// # Ok::<(), rqr::utils::error::RqrError>(())
     /// ```
-    pub fn encode(&self, content: &str) -> Result<QrCode> {
-        QrCode::with_error_correction_level(content, self.error_correction)
-            .map_err(|e| RqrError::EncodingError(e.to_string()))
+    pub fn encode(&self, content: &str) -> Result<Symbol> {
+        self.encode_bytes(content.as_bytes())
     }
 
-    /// Convert a QR code to an image
+    /// Encode arbitrary bytes into a QR code
+    ///
+    /// The data does not have to be text: every byte is stored exactly and
+    /// comes back unchanged from `QrDecoder::decode_bytes_from_image`.
     ///
     /// # Arguments
-    /// * `qr_code` - The QR code to render
+    /// * `data` - The bytes to encode
     ///
     /// # Returns
-    /// Returns a `DynamicImage` or an error if rendering fails
-    pub fn to_image(&self, qr_code: &QrCode) -> Result<DynamicImage> {
-        // Get the QR code as a vector of colors
-        let qr_matrix = qr_code.to_colors();
-        let qr_width = qr_code.width();
+    /// Returns a `Symbol` or an error if the data does not fit
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::encoder::QrEncoder;
+    ///
+    /// let encoder = QrEncoder::new(200, 10, "M")?;
+    /// let qr_code = encoder.encode_bytes(&[0x00, 0xff, 0xfe])?;
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn encode_bytes(&self, data: &[u8]) -> Result<Symbol> {
+        if self.gs1 && self.symbology != Symbology::DataMatrix {
+            return Err(RqrError::InvalidInput(
+                "GS1 mode is only supported for Data Matrix".to_string(),
+            ));
+        }
+        if self.ec_percent.is_some() && self.symbology != Symbology::Aztec {
+            return Err(RqrError::InvalidInput(
+                "An error correction percentage is only supported for Aztec".to_string(),
+            ));
+        }
+        if (self.columns.is_some() || self.rows.is_some() || self.security_level.is_some())
+            && self.symbology != Symbology::Pdf417
+        {
+            return Err(RqrError::InvalidInput(
+                "Columns, rows and a security level are only supported for PDF417".to_string(),
+            ));
+        }
+
+        let Some(logo) = &self.logo else {
+            return self.encode_at_level(data, self.error_correction);
+        };
+
+        // Raise error correction until the code can recover the logo area
+        let mut max_scale = 0.0;
+        for ec_level in [self.error_correction, EcLevel::Q, EcLevel::H] {
+            if ec_level < self.error_correction
+                || (ec_level != self.error_correction
+                    && !self.symbology.supports_error_correction(ec_level))
+            {
+                continue;
+            }
+
+            let qr_code = self.encode_at_level(data, ec_level)?;
+            max_scale = self.max_logo_scale(&qr_code);
+            if logo.scale <= max_scale {
+                return Ok(qr_code);
+            }
+        }
+
+        Err(RqrError::EncodingError(format!(
+            "Logo scale {} exceeds the safe maximum of {:.2} even at error correction H",
+            logo.scale, max_scale
+        )))
+    }
+
+    /// Encode in the smallest allowed version at one error correction level
+    fn encode_at_level(&self, data: &[u8], ec_level: EcLevel) -> Result<Symbol> {
+        let too_long = |versions: String| {
+            RqrError::EncodingError(format!(
+                "Content does not fit in {} at error correction level {:?}",
+                versions, ec_level
+            ))
+        };
+
+        let (prefix, symbol_version): (_, fn(i16) -> Version) = match self.symbology {
+            Symbology::Qr => {
+                if self.min_version == 1 && self.max_version == 40 {
+                    let bits = bits::encode_auto(data, ec_level)
+                        .map_err(|_| too_long("any version".to_string()))?;
+                    return Symbol::from_bits(bits, ec_level, self.mask);
+                }
+                ("", Version::Normal)
+            }
+            Symbology::Micro => {
+                if self.min_version > 4 {
+                    return Err(RqrError::InvalidInput(format!(
+                        "Micro QR version must be between 1 and 4, got {}",
+                        self.min_version
+                    )));
+                }
+                if ec_level == EcLevel::H {
+                    return Err(RqrError::InvalidInput(
+                        "Micro QR supports error correction levels L, M and Q, not H".to_string(),
+                    ));
+                }
+                ("M", Version::Micro)
+            }
+            Symbology::Rmqr => return self.encode_rmqr(data, ec_level),
+            Symbology::DataMatrix => return self.encode_data_matrix(data),
+            Symbology::Aztec => return self.encode_aztec(data, ec_level),
+            Symbology::Pdf417 => return self.encode_pdf417(data, ec_level),
+            Symbology::Code128
+            | Symbology::Ean13
+            | Symbology::UpcA
+            | Symbology::Code39
+            | Symbology::Itf => return self.encode_linear(data),
+        };
+
+        let max_version = self.max_version.min(self.symbology.max_version());
+        for version in self.min_version..=max_version {
+            let mut bits = Bits::new(symbol_version(i16::from(version)));
+            if bits.push_optimal_data(data).is_ok() && bits.push_terminator(ec_level).is_ok() {
+                return Symbol::from_bits(bits, ec_level, self.mask);
+            }
+        }
+        Err(too_long(if self.min_version == max_version {
+            format!("version {}{}", prefix, self.min_version)
+        } else {
+            format!(
+                "versions {}{}-{}{}",
+                prefix, self.min_version, prefix, max_version
+            )
+        }))
+    }
+
+    /// Encode an rMQR symbol in the flattest allowed version that fits
+    fn encode_rmqr(&self, data: &[u8], ec_level: EcLevel) -> Result<Symbol> {
+        if !self.symbology.supports_error_correction(ec_level) {
+            return Err(RqrError::InvalidInput(format!(
+                "rMQR supports error correction levels M and H, not {:?}",
+                ec_level
+            )));
+        }
+        if self.mask.is_some() {
+            return Err(RqrError::InvalidInput(
+                "rMQR has a single mask pattern, a mask cannot be chosen".to_string(),
+            ));
+        }
+        if self.min_version > 32 {
+            return Err(RqrError::InvalidInput(format!(
+                "rMQR version must be between 1 and 32, got {}",
+                self.min_version
+            )));
+        }
+
+        let max_version = self.max_version.min(32);
+        rmqr::encode(data, ec_level, self.min_version..=max_version).ok_or_else(|| {
+            let versions = if self.min_version == max_version {
+                format!("version {}", rmqr::label(max_version))
+            } else {
+                format!(
+                    "versions {}-{}",
+                    rmqr::label(self.min_version),
+                    rmqr::label(max_version)
+                )
+            };
+            RqrError::EncodingError(format!(
+                "Content does not fit in {} at error correction level {:?}",
+                versions, ec_level
+            ))
+        })
+    }
+
+    /// Encode a Data Matrix symbol in the smallest allowed size that fits
+    fn encode_data_matrix(&self, data: &[u8]) -> Result<Symbol> {
+        if self.logo.is_some() {
+            return Err(RqrError::InvalidInput(
+                "Data Matrix symbols cannot carry a logo".to_string(),
+            ));
+        }
+        if self.mask.is_some() {
+            return Err(RqrError::InvalidInput(
+                "Data Matrix has no mask patterns, a mask cannot be chosen".to_string(),
+            ));
+        }
+        if self.min_version > 30 {
+            return Err(RqrError::InvalidInput(format!(
+                "Data Matrix version must be between 1 and 30, got {}",
+                self.min_version
+            )));
+        }
+
+        let max_version = self.max_version.min(30);
+        datamatrix::encode(data, self.gs1, self.min_version..=max_version).ok_or_else(|| {
+            // The rectangles only hold less than the largest square
+            let largest = if self.min_version <= datamatrix::LAST_SQUARE {
+                max_version.min(datamatrix::LAST_SQUARE)
+            } else {
+                max_version
+            };
+            RqrError::EncodingError(if self.min_version == max_version {
+                format!(
+                    "Content does not fit in a {} Data Matrix",
+                    datamatrix::label(max_version)
+                )
+            } else {
+                format!(
+                    "Content does not fit in Data Matrix sizes {} to {}",
+                    datamatrix::label(self.min_version),
+                    datamatrix::label(largest)
+                )
+            })
+        })
+    }
+
+    /// Encode an Aztec symbol in the smallest allowed size that fits
+    fn encode_aztec(&self, data: &[u8], ec_level: EcLevel) -> Result<Symbol> {
+        if self.logo.is_some() {
+            return Err(RqrError::InvalidInput(
+                "Aztec symbols cannot carry a logo".to_string(),
+            ));
+        }
+        if self.mask.is_some() {
+            return Err(RqrError::InvalidInput(
+                "Aztec has no mask patterns, a mask cannot be chosen".to_string(),
+            ));
+        }
+        if self.min_version > 36 {
+            return Err(RqrError::InvalidInput(format!(
+                "Aztec version must be between 1 and 36, got {}",
+                self.min_version
+            )));
+        }
+
+        let max_version = self.max_version.min(36);
+        let ec_percent = self
+            .ec_percent
+            .unwrap_or_else(|| aztec::level_ec_percent(ec_level));
+        aztec::encode(data, ec_percent, self.min_version..=max_version).ok_or_else(|| {
+            RqrError::EncodingError(if self.min_version == max_version {
+                format!(
+                    "Content does not fit in a {} Aztec symbol",
+                    aztec::label(max_version)
+                )
+            } else {
+                format!(
+                    "Content does not fit in Aztec sizes {} to {}",
+                    aztec::label(self.min_version),
+                    aztec::label(max_version)
+                )
+            })
+        })
+    }
+
+    /// Encode a PDF417 symbol with the columns and rows asked for, or a
+    /// size that fits
+    fn encode_pdf417(&self, data: &[u8], ec_level: EcLevel) -> Result<Symbol> {
+        if self.logo.is_some() {
+            return Err(RqrError::InvalidInput(
+                "PDF417 symbols cannot carry a logo".to_string(),
+            ));
+        }
+        if self.mask.is_some() {
+            return Err(RqrError::InvalidInput(
+                "PDF417 has no mask patterns, a mask cannot be chosen".to_string(),
+            ));
+        }
+        if self.min_version != 1 || self.max_version != 40 {
+            return Err(RqrError::InvalidInput(
+                "PDF417 has no versions, set its size with columns and rows".to_string(),
+            ));
+        }
+
+        pdf417::encode(data, self.security_level, ec_level, self.columns, self.rows).ok_or_else(
+            || {
+                RqrError::EncodingError(match (self.columns, self.rows) {
+                    (Some(columns), Some(rows)) => format!(
+                        "Content does not fit in a PDF417 symbol of {} rows and {} columns",
+                        rows, columns
+                    ),
+                    (Some(columns), None) => {
+                        format!(
+                            "Content does not fit in a PDF417 symbol of {} columns",
+                            columns
+                        )
+                    }
+                    (None, Some(rows)) => {
+                        format!("Content does not fit in a PDF417 symbol of {} rows", rows)
+                    }
+                    (None, None) => "Content does not fit in a PDF417 symbol".to_string(),
+                })
+            },
+        )
+    }
+
+    /// Encode a 1D barcode with its text beneath the bars
+    fn encode_linear(&self, data: &[u8]) -> Result<Symbol> {
+        if self.logo.is_some() {
+            return Err(RqrError::InvalidInput(
+                "1D barcodes cannot carry a logo".to_string(),
+            ));
+        }
+        if self.mask.is_some() {
+            return Err(RqrError::InvalidInput(
+                "1D barcodes have no mask patterns, a mask cannot be chosen".to_string(),
+            ));
+        }
+        if self.min_version != 1 || self.max_version != 40 {
+            return Err(RqrError::InvalidInput(
+                "1D barcodes have no versions, their width follows the content".to_string(),
+            ));
+        }
+        if self.style.module_shape != ModuleShape::Square {
+            return Err(RqrError::InvalidInput(
+                "1D barcodes are drawn with solid bars, the module shape must be square"
+                    .to_string(),
+            ));
+        }
+
+        linear::encode(data, self.symbology)
+    }
+
+    /// Convert a QR code to an image
+    ///
+    /// Modules are drawn with a whole number of pixels each. When `size` is
+    /// not a multiple of the module count, the configured `FitMode` decides
+    /// whether the remainder is padded into the quiet zone, the image is
+    /// resampled to the exact size, or rendering fails.
+    ///
+    /// `size` is the width of the image. Square codes get a square image;
+    /// rMQR symbols get one just tall enough for their rows, with the same
+    /// padding on every side.
+    ///
+    /// # Arguments
+    /// * `qr_code` - The QR code to render
+    ///
+    /// # Returns
+    /// Returns a `DynamicImage` or an error if rendering fails
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::{encoder::QrEncoder, symbol::Symbology};
+    ///
+    /// // R7x43 with a two module margin is 47x11 modules of 10 pixels
+    /// let encoder = QrEncoder::new(470, 2, "M")?.with_symbology(Symbology::Rmqr);
+    /// let image = encoder.to_image(&encoder.encode("CABLE-7")?)?;
+    /// assert_eq!((image.width(), image.height()), (470, 110));
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn to_image(&self, qr_code: &Symbol) -> Result<DynamicImage> {
+        // Calculate module size based on desired output size and margin
+        let size = self.size as usize;
+        let (total_width, total_height) = self.total_modules(qr_code);
+        let module_size = size.checked_div(total_width).unwrap_or(1);
+
+        if module_size == 0 {
+            return Err(RqrError::InvalidInput(
+                "Size too small for the specified margin".to_string(),
+            ));
+        }
+
+        // Work out the canvas size and where the first module starts
+        let (canvas, offset) = match self.fit {
+            FitMode::Pad => {
+                let padding = size - total_width * module_size;
+                ((size, total_height * module_size + padding), padding / 2)
+            }
+            FitMode::Scale => ((total_width * module_size, total_height * module_size), 0),
+            FitMode::ExactModules => {
+                if !size.is_multiple_of(total_width) {
+                    return Err(RqrError::InvalidInput(format!(
+                        "Size {} is not a multiple of {} modules (margin included), try {} or {}",
+                        size,
+                        total_width,
+                        total_width * module_size,
+                        total_width * (module_size + 1)
+                    )));
+                }
+                ((size, total_height * module_size), 0)
+            }
+        };
+
+        let mut image = if self.style.is_plain() {
+            DynamicImage::ImageLuma8(self.draw_plain(qr_code, canvas, offset, module_size))
+        } else {
+            DynamicImage::ImageRgba8(self.draw_styled(qr_code, canvas, offset, module_size))
+        };
+        if canvas.0 != size {
+            let height = self.output_height(qr_code, f64::from(self.size)).round() as u32;
+            image = image.resize_exact(self.size, height, FilterType::Nearest);
+        }
+
+        // Scaling stretches the modules along with the canvas
+        let pixels_per_module = (module_size * size) as f64 / canvas.0 as f64;
+        let image = self.colorize(image);
+        Ok(self.overlay_logo(image, qr_code, pixels_per_module))
+    }
+
+    /// Modules across and down the rendered code, margin included
+    fn total_modules(&self, qr_code: &Symbol) -> (usize, usize) {
+        let margin = self.margin as usize * 2;
+        (qr_code.width() + margin, qr_code.height() + margin)
+    }
+
+    /// The height of SVG and PDF output for a `width` wide drawing, in the
+    /// same unit
+    ///
+    /// Square codes are as tall as they are wide; rMQR symbols keep the
+    /// proportions of their modules, margin included.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::{encoder::QrEncoder, symbol::Symbology};
+    ///
+    /// let encoder = QrEncoder::new(470, 2, "M")?.with_symbology(Symbology::Rmqr);
+    /// let symbol = encoder.encode("CABLE-7")?;
+    /// assert_eq!(encoder.output_height(&symbol, 470.0), 110.0);
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn output_height(&self, qr_code: &Symbol, width: f64) -> f64 {
+        let (total_width, total_height) = self.total_modules(qr_code);
+        width * total_height as f64 / total_width as f64
+    }
+
+    /// Draw square modules in black and white onto a canvas
+    fn draw_plain(
+        &self,
+        qr_code: &Symbol,
+        (canvas_width, canvas_height): (usize, usize),
+        offset: usize,
+        module_size: usize,
+    ) -> ImageBuffer<Luma<u8>, Vec<u8>> {
+        let qr_matrix = qr_code.to_colors();
+        let qr_width = qr_code.width();
+
+        let mut output_image: ImageBuffer<Luma<u8>, Vec<u8>> =
+            ImageBuffer::new(canvas_width as u32, canvas_height as u32);
+
+        // Fill with white background
+        for pixel in output_image.pixels_mut() {
+            *pixel = Luma([255u8]);
+        }
+
+        // Draw the QR code
+        for (y, row) in qr_matrix.chunks(qr_width).enumerate() {
+            for (x, &module) in row.iter().enumerate() {
+                let color = match module {
+                    qrcode::Color::Light => Luma([255u8]), // White
+                    qrcode::Color::Dark => Luma([0u8]),    // Black
+                };
+
+                // Draw the module as a square block
+                for dy in 0..module_size {
+                    for dx in 0..module_size {
+                        let out_x = offset + (x + self.margin as usize) * module_size + dx;
+                        let out_y = offset + (y + self.margin as usize) * module_size + dy;
+
+                        if out_x < canvas_width && out_y < canvas_height {
+                            output_image.put_pixel(out_x as u32, out_y as u32, color);
+                        }
+                    }
+                }
+            }
+        }
+
+        output_image
+    }
+
+    /// Draw the styled shapes in their final colours onto a canvas
+    ///
+    /// Each pixel is painted when its centre falls inside a shape, with the
+    /// outlines of one shape combined by the even-odd rule.
+    fn draw_styled(
+        &self,
+        qr_code: &Symbol,
+        (canvas_width, canvas_height): (usize, usize),
+        offset: usize,
+        module_size: usize,
+    ) -> RgbaImage {
+        let mut canvas = RgbaImage::from_pixel(
+            canvas_width as u32,
+            canvas_height as u32,
+            Rgba(self.background.to_rgba()),
+        );
+        let origin = (offset + self.margin as usize * module_size) as f64;
+        let module_size = module_size as f64;
+        let to_pixel =
+            |module: f64, limit: usize| (origin + module * module_size).clamp(0.0, limit as f64);
+
+        for shape in style::layout(qr_code, &self.style, self.foreground) {
+            let mut coverage = vec![false; canvas_width * canvas_height];
+            for outline in &shape.outlines {
+                let left = to_pixel(outline.x, canvas_width) as usize;
+                let top = to_pixel(outline.y, canvas_height) as usize;
+                let right = to_pixel(outline.x + outline.width, canvas_width).ceil() as usize;
+                let bottom = to_pixel(outline.y + outline.height, canvas_height).ceil() as usize;
+
+                for py in top..bottom {
+                    for px in left..right {
+                        let mx = (px as f64 + 0.5 - origin) / module_size;
+                        let my = (py as f64 + 0.5 - origin) / module_size;
+                        if outline.contains(mx, my) {
+                            coverage[py * canvas_width + px] ^= true;
+                        }
+                    }
+                }
+            }
+
+            let color = Rgba(shape.color.to_rgba());
+            for (index, _) in coverage.iter().enumerate().filter(|(_, covered)| **covered) {
+                let (x, y) = (index % canvas_width, index / canvas_width);
+                canvas.put_pixel(x as u32, y as u32, color);
+            }
+        }
+
+        canvas
+    }
+
+    /// Composite the logo, if any, over the centre of a rendered code
+    ///
+    /// The logo's square is first cleared to the background colour so the
+    /// covered area is the same no matter how transparent the logo is. It is
+    /// sized from the modules as drawn, not the canvas, so padding never
+    /// makes it larger than `max_logo_scale` allows.
+    fn overlay_logo(
+        &self,
+        image: DynamicImage,
+        qr_code: &Symbol,
+        pixels_per_module: f64,
+    ) -> DynamicImage {
+        let Some(logo) = &self.logo else {
+            return image;
+        };
+
+        let mut canvas = image.to_rgba8();
+        let box_size = (logo.scale * short_side(qr_code) as f64 * pixels_per_module).round() as u32;
+        if box_size == 0 {
+            return DynamicImage::ImageRgba8(canvas);
+        }
+
+        let box_x = (canvas.width() - box_size) / 2;
+        let box_y = (canvas.height() - box_size) / 2;
+        let background = Rgba(self.background.to_rgba());
+        for y in box_y..box_y + box_size {
+            for x in box_x..box_x + box_size {
+                canvas.put_pixel(x, y, background);
+            }
+        }
+
+        let resized = logo
+            .image
+            .resize(box_size, box_size, FilterType::Lanczos3)
+            .to_rgba8();
+        let logo_x = box_x + (box_size - resized.width()) / 2;
+        let logo_y = box_y + (box_size - resized.height()) / 2;
+        image::imageops::overlay(&mut canvas, &resized, logo_x.into(), logo_y.into());
+
+        DynamicImage::ImageRgba8(canvas)
+    }
+
+    /// Map a black-and-white rendering onto the configured colours
+    fn colorize(&self, image: DynamicImage) -> DynamicImage {
+        let default_colors =
+            self.foreground == RgbaColor::BLACK && self.background == RgbaColor::WHITE;
+        if default_colors || !self.style.is_plain() {
+            return image;
+        }
+
+        let luma = image.to_luma8();
+        let foreground = Rgba(self.foreground.to_rgba());
+        let background = Rgba(self.background.to_rgba());
+        let colored = RgbaImage::from_fn(luma.width(), luma.height(), |x, y| {
+            if luma.get_pixel(x, y)[0] < 128 {
+                foreground
+            } else {
+                background
+            }
+        });
+
+        DynamicImage::ImageRgba8(colored)
+    }
+
+    /// Check that a rendered image still decodes to the expected content
+    ///
+    /// Transparent pixels are judged as if the code were printed on white.
+    ///
+    /// # Arguments
+    /// * `image` - The rendered QR code
+    /// * `content` - The text or bytes the image must decode to
+    ///
+    /// # Returns
+    /// Returns `Ok(())` if a QR code with exactly `content` is found,
+    /// or an `EncodingError` describing why the image is unreadable
+    pub fn verify_image(&self, image: &DynamicImage, content: impl AsRef<[u8]>) -> Result<()> {
+        let content = content.as_ref();
+        let results = QrDecoder::new()
+            .decode_bytes_from_image(flatten_on_white(image))
+            .map_err(|e| {
+                RqrError::EncodingError(format!("Rendered QR code is not readable: {}", e))
+            })?;
+
+        if !results.iter().any(|result| result == content) {
+            return Err(RqrError::EncodingError(
+                "Rendered QR code decodes to different content".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Convert a QR code to an SVG document
+    ///
+    /// The drawing is a true vector image: the view box is measured in modules
+    /// (margin included) and each horizontal run of dark modules is emitted as
+    /// its own `<path>`. The `size` setting becomes the physical width and height.
+    ///
+    /// # Arguments
+    /// * `qr_code` - The QR code to render
+    ///
+    /// # Returns
+    /// Returns the SVG document as a string
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::encoder::QrEncoder;
+    ///
+    /// let encoder = QrEncoder::new(200, 4, "M")?;
+    /// let qr_code = encoder.encode("Hello, SVG!")?;
+    /// let svg = encoder.to_svg_string(&qr_code);
+    /// assert!(svg.starts_with("<?xml"));
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn to_svg_string(&self, qr_code: &Symbol) -> String {
+        let qr_matrix = qr_code.to_colors();
+        let qr_width = qr_code.width();
+        let margin = self.margin as usize;
+        let (total_width, total_height) = self.total_modules(qr_code);
+
+        let mut svg = String::new();
+        svg.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        svg.push_str(&format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" \
+             width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {total_width} {total_height}\"{rendering}>\n",
+            width = self.size,
+            height = pdf_number(self.output_height(qr_code, f64::from(self.size))),
+            rendering = if self.style.is_plain() {
+                " shape-rendering=\"crispEdges\""
+            } else {
+                ""
+            },
+        ));
+        if self.background.alpha() > 0 {
+            svg.push_str(&format!(
+                "<rect width=\"{total_width}\" height=\"{total_height}\"{fill}/>\n",
+                fill = svg_fill(self.background),
+            ));
+        }
+        if !self.style.is_plain() {
+            // One even-odd path per shape, with every outline as a subpath
+            for shape in style::layout(qr_code, &self.style, self.foreground) {
+                let d: String = shape
+                    .outlines
+                    .iter()
+                    .map(|outline| outline.to_path(margin as f64, margin as f64, PathSyntax::Svg))
+                    .collect();
+                svg.push_str(&format!(
+                    "<path d=\"{}\"{} fill-rule=\"evenodd\"/>\n",
+                    d,
+                    svg_fill(shape.color)
+                ));
+            }
+            svg.push_str("</svg>\n");
+            return svg;
+        }
+
+        let foreground = svg_fill(self.foreground);
+
+        // Emit one path per horizontal run of dark modules
+        for (y, row) in qr_matrix.chunks(qr_width).enumerate() {
+            let mut x = 0;
+            while x < qr_width {
+                if row[x] != qrcode::Color::Dark {
+                    x += 1;
+                    continue;
+                }
+
+                let start = x;
+                while x < qr_width && row[x] == qrcode::Color::Dark {
+                    x += 1;
+                }
+
+                svg.push_str(&format!(
+                    "<path d=\"M{} {}h{}v1h-{}z\"{}/>\n",
+                    start + margin,
+                    y + margin,
+                    x - start,
+                    x - start,
+                    foreground,
+                ));
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Convert a QR code to a single-page PDF document
+    ///
+    /// The symbol is exactly `physical_size` wide and as high as its
+    /// proportions call for; the page grows by the margin on every side.
+    /// Every horizontal run of dark modules is drawn as a filled rectangle,
+    /// so the file can go to press without any rasterization. PDF fills are
+    /// opaque, so colours are flattened onto white paper and a fully
+    /// transparent background is simply not drawn.
+    ///
+    /// # Arguments
+    /// * `qr_code` - The QR code to render
+    ///
+    /// # Returns
+    /// Returns the raw bytes of the PDF file
+    pub fn to_pdf_bytes(&self, qr_code: &Symbol) -> Vec<u8> {
+        let qr_matrix = qr_code.to_colors();
+        let qr_width = qr_code.width();
+        let margin = self.margin as usize;
+        let (total_width, total_height) = self.total_modules(qr_code);
+        let module_size = self.physical_size().points() / qr_width as f64;
+        let page_width = module_size * total_width as f64;
+        let page_height = module_size * total_height as f64;
+
+        // Draw in module units; PDF's origin is the bottom-left corner
+        let mut content = String::new();
+        content.push_str("q\n");
+        content.push_str(&format!(
+            "{scale} 0 0 {scale} 0 0 cm\n",
+            scale = pdf_number(module_size),
+        ));
+        if self.background.alpha() > 0 {
+            content.push_str(&format!(
+                "{}\n0 0 {} {} re f\n",
+                pdf_fill(self.background),
+                total_width,
+                total_height
+            ));
+        }
+        if !self.style.is_plain() {
+            // Flip to a top-left origin so the outlines can be used as laid out
+            content.push_str(&format!("1 0 0 -1 0 {} cm\n", total_height));
+            for shape in style::layout(qr_code, &self.style, self.foreground) {
+                content.push_str(&format!("{}\n", pdf_fill(shape.color)));
+                for outline in &shape.outlines {
+                    content.push_str(&outline.to_path(
+                        margin as f64,
+                        margin as f64,
+                        PathSyntax::Pdf,
+                    ));
+                }
+                content.push_str("f*\n");
+            }
+            content.push_str("Q\n");
+        } else {
+            content.push_str(&format!("{}\n", pdf_fill(self.foreground)));
+            for (y, row) in qr_matrix.chunks(qr_width).enumerate() {
+                let pdf_y = total_height - margin - y - 1;
+                let mut x = 0;
+                while x < qr_width {
+                    if row[x] != qrcode::Color::Dark {
+                        x += 1;
+                        continue;
+                    }
+
+                    let start = x;
+                    while x < qr_width && row[x] == qrcode::Color::Dark {
+                        x += 1;
+                    }
+
+                    content.push_str(&format!(
+                        "{} {} {} 1 re\n",
+                        start + margin,
+                        pdf_y,
+                        x - start
+                    ));
+                }
+            }
+            content.push_str("f\nQ\n");
+        }
+
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                 /Resources << >> /Contents 4 0 R >>",
+                pdf_number(page_width),
+                pdf_number(page_height),
+            ),
+            format!(
+                "<< /Length {} >>\nstream\n{}endstream",
+                content.len(),
+                content
+            ),
+        ];
+
+        let mut pdf = Vec::new();
+        pdf.extend_from_slice(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n");
+
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+        }
+
+        let xref_offset = pdf.len();
+        let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            trailer.push_str(&format!("{:010} 00000 n \n", offset));
+        }
+        trailer.push_str(&format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref_offset
+        ));
+        pdf.extend_from_slice(trailer.as_bytes());
+
+        pdf
+    }
+
+    /// Render a QR code and save it as a raster image
+    ///
+    /// # Arguments
+    /// * `qr_code` - The QR code to render
+    /// * `path` - Where to write the image, the extension picks the format
+    pub fn save_to_file(&self, qr_code: &Symbol, path: &Path) -> Result<()> {
+        let image = self.to_image(qr_code)?;
+        self.save_image(&image, path)
+    }
+
+    /// Save a rendered image in the raster format matching the path
+    ///
+    /// JPEG is written at the configured quality. Formats without an alpha
+    /// channel (JPEG, PNM) get transparent areas flattened onto white, and
+    /// PNM files are written as binary PBM, PGM or PPM to match the extension.
+    ///
+    /// # Arguments
+    /// * `image` - The image returned by `to_image`
+    /// * `path` - Where to write the image, the extension picks the format
+    ///
+    /// # Returns
+    /// Returns `Ok(())` on success, or an error for non-raster extensions
+    pub fn save_image(&self, image: &DynamicImage, path: &Path) -> Result<()> {
+        let format = OutputFormat::from_path(path)?;
+        if !format.is_raster() {
+            return Err(RqrError::UnsupportedFormat(format!(
+                "{:?} is not a raster image format",
+                format
+            )));
+        }
+
+        let image = if format.supports_alpha() || !image.color().has_alpha() {
+            image.clone()
+        } else {
+            flatten_on_white(image)
+        };
+
+        match format {
+            OutputFormat::Jpeg => {
+                let writer = BufWriter::new(File::create(path)?);
+                image.write_with_encoder(JpegEncoder::new_with_quality(writer, self.quality))?;
+            }
+            OutputFormat::Pnm => {
+                let extension = path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .map(str::to_lowercase);
+                let (subtype, image) = match extension.as_deref() {
+                    Some("pbm") => {
+                        // PBM samples are 0 for black and 1 for white
+                        let mut bits = image.to_luma8();
+                        for pixel in bits.pixels_mut() {
+                            pixel[0] = u8::from(pixel[0] >= 128);
+                        }
+                        (PnmSubtype::Bitmap(SampleEncoding::Binary), bits.into())
+                    }
+                    Some("pgm") => (
+                        PnmSubtype::Graymap(SampleEncoding::Binary),
+                        image.to_luma8().into(),
+                    ),
+                    _ => (
+                        PnmSubtype::Pixmap(SampleEncoding::Binary),
+                        DynamicImage::from(image.to_rgb8()),
+                    ),
+                };
+                let writer = BufWriter::new(File::create(path)?);
+                image.write_with_encoder(PnmEncoder::new(writer).with_subtype(subtype))?;
+            }
+            // The GIF encoder only takes RGB(A) pixels
+            OutputFormat::Gif => DynamicImage::from(image.to_rgba8()).save(path)?,
+            _ => image.save(path)?,
+        }
+        Ok(())
+    }
+
+    /// Save a QR code as an SVG file
+    ///
+    /// # Arguments
+    /// * `qr_code` - The QR code to render
+    /// * `path` - Where to write the SVG document
+    pub fn save_to_svg(&self, qr_code: &Symbol, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_svg_string(qr_code))?;
+        Ok(())
+    }
+
+    /// Save a QR code as a single-page PDF file
+    ///
+    /// # Arguments
+    /// * `qr_code` - The QR code to render
+    /// * `path` - Where to write the PDF document
+    pub fn save_to_pdf(&self, qr_code: &Symbol, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_pdf_bytes(qr_code))?;
+        Ok(())
+    }
+
+    /// Draw a QR code with Unicode block characters for the terminal
+    ///
+    /// # Arguments
+    /// * `qr_code` - The QR code to render
+    /// * `mode` - How modules map onto character cells
+    /// * `invert` - Draw light modules instead of dark ones, for dark themes
+    ///
+    /// # Returns
+    /// Returns the drawing, including the symbol's quiet zone
+    pub fn to_terminal_string(&self, qr_code: &Symbol, mode: TerminalMode, invert: bool) -> String {
+        terminal::render(qr_code, mode, invert)
+    }
+}
+
+/// Check that a symbol version is between 1 and 40
+fn check_version(version: u8) -> Result<u8> {
+    if !(1..=40).contains(&version) {
+        return Err(RqrError::InvalidInput(format!(
+            "Version must be between 1 and 40, got {}",
+            version
+        )));
+    }
+    Ok(version)
+}
+
+/// The number of modules along a symbol's shorter side
+fn short_side(qr_code: &Symbol) -> usize {
+    qr_code.width().min(qr_code.height())
+}
+
+/// Composite an image onto white, dropping its alpha channel
+fn flatten_on_white(image: &DynamicImage) -> DynamicImage {
+    let mut flattened = RgbaImage::from_pixel(image.width(), image.height(), Rgba([255; 4]));
+    image::imageops::overlay(&mut flattened, &image.to_rgba8(), 0, 0);
+    DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(flattened).to_rgb8())
+}
+
+/// SVG fill attributes for a colour, with opacity when it is translucent
+fn svg_fill(color: RgbaColor) -> String {
+    if color.alpha() == 255 {
+        format!(" fill=\"{}\"", color.to_hex_rgb())
+    } else {
+        format!(
+            " fill=\"{}\" fill-opacity=\"{}\"",
+            color.to_hex_rgb(),
+            pdf_number(f64::from(color.alpha()) / 255.0)
+        )
+    }
+}
+
+/// PDF non-stroking fill operator for a colour flattened onto white
+fn pdf_fill(color: RgbaColor) -> String {
+    let [r, g, b] = color
+        .flatten_on_white()
+        .map(|c| pdf_number(f64::from(c) / 255.0));
+    format!("{} {} {} rg", r, g, b)
+}
+
+/// Format a number for a PDF content stream (no exponent, trimmed zeros)
+pub(crate) fn pdf_number(value: f64) -> String {
+    let formatted = format!("{:.6}", value);
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::qr::{
+        style::{EyeShape, ModuleShape},
+        symbol::SymbolVersion,
+    };
+
+    #[test]
+    fn test_encoder_new_with_valid_levels() {
+        assert!(QrEncoder::new(200, 10, "L").is_ok());
+        assert!(QrEncoder::new(200, 10, "M").is_ok());
+        assert!(QrEncoder::new(200, 10, "Q").is_ok());
+        assert!(QrEncoder::new(200, 10, "H").is_ok());
+    }
+
+    #[test]
+    fn test_encoder_new_with_lowercase() {
+        assert!(QrEncoder::new(200, 10, "l").is_ok());
+        assert!(QrEncoder::new(200, 10, "m").is_ok());
+        assert!(QrEncoder::new(200, 10, "q").is_ok());
+        assert!(QrEncoder::new(200, 10, "h").is_ok());
+    }
+
+    #[test]
+    fn test_encoder_new_with_invalid_level() {
+        let result = QrEncoder::new(200, 10, "X");
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("Error correction level must be"));
+    }
+
+    #[test]
+    fn test_encoder_new_with_empty_level() {
+        let result = QrEncoder::new(200, 10, "");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_encoder_new_with_multi_char_level() {
+        let result = QrEncoder::new(200, 10, "LM");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_encode_simple_text() {
+        let encoder = QrEncoder::new(200, 10, "M").unwrap();
+        let result = encoder.encode("Hello, World!");
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_encode_empty_string() {
+        let encoder = QrEncoder::new(200, 10, "M").unwrap();
+        let result = encoder.encode("");
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_encode_long_text() {
+        let encoder = QrEncoder::new(400, 10, "M").unwrap();
+        let long_text = "a".repeat(100);
+        let result = encoder.encode(&long_text);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_encode_unicode() {
+        let encoder = QrEncoder::new(200, 10, "M").unwrap();
+        let result = encoder.encode("你好世界 🌍 Привет мир");
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_encode_special_characters() {
+        let encoder = QrEncoder::new(200, 10, "M").unwrap();
+        let result = encoder.encode("!@#$%^&*()_+-=[]{}|;':\",./<>?");
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_encode_url() {
+        let encoder = QrEncoder::new(200, 10, "M").unwrap();
+        let url = "https://example.com/path?query=value&foo=bar";
+        let result = encoder.encode(url);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_to_image_basic() {
+        let encoder = QrEncoder::new(200, 10, "M").unwrap();
+        let qr_code = encoder.encode("Test").unwrap();
+        let image = encoder.to_image(&qr_code);
+        assert!(image.is_ok());
+
+        let img = image.unwrap();
+        assert_eq!(img.width(), img.height());
+    }
+
+    #[test]
+    fn test_to_image_different_sizes() {
+        for size in [100, 200, 400, 800] {
+            let encoder = QrEncoder::new(size, 10, "M").unwrap();
+            let qr_code = encoder.encode("Size test").unwrap();
+            let image = encoder.to_image(&qr_code).unwrap();
+            assert!(image.width() > 0);
+            assert!(image.height() > 0);
+        }
+    }
+
+    #[test]
+    fn test_to_image_different_margins() {
+        for margin in [0, 5, 10, 20] {
+            let encoder = QrEncoder::new(200, margin, "M").unwrap();
+            let qr_code = encoder.encode("Margin test").unwrap();
+            let image = encoder.to_image(&qr_code);
+            assert!(image.is_ok());
+        }
+    }
+
+    #[test]
+    fn test_to_image_size_too_small() {
+        let encoder = QrEncoder::new(10, 10, "M").unwrap();
+        let qr_code = encoder.encode("Test").unwrap();
+        let result = encoder.to_image(&qr_code);
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("Size too small"));
+    }
+
+    #[test]
+    fn test_to_image_pad_fills_exact_size() {
+        // Version 1 plus a 10 module margin is 41 modules, 4px each = 164px
+        let encoder = QrEncoder::new(200, 10, "M").unwrap();
+        let qr_code = encoder.encode("Pad").unwrap();
+        assert_eq!(qr_code.width(), 21);
+
+        let image = encoder.to_image(&qr_code).unwrap().to_luma8();
+        assert_eq!(image.dimensions(), (200, 200));
+
+        // The 36 spare pixels are split evenly into the quiet zone
+        let first_dark = 18 + 10 * 4;
+        assert_eq!(image.get_pixel(first_dark - 1, first_dark)[0], 255);
+        assert_eq!(image.get_pixel(first_dark, first_dark)[0], 0);
+        assert_eq!(image.get_pixel(first_dark + 27, first_dark + 27)[0], 0);
+        assert_eq!(image.get_pixel(first_dark + 28, first_dark + 28)[0], 255);
+    }
+
+    #[test]
+    fn test_to_image_scale_fills_exact_size() {
+        let encoder = QrEncoder::new(200, 10, "M")
+            .unwrap()
+            .with_fit(FitMode::Scale);
+        let qr_code = encoder.encode("Scale").unwrap();
+
+        let image = encoder.to_image(&qr_code).unwrap();
+        assert_eq!((image.width(), image.height()), (200, 200));
+
+        let results = QrDecoder::new().decode_from_image(image).unwrap();
+        assert_eq!(results[0], "Scale");
+    }
+
+    #[test]
+    fn test_to_image_exact_modules() {
+        let qr_code = QrEncoder::new(200, 10, "M")
+            .unwrap()
+            .encode("Exact")
+            .unwrap();
+
+        let encoder = QrEncoder::new(200, 10, "M")
+            .unwrap()
+            .with_fit(FitMode::ExactModules);
+        let err = encoder.to_image(&qr_code).unwrap_err();
+        assert!(err.to_string().contains("not a multiple of 41 modules"));
+        assert!(err.to_string().contains("try 164 or 205"));
+
+        let encoder = QrEncoder::new(205, 10, "M")
+            .unwrap()
+            .with_fit(FitMode::ExactModules);
+        let image = encoder.to_image(&qr_code).unwrap();
+        assert_eq!((image.width(), image.height()), (205, 205));
+    }
+
+    #[test]
+    fn test_to_image_fit_modes_size_too_small() {
+        for fit in [FitMode::Pad, FitMode::Scale, FitMode::ExactModules] {
+            let encoder = QrEncoder::new(40, 10, "M").unwrap().with_fit(fit);
+            let qr_code = encoder.encode("Test").unwrap();
+            let err = encoder.to_image(&qr_code).unwrap_err();
+            assert!(
+                err.to_string().contains("Size too small"),
+                "Failed for {}",
+                fit
+            );
+        }
+    }
+
+    #[test]
+    fn test_save_to_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let output_path = temp_dir.path().join("test_qr.png");
+
+        let encoder = QrEncoder::new(200, 10, "M").unwrap();
+        let qr_code = encoder.encode("Save test").unwrap();
+
+        let result = encoder.save_to_file(&qr_code, &output_path);
+        assert!(result.is_ok());
+        assert!(output_path.exists());
+
+        let loaded = image::open(&output_path);
+        assert!(loaded.is_ok());
+    }
+
+    #[test]
+    fn test_save_to_file_raster_formats() {
+        let temp_dir = TempDir::new().unwrap();
+        let encoder = QrEncoder::new(200, 4, "M")
+            .unwrap()
+            .with_colors("#1e3a8a".parse().unwrap(), RgbaColor::TRANSPARENT);
+        let qr_code = encoder.encode("Raster test").unwrap();
+
+        for ext in [
+            "jpg", "jpeg", "webp", "bmp", "gif", "tif", "tiff", "pbm", "pgm", "ppm", "pnm",
+        ] {
+            let output_path = temp_dir.path().join(format!("test_qr.{}", ext));
+            encoder.save_to_file(&qr_code, &output_path).unwrap();
+
+            let results = QrDecoder::new().decode_from_file(&output_path).unwrap();
+            assert_eq!(
+                results,
+                vec!["Raster test".to_string()],
+                "Failed for {}",
+                ext
+            );
+        }
+    }
+
+    #[test]
+    fn test_save_image_flattens_alpha_for_jpeg() {
+        let temp_dir = TempDir::new().unwrap();
+        let output_path = temp_dir.path().join("transparent.jpg");
+        let encoder = QrEncoder::new(200, 4, "M")
+            .unwrap()
+            .with_colors(RgbaColor::BLACK, RgbaColor::TRANSPARENT);
+        let qr_code = encoder.encode("JPEG").unwrap();
+
+        encoder.save_to_file(&qr_code, &output_path).unwrap();
+        let loaded = image::open(&output_path).unwrap();
+        assert!(!loaded.color().has_alpha());
+        assert!(loaded.to_luma8().get_pixel(0, 0)[0] > 250);
+    }
+
+    #[test]
+    fn test_save_image_pnm_subtypes() {
+        let temp_dir = TempDir::new().unwrap();
+        let encoder = QrEncoder::new(200, 4, "M").unwrap();
+        let qr_code = encoder.encode("PNM").unwrap();
+
+        for (ext, magic) in [
+            ("pbm", b"P4"),
+            ("pgm", b"P5"),
+            ("ppm", b"P6"),
+            ("pnm", b"P6"),
+        ] {
+            let output_path = temp_dir.path().join(format!("test_qr.{}", ext));
+            encoder.save_to_file(&qr_code, &output_path).unwrap();
+            let bytes = std::fs::read(&output_path).unwrap();
+            assert_eq!(&bytes[..2], magic, "Failed for {}", ext);
+        }
+    }
+
+    #[test]
+    fn test_save_image_jpeg_quality() {
+        let temp_dir = TempDir::new().unwrap();
+        let low_path = temp_dir.path().join("low.jpg");
+        let high_path = temp_dir.path().join("high.jpg");
+        let content = "https://example.com/jpeg/quality";
+
+        let low = QrEncoder::new(300, 4, "M")
+            .unwrap()
+            .with_quality(10)
+            .unwrap();
+        low.save_to_file(&low.encode(content).unwrap(), &low_path)
+            .unwrap();
+        let high = QrEncoder::new(300, 4, "M")
+            .unwrap()
+            .with_quality(100)
+            .unwrap();
+        high.save_to_file(&high.encode(content).unwrap(), &high_path)
+            .unwrap();
+
+        let low_size = std::fs::metadata(&low_path).unwrap().len();
+        let high_size = std::fs::metadata(&high_path).unwrap().len();
+        assert!(low_size < high_size);
+    }
+
+    #[test]
+    fn test_with_quality_invalid() {
+        for quality in [0, 101] {
+            let result = QrEncoder::new(200, 4, "M").unwrap().with_quality(quality);
+            assert!(matches!(result, Err(RqrError::InvalidInput(_))));
+        }
+        let encoder = QrEncoder::new(200, 4, "M").unwrap();
+        assert_eq!(encoder.quality(), DEFAULT_QUALITY);
+    }
+
+    #[test]
+    fn test_save_image_rejects_vector_extension() {
+        let temp_dir = TempDir::new().unwrap();
+        let encoder = QrEncoder::new(200, 4, "M").unwrap();
+        let image = encoder
+            .to_image(&encoder.encode("Vector").unwrap())
+            .unwrap();
+
+        let result = encoder.save_image(&image, &temp_dir.path().join("test.svg"));
+        assert!(matches!(result, Err(RqrError::UnsupportedFormat(_))));
+    }
+
+    #[test]
+    fn test_save_to_file_nested_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let nested_path = temp_dir.path().join("sub").join("dir").join("qr.png");
+
+        let encoder = QrEncoder::new(200, 10, "M").unwrap();
+        let qr_code = encoder.encode("Nested test").unwrap();
+
+        let result = encoder.save_to_file(&qr_code, &nested_path);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_to_svg_string_dimensions() {
+        let encoder = QrEncoder::new(300, 4, "M").unwrap();
+        let qr_code = encoder.encode("SVG test").unwrap();
+        let total = qr_code.width() + 8;
+
+        let svg = encoder.to_svg_string(&qr_code);
+        assert!(svg.contains("width=\"300\" height=\"300\""));
+        assert!(svg.contains(&format!("viewBox=\"0 0 {} {}\"", total, total)));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn test_to_svg_string_one_path_per_run() {
+        let encoder = QrEncoder::new(200, 2, "M").unwrap();
+        let qr_code = encoder.encode("Run test").unwrap();
+
+        let runs: usize = qr_code
+            .to_colors()
+            .chunks(qr_code.width())
+            .map(|row| {
+                row.iter()
+                    .enumerate()
+                    .filter(|&(x, &module)| {
+                        module == qrcode::Color::Dark
+                            && (x == 0 || row[x - 1] == qrcode::Color::Light)
+                    })
+                    .count()
+            })
+            .sum();
+
+        let svg = encoder.to_svg_string(&qr_code);
+        assert_eq!(svg.matches("<path ").count(), runs);
+        // The first finder pattern row starts right after the margin
+        assert!(svg.contains("<path d=\"M2 2h7v1h-7z\""));
+    }
+
+    #[test]
+    fn test_save_to_svg() {
+        let temp_dir = TempDir::new().unwrap();
+        let output_path = temp_dir.path().join("test_qr.svg");
+
+        let encoder = QrEncoder::new(200, 10, "M").unwrap();
+        let qr_code = encoder.encode("Save SVG test").unwrap();
+
+        encoder.save_to_svg(&qr_code, &output_path).unwrap();
+
+        let svg = std::fs::read_to_string(&output_path).unwrap();
+        assert_eq!(svg, encoder.to_svg_string(&qr_code));
+    }
+
+    #[test]
+    fn test_save_to_svg_nested_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let nested_path = temp_dir.path().join("sub").join("dir").join("qr.svg");
+
+        let encoder = QrEncoder::new(200, 10, "M").unwrap();
+        let qr_code = encoder.encode("Nested test").unwrap();
+
+        let result = encoder.save_to_svg(&qr_code, &nested_path);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_to_image_default_colors_are_grayscale() {
+        let encoder = QrEncoder::new(200, 4, "M").unwrap();
+        let qr_code = encoder.encode("Gray").unwrap();
+
+        let image = encoder.to_image(&qr_code).unwrap();
+        assert!(matches!(image, DynamicImage::ImageLuma8(_)));
+    }
+
+    #[test]
+    fn test_to_image_custom_colors() {
+        let foreground = RgbaColor::new(0x1e, 0x3a, 0x8a, 255);
+        let background = RgbaColor::new(0xfe, 0xf3, 0xc7, 255);
+        let encoder = QrEncoder::new(200, 4, "M")
+            .unwrap()
+            .with_colors(foreground, background);
+        let qr_code = encoder.encode("Colors").unwrap();
+
+        let image = encoder.to_image(&qr_code).unwrap();
+        let rgba = image.to_rgba8();
+        assert_eq!(rgba.get_pixel(0, 0).0, background.to_rgba());
+        // Version 1 with a 4 module margin: 29 modules of 6px, 13px padding
+        assert_eq!(rgba.get_pixel(13 + 24, 13 + 24).0, foreground.to_rgba());
+
+        encoder.verify_image(&image, "Colors").unwrap();
+    }
+
+    #[test]
+    fn test_to_image_transparent_background() {
+        let encoder = QrEncoder::new(200, 4, "M")
+            .unwrap()
+            .with_colors(RgbaColor::BLACK, RgbaColor::TRANSPARENT);
+        let qr_code = encoder.encode("Transparent").unwrap();
+
+        let image = encoder.to_image(&qr_code).unwrap();
+        assert!(image.color().has_alpha());
+        assert_eq!(image.to_rgba8().get_pixel(0, 0)[3], 0);
+
+        encoder.verify_image(&image, "Transparent").unwrap();
+    }
+
+    #[test]
+    fn test_verify_image_rejects_unreadable_colors() {
+        let encoder = QrEncoder::new(200, 4, "M")
+            .unwrap()
+            .with_colors("#eeeeee".parse().unwrap(), RgbaColor::WHITE);
+        let qr_code = encoder.encode("Faint").unwrap();
+
+        let image = encoder.to_image(&qr_code).unwrap();
+        let err = encoder.verify_image(&image, "Faint").unwrap_err();
+        assert!(err.to_string().contains("not readable"));
+    }
+
+    #[test]
+    fn test_verify_image_rejects_wrong_content() {
+        let encoder = QrEncoder::new(200, 4, "M").unwrap();
+        let qr_code = encoder.encode("Expected").unwrap();
+
+        let image = encoder.to_image(&qr_code).unwrap();
+        let err = encoder.verify_image(&image, "Something else").unwrap_err();
+        assert!(err.to_string().contains("different content"));
+    }
+
+    #[test]
+    fn test_vector_output_colors() {
+        let encoder = QrEncoder::new(200, 4, "M")
+            .unwrap()
+            .with_colors("#ff000080".parse().unwrap(), RgbaColor::TRANSPARENT);
+        let qr_code = encoder.encode("Vector colors").unwrap();
 
-        // Calculate module size based on desired output size and margin
-        let total_modules = qr_width + (self.margin as usize * 2);
-        let module_size = (self.size as usize).checked_div(total_modules).unwrap_or(1);
+        let svg = encoder.to_svg_string(&qr_code);
+        assert!(!svg.contains("<rect"));
+        assert!(svg.contains("fill=\"#ff0000\" fill-opacity=\"0.501961\""));
 
-        if module_size == 0 {
-            return Err(RqrError::InvalidInput(
-                "Size too small for the specified margin".to_string(),
-            ));
+        let pdf = String::from_utf8_lossy(&encoder.to_pdf_bytes(&qr_code)).into_owned();
+        assert!(!pdf.contains(" re f\n"));
+        assert!(pdf.contains("1 0.498039 0.498039 rg"));
+    }
+
+    fn red_logo() -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(64, 64, Rgba([255, 0, 0, 255])))
+    }
+
+    fn all_styles() -> Vec<Style> {
+        let mut styles = Vec::new();
+        for module_shape in [
+            ModuleShape::Square,
+            ModuleShape::Dot,
+            ModuleShape::Rounded,
+            ModuleShape::Liquid,
+        ] {
+            for eye_shape in [EyeShape::Square, EyeShape::Rounded, EyeShape::Circle] {
+                styles.push(Style {
+                    module_shape,
+                    eye_shape,
+                    eye_color: None,
+                });
+            }
         }
+        styles
+    }
 
-        // Create output image with margin
-        let output_size = total_modules * module_size;
-        let mut output_image: ImageBuffer<Luma<u8>, Vec<u8>> =
-            ImageBuffer::new(output_size as u32, output_size as u32);
+    #[test]
+    fn test_to_image_styles_decode() {
+        for style in all_styles() {
+            let encoder = QrEncoder::new(400, 4, "M").unwrap().with_style(style);
+            let qr_code = encoder.encode("Styled QR").unwrap();
 
-        // Fill with white background
-        for pixel in output_image.pixels_mut() {
-            *pixel = Luma([255u8]);
+            let image = encoder.to_image(&qr_code).unwrap();
+            assert_eq!(image.width(), 400);
+            assert!(
+                encoder.verify_image(&image, "Styled QR").is_ok(),
+                "{:?} does not decode",
+                style
+            );
         }
+    }
 
-        // Draw the QR code
-        for (y, row) in qr_matrix.chunks(qr_width).enumerate() {
-            for (x, &module) in row.iter().enumerate() {
-                let color = match module {
-                    qrcode::Color::Light => Luma([255u8]), // White
-                    qrcode::Color::Dark => Luma([0u8]),    // Black
-                };
+    #[test]
+    fn test_to_image_eye_color() {
+        let style = Style {
+            eye_color: Some("#ff0000".parse().unwrap()),
+            ..Default::default()
+        };
+        let encoder = QrEncoder::new(290, 4, "M")
+            .unwrap()
+            .with_fit(FitMode::ExactModules)
+            .with_style(style);
+        let qr_code = encoder.encode("Eye colour").unwrap();
+        let image = encoder.to_image(&qr_code).unwrap().to_rgba8();
 
-                // Draw the module as a square block
-                for dy in 0..module_size {
-                    for dx in 0..module_size {
-                        let out_x = (x + self.margin as usize) * module_size + dx;
-                        let out_y = (y + self.margin as usize) * module_size + dy;
+        // 29 modules of 10 pixels: the first eye starts at module 4
+        assert_eq!(image.get_pixel(45, 45).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(75, 75).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(55, 55).0, [255, 255, 255, 255]);
+        assert_eq!(image.get_pixel(5, 5).0, [255, 255, 255, 255]);
+    }
 
-                        if out_x < output_size && out_y < output_size {
-                            output_image.put_pixel(out_x as u32, out_y as u32, color);
-                        }
-                    }
-                }
-            }
-        }
+    #[test]
+    fn test_to_pdf_bytes_styled() {
+        let style = Style {
+            module_shape: ModuleShape::Dot,
+            eye_shape: EyeShape::Circle,
+            eye_color: Some("#ff0000".parse().unwrap()),
+        };
+        let encoder = QrEncoder::new(200, 4, "M").unwrap().with_style(style);
+        let qr_code = encoder.encode("Styled PDF").unwrap();
 
-        Ok(DynamicImage::ImageLuma8(output_image))
+        let pdf = String::from_utf8_lossy(&encoder.to_pdf_bytes(&qr_code)).into_owned();
+        assert!(pdf.contains("1 0 0 -1 0 29 cm\n"));
+        assert!(pdf.contains(" c\n"));
+        assert_eq!(pdf.matches("f*\n").count(), 4);
+        assert_eq!(pdf.matches("1 0 0 rg").count(), 3);
+        assert!(!pdf.contains(" re\n"));
     }
 
-    pub fn save_to_file(&self, qr_code: &QrCode, path: &Path) -> Result<()> {
-        let image = self.to_image(qr_code)?;
-        image.save(path)?;
-        Ok(())
+    #[test]
+    fn test_with_logo_invalid_scale() {
+        for scale in [0.0, -0.1, 1.0, 1.5, f64::NAN] {
+            let result = QrEncoder::new(400, 4, "M")
+                .unwrap()
+                .with_logo(red_logo(), scale);
+            assert!(result.is_err(), "Accepted scale {}", scale);
+        }
     }
 
-    pub fn to_terminal_string(&self, qr_code: &QrCode) -> String {
-        qr_code
-            .render::<char>()
-            .quiet_zone(false)
-            .module_dimensions(2, 1)
-            .build()
+    #[test]
+    fn test_max_logo_scale_grows_with_error_correction() {
+        let encoder = QrEncoder::new(400, 4, "M").unwrap();
+        let scales: Vec<f64> = [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H]
+            .into_iter()
+            .map(|level| {
+                let qr_code =
+                    qrcode::QrCode::with_version("Logo", Version::Normal(5), level).unwrap();
+                encoder.max_logo_scale(&qr_code.into())
+            })
+            .collect();
+
+        assert!(scales.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(scales[3] < 0.5);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use tempfile::TempDir;
+    #[test]
+    fn test_encode_with_logo_raises_error_correction() {
+        let content = "https://example.com/product/12345";
 
-    use super::*;
+        let encoder = QrEncoder::new(400, 4, "M")
+            .unwrap()
+            .with_logo(red_logo(), 0.1)
+            .unwrap();
+        let qr_code = encoder.encode(content).unwrap();
+        assert_eq!(qr_code.error_correction_level(), Some(EcLevel::M));
+
+        let encoder = QrEncoder::new(400, 4, "M")
+            .unwrap()
+            .with_logo(red_logo(), 0.3)
+            .unwrap();
+        let qr_code = encoder.encode(content).unwrap();
+        assert_eq!(qr_code.error_correction_level(), Some(EcLevel::H));
+        assert!(encoder.max_logo_scale(&qr_code) >= 0.3);
+    }
 
     #[test]
-    fn test_encoder_new_with_valid_levels() {
-        assert!(QrEncoder::new(200, 10, "L").is_ok());
-        assert!(QrEncoder::new(200, 10, "M").is_ok());
-        assert!(QrEncoder::new(200, 10, "Q").is_ok());
-        assert!(QrEncoder::new(200, 10, "H").is_ok());
+    fn test_encode_with_oversized_logo() {
+        let encoder = QrEncoder::new(400, 4, "M")
+            .unwrap()
+            .with_logo(red_logo(), 0.6)
+            .unwrap();
+        let result = encoder.encode("Too big");
+        assert!(
+            matches!(result, Err(RqrError::EncodingError(e)) if e.contains("exceeds the safe maximum"))
+        );
     }
 
     #[test]
-    fn test_encoder_new_with_lowercase() {
-        assert!(QrEncoder::new(200, 10, "l").is_ok());
-        assert!(QrEncoder::new(200, 10, "m").is_ok());
-        assert!(QrEncoder::new(200, 10, "q").is_ok());
-        assert!(QrEncoder::new(200, 10, "h").is_ok());
+    fn test_to_image_with_logo() {
+        let content = "https://example.com/product/12345";
+        let encoder = QrEncoder::new(400, 4, "M")
+            .unwrap()
+            .with_logo(red_logo(), 0.25)
+            .unwrap();
+        let qr_code = encoder.encode(content).unwrap();
+
+        let image = encoder.to_image(&qr_code).unwrap();
+        assert_eq!((image.width(), image.height()), (400, 400));
+        assert_eq!(image.to_rgba8().get_pixel(200, 200).0, [255, 0, 0, 255]);
+
+        encoder.verify_image(&image, content).unwrap();
     }
 
     #[test]
-    fn test_encoder_new_with_invalid_level() {
-        let result = QrEncoder::new(200, 10, "X");
-        assert!(result.is_err());
-        let err = result.unwrap_err();
-        assert!(err.to_string().contains("Error correction level must be"));
+    fn test_logo_box_ignores_padding() {
+        // Version 2 with a margin of 10 is 45 modules, so 4 pixels each
+        for size in [200, 224] {
+            let encoder = QrEncoder::new(size, 10, "H")
+                .unwrap()
+                .with_version(2)
+                .unwrap()
+                .with_logo(red_logo(), 0.2)
+                .unwrap();
+            let qr_code = encoder.encode("Logo box").unwrap();
+
+            let image = encoder.to_image(&qr_code).unwrap().to_rgba8();
+            let red: Vec<(u32, u32)> = image
+                .enumerate_pixels()
+                .filter(|(_, _, pixel)| pixel.0 == [255, 0, 0, 255])
+                .map(|(x, y, _)| (x, y))
+                .collect();
+            let extent =
+                |coords: Vec<u32>| coords.iter().max().unwrap() - coords.iter().min().unwrap() + 1;
+            let width = extent(red.iter().map(|p| p.0).collect());
+            let height = extent(red.iter().map(|p| p.1).collect());
+            // 0.2 of the 25-module symbol at 4 pixels a module
+            assert_eq!((width, height), (20, 20), "size {}", size);
+        }
     }
 
     #[test]
-    fn test_encoder_new_with_empty_level() {
-        let result = QrEncoder::new(200, 10, "");
-        assert!(result.is_err());
+    fn test_physical_size_defaults_to_points() {
+        let encoder = QrEncoder::new(200, 4, "M").unwrap();
+        assert!((encoder.physical_size().points() - 200.0).abs() < 1e-9);
+
+        let encoder = encoder.with_physical_size("1in".parse().unwrap());
+        assert!((encoder.physical_size().points() - 72.0).abs() < 1e-9);
     }
 
     #[test]
-    fn test_encoder_new_with_multi_char_level() {
-        let result = QrEncoder::new(200, 10, "LM");
-        assert!(result.is_err());
+    fn test_to_pdf_bytes_structure() {
+        let encoder = QrEncoder::new(200, 4, "M")
+            .unwrap()
+            .with_physical_size("25mm".parse().unwrap());
+        let qr_code = encoder.encode("PDF test").unwrap();
+
+        let pdf = encoder.to_pdf_bytes(&qr_code);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.trim_end().ends_with("%%EOF"));
+        // The 21-module symbol is 25mm wide, the page 29 modules with the margin
+        let page = pdf_number(25.0 / 25.4 * 72.0 * 29.0 / 21.0);
+        assert!(text.contains(&format!("/MediaBox [0 0 {page} {page}]")));
+
+        // Every cross-reference entry must point at its object
+        let xref = pdf.windows(6).position(|w| w == b"\nxref\n").unwrap() + 1;
+        let table = String::from_utf8_lossy(&pdf[xref..]);
+        for (i, entry) in table.lines().skip(3).take(4).enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(format!("{} 0 obj", i + 1).as_bytes()));
+        }
+        let startxref = table.lines().rev().nth(1).unwrap();
+        assert_eq!(startxref.parse::<usize>().unwrap(), xref);
     }
 
     #[test]
-    fn test_encode_simple_text() {
+    fn test_save_to_pdf() {
+        let temp_dir = TempDir::new().unwrap();
+        let output_path = temp_dir.path().join("test_qr.pdf");
+
+        let encoder = QrEncoder::new(200, 4, "M").unwrap();
+        let qr_code = encoder.encode("Save PDF test").unwrap();
+
+        encoder.save_to_pdf(&qr_code, &output_path).unwrap();
+
+        let pdf = std::fs::read(&output_path).unwrap();
+        assert_eq!(pdf, encoder.to_pdf_bytes(&qr_code));
+    }
+
+    #[test]
+    fn test_to_terminal_string() {
         let encoder = QrEncoder::new(200, 10, "M").unwrap();
-        let result = encoder.encode("Hello, World!");
-        assert!(result.is_ok());
+        let qr_code = encoder.encode("Terminal test").unwrap();
+
+        let output = encoder.to_terminal_string(&qr_code, TerminalMode::Half, false);
+        assert!(!output.is_empty());
+        assert!(output.contains('█') && output.contains('▀') && output.contains('▄'));
     }
 
     #[test]
-    fn test_encode_empty_string() {
+    fn test_to_terminal_string_empty_content() {
         let encoder = QrEncoder::new(200, 10, "M").unwrap();
-        let result = encoder.encode("");
-        assert!(result.is_ok());
+        let qr_code = encoder.encode("").unwrap();
+
+        let output = encoder.to_terminal_string(&qr_code, TerminalMode::Full, true);
+        assert!(!output.is_empty());
     }
 
     #[test]
-    fn test_encode_long_text() {
-        let encoder = QrEncoder::new(400, 10, "M").unwrap();
-        let long_text = "a".repeat(100);
-        let result = encoder.encode(&long_text);
-        assert!(result.is_ok());
+    fn test_encode_with_fixed_version() {
+        let encoder = QrEncoder::new(200, 4, "M")
+            .unwrap()
+            .with_version(10)
+            .unwrap();
+        for content in ["a", "medium length content", &"x".repeat(150)] {
+            let qr_code = encoder.encode(content).unwrap();
+            assert_eq!(qr_code.version(), SymbolVersion::Qr(10));
+            assert_eq!(qr_code.width(), 57);
+        }
+
+        let err = encoder.encode(&"x".repeat(300)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "QR code encoding failed: Content does not fit in version 10 at error correction \
+             level M"
+        );
     }
 
     #[test]
-    fn test_encode_unicode() {
-        let encoder = QrEncoder::new(200, 10, "M").unwrap();
-        let result = encoder.encode("你好世界 🌍 Привет мир");
-        assert!(result.is_ok());
+    fn test_encode_with_min_version() {
+        let encoder = QrEncoder::new(200, 4, "L")
+            .unwrap()
+            .with_min_version(4)
+            .unwrap();
+        assert_eq!(encoder.encode("a").unwrap().version(), SymbolVersion::Qr(4));
+
+        // Longer content still grows past the minimum
+        let qr_code = encoder.encode(&"x".repeat(200)).unwrap();
+        assert_eq!(qr_code.version(), SymbolVersion::Qr(9));
     }
 
     #[test]
-    fn test_encode_special_characters() {
-        let encoder = QrEncoder::new(200, 10, "M").unwrap();
-        let result = encoder.encode("!@#$%^&*()_+-=[]{}|;':\",./<>?");
-        assert!(result.is_ok());
+    fn test_encode_with_mask_roundtrip() {
+        let decoder = QrDecoder::new();
+        for mask in 0..8 {
+            let encoder = QrEncoder::new(200, 4, "M")
+                .unwrap()
+                .with_mask(mask)
+                .unwrap();
+            let qr_code = encoder.encode("Masked").unwrap();
+            assert_eq!(qr_code.mask(), mask);
+
+            let decoded = decoder
+                .decode_detailed_from_image(encoder.to_image(&qr_code).unwrap())
+                .unwrap();
+            assert_eq!(decoded[0].mask, mask);
+        }
     }
 
     #[test]
-    fn test_encode_url() {
-        let encoder = QrEncoder::new(200, 10, "M").unwrap();
-        let url = "https://example.com/path?query=value&foo=bar";
-        let result = encoder.encode(url);
-        assert!(result.is_ok());
+    fn test_version_and_mask_validation() {
+        let encoder = || QrEncoder::new(200, 4, "M").unwrap();
+        assert!(encoder().with_version(0).is_err());
+        assert!(encoder().with_version(41).is_err());
+        assert!(encoder().with_min_version(41).is_err());
+        assert!(encoder().with_mask(8).is_err());
+        assert!(encoder().with_version(40).is_ok());
     }
 
     #[test]
-    fn test_to_image_basic() {
-        let encoder = QrEncoder::new(200, 10, "M").unwrap();
-        let qr_code = encoder.encode("Test").unwrap();
-        let image = encoder.to_image(&qr_code);
-        assert!(image.is_ok());
+    fn test_encode_micro_picks_smallest_version() {
+        let encoder = |level| {
+            QrEncoder::new(200, 2, level)
+                .unwrap()
+                .with_symbology(Symbology::Micro)
+        };
+        for (level, content, version) in [
+            ("L", "12345", 1),
+            ("L", "MICRO", 2),
+            ("M", "12345", 2),
+            ("L", "micro", 3),
+            ("Q", "Q", 4),
+        ] {
+            let symbol = encoder(level).encode(content).unwrap();
+            assert_eq!(
+                symbol.version(),
+                SymbolVersion::Micro(version),
+                "{}",
+                content
+            );
+            assert_eq!(symbol.symbology(), Symbology::Micro);
+            assert_eq!(symbol.quiet_zone(), 2);
+        }
 
-        let img = image.unwrap();
-        assert_eq!(img.width(), img.height());
+        let err = encoder("M").encode(&"x".repeat(40)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "QR code encoding failed: Content does not fit in versions M1-M4 at error \
+             correction level M"
+        );
     }
 
     #[test]
-    fn test_to_image_different_sizes() {
-        for size in [100, 200, 400, 800] {
-            let encoder = QrEncoder::new(size, 10, "M").unwrap();
-            let qr_code = encoder.encode("Size test").unwrap();
-            let image = encoder.to_image(&qr_code).unwrap();
-            assert!(image.width() > 0);
-            assert!(image.height() > 0);
+    fn test_encode_micro_with_version_and_mask() {
+        let encoder = QrEncoder::new(200, 2, "L")
+            .unwrap()
+            .with_symbology(Symbology::Micro)
+            .with_version(3)
+            .unwrap()
+            .with_mask(2)
+            .unwrap();
+        let symbol = encoder.encode("1").unwrap();
+        assert_eq!(
+            (symbol.version(), symbol.mask()),
+            (SymbolVersion::Micro(3), 2)
+        );
+
+        let decoded = QrDecoder::new()
+            .decode_detailed_from_image(encoder.to_image(&symbol).unwrap())
+            .unwrap();
+        assert_eq!((decoded[0].version, decoded[0].mask), (3, 2));
+        assert_eq!(decoded[0].text.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn test_encode_micro_validation() {
+        let micro = |level| {
+            QrEncoder::new(200, 2, level)
+                .unwrap()
+                .with_symbology(Symbology::Micro)
+        };
+        let invalid = [
+            micro("H").encode("1"),
+            micro("L").with_version(5).unwrap().encode("1"),
+            micro("L").with_mask(4).unwrap().encode("1"),
+        ];
+        for result in invalid {
+            assert!(matches!(result, Err(RqrError::InvalidInput(_))));
         }
     }
 
     #[test]
-    fn test_to_image_different_margins() {
-        for margin in [0, 5, 10, 20] {
-            let encoder = QrEncoder::new(200, margin, "M").unwrap();
-            let qr_code = encoder.encode("Margin test").unwrap();
-            let image = encoder.to_image(&qr_code);
-            assert!(image.is_ok());
+    fn test_encode_rmqr_picks_flattest_version() {
+        let rmqr = |level| {
+            QrEncoder::new(600, 2, level)
+                .unwrap()
+                .with_symbology(Symbology::Rmqr)
+        };
+        let symbol = rmqr("M").encode("CABLE-0042").unwrap();
+        assert_eq!(symbol.version_label(), "R7x59");
+        assert_eq!((symbol.width(), symbol.height()), (59, 7));
+        assert_eq!(symbol.symbology(), Symbology::Rmqr);
+
+        let symbol = rmqr("H").with_min_version(11).unwrap().encode("1").unwrap();
+        assert_eq!(symbol.version_label(), "R11x27");
+
+        let decoded = QrDecoder::new()
+            .decode_detailed_from_image(rmqr("H").to_image(&symbol).unwrap())
+            .unwrap();
+        assert_eq!(decoded[0].symbology, Symbology::Rmqr);
+        assert_eq!(
+            (decoded[0].version, decoded[0].error_correction),
+            (11, EcLevel::H)
+        );
+
+        let err = rmqr("M")
+            .with_version(1)
+            .unwrap()
+            .encode("CABLE-0042")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "QR code encoding failed: Content does not fit in version R7x43 at error \
+             correction level M"
+        );
+    }
+
+    #[test]
+    fn test_encode_rmqr_validation() {
+        let rmqr = |level| {
+            QrEncoder::new(600, 2, level)
+                .unwrap()
+                .with_symbology(Symbology::Rmqr)
+        };
+        let invalid = [
+            rmqr("L").encode("1"),
+            rmqr("Q").encode("1"),
+            rmqr("M").with_version(33).unwrap().encode("1"),
+            rmqr("M").with_mask(0).unwrap().encode("1"),
+        ];
+        for result in invalid {
+            assert!(matches!(result, Err(RqrError::InvalidInput(_))));
         }
     }
 
     #[test]
-    fn test_to_image_size_too_small() {
-        let encoder = QrEncoder::new(10, 10, "M").unwrap();
-        let qr_code = encoder.encode("Test").unwrap();
-        let result = encoder.to_image(&qr_code);
-        assert!(result.is_err());
-        let err = result.unwrap_err();
-        assert!(err.to_string().contains("Size too small"));
+    fn test_rmqr_logo_raises_to_h() {
+        // Too big for R7x43 at M, so level Q, which rMQR lacks, is skipped
+        let logo = DynamicImage::new_rgb8(10, 10);
+        let encoder = QrEncoder::new(600, 2, "M")
+            .unwrap()
+            .with_symbology(Symbology::Rmqr)
+            .with_logo(logo, 0.55)
+            .unwrap();
+        assert_eq!(
+            encoder.encode("R").unwrap().error_correction_level(),
+            Some(EcLevel::H)
+        );
     }
 
     #[test]
-    fn test_save_to_file() {
-        let temp_dir = TempDir::new().unwrap();
-        let output_path = temp_dir.path().join("test_qr.png");
+    fn test_to_image_rmqr_is_rectangular() {
+        let encoder = |size, fit| {
+            QrEncoder::new(size, 2, "M")
+                .unwrap()
+                .with_symbology(Symbology::Rmqr)
+                .with_fit(fit)
+        };
+        // R7x43 plus the margin is 47x11 modules
+        let symbol = encoder(470, FitMode::Pad).encode("1").unwrap();
+        for (size, fit, dimensions) in [
+            (470, FitMode::Pad, (470, 110)),
+            (480, FitMode::Pad, (480, 120)),
+            (480, FitMode::Scale, (480, 112)),
+            (470, FitMode::ExactModules, (470, 110)),
+        ] {
+            let image = encoder(size, fit).to_image(&symbol).unwrap();
+            assert_eq!((image.width(), image.height()), dimensions, "{:?}", fit);
+        }
 
-        let encoder = QrEncoder::new(200, 10, "M").unwrap();
-        let qr_code = encoder.encode("Save test").unwrap();
+        // Ten modules in, 2px of padding, the first module of the finder
+        let image = encoder(480, FitMode::Pad).to_image(&symbol).unwrap();
+        let image = image.to_luma8();
+        assert_eq!(image.get_pixel(25, 25)[0], 0);
+        assert_eq!(image.get_pixel(25, 15)[0], 255);
+        assert_eq!(image.get_pixel(25, 85)[0], 0);
+        assert_eq!(image.get_pixel(25, 95)[0], 255);
+    }
 
-        let result = encoder.save_to_file(&qr_code, &output_path);
-        assert!(result.is_ok());
-        assert!(output_path.exists());
+    #[test]
+    fn test_vector_output_rmqr_is_rectangular() {
+        let encoder = QrEncoder::new(470, 2, "M")
+            .unwrap()
+            .with_symbology(Symbology::Rmqr)
+            .with_physical_size("47mm".parse().unwrap());
+        let symbol = encoder.encode("1").unwrap();
 
-        let loaded = image::open(&output_path);
-        assert!(loaded.is_ok());
+        let svg = encoder.to_svg_string(&symbol);
+        assert!(svg.contains("width=\"470\" height=\"110\" viewBox=\"0 0 47 11\""));
+
+        let pdf = encoder.to_pdf_bytes(&symbol);
+        // The R7x43 symbol is 47mm wide, without its two-module margin
+        let module_size = 47.0 / 25.4 * 72.0 / 43.0;
+        let (width, height) = (module_size * 47.0, module_size * 11.0);
+        assert!(String::from_utf8_lossy(&pdf).contains(&format!(
+            "/MediaBox [0 0 {} {}]",
+            pdf_number(width),
+            pdf_number(height)
+        )));
     }
 
     #[test]
-    fn test_save_to_file_nested_directory() {
-        let temp_dir = TempDir::new().unwrap();
-        let nested_path = temp_dir.path().join("sub").join("dir").join("qr.png");
+    fn test_encode_data_matrix_picks_smallest_size() {
+        let data_matrix = || {
+            QrEncoder::new(300, 2, "M")
+                .unwrap()
+                .with_symbology(Symbology::DataMatrix)
+        };
+        let symbol = data_matrix().encode("PART-0042").unwrap();
+        assert_eq!(symbol.version_label(), "14x14");
+        assert_eq!(symbol.symbology(), Symbology::DataMatrix);
 
-        let encoder = QrEncoder::new(200, 10, "M").unwrap();
-        let qr_code = encoder.encode("Nested test").unwrap();
+        let symbol = data_matrix()
+            .with_min_version(25)
+            .unwrap()
+            .encode("1")
+            .unwrap();
+        assert_eq!((symbol.width(), symbol.height()), (18, 8));
+        let image = data_matrix().to_image(&symbol).unwrap();
+        assert_eq!((image.width(), image.height()), (300, 170));
 
-        let result = encoder.save_to_file(&qr_code, &nested_path);
-        assert!(result.is_err());
+        let decoded = QrDecoder::new().decode_detailed_from_image(image).unwrap();
+        assert_eq!(decoded[0].symbology, Symbology::DataMatrix);
+        assert_eq!(decoded[0].version, 25);
+
+        let err = data_matrix()
+            .with_version(1)
+            .unwrap()
+            .encode("PART-0042")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "QR code encoding failed: Content does not fit in a 10x10 Data Matrix"
+        );
     }
 
     #[test]
-    fn test_to_terminal_string() {
-        let encoder = QrEncoder::new(200, 10, "M").unwrap();
-        let qr_code = encoder.encode("Terminal test").unwrap();
+    fn test_encode_data_matrix_validation() {
+        let data_matrix = || {
+            QrEncoder::new(300, 2, "M")
+                .unwrap()
+                .with_symbology(Symbology::DataMatrix)
+        };
+        let invalid = [
+            data_matrix().with_version(31).unwrap().encode("1"),
+            data_matrix().with_mask(0).unwrap().encode("1"),
+            data_matrix()
+                .with_logo(red_logo(), 0.2)
+                .unwrap()
+                .encode("1"),
+            QrEncoder::new(300, 2, "M")
+                .unwrap()
+                .with_gs1(true)
+                .encode("011"),
+        ];
+        for result in invalid {
+            assert!(matches!(result, Err(RqrError::InvalidInput(_))));
+        }
+    }
 
-        let output = encoder.to_terminal_string(&qr_code);
-        assert!(!output.is_empty());
-        assert!(
-            output.contains('█')
-                || output.contains('▀')
-                || output.contains('▄')
-                || output.contains(' ')
+    #[test]
+    fn test_encode_aztec_picks_smallest_size() {
+        let aztec = || {
+            QrEncoder::new(300, 0, "M")
+                .unwrap()
+                .with_symbology(Symbology::Aztec)
+        };
+        let symbol = aztec().encode("TKT 0421").unwrap();
+        assert_eq!(symbol.version_label(), "15x15 compact");
+        assert_eq!(symbol.symbology(), Symbology::Aztec);
+
+        // A share of error correction above the levels' needs more layers
+        let text = "https://tickets.example.com/t/8d0a4c9f31b7e2";
+        let low = aztec().encode(text).unwrap();
+        let high = aztec().with_ec_percent(95).unwrap().encode(text).unwrap();
+        assert!(high.max_allowed_errors() > low.max_allowed_errors());
+        assert!(high.width() > low.width());
+
+        let image = aztec().to_image(&high).unwrap();
+        let decoded = QrDecoder::new().decode_detailed_from_image(image).unwrap();
+        assert_eq!(decoded[0].symbology, Symbology::Aztec);
+        assert_eq!(decoded[0].text.as_deref(), Some(text));
+
+        let err = aztec().with_version(1).unwrap().encode(text).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "QR code encoding failed: Content does not fit in a 15x15 compact Aztec symbol"
         );
     }
 
     #[test]
-    fn test_to_terminal_string_empty_content() {
-        let encoder = QrEncoder::new(200, 10, "M").unwrap();
-        let qr_code = encoder.encode("").unwrap();
+    fn test_encode_aztec_validation() {
+        let aztec = || {
+            QrEncoder::new(300, 0, "M")
+                .unwrap()
+                .with_symbology(Symbology::Aztec)
+        };
+        let invalid = [
+            aztec().with_mask(0).unwrap().encode("1"),
+            aztec().with_logo(red_logo(), 0.2).unwrap().encode("1"),
+            QrEncoder::new(300, 2, "M")
+                .unwrap()
+                .with_ec_percent(50)
+                .unwrap()
+                .encode("1"),
+        ];
+        for result in invalid {
+            assert!(matches!(result, Err(RqrError::InvalidInput(_))));
+        }
+        assert!(aztec().with_ec_percent(4).is_err());
+        assert!(aztec().with_ec_percent(96).is_err());
+    }
 
-        let output = encoder.to_terminal_string(&qr_code);
-        assert!(!output.is_empty());
+    #[test]
+    fn test_encode_pdf417_sizes() {
+        let pdf417 = || {
+            QrEncoder::new(600, 2, "M")
+                .unwrap()
+                .with_symbology(Symbology::Pdf417)
+        };
+        let text = "SHIP TO: ACME WAREHOUSE 4, DOCK 7, ORDER 20931";
+        let symbol = pdf417().encode(text).unwrap();
+        assert_eq!(symbol.symbology(), Symbology::Pdf417);
+        assert!(symbol.width() > 2 * symbol.height());
+
+        let narrow = pdf417().with_columns(2).unwrap().encode(text).unwrap();
+        assert_eq!(
+            narrow.version(),
+            SymbolVersion::Pdf417 {
+                columns: 2,
+                rows: 22
+            }
+        );
+        assert!(narrow.height() > symbol.height());
+
+        let image = pdf417().to_image(&narrow).unwrap();
+        assert!(image.height() > image.width() / 2);
+        let decoded = QrDecoder::new().decode_detailed_from_image(image).unwrap();
+        assert_eq!(decoded[0].symbology, Symbology::Pdf417);
+        assert_eq!(decoded[0].text.as_deref(), Some(text));
+        assert_eq!(decoded[0].version, 2);
+
+        let stronger = pdf417()
+            .with_security_level(6)
+            .unwrap()
+            .encode(text)
+            .unwrap();
+        assert_eq!(stronger.max_allowed_errors(), 63);
+
+        let err = pdf417()
+            .with_columns(1)
+            .unwrap()
+            .with_rows(3)
+            .unwrap()
+            .encode(text)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "QR code encoding failed: Content does not fit in a PDF417 symbol of 3 rows and 1 columns"
+        );
+    }
+
+    #[test]
+    fn test_encode_linear() {
+        let barcode = |symbology| {
+            QrEncoder::new(400, 10, "H")
+                .unwrap()
+                .with_symbology(symbology)
+        };
+        let symbol = barcode(Symbology::Code128).encode("SKU-4411").unwrap();
+        assert_eq!(symbol.symbology(), Symbology::Code128);
+        assert_eq!(symbol.max_allowed_errors(), 0);
+        assert!(symbol.width() > symbol.height());
+
+        let image = barcode(Symbology::Code128).to_image(&symbol).unwrap();
+        assert!(image.height() < image.width());
+        let decoded = QrDecoder::new().decode_detailed_from_image(image).unwrap();
+        assert_eq!(decoded[0].symbology, Symbology::Code128);
+        assert_eq!(decoded[0].text.as_deref(), Some("SKU-4411"));
+
+        let err = barcode(Symbology::Ean13)
+            .encode("4006381333932")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid input: EAN-13 check digit should be 1, got 2"
+        );
+    }
+
+    #[test]
+    fn test_encode_linear_validation() {
+        let code39 = || {
+            QrEncoder::new(400, 10, "M")
+                .unwrap()
+                .with_symbology(Symbology::Code39)
+        };
+        let invalid = [
+            code39().with_mask(0).unwrap().encode("A1"),
+            code39().with_logo(red_logo(), 0.2).unwrap().encode("A1"),
+            code39().with_version(1).unwrap().encode("A1"),
+            code39()
+                .with_style(Style {
+                    module_shape: ModuleShape::Dot,
+                    ..Style::default()
+                })
+                .encode("A1"),
+            code39().encode(""),
+        ];
+        for result in invalid {
+            assert!(matches!(result, Err(RqrError::InvalidInput(_))));
+        }
+    }
+
+    #[test]
+    fn test_encode_pdf417_validation() {
+        let pdf417 = || {
+            QrEncoder::new(600, 2, "M")
+                .unwrap()
+                .with_symbology(Symbology::Pdf417)
+        };
+        let invalid = [
+            pdf417().with_mask(0).unwrap().encode("1"),
+            pdf417().with_logo(red_logo(), 0.2).unwrap().encode("1"),
+            pdf417().with_version(3).unwrap().encode("1"),
+            QrEncoder::new(300, 2, "M")
+                .unwrap()
+                .with_columns(3)
+                .unwrap()
+                .encode("1"),
+        ];
+        for result in invalid {
+            assert!(matches!(result, Err(RqrError::InvalidInput(_))));
+        }
+        assert!(pdf417().with_columns(0).is_err());
+        assert!(pdf417().with_columns(31).is_err());
+        assert!(pdf417().with_rows(2).is_err());
+        assert!(pdf417().with_rows(91).is_err());
+        assert!(pdf417().with_security_level(9).is_err());
+    }
+
+    #[test]
+    fn test_default_encoding_reports_chosen_mask() {
+        let encoder = QrEncoder::new(200, 4, "M").unwrap();
+        let qr_code = encoder.encode("Which mask?").unwrap();
+        let decoded = QrDecoder::new()
+            .decode_detailed_from_image(encoder.to_image(&qr_code).unwrap())
+            .unwrap();
+        assert_eq!(decoded[0].mask, qr_code.mask());
     }
 
     #[test]
@@ -342,8 +2834,18 @@ mod tests {
     fn test_encode_binary_data() {
         let encoder = QrEncoder::new(200, 10, "M").unwrap();
         let binary_content = vec![0u8, 1, 2, 255, 128, 64];
-        let result = encoder.encode(&String::from_utf8_lossy(&binary_content));
-        assert!(result.is_ok());
+        let qr_code = encoder.encode_bytes(&binary_content).unwrap();
+
+        let image = encoder.to_image(&qr_code).unwrap();
+        encoder.verify_image(&image, &binary_content).unwrap();
+        assert!(encoder.verify_image(&image, [0u8, 1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_encode_bytes_too_long() {
+        let encoder = QrEncoder::new(200, 10, "H").unwrap();
+        let result = encoder.encode_bytes(&[0xab; 4000]);
+        assert!(matches!(result, Err(RqrError::EncodingError(_))));
     }
 
     #[test]
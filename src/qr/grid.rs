@@ -0,0 +1,322 @@
+use image::GrayImage;
+
+use crate::qr::decoded::Point;
+
+/// An image thresholded into dark and light pixels
+pub(super) struct Bitmap {
+    pub(super) width: usize,
+    pub(super) height: usize,
+    dark: Vec<bool>,
+}
+
+impl Bitmap {
+    /// Threshold an image halfway between its darkest and lightest pixel
+    ///
+    /// Returns `None` for images without enough contrast to hold a code.
+    pub(super) fn new(image: &GrayImage) -> Option<Self> {
+        let (min, max) = image.pixels().fold((u8::MAX, u8::MIN), |(min, max), p| {
+            (min.min(p[0]), max.max(p[0]))
+        });
+        if max.saturating_sub(min) < 32 {
+            return None;
+        }
+        let threshold = (u16::from(min) + u16::from(max)) / 2;
+        Some(Self {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            dark: image
+                .pixels()
+                .map(|p| u16::from(p[0]) < threshold)
+                .collect(),
+        })
+    }
+
+    pub(super) fn get(&self, x: usize, y: usize) -> bool {
+        self.dark[y * self.width + x]
+    }
+
+    /// Whether the pixel at an image position is dark, light outside the
+    /// image
+    pub(super) fn dark_at(&self, x: f64, y: f64) -> bool {
+        if x < 0.0 || y < 0.0 {
+            return false;
+        }
+        let (x, y) = (x as usize, y as usize);
+        x < self.width && y < self.height && self.get(x, y)
+    }
+}
+
+/// The directions of the symbol's x and y axes in the image: the four
+/// quarter turns, each also mirrored
+pub(super) const ORIENTATIONS: [((f64, f64), (f64, f64)); 8] = [
+    ((1.0, 0.0), (0.0, 1.0)),
+    ((0.0, 1.0), (-1.0, 0.0)),
+    ((-1.0, 0.0), (0.0, -1.0)),
+    ((0.0, -1.0), (1.0, 0.0)),
+    ((0.0, 1.0), (1.0, 0.0)),
+    ((1.0, 0.0), (0.0, -1.0)),
+    ((0.0, -1.0), (-1.0, 0.0)),
+    ((-1.0, 0.0), (0.0, 1.0)),
+];
+
+/// The module grid of a symbol laid over the image: where its top left
+/// corner is and how far one module reaches along its rows and columns
+#[derive(Clone, Copy)]
+pub(super) struct Grid<'a> {
+    bitmap: &'a Bitmap,
+    origin: (f64, f64),
+    ex: (f64, f64),
+    ey: (f64, f64),
+}
+
+impl<'a> Grid<'a> {
+    pub(super) fn new(
+        bitmap: &'a Bitmap,
+        origin: (f64, f64),
+        ex: (f64, f64),
+        ey: (f64, f64),
+    ) -> Self {
+        Self {
+            bitmap,
+            origin,
+            ex,
+            ey,
+        }
+    }
+
+    /// The grid of a symbol whose top left corner holds a 7x7 finder
+    /// pattern, turned as `axes` gives
+    pub(super) fn from_finder(
+        bitmap: &'a Bitmap,
+        finder: &Finder,
+        (ax, ay): ((f64, f64), (f64, f64)),
+    ) -> Self {
+        let scale = |(x, y): (f64, f64)| (x * finder.module_x, y * finder.module_y);
+        let (ex, ey) = (scale(ax), scale(ay));
+        // The finder pattern's centre is 3.5 modules from the corner
+        let origin = (
+            finder.x - 3.5 * (ex.0 + ey.0),
+            finder.y - 3.5 * (ex.1 + ey.1),
+        );
+        Self::new(bitmap, origin, ex, ey)
+    }
+
+    /// The same grid stretched along the symbol's rows and columns
+    pub(super) fn scaled(&self, sx: f64, sy: f64) -> Self {
+        Self {
+            ex: (self.ex.0 * sx, self.ex.1 * sx),
+            ey: (self.ey.0 * sy, self.ey.1 * sy),
+            ..*self
+        }
+    }
+
+    /// Length of the shorter side of a module, in pixels
+    pub(super) fn module_size(&self) -> f64 {
+        (self.ex.0.abs() + self.ex.1.abs()).min(self.ey.0.abs() + self.ey.1.abs())
+    }
+
+    /// Image position of a point `x` modules along and `y` modules down
+    /// from the top left corner
+    pub(super) fn position(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.origin.0 + self.ex.0 * x + self.ey.0 * y,
+            self.origin.1 + self.ex.1 * x + self.ey.1 * y,
+        )
+    }
+
+    /// Whether the module at column `x`, row `y` is dark, sampled at its
+    /// centre
+    pub(super) fn dark(&self, x: usize, y: usize) -> bool {
+        let (px, py) = self.position(x as f64 + 0.5, y as f64 + 0.5);
+        self.bitmap.dark_at(px, py)
+    }
+
+    /// The image corners of a symbol `width` by `height` modules, clockwise
+    /// from the top left
+    pub(super) fn corners(&self, width: usize, height: usize) -> [Point; 4] {
+        let (w, h) = (width as f64, height as f64);
+        [(0.0, 0.0), (w, 0.0), (w, h), (0.0, h)].map(|(x, y)| {
+            let (px, py) = self.position(x, y);
+            Point {
+                x: px.round() as i32,
+                y: py.round() as i32,
+            }
+        })
+    }
+}
+
+/// Module positions read two columns at a time, right column first, from
+/// column `right` leftwards: upwards through the first pair, downwards
+/// through the next and so on, skipping modules `is_data` rejects
+pub(super) fn column_pairs(
+    right: usize,
+    height: usize,
+    is_data: impl Fn(usize, usize) -> bool,
+) -> Vec<(usize, usize)> {
+    let mut cells = Vec::new();
+    for (pair, right) in (1..=right).rev().step_by(2).enumerate() {
+        for step in 0..height {
+            let y = if pair % 2 == 0 {
+                height - 1 - step
+            } else {
+                step
+            };
+            for x in [right, right - 1] {
+                if is_data(x, y) {
+                    cells.push((x, y));
+                }
+            }
+        }
+    }
+    cells
+}
+
+/// The centre of a finder pattern and the size of its modules in pixels
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Finder {
+    pub(super) x: f64,
+    pub(super) y: f64,
+    pub(super) module_x: f64,
+    pub(super) module_y: f64,
+}
+
+/// Find the centres of 1:1:3:1:1 finder patterns
+///
+/// Rows are scanned for the pattern, each hit is confirmed down its column
+/// and across again, and hits on the same pattern are averaged.
+pub(super) fn find_finders(bitmap: &Bitmap) -> Vec<Finder> {
+    let mut hits: Vec<(Finder, usize)> = Vec::new();
+    for y in 0..bitmap.height {
+        let runs = row_runs(bitmap, y);
+        for window in runs.windows(5) {
+            let lengths = [0, 1, 2, 3, 4].map(|i| window[i].1);
+            if !window[0].2 || !is_finder_ratio(lengths) {
+                continue;
+            }
+            let (start, length, _) = window[2];
+            let x = start + length / 2;
+            let Some((cy, height)) = cross_check(bitmap.height, y, |i| bitmap.get(x, i)) else {
+                continue;
+            };
+            let row = cy as usize;
+            let Some((cx, width)) = cross_check(bitmap.width, x, |i| bitmap.get(i, row)) else {
+                continue;
+            };
+            let finder = Finder {
+                x: cx,
+                y: cy,
+                module_x: width / 7.0,
+                module_y: height / 7.0,
+            };
+
+            match hits.iter_mut().find(|(hit, count)| {
+                let n = *count as f64;
+                (hit.x / n - cx).abs() < 2.0 * finder.module_x
+                    && (hit.y / n - cy).abs() < 2.0 * finder.module_y
+            }) {
+                Some((hit, count)) => {
+                    hit.x += finder.x;
+                    hit.y += finder.y;
+                    hit.module_x += finder.module_x;
+                    hit.module_y += finder.module_y;
+                    *count += 1;
+                }
+                None => hits.push((finder, 1)),
+            }
+        }
+    }
+
+    hits.into_iter()
+        .map(|(sum, count)| {
+            let n = count as f64;
+            Finder {
+                x: sum.x / n,
+                y: sum.y / n,
+                module_x: sum.module_x / n,
+                module_y: sum.module_y / n,
+            }
+        })
+        .collect()
+}
+
+/// Runs of same-coloured pixels along a row as `(start, length, dark)`
+fn row_runs(bitmap: &Bitmap, y: usize) -> Vec<(usize, usize, bool)> {
+    let mut runs: Vec<(usize, usize, bool)> = Vec::new();
+    for x in 0..bitmap.width {
+        let dark = bitmap.get(x, y);
+        match runs.last_mut() {
+            Some((_, length, color)) if *color == dark => *length += 1,
+            _ => runs.push((x, 1, dark)),
+        }
+    }
+    runs
+}
+
+/// Whether five run lengths are in the 1:1:3:1:1 ratio of a finder pattern
+fn is_finder_ratio(runs: [usize; 5]) -> bool {
+    let total: usize = runs.iter().sum();
+    if total < 7 {
+        return false;
+    }
+    let module = total as f64 / 7.0;
+    let variance = module / 2.0;
+    runs.iter()
+        .zip([1.0, 1.0, 3.0, 1.0, 1.0])
+        .all(|(&run, modules)| (run as f64 - module * modules).abs() < variance * modules)
+}
+
+/// Measure a finder pattern along one line through a dark pixel of its
+/// centre square
+///
+/// # Returns
+/// Returns the centre and total length of the pattern along the line, or
+/// `None` when the runs around `start` are not a finder pattern
+fn cross_check(len: usize, start: usize, dark: impl Fn(usize) -> bool) -> Option<(f64, f64)> {
+    if !dark(start) {
+        return None;
+    }
+    let back = |mut i: usize, color: bool| {
+        while i > 0 && dark(i - 1) == color {
+            i -= 1;
+        }
+        i
+    };
+    let forward = |mut i: usize, color: bool| {
+        while i < len && dark(i) == color {
+            i += 1;
+        }
+        i
+    };
+
+    let centre_start = back(start, true);
+    let light_start = back(centre_start, false);
+    let outer_start = back(light_start, true);
+    let centre_end = forward(start, true);
+    let light_end = forward(centre_end, false);
+    let outer_end = forward(light_end, true);
+
+    let runs = [
+        light_start - outer_start,
+        centre_start - light_start,
+        centre_end - centre_start,
+        light_end - centre_end,
+        outer_end - light_end,
+    ];
+    if runs.contains(&0) || !is_finder_ratio(runs) {
+        return None;
+    }
+    let centre = centre_start as f64 + runs[2] as f64 / 2.0;
+    Some((centre, (outer_end - outer_start) as f64))
+}
+
+/// Whether a point lies inside a convex quadrilateral
+pub(super) fn contains(corners: &[Point; 4], x: f64, y: f64) -> bool {
+    let sides: Vec<f64> = (0..4)
+        .map(|i| {
+            let (a, b) = (corners[i], corners[(i + 1) % 4]);
+            f64::from(b.x - a.x) * (y - f64::from(a.y))
+                - f64::from(b.y - a.y) * (x - f64::from(a.x))
+        })
+        .collect();
+    sides.iter().all(|&side| side >= 0.0) || sides.iter().all(|&side| side <= 0.0)
+}
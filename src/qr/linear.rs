@@ -0,0 +1,1286 @@
+use image::GrayImage;
+use qrcode::{Color, EcLevel};
+
+use crate::{
+    qr::{
+        aztec::{Run, runs},
+        decoded::{DecodedQr, Point},
+        grid::{self, Bitmap},
+        symbol::{ErrorCorrection, Symbol, SymbolVersion, Symbology},
+    },
+    utils::error::{Result, RqrError},
+};
+
+/// Modules the bars are drawn high
+const BAR_HEIGHT: usize = 50;
+
+/// Light modules between the bars and the text beneath them
+const TEXT_GAP: usize = 2;
+
+/// Modules the EAN-13 and UPC-A guard bars reach down past the others
+const GUARD_EXTENSION: usize = 5;
+
+/// Characters of the 5x7 font are drawn a module per pixel, a module apart
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 7;
+const GLYPH_ADVANCE: usize = GLYPH_WIDTH + 1;
+
+/// Columns beside the EAN-13 and UPC-A bars for the digits printed there
+const OUTSIDE_DIGIT: usize = 7;
+
+/// Wide elements of Code 39 and ITF are three narrow ones
+const WIDE: usize = 3;
+
+/// Light modules read as a quiet zone; half of what the standards ask for,
+/// so that tight margins still scan
+const MIN_QUIET_ZONE: f64 = 5.0;
+
+/// Scan lines that must read the same barcode before it is reported
+const MIN_LINES: usize = 3;
+
+/// Largest share of a pattern's length its elements may be off in total,
+/// and largest part of a module any one of them may be off
+const MAX_VARIANCE: f64 = 0.48;
+const MAX_CODE128_VARIANCE: f64 = 0.25;
+const MAX_ELEMENT_VARIANCE: f64 = 0.7;
+
+/// Element widths of the Code 128 symbols 0 to 106 as digits, bar first;
+/// the stop symbol, 106, ends with one more bar two modules wide
+const CODE128: [u32; 107] = [
+    212222, 222122, 222221, 121223, 121322, 131222, 122213, 122312, 132212, 221213, 221312, 231212,
+    112232, 122132, 122231, 113222, 123122, 123221, 223211, 221132, 221231, 213212, 223112, 312131,
+    311222, 321122, 321221, 312212, 322112, 322211, 212123, 212321, 232121, 111323, 131123, 131321,
+    112313, 132113, 132311, 211313, 231113, 231311, 112133, 112331, 132131, 113123, 113321, 133121,
+    313121, 211331, 231131, 213113, 213311, 213131, 311123, 311321, 331121, 312113, 312311, 332111,
+    314111, 221411, 431111, 111224, 111422, 121124, 121421, 141122, 141221, 112214, 112412, 122114,
+    122411, 142112, 142211, 241211, 221114, 413111, 241112, 134111, 111242, 121142, 121241, 114212,
+    124112, 124211, 411212, 421112, 421211, 212141, 214121, 412121, 111143, 111341, 131141, 114113,
+    114311, 411113, 411311, 113141, 114131, 311141, 411131, 211412, 211214, 211232, 233111,
+];
+
+/// Code 128 function symbols; in code sets A and B, the symbol that
+/// switches to the other set is FNC4 when already in it
+const FNC3: u8 = 96;
+const FNC2: u8 = 97;
+const SHIFT: u8 = 98;
+const CODE_C: u8 = 99;
+const CODE_B: u8 = 100;
+const CODE_A: u8 = 101;
+const FNC1: u8 = 102;
+const START_A: u8 = 103;
+const START_C: u8 = 105;
+const STOP: u8 = 106;
+
+/// Group separator, which FNC1 stands for after the first symbol
+const GS: u8 = 0x1d;
+
+/// Element widths of the EAN and UPC digits in the L set, space first;
+/// the G set mirrors them and the R set swaps their colours
+const EAN_DIGITS: [[usize; 4]; 10] = [
+    [3, 2, 1, 1],
+    [2, 2, 2, 1],
+    [2, 1, 2, 2],
+    [1, 4, 1, 1],
+    [1, 1, 3, 2],
+    [1, 2, 3, 1],
+    [1, 1, 1, 4],
+    [1, 3, 1, 2],
+    [1, 2, 1, 3],
+    [3, 1, 1, 2],
+];
+
+/// Which of the six left-hand digits are drawn from the G set, as bits from
+/// the first, for each leading EAN-13 digit
+const EAN_PARITY: [u8; 10] = [0x00, 0x0b, 0x0d, 0x0e, 0x13, 0x19, 0x1c, 0x15, 0x16, 0x1a];
+
+/// The Code 39 characters and their wide elements, as bits from the first
+/// of nine; the start and stop character `*` comes last
+const CODE39_CHARS: &[u8; 44] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ-. $/+%*";
+const CODE39_PATTERNS: [u16; 44] = [
+    0x034, 0x121, 0x061, 0x160, 0x031, 0x130, 0x070, 0x025, 0x124, 0x064, 0x109, 0x049, 0x148,
+    0x019, 0x118, 0x058, 0x00d, 0x10c, 0x04c, 0x01c, 0x103, 0x043, 0x142, 0x013, 0x112, 0x052,
+    0x007, 0x106, 0x046, 0x016, 0x181, 0x0c1, 0x1c0, 0x091, 0x190, 0x0d0, 0x085, 0x184, 0x0c4,
+    0x0a8, 0x0a2, 0x08a, 0x02a, 0x094,
+];
+
+/// The wide elements of each ITF digit, as bits from the first of five
+const ITF_DIGITS: [u16; 10] = [0x06, 0x11, 0x09, 0x18, 0x05, 0x14, 0x0c, 0x03, 0x12, 0x0a];
+
+/// The Code 128 code sets: A holds upper case and control characters, B
+/// printable ASCII and C pairs of digits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CodeSet {
+    A,
+    B,
+    C,
+}
+
+/// Code sets in the order ties are broken, text first
+const CODE_SETS: [CodeSet; 3] = [CodeSet::B, CodeSet::C, CodeSet::A];
+
+impl CodeSet {
+    /// The symbol value for the start of the data and the bytes it takes
+    fn value(self, data: &[u8]) -> Option<(u8, usize)> {
+        match (self, data) {
+            (CodeSet::A, [byte, ..]) if *byte < 96 => Some(((byte + 64) % 96, 1)),
+            (CodeSet::B, [byte, ..]) if (32..128).contains(byte) => Some((byte - 32, 1)),
+            (CodeSet::C, [tens, ones, ..]) if tens.is_ascii_digit() && ones.is_ascii_digit() => {
+                Some(((tens - b'0') * 10 + ones - b'0', 2))
+            }
+            _ => None,
+        }
+    }
+
+    fn start(self) -> u8 {
+        START_A + self as u8
+    }
+
+    /// The symbol that switches to this code set
+    fn code(self) -> u8 {
+        match self {
+            CodeSet::A => CODE_A,
+            CodeSet::B => CODE_B,
+            CodeSet::C => CODE_C,
+        }
+    }
+}
+
+/// Bars and the text printed beneath them, in module columns
+struct Layout {
+    width: usize,
+    /// Dark and light modules of the bars, from column `left`
+    bars: Vec<bool>,
+    left: usize,
+    /// Columns whose bars reach down into the text
+    guards: Vec<usize>,
+    /// Characters of the text with the column of their left edge
+    text: Vec<(usize, u8)>,
+}
+
+impl Layout {
+    /// Bars with their text centred beneath them; text wider than the bars
+    /// widens the symbol
+    fn centred(bars: Vec<bool>, text: &[u8]) -> Self {
+        let text_width = (text.len() * GLYPH_ADVANCE).saturating_sub(1);
+        let width = bars.len().max(text_width);
+        let start = (width - text_width) / 2;
+        Self {
+            width,
+            left: (width - bars.len()) / 2,
+            bars,
+            guards: Vec::new(),
+            text: text
+                .iter()
+                .enumerate()
+                .map(|(i, &c)| (start + i * GLYPH_ADVANCE, c))
+                .collect(),
+        }
+    }
+
+    fn render(self, symbology: Symbology) -> Symbol {
+        let height = BAR_HEIGHT + TEXT_GAP + GLYPH_HEIGHT;
+        let mut colors = vec![Color::Light; self.width * height];
+        for (i, _) in self.bars.iter().enumerate().filter(|(_, dark)| **dark) {
+            let x = self.left + i;
+            let bottom = if self.guards.contains(&x) {
+                BAR_HEIGHT + GUARD_EXTENSION
+            } else {
+                BAR_HEIGHT
+            };
+            for y in 0..bottom {
+                colors[y * self.width + x] = Color::Dark;
+            }
+        }
+        for &(x, c) in &self.text {
+            let glyph = GLYPHS
+                .get(usize::from(c).wrapping_sub(32))
+                .copied()
+                .unwrap_or_default();
+            for (dy, row) in glyph.iter().enumerate() {
+                for dx in 0..GLYPH_WIDTH {
+                    if row >> (GLYPH_WIDTH - 1 - dx) & 1 == 1 {
+                        colors[(BAR_HEIGHT + TEXT_GAP + dy) * self.width + x + dx] = Color::Dark;
+                    }
+                }
+            }
+        }
+        Symbol::from_modules(
+            colors,
+            self.width,
+            height,
+            symbology,
+            SymbolVersion::Linear,
+            ErrorCorrection::None,
+        )
+    }
+}
+
+/// Encode data in a 1D barcode, with its human-readable text beneath the
+/// bars
+///
+/// EAN-13 and UPC-A take their number with or without the check digit,
+/// which is appended or verified. Code 128 takes any ASCII text, switching
+/// between its code sets for the fewest symbols; Code 39 takes digits,
+/// upper case letters, space and `- . $ / + %`; ITF takes an even number of
+/// digits. Only EAN-13, UPC-A and Code 128 carry a check character.
+///
+/// # Arguments
+/// * `data` - The bytes to encode
+/// * `symbology` - Code 128, EAN-13, UPC-A, Code 39 or ITF
+///
+/// # Returns
+/// Returns the symbol, or an error if the symbology cannot encode the data
+pub(crate) fn encode(data: &[u8], symbology: Symbology) -> Result<Symbol> {
+    if data.is_empty() {
+        return Err(RqrError::InvalidInput(format!(
+            "{} barcodes cannot be empty",
+            name(symbology)
+        )));
+    }
+
+    let layout = match symbology {
+        Symbology::Code128 => {
+            if let Some(byte) = data.iter().find(|byte| !byte.is_ascii()) {
+                return Err(RqrError::InvalidInput(format!(
+                    "Code 128 only encodes ASCII, got byte 0x{:02x}",
+                    byte
+                )));
+            }
+            let mut values = code128_values(data);
+            values.push(code128_check(&values));
+            values.push(STOP);
+
+            let mut bars = Vec::new();
+            for &value in &values {
+                push_elements(&mut bars, code128_widths(value), true);
+            }
+            bars.extend([true, true]);
+            Layout::centred(bars, data)
+        }
+        Symbology::Ean13 => ean13_layout(&check_digits(data, 13, "EAN-13")?),
+        Symbology::UpcA => upca_layout(&check_digits(data, 12, "UPC-A")?),
+        Symbology::Code39 => {
+            let mut bars = Vec::new();
+            for (i, &c) in [b'*'].iter().chain(data).chain(b"*").enumerate() {
+                let index = CODE39_CHARS
+                    .iter()
+                    .position(|&valid| valid == c)
+                    .filter(|&index| index < 43 || i == 0 || i == data.len() + 1)
+                    .ok_or_else(|| {
+                        RqrError::InvalidInput(format!(
+                            "Code 39 cannot encode '{}', only digits, upper case letters, space and - . $ / + %",
+                            c.escape_ascii()
+                        ))
+                    })?;
+                if i > 0 {
+                    // A narrow gap between characters
+                    bars.push(false);
+                }
+                push_elements(&mut bars, wide_widths(CODE39_PATTERNS[index], 9), true);
+            }
+            Layout::centred(bars, data)
+        }
+        Symbology::Itf => {
+            if !data.iter().all(u8::is_ascii_digit) || !data.len().is_multiple_of(2) {
+                return Err(RqrError::InvalidInput(format!(
+                    "ITF takes an even number of digits, got '{}'",
+                    data.escape_ascii()
+                )));
+            }
+            let mut bars = Vec::new();
+            push_elements(&mut bars, [1; 4], true);
+            for pair in data.chunks(2) {
+                // The first digit is drawn in the bars, the second in the
+                // spaces between them
+                let bars_of = wide_widths(ITF_DIGITS[usize::from(pair[0] - b'0')], 5);
+                let spaces_of = wide_widths(ITF_DIGITS[usize::from(pair[1] - b'0')], 5);
+                let widths = bars_of.into_iter().zip(spaces_of).flat_map(|(b, s)| [b, s]);
+                push_elements(&mut bars, widths, true);
+            }
+            push_elements(&mut bars, [WIDE, 1, 1], true);
+            Layout::centred(bars, data)
+        }
+        _ => {
+            return Err(RqrError::InvalidInput(format!(
+                "{} is not a 1D barcode",
+                symbology
+            )));
+        }
+    };
+    Ok(layout.render(symbology))
+}
+
+/// The name of a 1D symbology as printed in messages
+fn name(symbology: Symbology) -> &'static str {
+    match symbology {
+        Symbology::Code128 => "Code 128",
+        Symbology::Ean13 => "EAN-13",
+        Symbology::UpcA => "UPC-A",
+        Symbology::Code39 => "Code 39",
+        _ => "ITF",
+    }
+}
+
+/// Append elements of alternating colour, `dark` first
+fn push_elements(bars: &mut Vec<bool>, widths: impl IntoIterator<Item = usize>, dark: bool) {
+    for (i, width) in widths.into_iter().enumerate() {
+        let dark = dark == (i % 2 == 0);
+        bars.extend(std::iter::repeat_n(dark, width));
+    }
+}
+
+/// Element widths of a Code 39 or ITF character from its wide elements
+fn wide_widths(pattern: u16, count: usize) -> Vec<usize> {
+    (0..count)
+        .rev()
+        .map(|bit| if pattern >> bit & 1 == 1 { WIDE } else { 1 })
+        .collect()
+}
+
+fn code128_widths(value: u8) -> [usize; 6] {
+    let mut digits = CODE128[usize::from(value)];
+    let mut widths = [0; 6];
+    for width in widths.iter_mut().rev() {
+        *width = (digits % 10) as usize;
+        digits /= 10;
+    }
+    widths
+}
+
+/// The start symbol and data symbols of the shortest Code 128 encoding
+///
+/// Every position of the data is reached in each code set with the fewest
+/// symbols, counting the switches between sets, and the cheapest way to
+/// the end is followed back.
+fn code128_values(data: &[u8]) -> Vec<u8> {
+    // Symbols so far, the position the last character started at and the
+    // code set before it, indexed by code set
+    let mut best: Vec<[Option<(usize, usize, CodeSet)>; 3]> = vec![[None; 3]; data.len() + 1];
+    for set in CODE_SETS {
+        best[0][set as usize] = Some((1, 0, set));
+    }
+    for i in 0..data.len() {
+        for from in CODE_SETS {
+            let Some((symbols, _, _)) = best[i][from as usize] else {
+                continue;
+            };
+            for to in CODE_SETS {
+                let Some((_, len)) = to.value(&data[i..]) else {
+                    continue;
+                };
+                let symbols = symbols + usize::from(from != to) + 1;
+                let slot = &mut best[i + len][to as usize];
+                if slot.is_none_or(|(fewest, _, _)| symbols < fewest) {
+                    *slot = Some((symbols, i, from));
+                }
+            }
+        }
+    }
+
+    let mut set = CODE_SETS
+        .into_iter()
+        .filter(|&set| best[data.len()][set as usize].is_some())
+        .min_by_key(|&set| best[data.len()][set as usize].map(|(symbols, _, _)| symbols))
+        .unwrap_or(CodeSet::B);
+    let mut values = Vec::new();
+    let mut end = data.len();
+    while end > 0 {
+        let Some((_, start, from)) = best[end][set as usize] else {
+            break;
+        };
+        values.extend(set.value(&data[start..]).map(|(value, _)| value));
+        if from != set {
+            values.push(set.code());
+        }
+        (end, set) = (start, from);
+    }
+    values.push(set.start());
+    values.reverse();
+    values
+}
+
+/// The Code 128 check symbol: the start symbol plus each data symbol times
+/// its position, modulo 103
+fn code128_check(values: &[u8]) -> u8 {
+    let sum: usize = values
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| i.max(1) * usize::from(value))
+        .sum();
+    (sum % 103) as u8
+}
+
+/// The digits of an EAN-13 or UPC-A number, with the check digit appended
+/// when it is left out
+fn check_digits(data: &[u8], length: usize, name: &str) -> Result<Vec<u8>> {
+    if !data.iter().all(u8::is_ascii_digit) || !(length - 1..=length).contains(&data.len()) {
+        return Err(RqrError::InvalidInput(format!(
+            "{} takes {} digits, or {} without the check digit, got '{}'",
+            name,
+            length,
+            length - 1,
+            data.escape_ascii()
+        )));
+    }
+
+    let mut digits: Vec<u8> = data.iter().map(|digit| digit - b'0').collect();
+    let check = check_digit(&digits[..length - 1]);
+    match digits.get(length - 1) {
+        Some(&digit) if digit != check => Err(RqrError::InvalidInput(format!(
+            "{} check digit should be {}, got {}",
+            name, check, digit
+        ))),
+        Some(_) => Ok(digits),
+        None => {
+            digits.push(check);
+            Ok(digits)
+        }
+    }
+}
+
+/// The GS1 check digit, weighting digits three and one from the right
+fn check_digit(digits: &[u8]) -> u8 {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &digit)| u32::from(digit) * if i % 2 == 0 { 3 } else { 1 })
+        .sum();
+    ((10 - sum % 10) % 10) as u8
+}
+
+/// The 95 modules of an EAN-13 symbol, guards included
+fn ean_bars(digits: &[u8]) -> Vec<bool> {
+    let mut bars = Vec::with_capacity(95);
+    push_elements(&mut bars, [1; 3], true);
+    let parity = EAN_PARITY[usize::from(digits[0])];
+    for (i, &digit) in digits[1..7].iter().enumerate() {
+        let mut widths = EAN_DIGITS[usize::from(digit)];
+        if parity >> (5 - i) & 1 == 1 {
+            widths.reverse();
+        }
+        push_elements(&mut bars, widths, false);
+    }
+    push_elements(&mut bars, [1; 5], false);
+    for &digit in &digits[7..] {
+        push_elements(&mut bars, EAN_DIGITS[usize::from(digit)], true);
+    }
+    push_elements(&mut bars, [1; 3], true);
+    bars
+}
+
+/// An EAN-13 symbol, with its first digit left of the bars and the others
+/// beneath the two halves
+fn ean13_layout(digits: &[u8]) -> Layout {
+    let left = OUTSIDE_DIGIT;
+    let text = digits.iter().enumerate().map(|(i, &digit)| {
+        let x = match i {
+            0 => 0,
+            1..=6 => left + 3 + 7 * (i - 1) + 1,
+            _ => left + 50 + 7 * (i - 7) + 1,
+        };
+        (x, b'0' + digit)
+    });
+    Layout {
+        width: left + 95,
+        bars: ean_bars(digits),
+        left,
+        guards: [0..3, 45..50, 92..95]
+            .into_iter()
+            .flatten()
+            .map(|x| left + x)
+            .collect(),
+        text: text.collect(),
+    }
+}
+
+/// A UPC-A symbol, which is an EAN-13 symbol with a leading 0; its first and
+/// last digits are printed beside the bars and their bars lengthened
+fn upca_layout(digits: &[u8]) -> Layout {
+    let left = OUTSIDE_DIGIT;
+    let text = digits.iter().enumerate().map(|(i, &digit)| {
+        let x = match i {
+            0 => 0,
+            1..=5 => left + 3 + 7 * i + 1,
+            6..=10 => left + 50 + 7 * (i - 6) + 1,
+            _ => left + 95 + OUTSIDE_DIGIT - GLYPH_WIDTH,
+        };
+        (x, b'0' + digit)
+    });
+    Layout {
+        width: left + 95 + OUTSIDE_DIGIT,
+        bars: ean_bars(&[&[0], digits].concat()),
+        left,
+        guards: [0..10, 45..50, 85..95]
+            .into_iter()
+            .flatten()
+            .map(|x| left + x)
+            .collect(),
+        text: text.collect(),
+    }
+}
+
+/// A barcode read along one scan line, between two pixels of it
+struct Read {
+    symbology: Symbology,
+    payload: Vec<u8>,
+    from: usize,
+    to: usize,
+}
+
+/// Neighbouring scan lines that read the same barcode
+struct Group {
+    read: Read,
+    /// Whether the lines are columns rather than rows
+    vertical: bool,
+    /// Whether the lines were read against the image axis, from the right
+    /// or from the bottom
+    reversed: bool,
+    first: usize,
+    last: usize,
+    lines: usize,
+}
+
+/// Find and decode the 1D barcodes in an image
+///
+/// Every row and column is read in both directions, so barcodes may be
+/// turned by any multiple of 90 degrees. A barcode is only reported when
+/// several neighbouring lines read it the same, with a quiet zone at both
+/// ends. UPC-A is read as an EAN-13 symbol with a leading 0, and reported
+/// as UPC-A.
+///
+/// # Arguments
+/// * `image` - The greyscale image to search
+/// * `skip` - Codes already decoded, whose areas are not searched again
+///
+/// # Returns
+/// Returns a `DecodedQr` for each barcode found
+pub fn decode(image: &GrayImage, skip: &[DecodedQr]) -> Vec<DecodedQr> {
+    let Some(bitmap) = Bitmap::new(image) else {
+        return Vec::new();
+    };
+
+    let mut groups: Vec<Group> = Vec::new();
+    for vertical in [false, true] {
+        let (lines, len) = if vertical {
+            (bitmap.width, bitmap.height)
+        } else {
+            (bitmap.height, bitmap.width)
+        };
+        for line in 0..lines {
+            let forward = runs(len, |i| {
+                if vertical {
+                    bitmap.get(line, i)
+                } else {
+                    bitmap.get(i, line)
+                }
+            });
+            let backward = forward
+                .iter()
+                .rev()
+                .map(|run| Run {
+                    start: len - run.start - run.len,
+                    ..*run
+                })
+                .collect();
+            for (reversed, line_runs) in [(false, forward), (true, backward)] {
+                for mut read in scan(&line_runs) {
+                    if reversed {
+                        (read.from, read.to) = (len - read.to, len - read.from);
+                    }
+                    let group = groups.iter_mut().find(|group| {
+                        let seen = &group.read;
+                        // Damage may break a few lines of a barcode
+                        let gap = (seen.to - seen.from) / 10 + 1;
+                        (group.vertical, group.reversed) == (vertical, reversed)
+                            && (seen.symbology, &seen.payload) == (read.symbology, &read.payload)
+                            && line <= group.last + gap
+                            && read.from < seen.to
+                            && seen.from < read.to
+                    });
+                    match group {
+                        Some(group) => {
+                            group.read.from = group.read.from.min(read.from);
+                            group.read.to = group.read.to.max(read.to);
+                            group.last = line;
+                            group.lines += 1;
+                        }
+                        None => groups.push(Group {
+                            read,
+                            vertical,
+                            reversed,
+                            first: line,
+                            last: line,
+                            lines: 1,
+                        }),
+                    }
+                }
+            }
+        }
+    }
+
+    let mut results: Vec<DecodedQr> = Vec::new();
+    for group in groups.into_iter().filter(|group| group.lines >= MIN_LINES) {
+        let corners = corners(&group);
+        let centre_x = f64::from(corners[0].x + corners[2].x) / 2.0;
+        let centre_y = f64::from(corners[0].y + corners[2].y) / 2.0;
+        if skip
+            .iter()
+            .chain(&results)
+            .any(|code| grid::contains(&code.corners, centre_x, centre_y))
+        {
+            continue;
+        }
+        let Read {
+            symbology, payload, ..
+        } = group.read;
+        results.push(DecodedQr {
+            text: String::from_utf8(payload.clone()).ok(),
+            payload,
+            symbology,
+            version: 0,
+            error_correction: EcLevel::M,
+            ec_percent: None,
+            security_level: None,
+            mask: 0,
+            eci: None,
+            corners,
+        });
+    }
+    results
+}
+
+/// The corners of the lines a barcode was read on, clockwise from the
+/// top-left of the barcode as drawn
+fn corners(group: &Group) -> [Point; 4] {
+    let (start, end) = if group.reversed {
+        (group.read.to, group.read.from)
+    } else {
+        (group.read.from, group.read.to)
+    };
+    // The tops of the bars face right when read down the columns
+    let (top, bottom) = if group.vertical != group.reversed {
+        (group.last + 1, group.first)
+    } else {
+        (group.first, group.last + 1)
+    };
+    let point = |along: usize, across: usize| {
+        let (x, y) = if group.vertical {
+            (across, along)
+        } else {
+            (along, across)
+        };
+        Point {
+            x: x as i32,
+            y: y as i32,
+        }
+    };
+    [
+        point(start, top),
+        point(end, top),
+        point(end, bottom),
+        point(start, bottom),
+    ]
+}
+
+/// Read the barcodes along one line, starting at each dark run after a
+/// light one
+fn scan(runs: &[Run]) -> Vec<Read> {
+    let mut reads = Vec::new();
+    let mut k = 1;
+    while k < runs.len() {
+        // Every symbology starts with a bar of at most two modules, so a
+        // light run shorter than that many quiet zones cannot lead one
+        let (before, first) = (runs[k - 1], runs[k]);
+        if !first.dark || (before.len as f64) < MIN_QUIET_ZONE * first.len as f64 / 2.0 {
+            k += 1;
+            continue;
+        }
+        let read = [read_ean, read_code128, read_code39, read_itf]
+            .iter()
+            .find_map(|read| read(runs, k));
+        match read {
+            Some((symbology, payload, end)) => {
+                let last = runs[end - 1];
+                reads.push(Read {
+                    symbology,
+                    payload,
+                    from: runs[k].start,
+                    to: last.start + last.len,
+                });
+                k = end;
+            }
+            None => k += 1,
+        }
+    }
+    reads
+}
+
+/// The lengths of `count` runs from `start`, if the line has that many
+fn lengths(runs: &[Run], start: usize, count: usize) -> Option<Vec<usize>> {
+    let lengths = runs.get(start..start + count)?;
+    Some(lengths.iter().map(|run| run.len).collect())
+}
+
+/// Whether the run at `index` is light and long enough for a quiet zone
+fn quiet(runs: &[Run], index: usize, module: f64) -> bool {
+    runs.get(index)
+        .is_some_and(|run| !run.dark && run.len as f64 >= MIN_QUIET_ZONE * module)
+}
+
+/// How far element widths are from a pattern, as a share of their total
+/// length, or `None` when one element is off by too much of a module
+fn variance(widths: &[usize], pattern: &[usize]) -> Option<f64> {
+    let total: usize = widths.iter().sum();
+    let module = total as f64 / pattern.iter().sum::<usize>() as f64;
+    let mut variance = 0.0;
+    for (&width, &modules) in widths.iter().zip(pattern) {
+        let off = (width as f64 - modules as f64 * module).abs();
+        if off > MAX_ELEMENT_VARIANCE * module {
+            return None;
+        }
+        variance += off;
+    }
+    Some(variance / total as f64)
+}
+
+/// The index of the pattern closest to the element widths, if it is within
+/// `limit`
+fn best_match<P: AsRef<[usize]>>(
+    widths: &[usize],
+    patterns: impl IntoIterator<Item = P>,
+    limit: f64,
+) -> Option<usize> {
+    patterns
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, pattern)| Some((i, variance(widths, pattern.as_ref())?)))
+        .filter(|&(_, variance)| variance < limit)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+}
+
+/// Which elements are wide, as bits from the first, when exactly `wide` of
+/// them stand out from the rest
+fn wide_elements(widths: &[usize], wide: usize) -> Option<u16> {
+    let mut sorted = widths.to_vec();
+    sorted.sort_unstable();
+    let narrowest_wide = sorted[widths.len() - wide];
+    let widest_narrow = sorted[widths.len() - wide - 1];
+    // Wide elements are two to three times as wide as narrow ones
+    if narrowest_wide * 2 < widest_narrow * 3 {
+        return None;
+    }
+    Some(widths.iter().fold(0, |bits, &width| {
+        bits << 1 | u16::from(width >= narrowest_wide)
+    }))
+}
+
+/// Read an EAN-13 or UPC-A symbol from its start guard at run `k`
+fn read_ean(runs: &[Run], k: usize) -> Option<(Symbology, Vec<u8>, usize)> {
+    // Guards, twelve digits of four elements and the middle guard
+    let widths = lengths(runs, k, 59)?;
+    let module = widths.iter().sum::<usize>() as f64 / 95.0;
+    if !quiet(runs, k - 1, module) || !quiet(runs, k + 59, module) {
+        return None;
+    }
+    for (at, guard) in [(0, &[1; 3][..]), (27, &[1; 5]), (56, &[1; 3])] {
+        if variance(&widths[at..at + guard.len()], guard)? >= MAX_VARIANCE {
+            return None;
+        }
+    }
+
+    let l_and_g: Vec<[usize; 4]> = EAN_DIGITS
+        .iter()
+        .copied()
+        .chain(EAN_DIGITS.iter().map(|&widths| {
+            let mut mirrored = widths;
+            mirrored.reverse();
+            mirrored
+        }))
+        .collect();
+    let mut digits = vec![0];
+    let mut parity = 0;
+    for digit in widths[3..27].chunks(4) {
+        let index = best_match(digit, &l_and_g, MAX_VARIANCE)?;
+        digits.push(index as u8 % 10);
+        parity = parity << 1 | u8::from(index >= 10);
+    }
+    for digit in widths[32..56].chunks(4) {
+        digits.push(best_match(digit, EAN_DIGITS, MAX_VARIANCE)? as u8);
+    }
+    digits[0] = EAN_PARITY.iter().position(|&p| p == parity)? as u8;
+    if check_digit(&digits[..12]) != digits[12] {
+        return None;
+    }
+
+    let (symbology, digits) = match digits.split_first() {
+        Some((0, upc)) => (Symbology::UpcA, upc),
+        _ => (Symbology::Ean13, &digits[..]),
+    };
+    Some((
+        symbology,
+        digits.iter().map(|digit| b'0' + digit).collect(),
+        k + 59,
+    ))
+}
+
+/// Read a Code 128 symbol from its start symbol at run `k`
+fn read_code128(runs: &[Run], k: usize) -> Option<(Symbology, Vec<u8>, usize)> {
+    let start = lengths(runs, k, 6)?;
+    let module = start.iter().sum::<usize>() as f64 / 11.0;
+    if !quiet(runs, k - 1, module) {
+        return None;
+    }
+    let symbol = |i: usize| {
+        let widths = lengths(runs, i, 6)?;
+        best_match(
+            &widths,
+            (0..=STOP).map(code128_widths),
+            MAX_CODE128_VARIANCE,
+        )
+    };
+    let first = symbol(k)? as u8;
+    if !(START_A..=START_C).contains(&first) {
+        return None;
+    }
+
+    let mut values = vec![first];
+    let mut i = k + 6;
+    loop {
+        let value = symbol(i)? as u8;
+        if value == STOP {
+            break;
+        }
+        values.push(value);
+        i += 6;
+    }
+    // The stop symbol ends with a bar two modules wide
+    let stop = lengths(runs, i, 7)?;
+    let mut stop_widths = code128_widths(STOP).to_vec();
+    stop_widths.push(2);
+    if variance(&stop, &stop_widths)? >= MAX_CODE128_VARIANCE || !quiet(runs, i + 7, module) {
+        return None;
+    }
+
+    let (&check, values) = values.split_last()?;
+    if values.len() < 2 || code128_check(values) != check {
+        return None;
+    }
+    Some((Symbology::Code128, code128_text(values)?, i + 7))
+}
+
+/// The bytes of Code 128 symbols, the start symbol first
+fn code128_text(values: &[u8]) -> Option<Vec<u8>> {
+    let mut set = match values[0] {
+        START_A => CodeSet::A,
+        START_C => CodeSet::C,
+        _ => CodeSet::B,
+    };
+    let mut text = Vec::new();
+    let mut shifted = false;
+    let mut fnc4 = false;
+    for (i, &value) in values.iter().enumerate().skip(1) {
+        // SHIFT reads one character from the other of sets A and B
+        let current = match (shifted, set) {
+            (true, CodeSet::A) => CodeSet::B,
+            (true, _) => CodeSet::A,
+            (false, set) => set,
+        };
+        shifted = false;
+        match (current, value) {
+            (CodeSet::C, 0..=99) => text.extend([b'0' + value / 10, b'0' + value % 10]),
+            (CodeSet::A | CodeSet::B, 0..FNC3) => {
+                let byte = match current {
+                    CodeSet::A => (value + 32) % 96,
+                    _ => value + 32,
+                };
+                // FNC4 adds 128 to the next character
+                text.push(if fnc4 { byte + 128 } else { byte });
+                fnc4 = false;
+            }
+            // FNC1 first marks GS1-128 data, later it separates fields
+            (_, FNC1) if i > 1 => text.push(GS),
+            (_, FNC1) | (CodeSet::A | CodeSet::B, FNC2 | FNC3) => {}
+            (CodeSet::A | CodeSet::B, SHIFT) => shifted = true,
+            (CodeSet::A, CODE_A) | (CodeSet::B, CODE_B) => fnc4 = true,
+            (_, CODE_A) => set = CodeSet::A,
+            (_, CODE_B) => set = CodeSet::B,
+            (_, CODE_C) => set = CodeSet::C,
+            _ => return None,
+        }
+    }
+    Some(text)
+}
+
+/// Read a Code 39 symbol from its start character at run `k`
+fn read_code39(runs: &[Run], k: usize) -> Option<(Symbology, Vec<u8>, usize)> {
+    let start = lengths(runs, k, 9)?;
+    let module = start.iter().sum::<usize>() as f64 / (6 + 3 * WIDE) as f64;
+    let character = |i: usize| {
+        let pattern = wide_elements(&lengths(runs, i, 9)?, 3)?;
+        let index = CODE39_PATTERNS.iter().position(|&p| p == pattern)?;
+        Some(CODE39_CHARS[index])
+    };
+    if !quiet(runs, k - 1, module) || character(k)? != b'*' {
+        return None;
+    }
+
+    let mut text = Vec::new();
+    let mut i = k + 9;
+    loop {
+        // Characters are a narrow gap apart
+        if quiet(runs, i, module) {
+            return None;
+        }
+        let c = character(i + 1)?;
+        i += 10;
+        if c == b'*' {
+            break;
+        }
+        text.push(c);
+    }
+    if text.is_empty() || !quiet(runs, i, module) {
+        return None;
+    }
+    Some((Symbology::Code39, text, i))
+}
+
+/// Read an ITF symbol from its start pattern at run `k`
+fn read_itf(runs: &[Run], k: usize) -> Option<(Symbology, Vec<u8>, usize)> {
+    let start = lengths(runs, k, 4)?;
+    let module = start.iter().sum::<usize>() as f64 / 4.0;
+    if !quiet(runs, k - 1, module) || variance(&start, &[1; 4])? >= MAX_VARIANCE {
+        return None;
+    }
+    let digit = |widths: Vec<usize>| {
+        let pattern = wide_elements(&widths, 2)?;
+        let digit = ITF_DIGITS.iter().position(|&p| p == pattern)?;
+        Some(b'0' + digit as u8)
+    };
+
+    let mut digits = Vec::new();
+    let mut i = k + 4;
+    // The stop pattern is a wide bar, a space and a bar before the quiet zone
+    while !quiet(runs, i + 3, module) {
+        let pair = lengths(runs, i, 10)?;
+        digits.push(digit(pair.iter().step_by(2).copied().collect())?);
+        digits.push(digit(pair.iter().skip(1).step_by(2).copied().collect())?);
+        i += 10;
+    }
+    if digits.is_empty() || variance(&lengths(runs, i, 3)?, &[WIDE, 1, 1])? >= MAX_VARIANCE {
+        return None;
+    }
+    Some((Symbology::Itf, digits, i + 3))
+}
+
+/// The 5x7 font for printable ASCII, one row of five pixels per byte with
+/// the leftmost pixel in bit 4
+const GLYPHS: [[u8; 7]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // space
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04], // !
+    [0x0a, 0x0a, 0x0a, 0x00, 0x00, 0x00, 0x00], // "
+    [0x0a, 0x0a, 0x1f, 0x0a, 0x1f, 0x0a, 0x0a], // #
+    [0x04, 0x0f, 0x14, 0x0e, 0x05, 0x1e, 0x04], // $
+    [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03], // %
+    [0x0c, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0d], // &
+    [0x04, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00], // '
+    [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02], // (
+    [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08], // )
+    [0x00, 0x04, 0x15, 0x0e, 0x15, 0x04, 0x00], // *
+    [0x00, 0x04, 0x04, 0x1f, 0x04, 0x04, 0x00], // +
+    [0x00, 0x00, 0x00, 0x00, 0x0c, 0x04, 0x08], // ,
+    [0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00], // -
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x0c], // .
+    [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00], // /
+    [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e], // 0
+    [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e], // 1
+    [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f], // 2
+    [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e], // 3
+    [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02], // 4
+    [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e], // 5
+    [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e], // 6
+    [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08], // 7
+    [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e], // 8
+    [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c], // 9
+    [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x0c, 0x00], // :
+    [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x04, 0x08], // ;
+    [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02], // <
+    [0x00, 0x00, 0x1f, 0x00, 0x1f, 0x00, 0x00], // =
+    [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08], // >
+    [0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04], // ?
+    [0x0e, 0x11, 0x01, 0x0d, 0x15, 0x15, 0x0e], // @
+    [0x0e, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11], // A
+    [0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e], // B
+    [0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e], // C
+    [0x1c, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1c], // D
+    [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f], // E
+    [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10], // F
+    [0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f], // G
+    [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11], // H
+    [0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e], // I
+    [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c], // J
+    [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11], // K
+    [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f], // L
+    [0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11], // M
+    [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11], // N
+    [0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e], // O
+    [0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10], // P
+    [0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d], // Q
+    [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11], // R
+    [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e], // S
+    [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // T
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e], // U
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04], // V
+    [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a], // W
+    [0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11], // X
+    [0x11, 0x11, 0x0a, 0x04, 0x04, 0x04, 0x04], // Y
+    [0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f], // Z
+    [0x0e, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0e], // [
+    [0x00, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00], // backslash
+    [0x0e, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0e], // ]
+    [0x04, 0x0a, 0x11, 0x00, 0x00, 0x00, 0x00], // ^
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1f], // _
+    [0x08, 0x04, 0x02, 0x00, 0x00, 0x00, 0x00], // `
+    [0x00, 0x00, 0x0e, 0x01, 0x0f, 0x11, 0x0f], // a
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1e], // b
+    [0x00, 0x00, 0x0e, 0x10, 0x10, 0x11, 0x0e], // c
+    [0x01, 0x01, 0x0d, 0x13, 0x11, 0x11, 0x0f], // d
+    [0x00, 0x00, 0x0e, 0x11, 0x1f, 0x10, 0x0e], // e
+    [0x06, 0x09, 0x08, 0x1c, 0x08, 0x08, 0x08], // f
+    [0x00, 0x0f, 0x11, 0x11, 0x0f, 0x01, 0x0e], // g
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x11], // h
+    [0x04, 0x00, 0x0c, 0x04, 0x04, 0x04, 0x0e], // i
+    [0x02, 0x00, 0x06, 0x02, 0x02, 0x12, 0x0c], // j
+    [0x10, 0x10, 0x12, 0x14, 0x18, 0x14, 0x12], // k
+    [0x0c, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e], // l
+    [0x00, 0x00, 0x1a, 0x15, 0x15, 0x11, 0x11], // m
+    [0x00, 0x00, 0x16, 0x19, 0x11, 0x11, 0x11], // n
+    [0x00, 0x00, 0x0e, 0x11, 0x11, 0x11, 0x0e], // o
+    [0x00, 0x00, 0x1e, 0x11, 0x1e, 0x10, 0x10], // p
+    [0x00, 0x00, 0x0d, 0x13, 0x0f, 0x01, 0x01], // q
+    [0x00, 0x00, 0x16, 0x19, 0x10, 0x10, 0x10], // r
+    [0x00, 0x00, 0x0e, 0x10, 0x0e, 0x01, 0x1e], // s
+    [0x08, 0x08, 0x1c, 0x08, 0x08, 0x09, 0x06], // t
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x13, 0x0d], // u
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x0a, 0x04], // v
+    [0x00, 0x00, 0x11, 0x11, 0x15, 0x15, 0x0a], // w
+    [0x00, 0x00, 0x11, 0x0a, 0x04, 0x0a, 0x11], // x
+    [0x00, 0x00, 0x11, 0x11, 0x0f, 0x01, 0x0e], // y
+    [0x00, 0x00, 0x1f, 0x02, 0x04, 0x08, 0x1f], // z
+    [0x02, 0x04, 0x04, 0x08, 0x04, 0x04, 0x02], // {
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // |
+    [0x08, 0x04, 0x04, 0x02, 0x04, 0x04, 0x08], // }
+    [0x00, 0x00, 0x08, 0x15, 0x02, 0x00, 0x00], // ~
+];
+
+#[cfg(test)]
+mod tests {
+    use image::DynamicImage;
+
+    use super::*;
+    use crate::qr::encoder::QrEncoder;
+
+    fn barcode_image(symbol: &Symbol) -> DynamicImage {
+        QrEncoder::new(600, 10, "M")
+            .unwrap()
+            .with_symbology(symbol.symbology())
+            .to_image(symbol)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_code128_values() {
+        // Digits pack in pairs in code set C
+        assert_eq!(code128_values(b"123456"), vec![START_C, 12, 34, 56]);
+        // Text stays in set B, and four digits or more are worth a switch
+        assert_eq!(code128_values(b"AB12"), vec![START_A + 1, 33, 34, 17, 18]);
+        assert_eq!(
+            code128_values(b"AB1234"),
+            vec![START_A + 1, 33, 34, CODE_C, 12, 34]
+        );
+        // Control characters are in set A
+        assert_eq!(code128_values(b"\x04a"), vec![START_A, 68, CODE_B, 65]);
+        // An odd digit goes before the switch to set C or after it
+        assert_eq!(code128_values(b"12345").len(), 5);
+    }
+
+    #[test]
+    fn test_code128_check() {
+        // Start B, P, J, J, 1, 2, 3, C: 879 modulo 103
+        assert_eq!(code128_check(&[104, 48, 42, 42, 17, 18, 19, 35]), 55);
+        assert_eq!(
+            code128_text(&[START_C, 12, 34, CODE_B, 33]).unwrap(),
+            b"1234A"
+        );
+    }
+
+    #[test]
+    fn test_check_digits() {
+        assert_eq!(check_digit(&[4, 0, 0, 6, 3, 8, 1, 3, 3, 3, 9, 3]), 1);
+        assert_eq!(check_digit(&[0, 3, 6, 0, 0, 0, 2, 9, 1, 4, 5]), 2);
+        assert_eq!(check_digits(b"400638133393", 13, "EAN-13").unwrap()[12], 1);
+        assert!(check_digits(b"4006381333931", 13, "EAN-13").is_ok());
+
+        let err = check_digits(b"4006381333932", 13, "EAN-13").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid input: EAN-13 check digit should be 1, got 2"
+        );
+        assert!(check_digits(b"40063813339", 13, "EAN-13").is_err());
+        assert!(check_digits(b"03600029145A", 12, "UPC-A").is_err());
+    }
+
+    #[test]
+    fn test_encode_rejects_invalid_content() {
+        for (symbology, data) in [
+            (Symbology::Code128, &b"caf\xc3\xa9"[..]),
+            (Symbology::Code39, b"lower"),
+            (Symbology::Code39, b"A*B"),
+            (Symbology::Itf, b"123"),
+            (Symbology::Itf, b"12a4"),
+            (Symbology::Ean13, b""),
+            (Symbology::UpcA, b"12345"),
+        ] {
+            let result = encode(data, symbology);
+            assert!(
+                matches!(result, Err(RqrError::InvalidInput(_))),
+                "{:?}",
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn test_encode_dimensions() {
+        let symbol = encode(b"4006381333931", Symbology::Ean13).unwrap();
+        assert_eq!(symbol.width(), OUTSIDE_DIGIT + 95);
+        assert_eq!(symbol.height(), BAR_HEIGHT + TEXT_GAP + GLYPH_HEIGHT);
+        let colors = symbol.to_colors();
+        // The start guard reaches below the other bars, into the text
+        let guard = OUTSIDE_DIGIT;
+        assert_eq!(
+            colors[(BAR_HEIGHT + 1) * symbol.width() + guard],
+            Color::Dark
+        );
+        assert_eq!(
+            colors[(BAR_HEIGHT + 1) * symbol.width() + guard + 5],
+            Color::Light
+        );
+
+        let symbol = encode(b"036000291452", Symbology::UpcA).unwrap();
+        assert_eq!(symbol.width(), 2 * OUTSIDE_DIGIT + 95);
+
+        // Start, 3 in B, switch and 2 pairs in C, check and stop
+        let symbol = encode(b"ABC1234", Symbology::Code128).unwrap();
+        assert_eq!(symbol.width(), 8 * 11 + 13);
+        assert_eq!(symbol.version_label(), "none");
+
+        let symbol = encode(b"0000000000", Symbology::Itf).unwrap();
+        assert_eq!(symbol.width(), 4 + 5 * 18 + 5);
+        // Long runs of digits in set C are narrower than their text
+        let symbol = encode(&[b'1'; 100], Symbology::Code128).unwrap();
+        assert_eq!(symbol.width(), 100 * GLYPH_ADVANCE - 1);
+    }
+
+    #[test]
+    fn test_render_text() {
+        let symbol = encode(b"I", Symbology::Code39).unwrap();
+        let width = symbol.width();
+        let colors = symbol.to_colors();
+        let text_top = BAR_HEIGHT + TEXT_GAP;
+        let inked: Vec<usize> = (0..width)
+            .filter(|&x| colors[text_top * width + x] == Color::Dark)
+            .collect();
+        // The top of the I is three pixels wide, centred under the bars
+        let centre = width / 2;
+        assert_eq!(inked, vec![centre - 1, centre, centre + 1]);
+    }
+
+    #[test]
+    fn test_decode_round_trip() {
+        let printable: Vec<u8> = (b' '..=b'~').collect();
+        for (symbology, data, expected) in [
+            (Symbology::Code128, &b"SKU-4411/b"[..], &b"SKU-4411/b"[..]),
+            (Symbology::Code128, &printable[..40], &printable[..40]),
+            (
+                Symbology::Code128,
+                b"\tTab\r\n12345678",
+                b"\tTab\r\n12345678",
+            ),
+            (Symbology::Ean13, b"400638133393", b"4006381333931"),
+            (Symbology::UpcA, b"03600029145", b"036000291452"),
+            (Symbology::Code39, b"PART-7 $/+%.", b"PART-7 $/+%."),
+            (Symbology::Itf, b"10012345678902", b"10012345678902"),
+        ] {
+            let symbol = encode(data, symbology).unwrap();
+            let image = QrEncoder::new(900, symbology.default_margin(), "M")
+                .unwrap()
+                .to_image(&symbol)
+                .unwrap();
+            let decoded = decode(&image.to_luma8(), &[]);
+            assert_eq!(decoded.len(), 1, "{:?}", symbology);
+            assert_eq!(decoded[0].payload, expected);
+            assert_eq!(decoded[0].symbology, symbology);
+            assert_eq!(decoded[0].version, 0);
+        }
+    }
+
+    #[test]
+    fn test_decode_rotated() {
+        let symbol = encode(b"ROTATE 90", Symbology::Code39).unwrap();
+        let image = barcode_image(&symbol);
+        let upright = decode(&image.to_luma8(), &[]);
+        let top_left = upright[0].corners[0];
+        assert_eq!(top_left.y, upright[0].corners[1].y);
+
+        let (width, height) = (image.width() as i32, image.height() as i32);
+        for (turned, (x, y)) in [
+            (image.rotate90(), (height - top_left.y, top_left.x)),
+            (image.rotate180(), (width - top_left.x, height - top_left.y)),
+            (image.rotate270(), (top_left.y, width - top_left.x)),
+        ] {
+            let decoded = decode(&turned.to_luma8(), &[]);
+            assert_eq!(decoded.len(), 1);
+            assert_eq!(decoded[0].payload, b"ROTATE 90");
+            // The top-left corner of the barcode turns with it
+            let corner = decoded[0].corners[0];
+            assert!(
+                (corner.x - x).abs() <= 1 && (corner.y - y).abs() <= 1,
+                "{:?}",
+                decoded[0].corners
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_needs_quiet_zone() {
+        let symbol = encode(b"TIGHT", Symbology::Code128).unwrap();
+        for (margin, found) in [(2, false), (6, true)] {
+            let size = (symbol.width() as u32 + 2 * margin) * 4;
+            let image = QrEncoder::new(size, margin, "M")
+                .unwrap()
+                .to_image(&symbol)
+                .unwrap();
+            assert_eq!(decode(&image.to_luma8(), &[]).len(), usize::from(found));
+        }
+    }
+
+    #[test]
+    fn test_decode_ignores_2d_codes() {
+        for symbology in [
+            Symbology::Qr,
+            Symbology::DataMatrix,
+            Symbology::Aztec,
+            Symbology::Pdf417,
+        ] {
+            let encoder = QrEncoder::new(400, 4, "M")
+                .unwrap()
+                .with_symbology(symbology);
+            let image = encoder
+                .to_image(&encoder.encode("No bars in here, 0123456789").unwrap())
+                .unwrap();
+            assert!(decode(&image.to_luma8(), &[]).is_empty(), "{:?}", symbology);
+        }
+    }
+}
@@ -0,0 +1,393 @@
+use image::GrayImage;
+use qrcode::{EcLevel, Version, canvas::is_functional};
+
+use crate::qr::{
+    decoded::DecodedQr,
+    grid::{self, Bitmap, Grid, ORIENTATIONS},
+    reed_solomon::{GaloisField, ReedSolomon},
+    segments::{self, Segment},
+    symbol::Symbology,
+};
+
+/// The 32 masked format information words, indexed by
+/// `symbol number << 2 | mask`
+const FORMAT_INFOS: [u16; 32] = [
+    0x4445, 0x4172, 0x4e2b, 0x4b1c, 0x55ae, 0x5099, 0x5fc0, 0x5af7, 0x6793, 0x62a4, 0x6dfd, 0x68ca,
+    0x7678, 0x734f, 0x7c16, 0x7921, 0x06de, 0x03e9, 0x0cb0, 0x0987, 0x1735, 0x1202, 0x1d5b, 0x186c,
+    0x2508, 0x203f, 0x2f66, 0x2a51, 0x34e3, 0x31d4, 0x3e8d, 0x3bba,
+];
+
+/// Version, error correction level, data codewords and error correction
+/// codewords for each symbol number in the format information
+const SYMBOLS: [(u8, EcLevel, usize, usize); 8] = [
+    (1, EcLevel::L, 3, 2),
+    (2, EcLevel::L, 5, 5),
+    (2, EcLevel::M, 4, 6),
+    (3, EcLevel::L, 11, 6),
+    (3, EcLevel::M, 9, 8),
+    (4, EcLevel::L, 16, 8),
+    (4, EcLevel::M, 14, 10),
+    (4, EcLevel::Q, 10, 14),
+];
+
+/// Micro QR's 15-bit format information words further than this from all
+/// 32 valid ones are rejected
+const MAX_FORMAT_ERRORS: u32 = 3;
+
+/// Find and decode the Micro QR symbols in a greyscale image
+///
+/// Symbols are located by their single finder pattern and read in any of
+/// the eight axis-aligned orientations, so rotated by a quarter turn or
+/// mirrored codes are found too. Finder patterns inside the codes in `skip`
+/// belong to QR codes that were already decoded and are not looked at.
+///
+/// # Arguments
+/// * `image` - The greyscale image to search
+/// * `skip` - Codes already decoded from the image
+///
+/// # Returns
+/// Returns a `DecodedQr` for each Micro QR symbol that decodes
+///
+/// # Examples
+/// ```rust
+/// use rqr::qr::{encoder::QrEncoder, micro, symbol::Symbology};
+///
+/// let encoder = QrEncoder::new(130, 2, "L")?.with_symbology(Symbology::Micro);
+/// let image = encoder.to_image(&encoder.encode("12345")?)?;
+///
+/// let decoded = micro::decode(&image.to_luma8(), &[]);
+/// assert_eq!(decoded[0].text.as_deref(), Some("12345"));
+/// assert_eq!(decoded[0].symbology, Symbology::Micro);
+/// # Ok::<(), rqr::utils::error::RqrError>(())
+/// ```
+pub fn decode(image: &GrayImage, skip: &[DecodedQr]) -> Vec<DecodedQr> {
+    let Some(bitmap) = Bitmap::new(image) else {
+        return Vec::new();
+    };
+
+    grid::find_finders(&bitmap)
+        .into_iter()
+        .filter(|finder| {
+            !skip
+                .iter()
+                .any(|qr| grid::contains(&qr.corners, finder.x, finder.y))
+        })
+        .filter_map(|finder| {
+            ORIENTATIONS
+                .iter()
+                .find_map(|&axes| read_symbol(Grid::from_finder(&bitmap, &finder, axes)))
+        })
+        .collect()
+}
+
+/// Read a Micro QR symbol off a grid placed on its finder pattern
+///
+/// The format information beside the finder pattern gives the version,
+/// level and mask; the two timing patterns running from the finder pattern
+/// to the far edges must then line up before the data is read.
+fn read_symbol(grid: Grid) -> Option<DecodedQr> {
+    // Format information, most significant bit first: along row 8, then
+    // up column 8
+    let format = (1..=8)
+        .map(|x| (x, 8))
+        .chain((1..=7).rev().map(|y| (8, y)))
+        .fold(0u16, |bits, (x, y)| bits << 1 | u16::from(grid.dark(x, y)));
+    let (number, mask) = decode_format(format)?;
+    let (version, error_correction, data_len, ec_len) = SYMBOLS[number];
+    let width = usize::from(version) * 2 + 9;
+
+    let timing_errors = (8..width)
+        .filter(|&i| grid.dark(i, 0) != (i % 2 == 0))
+        .chain((8..width).filter(|&i| grid.dark(0, i) != (i % 2 == 0)))
+        .count();
+    if timing_errors > 2 {
+        return None;
+    }
+
+    let bits: Vec<bool> = data_modules(version)
+        .into_iter()
+        .map(|(x, y)| grid.dark(x, y) != mask_bit(mask, x, y))
+        .collect();
+    let payload = read_codewords(&bits, version, data_len, ec_len)
+        .and_then(|data| parse_segments(&data, version))?;
+
+    Some(DecodedQr {
+        text: String::from_utf8(payload.clone()).ok(),
+        payload,
+        symbology: Symbology::Micro,
+        version,
+        error_correction,
+        ec_percent: None,
+        security_level: None,
+        mask,
+        eci: None,
+        corners: grid.corners(width, width),
+    })
+}
+
+/// Find the symbol number and mask of the closest valid format word
+fn decode_format(format: u16) -> Option<(usize, u8)> {
+    FORMAT_INFOS
+        .iter()
+        .enumerate()
+        .map(|(i, &valid)| (i, (valid ^ format).count_ones()))
+        .filter(|&(_, errors)| errors <= MAX_FORMAT_ERRORS)
+        .min_by_key(|&(_, errors)| errors)
+        .map(|(i, _)| (i >> 2, (i & 0b11) as u8))
+}
+
+/// Data module positions in reading order, in column pairs from the right
+/// edge up to column 1; column 0 holds the timing pattern
+fn data_modules(version: u8) -> Vec<(usize, usize)> {
+    let width = usize::from(version) * 2 + 9;
+    let symbol = Version::Micro(i16::from(version));
+    grid::column_pairs(width - 1, width, |x, y| {
+        !is_functional(symbol, width as i16, x as i16, y as i16)
+    })
+}
+
+/// The four Micro QR data masks, numbered as in the format information, at
+/// column `x` and row `y`
+fn mask_bit(mask: u8, x: usize, y: usize) -> bool {
+    match mask {
+        0 => y.is_multiple_of(2),
+        1 => ((y / 2) + (x / 3)).is_multiple_of(2),
+        2 => ((x * y) % 2 + (x * y) % 3).is_multiple_of(2),
+        _ => ((x + y) % 2 + (x * y) % 3).is_multiple_of(2),
+    }
+}
+
+/// Group the unmasked bits into codewords, correct errors and return the
+/// data bits
+///
+/// M1 and M3 end their data with a four-bit codeword, which takes the high
+/// half of a byte for error correction. M1 only detects errors.
+fn read_codewords(bits: &[bool], version: u8, data_len: usize, ec_len: usize) -> Option<Vec<bool>> {
+    let half = version == 1 || version == 3;
+    let mut bits = bits.iter();
+    let mut codewords: Vec<u16> = (0..data_len + ec_len)
+        .map(|i| {
+            let len = if half && i == data_len - 1 { 4 } else { 8 };
+            let value = bits
+                .by_ref()
+                .take(len)
+                .fold(0u16, |byte, &bit| byte << 1 | u16::from(bit));
+            value << (8 - len)
+        })
+        .collect();
+
+    let rs = ReedSolomon::new(GaloisField::new(0x11d, 256), 0);
+    let corrected = rs.correct(&mut codewords, ec_len).ok()?;
+    if version == 1 && corrected > 0 {
+        return None;
+    }
+
+    let data_bits = data_len * 8 - if half { 4 } else { 0 };
+    Some(
+        codewords[..data_len]
+            .iter()
+            .flat_map(|&byte| (0..8).rev().map(move |i| (byte >> i) & 1 == 1))
+            .take(data_bits)
+            .collect(),
+    )
+}
+
+/// Decode a Micro QR symbol's data segments up to its terminator
+///
+/// Micro QR shortens the mode indicator to `version - 1` bits, so M1 is
+/// numeric only, and sizes the character counts to each version. It has
+/// no ECI or FNC1 modes. The terminator reads as an empty numeric segment.
+fn parse_segments(bits: &[bool], version: u8) -> Option<Vec<u8>> {
+    let v = usize::from(version) - 1;
+    let (payload, _) = segments::parse_segments(bits, |reader| {
+        let mode = if v == 0 {
+            0
+        } else {
+            match reader.read(v) {
+                Some(mode) => mode,
+                None => return Some(Segment::End),
+            }
+        };
+        let count_bits = match mode {
+            0 => [3, 4, 5, 6][v],
+            1 => [0, 3, 4, 5][v],
+            2 => [0, 0, 4, 5][v],
+            3 => [0, 0, 3, 4][v],
+            _ => return None,
+        };
+        if count_bits == 0 {
+            return None;
+        }
+        let Some(count) = reader.read(count_bits) else {
+            return Some(Segment::End);
+        };
+        if mode == 0 && count == 0 {
+            return Some(Segment::End);
+        }
+        Some(Segment::Characters {
+            mode,
+            count: count as usize,
+        })
+    })?;
+    Some(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::DynamicImage;
+    use qrcode::bits::Bits;
+
+    use super::*;
+    use crate::qr::{decoded::Point, encoder::QrEncoder, symbol::Symbol};
+
+    fn micro_image(symbol: &Symbol) -> DynamicImage {
+        QrEncoder::new(200, 2, "L")
+            .unwrap()
+            .to_image(symbol)
+            .unwrap()
+    }
+
+    fn micro_symbol(data: &[u8], version: i16, ec_level: EcLevel, mask: Option<u8>) -> Symbol {
+        let mut bits = Bits::new(Version::Micro(version));
+        bits.push_optimal_data(data).unwrap();
+        bits.push_terminator(ec_level).unwrap();
+        Symbol::from_bits(bits, ec_level, mask).unwrap()
+    }
+
+    #[test]
+    fn test_decode_every_version_and_level() {
+        for (version, ec_level, data) in [
+            (1, EcLevel::L, &b"12345"[..]),
+            (2, EcLevel::L, b"AB-12"),
+            (2, EcLevel::M, b"0042"),
+            (3, EcLevel::L, b"micro qr"),
+            (3, EcLevel::M, b"M3-M"),
+            (4, EcLevel::L, b"Micro QR, M4-L"),
+            (4, EcLevel::M, b"https://a.b/c"),
+            (4, EcLevel::Q, b"Q level"),
+        ] {
+            let symbol = micro_symbol(data, version, ec_level, None);
+            let decoded = decode(&micro_image(&symbol).to_luma8(), &[]);
+
+            assert_eq!(decoded.len(), 1, "M{}-{:?}", version, ec_level);
+            assert_eq!(decoded[0].payload, data);
+            assert_eq!(i16::from(decoded[0].version), version);
+            assert_eq!(decoded[0].error_correction, ec_level);
+            assert_eq!(decoded[0].mask, symbol.mask());
+        }
+    }
+
+    #[test]
+    fn test_decode_every_mask() {
+        for mask in 0..4 {
+            let symbol = micro_symbol(b"masks", 3, EcLevel::M, Some(mask));
+            let decoded = decode(&micro_image(&symbol).to_luma8(), &[]);
+            assert_eq!(decoded[0].mask, mask);
+            assert_eq!(decoded[0].payload, b"masks");
+        }
+    }
+
+    #[test]
+    fn test_decode_rotated_and_mirrored() {
+        let symbol = micro_symbol(b"TURN", 2, EcLevel::L, None);
+        let image = micro_image(&symbol);
+        for turned in [
+            image.rotate90(),
+            image.rotate180(),
+            image.rotate270(),
+            image.fliph(),
+            image.rotate90().flipv(),
+        ] {
+            let decoded = decode(&turned.to_luma8(), &[]);
+            assert_eq!(decoded.len(), 1);
+            assert_eq!(decoded[0].payload, b"TURN");
+        }
+    }
+
+    #[test]
+    fn test_decode_corrects_damage() {
+        let symbol = micro_symbol(b"Damaged", 4, EcLevel::Q, None);
+        let mut image = micro_image(&symbol).to_luma8();
+        // M4 plus the margin is 21 modules of 9px after 5px of padding;
+        // blot out a 2x2 module block in the data area
+        let origin = 5 + 2 * 9;
+        for y in origin + 9 * 12..origin + 9 * 14 {
+            for x in origin + 9 * 12..origin + 9 * 14 {
+                image.put_pixel(x, y, image::Luma([0]));
+            }
+        }
+
+        let decoded = decode(&image, &[]);
+        assert_eq!(decoded[0].payload, b"Damaged");
+    }
+
+    #[test]
+    fn test_decode_corners() {
+        let symbol = micro_symbol(b"1", 1, EcLevel::L, None);
+        // 11 modules plus a 2 module margin are 15, 13px each, 2px padding
+        let decoded = decode(&micro_image(&symbol).to_luma8(), &[]);
+        let offset = 2 + 2 * 13;
+        assert_eq!(
+            decoded[0].corners,
+            [
+                (offset, offset),
+                (offset + 143, offset),
+                (offset + 143, offset + 143),
+                (offset, offset + 143)
+            ]
+            .map(|(x, y)| Point { x, y })
+        );
+    }
+
+    #[test]
+    fn test_decode_ignores_qr_codes() {
+        let symbol = Symbol::from(qrcode::QrCode::new(b"not micro").unwrap());
+        let image = QrEncoder::new(200, 4, "M")
+            .unwrap()
+            .to_image(&symbol)
+            .unwrap();
+        assert!(decode(&image.to_luma8(), &[]).is_empty());
+    }
+
+    #[test]
+    fn test_decode_format() {
+        // Symbol number 3 (M3-L) with mask 2, then with three and four bits
+        // flipped
+        assert_eq!(decode_format(FORMAT_INFOS[14]), Some((3, 2)));
+        assert_eq!(
+            decode_format(FORMAT_INFOS[14] ^ 0b1_0010_0000_0001),
+            Some((3, 2))
+        );
+        assert_eq!(decode_format(FORMAT_INFOS[14] ^ 0b1111), None);
+    }
+
+    #[test]
+    fn test_data_modules_fill_the_symbol() {
+        // Codeword bits, including the four-bit ones of M1 and M3
+        for (version, bits) in [(1, 36), (2, 80), (3, 132), (4, 192)] {
+            assert_eq!(data_modules(version).len(), bits);
+        }
+    }
+
+    #[test]
+    fn test_parse_segments() {
+        let bits = |s: &str| {
+            s.chars()
+                .filter(|c| !c.is_whitespace())
+                .map(|c| c == '1')
+                .collect::<Vec<_>>()
+        };
+        // M2: alphanumeric "AC" then the terminator
+        assert_eq!(
+            parse_segments(&bits("1 010 00111001110 00000"), 2),
+            Some(b"AC".to_vec())
+        );
+        // M4: byte "a" then numeric "7", the terminator cut short
+        assert_eq!(
+            parse_segments(&bits("010 00001 01100001 000 000001 0111 0000"), 4),
+            Some(b"a7".to_vec())
+        );
+        // A numeric digit above 9
+        assert_eq!(parse_segments(&bits("0 0001 1111"), 2), None);
+    }
+}
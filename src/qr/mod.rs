@@ -2,6 +2,21 @@
 //!
 //! Core QR code functionality including encoding, decoding, and output formatting.
 
+pub mod aztec;
+pub mod datamatrix;
+pub mod decoded;
 pub mod decoder;
 pub mod encoder;
+mod grid;
+pub mod linear;
+pub mod micro;
 pub mod output;
+pub mod pdf417;
+pub mod reed_solomon;
+pub mod rmqr;
+mod segments;
+pub mod source;
+pub mod style;
+pub mod symbol;
+pub mod terminal;
+pub mod wifi;
@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::{fmt, path::Path, str::FromStr};
 
 use crate::utils::error::{Result, RqrError};
 
@@ -6,13 +6,28 @@ use crate::utils::error::{Result, RqrError};
 ///
 /// Specifies how the generated QR code should be presented
 /// to the user - either as an image file or terminal display.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum OutputFormat {
     /// Save QR code as PNG image file
     Png,
+    /// Save QR code as JPEG image file (lossy, no transparency)
+    Jpeg,
+    /// Save QR code as lossless WebP image file
+    WebP,
+    /// Save QR code as BMP image file
+    Bmp,
+    /// Save QR code as GIF image file
+    Gif,
+    /// Save QR code as TIFF image file
+    Tiff,
+    /// Save QR code as binary PNM (PBM/PGM/PPM) image file
+    Pnm,
+    /// Save QR code as SVG vector image file
+    Svg,
+    /// Save QR code as single-page vector PDF file
+    Pdf,
     /// Display QR code in terminal using ASCII art
     Terminal,
-    // TODO: Future formats: SVG, JPEG, etc.
 }
 
 impl OutputFormat {
@@ -41,6 +56,14 @@ impl OutputFormat {
                 let ext_lower = ext.to_lowercase();
                 match ext_lower.as_str() {
                     "png" => Ok(OutputFormat::Png),
+                    "jpg" | "jpeg" => Ok(OutputFormat::Jpeg),
+                    "webp" => Ok(OutputFormat::WebP),
+                    "bmp" => Ok(OutputFormat::Bmp),
+                    "gif" => Ok(OutputFormat::Gif),
+                    "tif" | "tiff" => Ok(OutputFormat::Tiff),
+                    "pnm" | "pbm" | "pgm" | "ppm" => Ok(OutputFormat::Pnm),
+                    "svg" => Ok(OutputFormat::Svg),
+                    "pdf" => Ok(OutputFormat::Pdf),
                     _ => Err(RqrError::UnsupportedFormat(format!(
                         "Unsupported format: {}",
                         ext
@@ -52,6 +75,306 @@ impl OutputFormat {
             )),
         }
     }
+
+    /// Whether the format stores a pixel image
+    pub fn is_raster(&self) -> bool {
+        !matches!(
+            self,
+            OutputFormat::Svg | OutputFormat::Pdf | OutputFormat::Terminal
+        )
+    }
+
+    /// Whether the format loses detail depending on `--quality`
+    pub fn is_lossy(&self) -> bool {
+        matches!(self, OutputFormat::Jpeg)
+    }
+
+    /// Whether the format can store transparent pixels
+    pub fn supports_alpha(&self) -> bool {
+        !matches!(
+            self,
+            OutputFormat::Jpeg | OutputFormat::Pnm | OutputFormat::Terminal
+        )
+    }
+}
+
+/// How `QrEncoder::to_image` reaches the requested pixel size
+///
+/// Modules are always drawn with a whole number of pixels; the fit mode
+/// decides what happens when the size is not a multiple of the module count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FitMode {
+    /// Add the leftover pixels to the quiet zone
+    #[default]
+    Pad,
+    /// Resample to the exact size with nearest-neighbour filtering
+    Scale,
+    /// Fail unless the size is an exact multiple of the module count
+    ExactModules,
+}
+
+impl FromStr for FitMode {
+    type Err = RqrError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "pad" => Ok(FitMode::Pad),
+            "scale" => Ok(FitMode::Scale),
+            "exact-modules" => Ok(FitMode::ExactModules),
+            _ => Err(RqrError::InvalidInput(format!(
+                "Fit mode must be pad, scale or exact-modules, got '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for FitMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FitMode::Pad => "pad",
+            FitMode::Scale => "scale",
+            FitMode::ExactModules => "exact-modules",
+        };
+        f.write_str(name)
+    }
+}
+
+/// An sRGB colour with alpha used for module and background fills
+///
+/// Parsed from hex strings in `RGB`, `RGBA`, `RRGGBB` or `RRGGBBAA` form
+/// (with or without a leading `#`), or the keyword `transparent`.
+///
+/// # Examples
+/// ```rust
+/// use rqr::qr::output::RgbaColor;
+///
+/// let color: RgbaColor = "#1e90ff".parse()?;
+/// assert_eq!(color.to_rgba(), [0x1e, 0x90, 0xff, 0xff]);
+/// # Ok::<(), rqr::utils::error::RqrError>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RgbaColor([u8; 4]);
+
+impl RgbaColor {
+    /// Opaque black, the default module colour
+    pub const BLACK: Self = Self([0, 0, 0, 255]);
+    /// Opaque white, the default background colour
+    pub const WHITE: Self = Self([255, 255, 255, 255]);
+    /// Fully transparent
+    pub const TRANSPARENT: Self = Self([255, 255, 255, 0]);
+
+    /// Create a colour from its red, green, blue and alpha channels
+    pub fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self([r, g, b, a])
+    }
+
+    /// The red, green, blue and alpha channels
+    pub fn to_rgba(self) -> [u8; 4] {
+        self.0
+    }
+
+    /// The alpha channel (0 is fully transparent)
+    pub fn alpha(self) -> u8 {
+        self.0[3]
+    }
+
+    /// Hex notation without alpha, e.g. `#1e90ff`
+    pub fn to_hex_rgb(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0[0], self.0[1], self.0[2])
+    }
+
+    /// The colour as seen when printed or displayed on white
+    pub fn flatten_on_white(self) -> [u8; 3] {
+        let alpha = u32::from(self.0[3]);
+        let blend = |c: u8| ((u32::from(c) * alpha + 255 * (255 - alpha) + 127) / 255) as u8;
+        [blend(self.0[0]), blend(self.0[1]), blend(self.0[2])]
+    }
+
+    /// WCAG relative luminance of the colour flattened on white
+    pub fn relative_luminance(self) -> f64 {
+        let linear = |c: u8| {
+            let c = f64::from(c) / 255.0;
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        let [r, g, b] = self.flatten_on_white();
+        0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b)
+    }
+
+    /// WCAG contrast ratio between two colours, from 1.0 (none) to 21.0
+    pub fn contrast_ratio(self, other: Self) -> f64 {
+        let a = self.relative_luminance();
+        let b = other.relative_luminance();
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+}
+
+impl FromStr for RgbaColor {
+    type Err = RqrError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("transparent") {
+            return Ok(Self::TRANSPARENT);
+        }
+
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        let invalid = || {
+            RqrError::InvalidInput(format!(
+                "Invalid colour '{}', expected hex like #000, #1e90ff or #1e90ff80",
+                s
+            ))
+        };
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let channel = |i: usize, width: usize| {
+            let value = u8::from_str_radix(&hex[i * width..(i + 1) * width], 16).unwrap_or(0);
+            if width == 1 { value * 17 } else { value }
+        };
+        match hex.len() {
+            3 => Ok(Self([channel(0, 1), channel(1, 1), channel(2, 1), 255])),
+            4 => Ok(Self([
+                channel(0, 1),
+                channel(1, 1),
+                channel(2, 1),
+                channel(3, 1),
+            ])),
+            6 => Ok(Self([channel(0, 2), channel(1, 2), channel(2, 2), 255])),
+            8 => Ok(Self([
+                channel(0, 2),
+                channel(1, 2),
+                channel(2, 2),
+                channel(3, 2),
+            ])),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for RgbaColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [r, g, b, a] = self.0;
+        write!(f, "#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+    }
+}
+
+/// Unit of a physical length
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthUnit {
+    /// Millimetres
+    Mm,
+    /// Centimetres
+    Cm,
+    /// Inches
+    In,
+    /// PostScript points (1/72 inch)
+    Pt,
+}
+
+impl LengthUnit {
+    /// Number of PostScript points in one unit
+    fn points(self) -> f64 {
+        match self {
+            LengthUnit::Mm => 72.0 / 25.4,
+            LengthUnit::Cm => 720.0 / 25.4,
+            LengthUnit::In => 72.0,
+            LengthUnit::Pt => 1.0,
+        }
+    }
+
+    fn suffix(self) -> &'static str {
+        match self {
+            LengthUnit::Mm => "mm",
+            LengthUnit::Cm => "cm",
+            LengthUnit::In => "in",
+            LengthUnit::Pt => "pt",
+        }
+    }
+}
+
+/// Physical edge length of a rendered QR code
+///
+/// Used by output formats with real units (PDF). Parsed from strings
+/// such as `25mm`, `2.5cm`, `1in` or `72pt`.
+///
+/// # Examples
+/// ```rust
+/// use rqr::qr::output::PhysicalSize;
+///
+/// let size: PhysicalSize = "25.4mm".parse()?;
+/// assert!((size.points() - 72.0).abs() < 1e-9);
+/// # Ok::<(), rqr::utils::error::RqrError>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhysicalSize {
+    pub(crate) value: f64,
+    pub(crate) unit: LengthUnit,
+}
+
+impl PhysicalSize {
+    /// Create a physical size from a value and unit
+    ///
+    /// # Returns
+    /// Returns an error unless `value` is a finite, positive number
+    pub fn new(value: f64, unit: LengthUnit) -> Result<Self> {
+        if !value.is_finite() || value <= 0.0 {
+            return Err(RqrError::InvalidInput(
+                "Physical size must be a positive number".to_string(),
+            ));
+        }
+
+        Ok(Self { value, unit })
+    }
+
+    /// The size expressed in PostScript points (1/72 inch)
+    pub fn points(&self) -> f64 {
+        self.value * self.unit.points()
+    }
+}
+
+impl FromStr for PhysicalSize {
+    type Err = RqrError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim().to_lowercase();
+        let split = s.find(|c: char| c.is_ascii_alphabetic()).ok_or_else(|| {
+            RqrError::InvalidInput(format!(
+                "Physical size '{}' needs a unit (mm, cm, in, pt)",
+                s
+            ))
+        })?;
+        let (number, unit) = s.split_at(split);
+
+        let unit = match unit {
+            "mm" => LengthUnit::Mm,
+            "cm" => LengthUnit::Cm,
+            "in" => LengthUnit::In,
+            "pt" => LengthUnit::Pt,
+            _ => {
+                return Err(RqrError::InvalidInput(format!(
+                    "Unknown unit '{}', expected mm, cm, in or pt",
+                    unit
+                )));
+            }
+        };
+        let value = number.trim().parse::<f64>().map_err(|_| {
+            RqrError::InvalidInput(format!("Invalid physical size value: '{}'", number))
+        })?;
+
+        Self::new(value, unit)
+    }
+}
+
+impl fmt::Display for PhysicalSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.value, self.unit.suffix())
+    }
 }
 
 #[cfg(test)]
@@ -79,23 +402,158 @@ mod tests {
     }
 
     #[test]
-    fn test_output_format_unsupported_jpg() {
-        let result = OutputFormat::from_path("test.jpg");
-        assert!(result.is_err());
-        let err = result.unwrap_err();
-        assert!(err.to_string().contains("Unsupported format: jpg"));
+    fn test_output_format_from_path_svg() {
+        let format = OutputFormat::from_path("test.svg").unwrap();
+        assert!(matches!(format, OutputFormat::Svg));
     }
 
     #[test]
-    fn test_output_format_unsupported_jpeg() {
-        let result = OutputFormat::from_path("image.jpeg");
-        assert!(result.is_err());
+    fn test_output_format_from_path_svg_uppercase() {
+        let format = OutputFormat::from_path("test.SVG").unwrap();
+        assert!(matches!(format, OutputFormat::Svg));
+    }
+
+    #[test]
+    fn test_output_format_from_path_pdf() {
+        let format = OutputFormat::from_path("label.pdf").unwrap();
+        assert!(matches!(format, OutputFormat::Pdf));
+    }
+
+    #[test]
+    fn test_fit_mode_from_str() {
+        assert_eq!("pad".parse::<FitMode>().unwrap(), FitMode::Pad);
+        assert_eq!("Scale".parse::<FitMode>().unwrap(), FitMode::Scale);
+        assert_eq!(
+            "exact-modules".parse::<FitMode>().unwrap(),
+            FitMode::ExactModules
+        );
+        assert!("stretch".parse::<FitMode>().is_err());
+    }
+
+    #[test]
+    fn test_fit_mode_display_roundtrip() {
+        for mode in [FitMode::Pad, FitMode::Scale, FitMode::ExactModules] {
+            assert_eq!(mode.to_string().parse::<FitMode>().unwrap(), mode);
+        }
+        assert_eq!(FitMode::default(), FitMode::Pad);
+    }
+
+    #[test]
+    fn test_rgba_color_from_str() {
+        let cases = [
+            ("#000", [0, 0, 0, 255]),
+            ("fff8", [255, 255, 255, 136]),
+            ("#1E90FF", [0x1e, 0x90, 0xff, 255]),
+            ("#1e90ff80", [0x1e, 0x90, 0xff, 0x80]),
+            ("transparent", [255, 255, 255, 0]),
+        ];
+
+        for (input, rgba) in cases {
+            let color: RgbaColor = input.parse().unwrap();
+            assert_eq!(color.to_rgba(), rgba, "Failed for {}", input);
+        }
     }
 
     #[test]
-    fn test_output_format_unsupported_gif() {
-        let result = OutputFormat::from_path("animation.gif");
+    fn test_rgba_color_invalid() {
+        for input in ["", "#", "#12", "#12345", "#gggggg", "red", "#1234567890"] {
+            assert!(input.parse::<RgbaColor>().is_err(), "Accepted {}", input);
+        }
+    }
+
+    #[test]
+    fn test_rgba_color_display_roundtrip() {
+        let color = RgbaColor::new(0x12, 0x34, 0x56, 0x78);
+        assert_eq!(color.to_string(), "#12345678");
+        assert_eq!(color.to_string().parse::<RgbaColor>().unwrap(), color);
+        assert_eq!(color.to_hex_rgb(), "#123456");
+    }
+
+    #[test]
+    fn test_rgba_color_contrast_ratio() {
+        let ratio = RgbaColor::BLACK.contrast_ratio(RgbaColor::WHITE);
+        assert!((ratio - 21.0).abs() < 1e-9);
+
+        let ratio = RgbaColor::WHITE.contrast_ratio(RgbaColor::TRANSPARENT);
+        assert!((ratio - 1.0).abs() < 1e-9);
+
+        // Transparent black over white is invisible
+        let faded = RgbaColor::new(0, 0, 0, 0);
+        assert_eq!(faded.flatten_on_white(), [255, 255, 255]);
+    }
+
+    #[test]
+    fn test_physical_size_units() {
+        let cases = [
+            ("25.4mm", 72.0),
+            ("2.54cm", 72.0),
+            ("1in", 72.0),
+            ("72pt", 72.0),
+            (" 10MM ", 720.0 / 25.4),
+        ];
+
+        for (input, points) in cases {
+            let size: PhysicalSize = input.parse().unwrap();
+            assert!(
+                (size.points() - points).abs() < 1e-9,
+                "Failed for {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_physical_size_display() {
+        let size: PhysicalSize = "25mm".parse().unwrap();
+        assert_eq!(size.to_string(), "25mm");
+    }
+
+    #[test]
+    fn test_physical_size_invalid() {
+        for input in ["25", "mm", "25px", "-5mm", "0in", "abcmm", ""] {
+            let result = input.parse::<PhysicalSize>();
+            assert!(result.is_err(), "Accepted {}", input);
+        }
+    }
+
+    #[test]
+    fn test_output_format_from_path_raster() {
+        let cases = [
+            ("test.jpg", OutputFormat::Jpeg),
+            ("test.JPEG", OutputFormat::Jpeg),
+            ("test.webp", OutputFormat::WebP),
+            ("test.bmp", OutputFormat::Bmp),
+            ("animation.gif", OutputFormat::Gif),
+            ("test.tif", OutputFormat::Tiff),
+            ("test.tiff", OutputFormat::Tiff),
+            ("test.pbm", OutputFormat::Pnm),
+            ("test.ppm", OutputFormat::Pnm),
+        ];
+
+        for (path, expected) in cases {
+            let format = OutputFormat::from_path(path).unwrap();
+            assert_eq!(format, expected, "Failed for {}", path);
+            assert!(format.is_raster());
+        }
+    }
+
+    #[test]
+    fn test_output_format_capabilities() {
+        assert!(OutputFormat::Jpeg.is_lossy());
+        assert!(!OutputFormat::WebP.is_lossy());
+        assert!(!OutputFormat::Jpeg.supports_alpha());
+        assert!(!OutputFormat::Pnm.supports_alpha());
+        assert!(OutputFormat::Gif.supports_alpha());
+        assert!(!OutputFormat::Svg.is_raster());
+        assert!(!OutputFormat::Terminal.is_raster());
+    }
+
+    #[test]
+    fn test_output_format_unsupported_extension() {
+        let result = OutputFormat::from_path("photo.heic");
         assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("Unsupported format: heic"));
     }
 
     #[test]
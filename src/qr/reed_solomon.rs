@@ -0,0 +1,335 @@
+use crate::utils::error::{Result, RqrError};
+
+/// Arithmetic in a binary Galois field GF(2^m)
+///
+/// Elements are stored as `u16`, so fields up to GF(4096) are supported.
+#[derive(Debug, Clone)]
+pub struct GaloisField {
+    exp: Vec<u16>,
+    log: Vec<u16>,
+}
+
+impl GaloisField {
+    /// Build the field generated by a primitive polynomial
+    ///
+    /// # Arguments
+    /// * `primitive` - The primitive polynomial, e.g. `0x11d` for QR codes
+    /// * `size` - Number of elements, a power of two
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::reed_solomon::GaloisField;
+    ///
+    /// let field = GaloisField::new(0x11d, 256);
+    /// assert_eq!(field.multiply(2, 128), 0x1d);
+    /// ```
+    pub fn new(primitive: u32, size: usize) -> Self {
+        let mut exp = vec![0; size];
+        let mut log = vec![0; size];
+        let mut x = 1u32;
+        for (i, slot) in exp.iter_mut().enumerate().take(size - 1) {
+            *slot = x as u16;
+            log[x as usize] = i as u16;
+            x <<= 1;
+            if x as usize >= size {
+                x ^= primitive;
+            }
+        }
+        Self { exp, log }
+    }
+
+    /// Number of non-zero elements
+    fn order(&self) -> usize {
+        self.exp.len() - 1
+    }
+
+    /// The generator raised to a power
+    pub fn exp(&self, power: usize) -> u16 {
+        self.exp[power % self.order()]
+    }
+
+    /// Product of two elements
+    pub fn multiply(&self, a: u16, b: u16) -> u16 {
+        if a == 0 || b == 0 {
+            return 0;
+        }
+        self.exp(usize::from(self.log[usize::from(a)]) + usize::from(self.log[usize::from(b)]))
+    }
+
+    /// Multiplicative inverse of a non-zero element
+    fn inverse(&self, a: u16) -> u16 {
+        self.exp(self.order() - usize::from(self.log[usize::from(a)]))
+    }
+
+    /// Evaluate a polynomial, highest degree coefficient first
+    fn evaluate(&self, poly: &[u16], x: u16) -> u16 {
+        poly.iter()
+            .fold(0, |acc, &coefficient| self.multiply(acc, x) ^ coefficient)
+    }
+}
+
+/// Reed-Solomon error correction over a binary Galois field
+///
+/// Codewords are ordered highest degree first, data followed by error
+/// correction, as in every 2D symbology.
+///
+/// # Examples
+/// ```rust
+/// use rqr::qr::reed_solomon::{GaloisField, ReedSolomon};
+///
+/// let rs = ReedSolomon::new(GaloisField::new(0x11d, 256), 0);
+/// let mut codewords = vec![0x40, 0x11, 0xec];
+/// codewords.extend(rs.encode(&codewords, 4));
+///
+/// codewords[1] ^= 0x55;
+/// assert_eq!(rs.correct(&mut codewords, 4)?, 1);
+/// assert_eq!(codewords[..3], [0x40, 0x11, 0xec]);
+/// # Ok::<(), rqr::utils::error::RqrError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReedSolomon {
+    field: GaloisField,
+    first_root: usize,
+}
+
+impl ReedSolomon {
+    /// Create a codec whose generator polynomial has the roots
+    /// `a^first_root`, `a^(first_root + 1)`, ...
+    ///
+    /// # Arguments
+    /// * `field` - The field the codewords live in
+    /// * `first_root` - Power of the first root: 0 for QR, 1 for Data Matrix
+    pub fn new(field: GaloisField, first_root: usize) -> Self {
+        Self { field, first_root }
+    }
+
+    /// Compute the error correction codewords for a block of data
+    ///
+    /// # Arguments
+    /// * `data` - Data codewords
+    /// * `ec_len` - Number of error correction codewords to produce
+    pub fn encode(&self, data: &[u16], ec_len: usize) -> Vec<u16> {
+        let field = &self.field;
+        // Generator polynomial, highest degree first
+        let mut generator = vec![1u16];
+        for i in 0..ec_len {
+            let root = field.exp(self.first_root + i);
+            let mut next = generator.clone();
+            next.push(0);
+            for (j, &coefficient) in generator.iter().enumerate() {
+                next[j + 1] ^= field.multiply(coefficient, root);
+            }
+            generator = next;
+        }
+
+        let mut remainder = vec![0u16; ec_len];
+        for &codeword in data {
+            let factor = codeword ^ remainder.first().copied().unwrap_or(0);
+            remainder.rotate_left(1);
+            if let Some(last) = remainder.last_mut() {
+                *last = 0;
+            }
+            for (slot, &coefficient) in remainder.iter_mut().zip(&generator[1..]) {
+                *slot ^= field.multiply(coefficient, factor);
+            }
+        }
+        remainder
+    }
+
+    /// Correct errors in a block of data and error correction codewords
+    ///
+    /// # Arguments
+    /// * `codewords` - The received block, corrected in place
+    /// * `ec_len` - How many of the codewords are error correction
+    ///
+    /// # Returns
+    /// Returns the number of codewords corrected, or a `DecodingError` when
+    /// there are more errors than the block can recover
+    pub fn correct(&self, codewords: &mut [u16], ec_len: usize) -> Result<usize> {
+        let field = &self.field;
+        let syndromes: Vec<u16> = (0..ec_len)
+            .map(|i| field.evaluate(codewords, field.exp(self.first_root + i)))
+            .collect();
+        if syndromes.iter().all(|&s| s == 0) {
+            return Ok(0);
+        }
+
+        let locator = self.error_locator(&syndromes);
+        let errors = locator.len() - 1;
+        if errors * 2 > ec_len {
+            return Err(too_many_errors());
+        }
+
+        // Chien search: position p (counted from the end) is wrong when
+        // a^-p is a root of the locator
+        let n = codewords.len();
+        let positions: Vec<usize> = (0..n)
+            .filter(|&p| evaluate_low_first(field, &locator, field.inverse(field.exp(p))) == 0)
+            .collect();
+        if positions.len() != errors {
+            return Err(too_many_errors());
+        }
+
+        // Forney: error values from the evaluator and the locator derivative
+        let mut evaluator = vec![0u16; ec_len];
+        for (i, &s) in syndromes.iter().enumerate() {
+            for (j, &l) in locator.iter().enumerate().take(ec_len - i) {
+                evaluator[i + j] ^= field.multiply(s, l);
+            }
+        }
+        for &p in &positions {
+            let x = field.exp(p);
+            let x_inverse = field.inverse(x);
+            let numerator = evaluate_low_first(field, &evaluator, x_inverse);
+            // Odd terms only: the derivative in characteristic 2
+            let denominator = locator
+                .iter()
+                .enumerate()
+                .skip(1)
+                .step_by(2)
+                .fold(0, |acc, (i, &l)| {
+                    acc ^ field.multiply(l, field.exp((i - 1) * (field.order() - p)))
+                });
+            if denominator == 0 {
+                return Err(too_many_errors());
+            }
+            // Roots start at a^first_root, which scales every error by
+            // x^(1 - first_root)
+            let scale = field.exp((field.order() + 1 - self.first_root % field.order()) * p);
+            let magnitude =
+                field.multiply(field.multiply(numerator, field.inverse(denominator)), scale);
+            codewords[n - 1 - p] ^= magnitude;
+        }
+
+        let fixed =
+            (0..ec_len).all(|i| field.evaluate(codewords, field.exp(self.first_root + i)) == 0);
+        if !fixed {
+            return Err(too_many_errors());
+        }
+        Ok(errors)
+    }
+
+    /// Find the error locator polynomial with Berlekamp-Massey, lowest
+    /// degree coefficient first
+    fn error_locator(&self, syndromes: &[u16]) -> Vec<u16> {
+        let field = &self.field;
+        let mut locator = vec![1u16];
+        let mut previous = vec![1u16];
+        let mut errors = 0;
+        let mut shift = 1;
+        let mut previous_discrepancy = 1u16;
+
+        for i in 0..syndromes.len() {
+            let discrepancy = (0..=errors.min(locator.len() - 1)).fold(0, |acc, j| {
+                acc ^ field.multiply(locator[j], syndromes[i - j])
+            });
+            if discrepancy == 0 {
+                shift += 1;
+                continue;
+            }
+
+            let factor = field.multiply(discrepancy, field.inverse(previous_discrepancy));
+            let mut next = locator.clone();
+            if next.len() < previous.len() + shift {
+                next.resize(previous.len() + shift, 0);
+            }
+            for (j, &p) in previous.iter().enumerate() {
+                next[j + shift] ^= field.multiply(factor, p);
+            }
+
+            if 2 * errors <= i {
+                previous = locator;
+                errors = i + 1 - errors;
+                previous_discrepancy = discrepancy;
+                shift = 1;
+            } else {
+                shift += 1;
+            }
+            locator = next;
+        }
+
+        while locator.len() > 1 && locator.last() == Some(&0) {
+            locator.pop();
+        }
+        locator
+    }
+}
+
+/// Evaluate a polynomial stored lowest degree coefficient first
+fn evaluate_low_first(field: &GaloisField, poly: &[u16], x: u16) -> u16 {
+    poly.iter()
+        .rev()
+        .fold(0, |acc, &coefficient| field.multiply(acc, x) ^ coefficient)
+}
+
+fn too_many_errors() -> RqrError {
+    RqrError::DecodingError("Too many errors to correct".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn qr_codec() -> ReedSolomon {
+        ReedSolomon::new(GaloisField::new(0x11d, 256), 0)
+    }
+
+    #[test]
+    fn test_encode_matches_qrcode() {
+        // The data and error correction codewords of a 1-M QR code
+        let data = b" [\x0bx\xd1r\xdcMC@\xec\x11\xec\x11\xec\x11".map(u16::from);
+        let ec = qr_codec().encode(&data, 10);
+        assert_eq!(ec, b"\xc4#'w\xeb\xd7\xe7\xe2]\x17".map(u16::from));
+    }
+
+    #[test]
+    fn test_correct_up_to_capacity() {
+        let rs = qr_codec();
+        let data: Vec<u16> = (1..=20).collect();
+        let mut clean = data.clone();
+        clean.extend(rs.encode(&data, 8));
+
+        for errors in 0..=4 {
+            let mut received = clean.clone();
+            for i in 0..errors {
+                received[i * 7] ^= 0x5a + i as u16;
+            }
+            assert_eq!(rs.correct(&mut received, 8).unwrap(), errors);
+            assert_eq!(received, clean);
+        }
+    }
+
+    #[test]
+    fn test_correct_rejects_too_many_errors() {
+        let rs = qr_codec();
+        let data: Vec<u16> = (1..=10).collect();
+        let mut received = data.clone();
+        received.extend(rs.encode(&data, 4));
+        for i in [0, 3, 6] {
+            received[i] ^= 0xff;
+        }
+        assert!(matches!(
+            rs.correct(&mut received, 4),
+            Err(RqrError::DecodingError(_))
+        ));
+    }
+
+    #[test]
+    fn test_other_fields_and_roots() {
+        // Data Matrix style: GF(256) over 0x12d with roots from a^1, and a
+        // small GF(16) field
+        for (primitive, size, first_root) in [(0x12d, 256, 1), (0x13, 16, 1)] {
+            let rs = ReedSolomon::new(GaloisField::new(primitive, size), first_root);
+            let data: Vec<u16> = (0..6).map(|i| (i * 5 + 3) % size as u16).collect();
+            let mut clean = data.clone();
+            clean.extend(rs.encode(&data, 6));
+
+            let mut received = clean.clone();
+            received[2] ^= 1;
+            received[8] ^= 3;
+            received[10] ^= 7;
+            assert_eq!(rs.correct(&mut received, 6).unwrap(), 3);
+            assert_eq!(received, clean);
+        }
+    }
+}
@@ -0,0 +1,1031 @@
+use std::ops::RangeInclusive;
+
+use image::GrayImage;
+use qrcode::{Color, EcLevel};
+
+use crate::qr::{
+    decoded::DecodedQr,
+    grid::{self, Bitmap, Grid, ORIENTATIONS},
+    reed_solomon::{GaloisField, ReedSolomon},
+    segments::{self, ALPHANUMERIC, Segment},
+    symbol::{ErrorCorrection, Symbol, SymbolVersion, Symbology},
+};
+
+/// Error correction codewords per block, then the two groups of blocks as
+/// (number of blocks, data codewords in each)
+#[derive(Debug, Clone, Copy)]
+struct Blocks {
+    ec: usize,
+    groups: [(usize, usize); 2],
+}
+
+const fn blocks(ec: usize, count: usize, data: usize, count2: usize, data2: usize) -> Blocks {
+    Blocks {
+        ec,
+        groups: [(count, data), (count2, data2)],
+    }
+}
+
+impl Blocks {
+    /// Data codewords of each block, in interleaving order
+    fn data_lens(&self) -> Vec<usize> {
+        self.groups
+            .iter()
+            .flat_map(|&(count, data)| std::iter::repeat_n(data, count))
+            .collect()
+    }
+
+    /// Data codewords of all blocks together
+    fn data_len(&self) -> usize {
+        self.data_lens().iter().sum()
+    }
+}
+
+/// Height, width and the blocks at levels M and H of versions 1 (R7x43)
+/// to 32 (R17x139)
+const VERSIONS: [(usize, usize, Blocks, Blocks); 32] = [
+    (7, 43, blocks(7, 1, 6, 0, 0), blocks(10, 1, 3, 0, 0)),
+    (7, 59, blocks(9, 1, 12, 0, 0), blocks(14, 1, 7, 0, 0)),
+    (7, 77, blocks(12, 1, 20, 0, 0), blocks(22, 1, 10, 0, 0)),
+    (7, 99, blocks(16, 1, 28, 0, 0), blocks(30, 1, 14, 0, 0)),
+    (7, 139, blocks(24, 1, 44, 0, 0), blocks(22, 2, 12, 0, 0)),
+    (9, 43, blocks(9, 1, 12, 0, 0), blocks(14, 1, 7, 0, 0)),
+    (9, 59, blocks(12, 1, 21, 0, 0), blocks(22, 1, 11, 0, 0)),
+    (9, 77, blocks(18, 1, 31, 0, 0), blocks(16, 1, 8, 1, 9)),
+    (9, 99, blocks(24, 1, 42, 0, 0), blocks(22, 2, 11, 0, 0)),
+    (9, 139, blocks(18, 1, 31, 1, 32), blocks(22, 3, 11, 0, 0)),
+    (11, 27, blocks(8, 1, 7, 0, 0), blocks(10, 1, 5, 0, 0)),
+    (11, 43, blocks(12, 1, 19, 0, 0), blocks(20, 1, 11, 0, 0)),
+    (11, 59, blocks(16, 1, 31, 0, 0), blocks(16, 1, 7, 1, 8)),
+    (11, 77, blocks(24, 1, 43, 0, 0), blocks(22, 1, 11, 1, 12)),
+    (11, 99, blocks(16, 1, 28, 1, 29), blocks(30, 1, 14, 1, 15)),
+    (11, 139, blocks(24, 2, 42, 0, 0), blocks(30, 3, 14, 0, 0)),
+    (13, 27, blocks(9, 1, 12, 0, 0), blocks(14, 1, 7, 0, 0)),
+    (13, 43, blocks(14, 1, 27, 0, 0), blocks(28, 1, 13, 0, 0)),
+    (13, 59, blocks(22, 1, 38, 0, 0), blocks(20, 2, 10, 0, 0)),
+    (13, 77, blocks(16, 1, 26, 1, 27), blocks(28, 1, 14, 1, 15)),
+    (13, 99, blocks(20, 1, 36, 1, 37), blocks(26, 1, 11, 2, 12)),
+    (13, 139, blocks(20, 2, 35, 1, 36), blocks(28, 2, 13, 2, 14)),
+    (15, 43, blocks(18, 1, 33, 0, 0), blocks(18, 1, 7, 1, 8)),
+    (15, 59, blocks(26, 1, 48, 0, 0), blocks(24, 2, 13, 0, 0)),
+    (15, 77, blocks(18, 1, 33, 1, 34), blocks(24, 2, 10, 1, 11)),
+    (15, 99, blocks(24, 2, 44, 0, 0), blocks(22, 4, 12, 0, 0)),
+    (15, 139, blocks(24, 2, 42, 1, 43), blocks(26, 1, 13, 4, 14)),
+    (17, 43, blocks(22, 1, 39, 0, 0), blocks(20, 1, 10, 1, 11)),
+    (17, 59, blocks(16, 2, 28, 0, 0), blocks(30, 2, 14, 0, 0)),
+    (17, 77, blocks(22, 2, 39, 0, 0), blocks(28, 1, 12, 2, 13)),
+    (17, 99, blocks(20, 2, 33, 1, 34), blocks(26, 4, 14, 0, 0)),
+    (17, 139, blocks(20, 4, 38, 0, 0), blocks(26, 2, 12, 4, 13)),
+];
+
+/// Bits of the character count for the numeric, alphanumeric, byte and
+/// Kanji modes, by version
+const COUNT_BITS: [[usize; 32]; 4] = [
+    [
+        4, 5, 6, 7, 7, 5, 6, 7, 7, 8, 4, 6, 7, 7, 8, 8, 5, 6, 7, 7, 8, 8, 7, 7, 8, 8, 9, 7, 8, 8,
+        8, 9,
+    ],
+    [
+        3, 5, 5, 6, 6, 5, 5, 6, 6, 7, 4, 5, 6, 6, 7, 7, 5, 6, 6, 7, 7, 8, 6, 7, 7, 7, 8, 6, 7, 7,
+        8, 8,
+    ],
+    [
+        3, 4, 5, 5, 6, 4, 5, 5, 6, 6, 3, 5, 5, 6, 6, 7, 4, 5, 6, 6, 7, 7, 6, 6, 7, 7, 7, 6, 6, 7,
+        7, 8,
+    ],
+    [
+        2, 3, 4, 5, 5, 3, 4, 5, 5, 6, 2, 4, 5, 5, 6, 6, 3, 5, 5, 6, 6, 7, 5, 5, 6, 6, 7, 5, 6, 6,
+        6, 7,
+    ],
+];
+
+/// Masks applied to the format information beside the finder pattern and
+/// beside the finder sub pattern
+const FINDER_FORMAT_MASK: u32 = 0x1fab2;
+const SUB_FORMAT_MASK: u32 = 0x20a7b;
+
+/// rMQR's 18-bit format information words, read beside either finder,
+/// further than this from all 64 valid ones are rejected
+const MAX_FORMAT_ERRORS: u32 = 3;
+
+/// The symbol size of a version, as (height, width)
+fn size(version: u8) -> Option<(usize, usize)> {
+    let &(height, width, _, _) = VERSIONS.get(usize::from(version).checked_sub(1)?)?;
+    Some((height, width))
+}
+
+/// The version as it is usually written, e.g. `R11x43`
+pub(crate) fn label(version: u8) -> String {
+    match size(version) {
+        Some((height, width)) => format!("R{}x{}", height, width),
+        None => version.to_string(),
+    }
+}
+
+/// The blocks of a version at error correction level M or H
+fn version_blocks(version: u8, ec_level: EcLevel) -> Option<Blocks> {
+    let &(_, _, m, h) = VERSIONS.get(usize::from(version).checked_sub(1)?)?;
+    match ec_level {
+        EcLevel::M => Some(m),
+        EcLevel::H => Some(h),
+        _ => None,
+    }
+}
+
+/// Number of codewords that can be wrong before the data is lost
+pub(crate) fn max_allowed_errors(version: u8, ec_level: EcLevel) -> usize {
+    version_blocks(version, ec_level)
+        .map(|blocks| blocks.data_lens().len() * (blocks.ec / 2))
+        .unwrap_or_default()
+}
+
+/// Columns of the alignment patterns and vertical timing patterns
+fn alignment_columns(width: usize) -> &'static [usize] {
+    match width {
+        43 => &[21],
+        59 => &[19, 39],
+        77 => &[25, 51],
+        99 => &[23, 49, 75],
+        139 => &[27, 55, 83, 111],
+        _ => &[],
+    }
+}
+
+/// The function patterns of a symbol, row by row: `Some(dark)` for
+/// function modules, with the format information still light, and `None`
+/// for data modules
+fn function_modules(height: usize, width: usize) -> Vec<Option<bool>> {
+    let mut modules = vec![None; width * height];
+    let mut set = |x: usize, y: usize, dark: bool| modules[y * width + x] = Some(dark);
+
+    // Timing patterns round the edge and down each alignment column
+    for x in 0..width {
+        set(x, 0, x % 2 == 0);
+        set(x, height - 1, x % 2 == 0);
+    }
+    for y in 0..height {
+        set(0, y, y % 2 == 0);
+        set(width - 1, y, y % 2 == 0);
+        for &x in alignment_columns(width) {
+            set(x, y, y % 2 == 0);
+        }
+    }
+
+    // Alignment patterns at both ends of the vertical timing patterns
+    for &cx in alignment_columns(width) {
+        for (dx, dy) in (0..3).flat_map(|dx| (0..3).map(move |dy| (dx, dy))) {
+            let dark = (dx, dy) != (1, 1);
+            set(cx + dx - 1, dy, dark);
+            set(cx + dx - 1, height - 3 + dy, dark);
+        }
+    }
+
+    // Corner finder patterns top right and, unless the finder pattern
+    // reaches it, bottom left
+    set(width - 2, 0, true);
+    set(width - 1, 1, true);
+    set(width - 2, 1, false);
+    if height > 7 {
+        set(1, height - 1, true);
+    }
+    if height > 9 {
+        set(0, height - 2, true);
+        set(1, height - 2, false);
+    }
+
+    // Finder pattern and its separator
+    for y in 0..height.min(8) {
+        for x in 0..8usize {
+            let ring = x.abs_diff(3).max(y.abs_diff(3));
+            set(x, y, ring != 2 && ring != 4);
+        }
+    }
+
+    // Finder sub pattern in the bottom-right corner
+    for y in 0..5usize {
+        for x in 0..5usize {
+            let ring = x.abs_diff(2).max(y.abs_diff(2));
+            set(width - 5 + x, height - 5 + y, ring != 1);
+        }
+    }
+
+    for (x, y) in finder_format_cells().chain(sub_format_cells(height, width)) {
+        set(x, y, false);
+    }
+    modules
+}
+
+/// The format information modules beside the finder pattern, most
+/// significant bit first
+fn finder_format_cells() -> impl Iterator<Item = (usize, usize)> {
+    [(11, 3), (11, 2), (11, 1)].into_iter().chain(
+        [10, 9, 8]
+            .into_iter()
+            .flat_map(|x| (1..=5).rev().map(move |y| (x, y))),
+    )
+}
+
+/// The format information modules beside the finder sub pattern, most
+/// significant bit first
+fn sub_format_cells(height: usize, width: usize) -> impl Iterator<Item = (usize, usize)> {
+    (3..=5)
+        .map(move |dx| (width - dx, height - 6))
+        .chain((6..=8).flat_map(move |dx| (2..=6).map(move |dy| (width - dx, height - dy))))
+}
+
+/// The unmasked format information of a version and level: one bit for
+/// the level, five for the version and twelve BCH check bits
+fn format_bits(version: u8, ec_level: EcLevel) -> u32 {
+    let data = u32::from(ec_level == EcLevel::H) << 5 | u32::from(version - 1);
+    let mut remainder = data << 12;
+    for bit in (12..18).rev() {
+        if remainder >> bit & 1 == 1 {
+            remainder ^= 0x1f25 << (bit - 12);
+        }
+    }
+    data << 12 | remainder
+}
+
+/// Find the version and level of the closest valid format word
+fn decode_format(format: u32, mask: u32) -> Option<(u8, EcLevel)> {
+    (1..=32)
+        .flat_map(|version| [(version, EcLevel::M), (version, EcLevel::H)])
+        .map(|(version, ec_level)| {
+            let errors = (format_bits(version, ec_level) ^ mask ^ format).count_ones();
+            (version, ec_level, errors)
+        })
+        .filter(|&(_, _, errors)| errors <= MAX_FORMAT_ERRORS)
+        .min_by_key(|&(_, _, errors)| errors)
+        .map(|(version, ec_level, _)| (version, ec_level))
+}
+
+/// Data module positions in reading order, in column pairs from the one
+/// beside the right edge's timing pattern leftwards
+fn data_modules(height: usize, width: usize) -> Vec<(usize, usize)> {
+    let functions = function_modules(height, width);
+    grid::column_pairs(width - 2, height, |x, y| functions[y * width + x].is_none())
+}
+
+/// The single rMQR data mask, Micro QR's mask 1, at column `x` and row `y`
+fn mask_bit(x: usize, y: usize) -> bool {
+    ((y / 2) + (x / 3)).is_multiple_of(2)
+}
+
+/// The data modes the encoder picks from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Numeric,
+    Alphanumeric,
+    Byte,
+}
+
+impl Mode {
+    const ALL: [Mode; 3] = [Mode::Numeric, Mode::Alphanumeric, Mode::Byte];
+
+    /// Position in `COUNT_BITS`, one less than the mode indicator
+    fn index(self) -> usize {
+        match self {
+            Mode::Numeric => 0,
+            Mode::Alphanumeric => 1,
+            Mode::Byte => 2,
+        }
+    }
+
+    fn encodes(self, byte: u8) -> bool {
+        match self {
+            Mode::Numeric => byte.is_ascii_digit(),
+            Mode::Alphanumeric => ALPHANUMERIC.contains(&byte),
+            Mode::Byte => true,
+        }
+    }
+
+    /// Bits per character, in sixths of a bit
+    fn character_cost(self) -> usize {
+        match self {
+            Mode::Numeric => 20,
+            Mode::Alphanumeric => 33,
+            Mode::Byte => 48,
+        }
+    }
+}
+
+/// Split data into the segments that take the fewest bits
+///
+/// A shortest path over the characters: each step either extends the
+/// current segment or starts a new one in another mode and pays for its
+/// header. Costs are kept in sixths of a bit so numeric and alphanumeric
+/// characters cost whole numbers.
+fn segments(data: &[u8], version: u8) -> Vec<(Mode, &[u8])> {
+    let header = |mode: Mode| (3 + COUNT_BITS[mode.index()][usize::from(version) - 1]) * 6;
+    // best[i][m]: cheapest cost of the first i bytes, the last in mode m,
+    // and the mode of the byte before
+    let mut best = vec![[(usize::MAX, 0); 3]; data.len() + 1];
+    for (i, &byte) in data.iter().enumerate() {
+        for mode in Mode::ALL.into_iter().filter(|mode| mode.encodes(byte)) {
+            let m = mode.index();
+            let candidates: Vec<(usize, usize)> = if i == 0 {
+                vec![(header(mode), m)]
+            } else {
+                Mode::ALL
+                    .iter()
+                    .map(|previous| previous.index())
+                    .filter(|&p| best[i][p].0 != usize::MAX)
+                    .map(|p| {
+                        let switch = if p == m { 0 } else { header(mode) };
+                        (best[i][p].0 + switch, p)
+                    })
+                    .collect()
+            };
+            if let Some((cost, previous)) = candidates.into_iter().min() {
+                best[i + 1][m] = (cost + mode.character_cost(), previous);
+            }
+        }
+    }
+
+    // Walk back from the cheapest final mode
+    let mut modes = vec![Mode::Byte; data.len()];
+    let mut mode = (0..3).min_by_key(|&m| best[data.len()][m].0).unwrap_or(2);
+    for i in (0..data.len()).rev() {
+        modes[i] = Mode::ALL[mode];
+        mode = best[i + 1][mode].1;
+    }
+
+    let mut segments: Vec<(Mode, &[u8])> = Vec::new();
+    let mut start = 0;
+    for end in 1..=data.len() {
+        if end == data.len() || modes[end] != modes[start] {
+            segments.push((modes[start], &data[start..end]));
+            start = end;
+        }
+    }
+    segments
+}
+
+/// Append `count` bits of `value`, most significant first
+fn push_bits(bits: &mut Vec<bool>, value: usize, count: usize) {
+    bits.extend((0..count).rev().map(|i| value >> i & 1 == 1));
+}
+
+/// Encode data as segments, without the terminator
+fn data_bits(data: &[u8], version: u8) -> Vec<bool> {
+    let mut bits = Vec::new();
+    for (mode, segment) in segments(data, version) {
+        let count_bits = COUNT_BITS[mode.index()][usize::from(version) - 1];
+        // Segments longer than the character count allows are split
+        for chunk in segment.chunks((1 << count_bits) - 1) {
+            push_bits(&mut bits, mode.index() + 1, 3);
+            push_bits(&mut bits, chunk.len(), count_bits);
+            match mode {
+                Mode::Numeric => {
+                    for digits in chunk.chunks(3) {
+                        let value = digits
+                            .iter()
+                            .fold(0, |value, digit| value * 10 + usize::from(digit - b'0'));
+                        push_bits(&mut bits, value, [4, 7, 10][digits.len() - 1]);
+                    }
+                }
+                Mode::Alphanumeric => {
+                    let value = |c: &u8| ALPHANUMERIC.iter().position(|a| a == c).unwrap_or(0);
+                    for pair in chunk.chunks(2) {
+                        match pair {
+                            [a, b] => push_bits(&mut bits, value(a) * 45 + value(b), 11),
+                            _ => push_bits(&mut bits, value(&pair[0]), 6),
+                        }
+                    }
+                }
+                Mode::Byte => {
+                    for &byte in chunk {
+                        push_bits(&mut bits, usize::from(byte), 8);
+                    }
+                }
+            }
+        }
+    }
+    bits
+}
+
+/// Encode data in the first of `versions` it fits
+///
+/// Versions are numbered from the flattest symbol up, so this picks the
+/// lowest height the data fits in, and the narrowest symbol of that height.
+///
+/// # Arguments
+/// * `data` - The bytes to encode
+/// * `ec_level` - Error correction level, M or H
+/// * `versions` - Versions to try, from 1 (R7x43) to 32 (R17x139)
+///
+/// # Returns
+/// Returns the symbol, or `None` if the data fits none of the versions
+pub(crate) fn encode(
+    data: &[u8],
+    ec_level: EcLevel,
+    versions: RangeInclusive<u8>,
+) -> Option<Symbol> {
+    versions.into_iter().find_map(|version| {
+        let blocks = version_blocks(version, ec_level)?;
+        let capacity = blocks.data_len() * 8;
+        let mut bits = data_bits(data, version);
+        if bits.len() > capacity {
+            return None;
+        }
+
+        // The terminator, cut short when the symbol is full, then zero bits
+        // up to a codeword boundary and alternating pad codewords
+        let terminator = (capacity - bits.len()).min(3);
+        bits.resize(bits.len() + terminator, false);
+        bits.resize(bits.len().next_multiple_of(8), false);
+        let mut codewords: Vec<u16> = bits
+            .chunks(8)
+            .map(|byte| {
+                byte.iter()
+                    .fold(0, |value, &bit| value << 1 | u16::from(bit))
+            })
+            .collect();
+        let padding = blocks.data_len() - codewords.len();
+        codewords.extend([0xec, 0x11].into_iter().cycle().take(padding));
+
+        Some(draw(version, ec_level, &interleave(&codewords, blocks)))
+    })
+}
+
+/// Add error correction to each block and interleave the blocks
+fn interleave(data: &[u16], blocks: Blocks) -> Vec<u16> {
+    let rs = ReedSolomon::new(GaloisField::new(0x11d, 256), 0);
+    let lens = blocks.data_lens();
+    let mut rest = data;
+    let mut split = Vec::new();
+    for &len in &lens {
+        let (block, tail) = rest.split_at(len);
+        split.push((block, rs.encode(block, blocks.ec)));
+        rest = tail;
+    }
+
+    let longest = lens.iter().copied().max().unwrap_or(0);
+    let mut stream = Vec::new();
+    for i in 0..longest {
+        stream.extend(split.iter().filter_map(|(block, _)| block.get(i)));
+    }
+    for i in 0..blocks.ec {
+        stream.extend(split.iter().map(|(_, ec)| ec[i]));
+    }
+    stream
+}
+
+/// Lay out the codewords, format information and function patterns
+fn draw(version: u8, ec_level: EcLevel, codewords: &[u16]) -> Symbol {
+    let (height, width) = size(version).unwrap_or((7, 43));
+    let mut modules = function_modules(height, width);
+
+    let format = format_bits(version, ec_level);
+    let copies = [
+        (
+            finder_format_cells().collect::<Vec<_>>(),
+            FINDER_FORMAT_MASK,
+        ),
+        (sub_format_cells(height, width).collect(), SUB_FORMAT_MASK),
+    ];
+    for (cells, mask) in copies {
+        let masked = format ^ mask;
+        for (i, (x, y)) in cells.into_iter().enumerate() {
+            modules[y * width + x] = Some(masked >> (17 - i) & 1 == 1);
+        }
+    }
+
+    // Modules past the last codeword stay light before masking
+    let mut bits = codewords
+        .iter()
+        .flat_map(|&codeword| (0..8).rev().map(move |i| codeword >> i & 1 == 1));
+    for (x, y) in data_modules(height, width) {
+        let bit = bits.next().unwrap_or(false);
+        modules[y * width + x] = Some(bit != mask_bit(x, y));
+    }
+
+    let colors = modules
+        .into_iter()
+        .map(|dark| {
+            if dark == Some(true) {
+                Color::Dark
+            } else {
+                Color::Light
+            }
+        })
+        .collect();
+    Symbol::from_modules(
+        colors,
+        width,
+        height,
+        Symbology::Rmqr,
+        SymbolVersion::Rmqr(version),
+        ErrorCorrection::Level(ec_level),
+    )
+}
+
+/// Find and decode the rMQR symbols in a greyscale image
+///
+/// Symbols are located by the finder pattern on their left end and read in
+/// any of the eight axis-aligned orientations. The module grid is then
+/// stretched until it lines up with the finder sub pattern on the right
+/// end, so long symbols stay in step. Finder patterns inside the codes in
+/// `skip` are not looked at.
+///
+/// # Arguments
+/// * `image` - The greyscale image to search
+/// * `skip` - Codes already decoded from the image
+///
+/// # Returns
+/// Returns a `DecodedQr` for each rMQR symbol that decodes
+///
+/// # Examples
+/// ```rust
+/// use rqr::qr::{encoder::QrEncoder, rmqr, symbol::Symbology};
+///
+/// let encoder = QrEncoder::new(400, 2, "M")?.with_symbology(Symbology::Rmqr);
+/// let image = encoder.to_image(&encoder.encode("CABLE-0042")?)?;
+///
+/// let decoded = rmqr::decode(&image.to_luma8(), &[]);
+/// assert_eq!(decoded[0].text.as_deref(), Some("CABLE-0042"));
+/// assert_eq!(decoded[0].symbology, Symbology::Rmqr);
+/// # Ok::<(), rqr::utils::error::RqrError>(())
+/// ```
+pub fn decode(image: &GrayImage, skip: &[DecodedQr]) -> Vec<DecodedQr> {
+    let Some(bitmap) = Bitmap::new(image) else {
+        return Vec::new();
+    };
+
+    let mut results: Vec<DecodedQr> = Vec::new();
+    for finder in grid::find_finders(&bitmap) {
+        if skip
+            .iter()
+            .chain(&results)
+            .any(|code| grid::contains(&code.corners, finder.x, finder.y))
+        {
+            continue;
+        }
+        let decoded = ORIENTATIONS
+            .iter()
+            .find_map(|&axes| read_symbol(Grid::from_finder(&bitmap, &finder, axes)));
+        results.extend(decoded);
+    }
+    results
+}
+
+/// Read an rMQR symbol off a grid placed on the finder pattern at its left
+/// end
+///
+/// The format information beside the finder pattern gives the version and
+/// level, and with them the symbol's length, over which the grid is then
+/// fitted to the finder sub pattern at the right end. The top timing
+/// pattern must line up before the data is read.
+fn read_symbol(grid: Grid) -> Option<DecodedQr> {
+    let format =
+        finder_format_cells().fold(0u32, |bits, (x, y)| bits << 1 | u32::from(grid.dark(x, y)));
+    let (version, error_correction) = decode_format(format, FINDER_FORMAT_MASK)?;
+    let (height, width) = size(version)?;
+    let grid = fit(grid, height, width)?;
+
+    // The top timing pattern, between the alignment patterns
+    let timing_errors = (8..width - 3)
+        .filter(|&x| {
+            alignment_columns(width)
+                .iter()
+                .all(|&cx| x.abs_diff(cx) > 1)
+        })
+        .filter(|&x| grid.dark(x, 0) != (x % 2 == 0))
+        .count();
+    if timing_errors > width / 10 {
+        return None;
+    }
+
+    let bits: Vec<bool> = data_modules(height, width)
+        .into_iter()
+        .map(|(x, y)| grid.dark(x, y) != mask_bit(x, y))
+        .collect();
+    let blocks = version_blocks(version, error_correction)?;
+    let (payload, eci) =
+        read_codewords(&bits, blocks).and_then(|data| parse_segments(&data, version))?;
+
+    Some(DecodedQr {
+        text: String::from_utf8(payload.clone()).ok(),
+        payload,
+        symbology: Symbology::Rmqr,
+        version,
+        error_correction,
+        ec_percent: None,
+        security_level: None,
+        mask: 0,
+        eci,
+        corners: grid.corners(width, height),
+    })
+}
+
+/// Stretch the grid until the finder sub pattern is where it is found
+///
+/// The module size measured on the finder pattern is only accurate to a
+/// pixel over seven modules, far too little for a 139 module wide symbol,
+/// so the grid is stretched first along the rows, then along the columns,
+/// and the middle of the range that hits is kept.
+fn fit(grid: Grid, height: usize, width: usize) -> Option<Grid> {
+    let sx = best_scale(grid, height, width, width - 6, |s| grid.scaled(s, 1.0))?;
+    let fitted = grid.scaled(sx, 1.0);
+    let sy = best_scale(fitted, height, width, height - 6, |s| fitted.scaled(1.0, s))?;
+    Some(fitted.scaled(1.0, sy))
+}
+
+/// The middle of the longest run of scales that put the finder sub pattern
+/// in place, trying up to 10% either way
+fn best_scale<'a>(
+    grid: Grid,
+    height: usize,
+    width: usize,
+    span: usize,
+    stretch: impl Fn(f64) -> Grid<'a>,
+) -> Option<f64> {
+    // Steps that move the sub pattern by about half a pixel
+    let step = 0.5 / (span as f64 * grid.module_size());
+    let steps = (0.1 / step).ceil() as i64;
+
+    let mut best: Option<(i64, i64)> = None;
+    let mut run_start = None;
+    for i in -steps..=steps + 1 {
+        let hit = i <= steps && has_sub_finder(stretch(1.0 + i as f64 * step), height, width);
+        match (hit, run_start) {
+            (true, None) => run_start = Some(i),
+            (false, Some(start)) => {
+                if best.is_none_or(|(a, b)| i - start > b - a) {
+                    best = Some((start, i));
+                }
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+    best.map(|(start, end)| 1.0 + (start + end - 1) as f64 / 2.0 * step)
+}
+
+/// Whether the finder sub pattern and the quiet zone beyond it are in place
+fn has_sub_finder(grid: Grid, height: usize, width: usize) -> bool {
+    (0..6usize).all(|dy| {
+        (0..6usize).all(|dx| {
+            let ring = dx.abs_diff(2).max(dy.abs_diff(2));
+            let dark = dx < 5 && dy < 5 && ring != 1;
+            grid.dark(width - 5 + dx, height - 5 + dy) == dark
+        })
+    })
+}
+
+/// Group the unmasked bits into codewords, undo the interleaving, correct
+/// errors and return the data codewords
+fn read_codewords(bits: &[bool], blocks: Blocks) -> Option<Vec<u8>> {
+    let mut stream = bits.chunks_exact(8).map(|byte| {
+        byte.iter()
+            .fold(0u16, |value, &bit| value << 1 | u16::from(bit))
+    });
+
+    let lens = blocks.data_lens();
+    let mut split: Vec<Vec<u16>> = lens.iter().map(|&len| Vec::with_capacity(len)).collect();
+    let longest = lens.iter().copied().max().unwrap_or(0);
+    for i in 0..longest {
+        for (block, &len) in split.iter_mut().zip(&lens) {
+            if i < len {
+                block.push(stream.next()?);
+            }
+        }
+    }
+    for _ in 0..blocks.ec {
+        for block in &mut split {
+            block.push(stream.next()?);
+        }
+    }
+
+    let rs = ReedSolomon::new(GaloisField::new(0x11d, 256), 0);
+    let mut data = Vec::new();
+    for (mut block, len) in split.into_iter().zip(lens) {
+        rs.correct(&mut block, blocks.ec).ok()?;
+        data.extend(block[..len].iter().map(|&codeword| codeword as u8));
+    }
+    Some(data)
+}
+
+/// Decode an rMQR symbol's data segments up to its terminator
+///
+/// rMQR mode indicators are three bits: 1 numeric, 2 alphanumeric, 3 byte,
+/// 4 Kanji, 5 and 6 FNC1 in first and second position and 7 ECI, with
+/// character counts sized to each version. Fewer than three bits left is a
+/// terminator cut short.
+fn parse_segments(data: &[u8], version: u8) -> Option<(Vec<u8>, Option<u32>)> {
+    let bits: Vec<bool> = data
+        .iter()
+        .flat_map(|&byte| (0..8).rev().map(move |i| (byte >> i) & 1 == 1))
+        .collect();
+    segments::parse_segments(&bits, |reader| {
+        Some(match reader.read(3) {
+            None | Some(0) => Segment::End,
+            Some(mode @ 1..=4) => {
+                let count_bits = COUNT_BITS[mode as usize - 1][usize::from(version) - 1];
+                Segment::Characters {
+                    mode: mode - 1,
+                    count: reader.read(count_bits)? as usize,
+                }
+            }
+            Some(5) => Segment::Gs1,
+            Some(6) => Segment::Application,
+            Some(_) => Segment::Eci,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, Luma};
+
+    use super::*;
+    use crate::qr::encoder::QrEncoder;
+
+    /// Rows of a symbol with `X` for dark modules, as printed in ISO/IEC
+    /// 23941 and other decoders' test suites
+    fn parse_rows(rows: &[&str]) -> Vec<Color> {
+        rows.iter()
+            .flat_map(|row| row.chars())
+            .map(|c| if c == 'X' { Color::Dark } else { Color::Light })
+            .collect()
+    }
+
+    /// Draw rows of modules 4px each inside a two module quiet zone
+    fn rows_image(rows: &[&str]) -> GrayImage {
+        let (width, height) = (rows[0].len() as u32, rows.len() as u32);
+        GrayImage::from_fn((width + 4) * 4, (height + 4) * 4, |x, y| {
+            let (mx, my) = (x / 4, y / 4);
+            let dark = (2..width + 2).contains(&mx)
+                && (2..height + 2).contains(&my)
+                && rows[(my - 2) as usize].as_bytes()[(mx - 2) as usize] == b'X';
+            Luma([if dark { 0 } else { 255 }])
+        })
+    }
+
+    fn rmqr_image(symbol: &Symbol) -> DynamicImage {
+        QrEncoder::new(600, 2, "M")
+            .unwrap()
+            .to_image(symbol)
+            .unwrap()
+    }
+
+    const R7X43_M: [&str; 7] = [
+        "XXXXXXX X X X X X X XXX X X X X X X X X XXX",
+        "X     X  X XXX  XXXXX XXX      X X XX   X X",
+        "X XXX X X XXX X X X XXXX XXXX X  X XXXXXXXX",
+        "X XXX X  XX    XXXXX   XXXXXX   X X   X   X",
+        "X XXX X   XX  XXX   XXXXXXX  X X  XX  X X X",
+        "X     X XXXXX XXX XXX XXXXX    XXXXXX X   X",
+        "XXXXXXX X X X X X X XXX X X X X X X X XXXXX",
+    ];
+
+    const R11X27_H: [&str; 11] = [
+        "XXXXXXX X X X X X X X X XXX",
+        "X     X  XX        X  X X X",
+        "X XXX X    X  XX X   X   XX",
+        "X XXX X XXXX XX X  XXXXXX  ",
+        "X XXX X  X X XX  XX   XXX X",
+        "X     X XXX  X XX  XXXX  X ",
+        "XXXXXXX     X   XX  X XXXXX",
+        "           X   X   X  X   X",
+        "XXXX  X   X X XX XXXXXX X X",
+        "X XX XXXXXX XXX  XXXX X   X",
+        "XXX X X X X X X X X X XXXXX",
+    ];
+
+    #[test]
+    fn test_encode_matches_reference_symbols() {
+        for (data, version, ec_level, rows) in [
+            ("ABCDEFG", 1, EcLevel::M, &R7X43_M[..]),
+            ("ABCDEF", 11, EcLevel::H, &R11X27_H[..]),
+        ] {
+            let symbol = encode(data.as_bytes(), ec_level, version..=version).unwrap();
+            assert_eq!(
+                (symbol.width(), symbol.height()),
+                (rows[0].len(), rows.len())
+            );
+            assert_eq!(symbol.to_colors(), parse_rows(rows), "{}", label(version));
+        }
+    }
+
+    #[test]
+    fn test_decode_reference_symbols() {
+        let r9x59_h = [
+            "XXXXXXX X X X X X XXX X X X X X X X X XXX X X X X X X X XXX",
+            "X     X    X  XXXXX XXX X  X XXXXXXXX X X  X    X XXXX  X X",
+            "X XXX X XX XXX  X XXX XXXX  X         XXXXXXX  X XXXXX X  X",
+            "X XXX X XXXX X XX X   XX   XXXX XX  XX   X  X  X XXX     X ",
+            "X XXX X    X    X XX XXXXXX X X XX   X XX   X X XXXX  XXXXX",
+            "X     X X  X  X  X  XXX X X   X   XX  X XXXX XX  X X  X   X",
+            "XXXXXXX  XXXXX  XXXXXX X XX XXX X    XXXX  X    X  X XX X X",
+            "          XXX  XXXX XX XXX    X XXXXXXX X XX XXX  XX XX   X",
+            "XXX X X X X X X X XXX X X X X X X X X XXX X X X X X X XXXXX",
+        ];
+        let r9x77_m = [
+            "XXXXXXX X X X X X X X X XXX X X X X X X X X X X X XXX X X X X X X X X X X XXX",
+            "X     X  XXX XX XXX   XXX XXXX XXX XX X XXXXXXXXX X XXX  XXXX X XXXX XX XXX X",
+            "X XXX X X  X X  XXX  X XXXX  XX  XX  X XX XX      XXX XXXX X X XX   X  X XX X",
+            "X XXX X X   X XXXXXX  X   XX XXXX X  XXX X XX X  XX  XX XX X XXX X X XXX  XX ",
+            "X XXX X     XXXX  X X   XXXX XXXX XX     XXX X XX XXXXXX X X     XXX XX XXXXX",
+            "X     X  X X XX XXX    X  X  XX   X X    XX XXX X X   X  X  X    XX XXXXX   X",
+            "XXXXXXX    X XX   XX X  XXXX X  X X     X  X  XX  XXX  X XX     X  XXX XX X X",
+            "         X XXXXX       XX X XXXXXX XX   XXXXX     X XX     XX   XXXXX XXX   X",
+            "XXX X X X X X X X X X X XXX X X X X X X X X X X X XXX X X X X X X X X X XXXXX",
+        ];
+        let r7x139_h = [
+            "XXXXXXX X X X X X X X X X XXX X X X X X X X X X X X X XXX X X X X X X X X X X X X XXX X X X X X X X X X X X X XXX X X X X X X X X X X X XXX",
+            "X     X XX XXX X X   X  X X XX XX  X   X X XXX XX  XXXX XXX XX  XX XX  X     XX X X X XXX  X   XX   XX   XX X X XX  X XX XXXX  X    X     X",
+            "X XXX X    X  XXXXX   X  XXXXX        X X XXX XX    X XXX X XX XXX XX X XXX  X X XXXX   X   XXXXXXX X XX      XXX   X     X  X  XXX X XXXXX",
+            "X XXX X  XXXX   X   XX X X    XX  XX  X XX  XX    X XXX XX X XX  X XX  X X   XX  X  X XXX  X  X      X X X X  X XX X   XX   XX   X    X   X",
+            "X XXX X XXXX XXXXX X  X XXXXXX XX X XXXX  X    XXXX X XXX  XXXX  X XXXXXXX   XXX XXXXXX X  X XX  X     XXX  X XXXXXXXXX X XXXX  X   X X X X",
+            "X     X X   XX  XX X  X  XX X X X XXXX X X   X XX X XXX X  X  X X X  XXX   XX   XXX X  X XX XXXX  XX X X  X   X XXXXX  XXX XX      X XX   X",
+            "XXXXXXX X X X X X X X X X XXX X X X X X X X X X X X X XXX X X X X X X X X X X X X XXX X X X X X X X X X X X X XXX X X X X X X X X X X XXXXX",
+        ];
+        let r17x99_h = [
+            "XXXXXXX X X X X X X X XXX X X X X X X X X X X X XXX X X X X X X X X X X X XXX X X X X X X X X X XXX",
+            "X     X   X XXXXX XXX X X  X XX X X  XX  XXXXX  X XX   X XX XXX X X XX X  X XXX     X X XX   X  X X",
+            "X XXX X X X   XXX     XXX X XXX XXX     X  X XX XXXX X  X X  X      XXX   XXXXX X    X XX X XX  X X",
+            "X XXX X   XX X  XX X    X   XX   X  XXXX X  XXXXX  X  X    XX X XXX XX X X       X  X   XXXXXX X   ",
+            "X XXX X    X XX  X X X X X  X   X X  XXX    XX XXXXXX X    X   XXX  X XXXXXX X   X X X X X X  XX  X",
+            "X     X XX  X   X XXXXX  XX   X XXX  X XX   X X    XXX X  XXX  XXX X  XXXX  XX     X X X XX   XXXX ",
+            "XXXXXXX X XX X      XX X X  XXX XX  X XXXX    X  X  XXX X X XX X XXXX  XX  X   X        X XX X XXXX",
+            "        XX XX XX XX  X  XX  X    X  X XXX XX    X     X  XXX     XXXX  XX X X  X      X XX XX  XXX ",
+            "XX       X XXX  X   X XXXX XXX XXXXX  XXX  XXX   X X X  X   X  XXX X  XX  XX X   X X  X  XX  X  XXX",
+            " X   XXXXX X  X   XXXXX X  XX       X XX XXXX   X     X XXXXX X XX X  XX  X XX   X XX           XX ",
+            "X XX XX   X  XX   XXX  XX XXXXXX X  XXXXX  XX    XXXX  X X X   X XXXX  XX  X   X  XXXXXX    XX  X X",
+            " XXX XX  XXX  XX  XX X   X X XX  X X X X XX   XXX XXXX      X XX  XXX X X X XXXX    XXXXX  X XXX   ",
+            "X  X  XX    X      XX XX  XX X X XX  X    X X XX XXXXXXXX X XX XX  X   X   X X X XX X X XXXXXXXXXXX",
+            "    X X    X XX    X X   X XX XXXX    X XXX  X XX X X X   X X  XXX XXXXX    XX X X  X XXXXX X X   X",
+            "XXXX XX XX   X  XXXX XXXX  X XX    X  XX  XX XX XXXX XXX X      X XX XX X XXXX   X XXX  XX X XX X X",
+            "X XXX XX  XXX X X X XXX X  XXX   X XXXX  XX     X X  XXXXX X XX X  X X X  X X X X XXXX     XXXX   X",
+            "XXX X X X X X X X X X XXX X X X X X X X X X X X XXX X X X X X X X X X X X XXX X X X X X X X X XXXXX",
+        ];
+        for (rows, text) in [
+            (&R7X43_M[..], "ABCDEFG"),
+            (&R11X27_H[..], "ABCDEF"),
+            (&r9x59_h[..], "ABCDEFGHIJKLMN"),
+            (&r9x77_m[..], "__ABCDEFGH__1234567890___ABCDEFGHIJK"),
+            (&r7x139_h[..], "1234567890,ABCDEFGHIJKLMOPQRSTUVW"),
+            (
+                &r17x99_h[..],
+                "1234567890ABCDEFGHIJKLMNOPQRSTUVWXYZ1234567890________________________",
+            ),
+        ] {
+            let decoded = decode(&rows_image(rows), &[]);
+            assert_eq!(decoded.len(), 1, "R{}x{}", rows.len(), rows[0].len());
+            assert_eq!(decoded[0].text.as_deref(), Some(text));
+            assert_eq!(decoded[0].symbology, Symbology::Rmqr);
+        }
+    }
+
+    #[test]
+    fn test_decode_eci_and_kanji() {
+        // "AB", the Kanji 貫 and "12345AB" after ECI 20 (Shift JIS)
+        let rows = [
+            "XXXXXXX X X X X X X X X XXX",
+            "X     X    XX XX XXX   XX X",
+            "X XXX X XX  X  XX XX XXX  X",
+            "X XXX X  XX X XX X X   XX  ",
+            "X XXX X XXXXXXX X X      XX",
+            "X     X   XX X  XXX  XX XX ",
+            "XXXXXXX   X   X X    X  XXX",
+            "        XXX XX X  XX   XXX ",
+            "XXX XX XX X  X XX XX  XXXXX",
+            " XXX  X    X X    X   X   X",
+            "X XX X  X   XX X XX X X X X",
+            "X   X   X  X X X X    X   X",
+            "XXX X X X X X X X X X XXXXX",
+        ];
+        let decoded = decode(&rows_image(&rows), &[]);
+        assert_eq!(decoded[0].eci, Some(20));
+        assert_eq!(decoded[0].version, 17);
+        assert_eq!(decoded[0].error_correction, EcLevel::M);
+        assert!(decoded[0].payload.starts_with(b"AB"));
+        assert!(decoded[0].payload.ends_with(b"12345AB"));
+        assert_eq!(decoded[0].payload.len(), 11);
+    }
+
+    #[test]
+    fn test_decode_rejects_too_much_damage() {
+        // The R7x43 reference symbol with six bits flipped, more than its
+        // seven error correction codewords can fix
+        let rows = [
+            "XXXXXXX X X X X X X XXX X X X X X X X X XXX",
+            "X     X  X XXX  XXXXX XXX      X X XX   X X",
+            "X XXX X X XXX   X X XXXX XXXX XX X XXXXXXXX",
+            "X XXX X  XX    XXXXX X XXXXXX   X X   X   X",
+            "X XXX X   XX  XXX   XXXXXXX  X X XXX  X X X",
+            "X     X XXXXX XXX XXX XXXX X   XXXXXX X   X",
+            "XXXXXXX X X X X X X XXX X X X X X X X XXXXX",
+        ];
+        assert!(decode(&rows_image(&rows), &[]).is_empty());
+    }
+
+    #[test]
+    fn test_decode_every_version_and_level() {
+        for version in 1..=32 {
+            for ec_level in [EcLevel::M, EcLevel::H] {
+                let symbol = encode(b"R7", ec_level, version..=version).unwrap();
+                let decoded = decode(&rmqr_image(&symbol).to_luma8(), &[]);
+
+                assert_eq!(decoded.len(), 1, "{}-{:?}", label(version), ec_level);
+                assert_eq!(decoded[0].payload, b"R7");
+                assert_eq!(decoded[0].version, version);
+                assert_eq!(decoded[0].error_correction, ec_level);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_rotated_and_mirrored() {
+        let symbol = encode(b"cable 7/12", EcLevel::M, 1..=32).unwrap();
+        let image = rmqr_image(&symbol);
+        for turned in [
+            image.rotate90(),
+            image.rotate180(),
+            image.rotate270(),
+            image.fliph(),
+            image.rotate90().flipv(),
+        ] {
+            let decoded = decode(&turned.to_luma8(), &[]);
+            assert_eq!(decoded.len(), 1);
+            assert_eq!(decoded[0].payload, b"cable 7/12");
+        }
+    }
+
+    #[test]
+    fn test_decode_corrects_damage() {
+        let symbol = encode(b"Damaged label", EcLevel::H, 13..=13).unwrap();
+        let mut image = rmqr_image(&symbol).to_luma8();
+        // R11x59 plus the margin is 63 modules of 9px after 5px of padding;
+        // blot out a 2x2 module block in the data area
+        let origin = 5 + 2 * 9;
+        for y in origin + 9 * 4..origin + 9 * 6 {
+            for x in origin + 9 * 30..origin + 9 * 32 {
+                image.put_pixel(x, y, Luma([0]));
+            }
+        }
+
+        let decoded = decode(&image, &[]);
+        assert_eq!(decoded[0].payload, b"Damaged label");
+    }
+
+    #[test]
+    fn test_encode_picks_flattest_version() {
+        // Five digits fit R7x43, anything longer than R7x139 allows does not
+        assert_eq!(encode(b"12345", EcLevel::M, 1..=32).unwrap().height(), 7);
+        let long = [b'7'; 60];
+        assert_eq!(encode(&long, EcLevel::M, 1..=32).unwrap().height(), 7);
+        assert_eq!(encode(&long, EcLevel::H, 1..=32).unwrap().height(), 9);
+        assert!(encode(&[b'x'; 400], EcLevel::H, 1..=32).is_none());
+        assert!(encode(b"12345", EcLevel::Q, 1..=32).is_none());
+    }
+
+    #[test]
+    fn test_format_bits() {
+        // Values from the format information tables of ISO/IEC 23941
+        assert_eq!(format_bits(2, EcLevel::M) ^ FINDER_FORMAT_MASK, 0x1e597);
+        assert_eq!(format_bits(32, EcLevel::H) ^ FINDER_FORMAT_MASK, 0x20137);
+        assert_eq!(format_bits(1, EcLevel::M) ^ SUB_FORMAT_MASK, 0x20a7b);
+        assert_eq!(format_bits(32, EcLevel::H) ^ SUB_FORMAT_MASK, 0x1f1fe);
+
+        let format = format_bits(20, EcLevel::H) ^ FINDER_FORMAT_MASK;
+        assert_eq!(
+            decode_format(format ^ 0b101, FINDER_FORMAT_MASK),
+            Some((20, EcLevel::H))
+        );
+        assert_eq!(decode_format(format ^ 0b1111, FINDER_FORMAT_MASK), None);
+    }
+
+    #[test]
+    fn test_data_modules_fill_the_symbol() {
+        // Every codeword has a place, with at most seven remainder bits
+        for version in 1..=32 {
+            let (height, width) = size(version).unwrap();
+            let blocks = version_blocks(version, EcLevel::M).unwrap();
+            let codewords = blocks.data_len() + blocks.data_lens().len() * blocks.ec;
+            let modules = data_modules(height, width).len();
+            assert!(
+                (codewords * 8..codewords * 8 + 8).contains(&modules),
+                "{}",
+                label(version)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_segments() {
+        // R7x43: numeric "01" then byte "a" and the terminator
+        assert_eq!(
+            parse_segments(&[0x24, 0x05, 0x96, 0x10], 1),
+            Some((b"01a".to_vec(), None))
+        );
+        // ECI 20 then alphanumeric "A"
+        assert_eq!(
+            parse_segments(&[0xe2, 0x88, 0x94, 0x00], 1),
+            Some((b"A".to_vec(), Some(20)))
+        );
+        // A numeric digit above 9
+        assert_eq!(parse_segments(&[0x23, 0xe0], 1), None);
+    }
+}
@@ -0,0 +1,139 @@
+/// Characters of the alphanumeric mode, by value
+pub(super) const ALPHANUMERIC: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// Reads a bit stream most significant bit first
+pub(super) struct BitReader<'a> {
+    bits: &'a [bool],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub(super) fn new(bits: &'a [bool]) -> Self {
+        Self { bits, position: 0 }
+    }
+
+    pub(super) fn read(&mut self, count: usize) -> Option<u32> {
+        let bits = self.bits.get(self.position..self.position + count)?;
+        self.position += count;
+        Some(
+            bits.iter()
+                .fold(0, |value, &bit| value << 1 | u32::from(bit)),
+        )
+    }
+}
+
+/// What a segment header announces, once a symbology has read its mode
+/// indicator and character count
+pub(super) enum Segment {
+    /// `count` characters in mode 0 numeric, 1 alphanumeric, 2 byte or 3
+    /// Kanji
+    Characters { mode: u32, count: usize },
+    /// An ECI designator follows
+    Eci,
+    /// FNC1 in first position; GS1 data follows as is
+    Gs1,
+    /// FNC1 in second position; an eight-bit application indicator follows
+    Application,
+    /// The terminator, or the end of the data
+    End,
+}
+
+/// Decode the data segments of a QR-family symbol up to the terminator
+///
+/// Each symbology has its own mode indicators and character count lengths,
+/// so `read_header` reads a segment header and says what follows, or
+/// returns `None` for a header the symbology does not define. The first
+/// ECI designator is returned along with the payload.
+pub(super) fn parse_segments(
+    bits: &[bool],
+    read_header: impl Fn(&mut BitReader) -> Option<Segment>,
+) -> Option<(Vec<u8>, Option<u32>)> {
+    let mut reader = BitReader::new(bits);
+    let mut payload = Vec::new();
+    let mut eci = None;
+
+    loop {
+        match read_header(&mut reader)? {
+            Segment::Characters { mode, count } => {
+                read_characters(&mut reader, mode, count, &mut payload)?;
+            }
+            Segment::Eci => {
+                let first = reader.read(8)?;
+                let designator = if first & 0x80 == 0 {
+                    first
+                } else if first & 0xc0 == 0x80 {
+                    (first & 0x3f) << 8 | reader.read(8)?
+                } else if first & 0xe0 == 0xc0 {
+                    (first & 0x1f) << 16 | reader.read(16)?
+                } else {
+                    return None;
+                };
+                eci.get_or_insert(designator);
+            }
+            Segment::Gs1 => {}
+            Segment::Application => {
+                reader.read(8)?;
+            }
+            Segment::End => break,
+        }
+    }
+
+    Some((payload, eci))
+}
+
+/// Read `count` characters of one data segment onto the payload
+///
+/// Kanji characters are returned as Shift JIS bytes.
+fn read_characters(
+    reader: &mut BitReader,
+    mode: u32,
+    count: usize,
+    payload: &mut Vec<u8>,
+) -> Option<()> {
+    match mode {
+        0 => {
+            let mut remaining = count;
+            while remaining > 0 {
+                let digits = remaining.min(3);
+                let value = reader.read([4, 7, 10][digits - 1])?;
+                if value >= 10u32.pow(digits as u32) {
+                    return None;
+                }
+                payload.extend(format!("{:0width$}", value, width = digits).bytes());
+                remaining -= digits;
+            }
+        }
+        1 => {
+            let mut remaining = count;
+            while remaining > 0 {
+                if remaining >= 2 {
+                    let value = reader.read(11)? as usize;
+                    payload.push(*ALPHANUMERIC.get(value / 45)?);
+                    payload.push(ALPHANUMERIC[value % 45]);
+                    remaining -= 2;
+                } else {
+                    payload.push(*ALPHANUMERIC.get(reader.read(6)? as usize)?);
+                    remaining -= 1;
+                }
+            }
+        }
+        2 => {
+            for _ in 0..count {
+                payload.push(reader.read(8)? as u8);
+            }
+        }
+        _ => {
+            for _ in 0..count {
+                let value = reader.read(13)?;
+                let code = (value / 0xc0) << 8 | (value % 0xc0);
+                let code = if code + 0x8140 <= 0x9ffc {
+                    code + 0x8140
+                } else {
+                    code + 0xc140
+                };
+                payload.extend([(code >> 8) as u8, code as u8]);
+            }
+        }
+    }
+    Some(())
+}
@@ -0,0 +1,514 @@
+use std::{
+    fmt,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use base64::{
+    Engine, alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+};
+use image::DynamicImage;
+
+use crate::utils::{
+    error::{Result, RqrError},
+    http::HttpFetcher,
+};
+
+/// Extensions picked up when a directory is scanned for images
+pub const IMAGE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "webp", "bmp", "gif", "tif", "tiff", "pnm", "pbm", "pgm", "ppm",
+];
+
+/// Base64 engine for data URIs, which are often written without padding
+const DATA_URI_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Where the image to decode comes from
+///
+/// Parsed from the decode command's input argument:
+///
+/// * `-` reads the image bytes from stdin
+/// * `http://` and `https://` URLs are downloaded with `HttpFetcher`
+/// * `data:` URIs carry the image inline, base64 or percent-encoded
+/// * `file://` URLs and anything else are paths on disk
+///
+/// # Examples
+/// ```rust
+/// use rqr::qr::source::ImageSource;
+///
+/// let source: ImageSource = "file:///tmp/my%20code.png".parse()?;
+/// assert_eq!(source, ImageSource::File("/tmp/my code.png".into()));
+///
+/// let source: ImageSource = "data:image/png;base64,iVBORw0KGgo=".parse()?;
+/// assert_eq!(source, ImageSource::Data(b"\x89PNG\r\n\x1a\n".to_vec()));
+/// # Ok::<(), rqr::utils::error::RqrError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageSource {
+    /// An image file on disk
+    File(PathBuf),
+    /// An image downloaded over HTTP(S)
+    Url(String),
+    /// Image bytes read from stdin
+    Stdin,
+    /// Image bytes carried by a `data:` URI
+    Data(Vec<u8>),
+}
+
+impl FromStr for ImageSource {
+    type Err = RqrError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let scheme = s.split_once(':').map(|(scheme, _)| scheme.to_lowercase());
+        match scheme.as_deref() {
+            _ if s == "-" => Ok(ImageSource::Stdin),
+            Some("http") | Some("https") => Ok(ImageSource::Url(s.to_string())),
+            Some("data") => parse_data_uri(s).map(ImageSource::Data),
+            Some("file") if s[5..].starts_with("//") => parse_file_url(s).map(ImageSource::File),
+            _ => Ok(ImageSource::File(PathBuf::from(s))),
+        }
+    }
+}
+
+impl fmt::Display for ImageSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageSource::File(path) => write!(f, "{}", path.display()),
+            ImageSource::Url(url) => f.write_str(url),
+            ImageSource::Stdin => f.write_str("stdin"),
+            ImageSource::Data(data) => write!(f, "data URI ({} bytes)", data.len()),
+        }
+    }
+}
+
+/// One entry of an expanded decode input: an image to decode, or a part
+/// of the input that could not be read
+pub type Expanded = std::result::Result<ImageSource, ExpandError>;
+
+/// A decode input, or a directory inside one, that could not be expanded
+#[derive(Debug)]
+pub struct ExpandError {
+    /// The input or directory, as shown to the user
+    pub input: String,
+    /// Why it could not be read
+    pub error: RqrError,
+}
+
+impl fmt::Display for ExpandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.input, self.error)
+    }
+}
+
+impl ImageSource {
+    /// Expand a decode input into the images it names
+    ///
+    /// Directories yield the image files inside them (by extension, see
+    /// `IMAGE_EXTENSIONS`), descending into subdirectories when `recursive`
+    /// is set. Paths containing `*`, `?` or `[` that do not exist as typed
+    /// are treated as glob patterns. Anything else parses as a single source.
+    /// Results are sorted by path so batch output is stable.
+    ///
+    /// A directory that cannot be read, at any depth, shows up as an
+    /// `ExpandError` entry in place of its images so the rest of the scan
+    /// still goes ahead.
+    ///
+    /// # Arguments
+    /// * `input` - A path, directory, glob pattern, URL, data URI or `-`
+    /// * `recursive` - Whether directories are scanned recursively
+    ///
+    /// # Returns
+    /// Returns the sources in order, or an error if a pattern is invalid or
+    /// matches nothing
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// use rqr::qr::source::ImageSource;
+    ///
+    /// let photos = ImageSource::expand("photos/**/*.jpg", false)?;
+    /// for entry in ImageSource::expand("scans", true)? {
+    ///     match entry {
+    ///         Ok(source) => println!("{}", source),
+    ///         Err(e) => eprintln!("Skipping {}", e),
+    ///     }
+    /// }
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn expand(input: &str, recursive: bool) -> Result<Vec<Expanded>> {
+        let source: ImageSource = input.parse()?;
+        let ImageSource::File(path) = &source else {
+            return Ok(vec![Ok(source)]);
+        };
+
+        if path.is_dir() {
+            let mut entries = Vec::new();
+            collect_images(path, recursive, &mut entries);
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            return Ok(entries
+                .into_iter()
+                .map(|(path, entry)| {
+                    entry
+                        .map(|()| ImageSource::File(path.clone()))
+                        .map_err(|error| ExpandError {
+                            input: path.display().to_string(),
+                            error,
+                        })
+                })
+                .collect());
+        }
+
+        if !path.exists() && input.contains(['*', '?', '[']) {
+            let pattern = path.to_string_lossy();
+            let matches = glob::glob(&pattern).map_err(|e| {
+                RqrError::InvalidInput(format!("Invalid glob pattern '{}': {}", pattern, e))
+            })?;
+            let mut files: Vec<PathBuf> = matches
+                .filter_map(|entry| entry.ok())
+                .filter(|path| path.is_file())
+                .collect();
+            if files.is_empty() {
+                return Err(RqrError::InvalidInput(format!(
+                    "No files match '{}'",
+                    pattern
+                )));
+            }
+            files.sort();
+            return Ok(files
+                .into_iter()
+                .map(|path| Ok(ImageSource::File(path)))
+                .collect());
+        }
+
+        Ok(vec![Ok(source)])
+    }
+
+    /// Read the raw image bytes
+    ///
+    /// # Returns
+    /// Returns the encoded image file contents, or an error if they cannot
+    /// be read or downloaded
+    pub fn read(&self) -> Result<Vec<u8>> {
+        match self {
+            ImageSource::File(path) => Ok(std::fs::read(path)?),
+            ImageSource::Url(url) => HttpFetcher::new().fetch_image(url),
+            ImageSource::Stdin => {
+                let mut data = Vec::new();
+                io::stdin().lock().read_to_end(&mut data)?;
+                if data.is_empty() {
+                    return Err(RqrError::InvalidInput("No image data on stdin".to_string()));
+                }
+                Ok(data)
+            }
+            ImageSource::Data(data) => Ok(data.clone()),
+        }
+    }
+
+    /// Read and decode the image
+    ///
+    /// # Returns
+    /// Returns the loaded image, or an error if it cannot be read or is not
+    /// a supported image format
+    pub fn load(&self) -> Result<DynamicImage> {
+        match self {
+            // Let image pick the format from the extension as well as the content
+            ImageSource::File(path) => Ok(image::open(path)?),
+            _ => Ok(image::load_from_memory(&self.read()?)?),
+        }
+    }
+}
+
+/// Add the image files in `dir` to `entries`, along with any directory
+/// that could not be read
+fn collect_images(dir: &Path, recursive: bool, entries: &mut Vec<(PathBuf, Result<()>)>) {
+    let read_dir = match std::fs::read_dir(dir) {
+        Ok(read_dir) => read_dir,
+        Err(e) => return entries.push((dir.to_path_buf(), Err(e.into()))),
+    };
+    for entry in read_dir {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(e) => {
+                entries.push((dir.to_path_buf(), Err(e.into())));
+                continue;
+            }
+        };
+        if path.is_dir() {
+            if recursive {
+                collect_images(&path, recursive, entries);
+            }
+        } else if path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        {
+            entries.push((path, Ok(())));
+        }
+    }
+}
+
+/// Decode the payload of a `data:[<media type>][;base64],<data>` URI
+fn parse_data_uri(uri: &str) -> Result<Vec<u8>> {
+    let (header, data) = uri[5..]
+        .split_once(',')
+        .ok_or_else(|| RqrError::InvalidInput("Data URI is missing the ','".to_string()))?;
+
+    let mut params = header.split(';');
+    let media_type = params.next().unwrap_or_default().trim().to_lowercase();
+    if !media_type.is_empty()
+        && !media_type.starts_with("image/")
+        && media_type != "application/octet-stream"
+    {
+        return Err(RqrError::NotAnImage(media_type));
+    }
+
+    if params.any(|param| param.trim().eq_ignore_ascii_case("base64")) {
+        let data: String = percent_decode(data)?
+            .into_iter()
+            .filter(|b| !b.is_ascii_whitespace())
+            .map(char::from)
+            .collect();
+        DATA_URI_BASE64
+            .decode(data)
+            .map_err(|e| RqrError::InvalidInput(format!("Invalid base64 in data URI: {}", e)))
+    } else {
+        percent_decode(data)
+    }
+}
+
+/// Turn a `file://` URL into a local path
+fn parse_file_url(url: &str) -> Result<PathBuf> {
+    let rest = &url["file://".len()..];
+    // Only local files: file:///path or file://localhost/path
+    let path = match rest.find('/') {
+        Some(0) => rest,
+        Some(i) if rest[..i].eq_ignore_ascii_case("localhost") => &rest[i..],
+        _ => {
+            return Err(RqrError::InvalidInput(format!(
+                "Only local file:// URLs are supported, got '{}'",
+                url
+            )));
+        }
+    };
+    let path = String::from_utf8(percent_decode(path)?)
+        .map_err(|_| RqrError::InvalidInput(format!("File URL is not valid UTF-8: {}", url)))?;
+
+    // file:///C:/dir/code.png names a Windows drive
+    let bytes = path.as_bytes();
+    if bytes.len() > 2 && bytes[0] == b'/' && bytes[1].is_ascii_alphabetic() && bytes[2] == b':' {
+        return Ok(PathBuf::from(&path[1..]));
+    }
+    Ok(PathBuf::from(path))
+}
+
+/// Decode `%XX` escapes
+fn percent_decode(s: &str) -> Result<Vec<u8>> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let byte = s
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| {
+                    RqrError::InvalidInput(format!("Invalid percent escape in '{}'", s))
+                })?;
+            out.push(byte);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::temp_dir;
+
+    #[test]
+    fn test_parse_stdin() {
+        assert_eq!("-".parse::<ImageSource>().unwrap(), ImageSource::Stdin);
+    }
+
+    #[test]
+    fn test_parse_urls() {
+        for url in ["http://example.com/qr.png", "HTTPS://example.com/qr.png"] {
+            assert_eq!(
+                url.parse::<ImageSource>().unwrap(),
+                ImageSource::Url(url.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_paths() {
+        for path in ["qr.png", "./dir/qr.png", "/tmp/qr.png", "C:\\codes\\qr.png"] {
+            assert_eq!(
+                path.parse::<ImageSource>().unwrap(),
+                ImageSource::File(PathBuf::from(path))
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_file_urls() {
+        assert_eq!(
+            "file:///tmp/qr.png".parse::<ImageSource>().unwrap(),
+            ImageSource::File(PathBuf::from("/tmp/qr.png"))
+        );
+        assert_eq!(
+            "file://localhost/tmp/a%20b.png"
+                .parse::<ImageSource>()
+                .unwrap(),
+            ImageSource::File(PathBuf::from("/tmp/a b.png"))
+        );
+        assert_eq!(
+            "file:///C:/codes/qr.png".parse::<ImageSource>().unwrap(),
+            ImageSource::File(PathBuf::from("C:/codes/qr.png"))
+        );
+        assert!("file://server/share/qr.png".parse::<ImageSource>().is_err());
+    }
+
+    #[test]
+    fn test_parse_data_uri_base64() {
+        let source: ImageSource = "data:image/png;base64,AAEC/w==".parse().unwrap();
+        assert_eq!(source, ImageSource::Data(vec![0x00, 0x01, 0x02, 0xff]));
+
+        // Unpadded, wrapped and with an uppercase scheme
+        let source: ImageSource = "DATA:image/gif;base64,AAE\nC/w".parse().unwrap();
+        assert_eq!(source, ImageSource::Data(vec![0x00, 0x01, 0x02, 0xff]));
+    }
+
+    #[test]
+    fn test_parse_data_uri_percent_encoded() {
+        let source: ImageSource = "data:,P1%0A1%201%0A1".parse().unwrap();
+        assert_eq!(source, ImageSource::Data(b"P1\n1 1\n1".to_vec()));
+    }
+
+    #[test]
+    fn test_parse_data_uri_errors() {
+        assert!(matches!(
+            "data:text/plain;base64,SGk=".parse::<ImageSource>(),
+            Err(RqrError::NotAnImage(_))
+        ));
+        assert!("data:image/png;base64".parse::<ImageSource>().is_err());
+        assert!("data:image/png;base64,***".parse::<ImageSource>().is_err());
+        assert!("data:image/png,%zz".parse::<ImageSource>().is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(ImageSource::File("qr.png".into()).to_string(), "qr.png");
+        assert_eq!(ImageSource::Stdin.to_string(), "stdin");
+        assert_eq!(
+            ImageSource::Data(vec![0; 3]).to_string(),
+            "data URI (3 bytes)"
+        );
+    }
+
+    fn touch_all(dir: &Path, names: &[&str]) {
+        for name in names {
+            let path = dir.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"").unwrap();
+        }
+    }
+
+    fn expand_ok(input: &str, recursive: bool) -> Vec<ImageSource> {
+        ImageSource::expand(input, recursive)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.unwrap())
+            .collect()
+    }
+
+    fn file_names(sources: &[ImageSource], root: &Path) -> Vec<String> {
+        sources
+            .iter()
+            .map(|source| match source {
+                ImageSource::File(path) => path
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/"),
+                other => panic!("unexpected source {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_expand_directory() {
+        let temp = temp_dir();
+        touch_all(
+            temp.path(),
+            &["b.png", "a.JPG", "notes.txt", "nested/c.png"],
+        );
+
+        let flat = expand_ok(temp.path().to_str().unwrap(), false);
+        assert_eq!(file_names(&flat, temp.path()), vec!["a.JPG", "b.png"]);
+
+        let deep = expand_ok(temp.path().to_str().unwrap(), true);
+        assert_eq!(
+            file_names(&deep, temp.path()),
+            vec!["a.JPG", "b.png", "nested/c.png"]
+        );
+    }
+
+    #[test]
+    fn test_collect_images_records_unreadable_directory() {
+        let temp = temp_dir();
+        touch_all(temp.path(), &["a.png"]);
+        let missing = temp.path().join("gone");
+
+        let mut entries = Vec::new();
+        collect_images(temp.path(), true, &mut entries);
+        collect_images(&missing, true, &mut entries);
+
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].1.is_ok());
+        assert_eq!(entries[1].0, missing);
+        assert!(matches!(entries[1].1, Err(RqrError::IoError(_))));
+    }
+
+    #[test]
+    fn test_expand_glob() {
+        let temp = temp_dir();
+        touch_all(temp.path(), &["one.png", "two.png", "three.gif"]);
+
+        let pattern = temp.path().join("*.png");
+        let sources = expand_ok(pattern.to_str().unwrap(), false);
+        assert_eq!(
+            file_names(&sources, temp.path()),
+            vec!["one.png", "two.png"]
+        );
+
+        let pattern = temp.path().join("*.svg");
+        assert!(ImageSource::expand(pattern.to_str().unwrap(), false).is_err());
+    }
+
+    #[test]
+    fn test_expand_single_sources() {
+        assert_eq!(
+            expand_ok("missing.png", false),
+            vec![ImageSource::File("missing.png".into())]
+        );
+        assert_eq!(expand_ok("-", true), vec![ImageSource::Stdin]);
+        assert_eq!(
+            expand_ok("https://example.com/*.png", false),
+            vec![ImageSource::Url("https://example.com/*.png".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_read_data() {
+        let source = ImageSource::Data(vec![1, 2, 3]);
+        assert_eq!(source.read().unwrap(), vec![1, 2, 3]);
+    }
+}
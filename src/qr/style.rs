@@ -0,0 +1,561 @@
+use std::{fmt, str::FromStr};
+
+use qrcode::Color;
+
+use crate::{
+    qr::{
+        encoder::pdf_number,
+        output::RgbaColor,
+        symbol::{Symbol, Symbology},
+    },
+    utils::error::{Result, RqrError},
+};
+
+/// Shape used to draw each dark data module
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModuleShape {
+    /// Plain squares that tile seamlessly
+    #[default]
+    Square,
+    /// Circles slightly smaller than the module
+    Dot,
+    /// Squares with rounded corners, slightly inset
+    Rounded,
+    /// Squares whose exposed corners are rounded so neighbours flow together
+    Liquid,
+}
+
+impl FromStr for ModuleShape {
+    type Err = RqrError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "square" => Ok(ModuleShape::Square),
+            "dot" => Ok(ModuleShape::Dot),
+            "rounded" => Ok(ModuleShape::Rounded),
+            "liquid" => Ok(ModuleShape::Liquid),
+            _ => Err(RqrError::InvalidInput(format!(
+                "Module shape must be square, dot, rounded or liquid, got '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ModuleShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ModuleShape::Square => "square",
+            ModuleShape::Dot => "dot",
+            ModuleShape::Rounded => "rounded",
+            ModuleShape::Liquid => "liquid",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Shape used to draw the finder patterns ("eyes") in the corners
+///
+/// Only the inside of the eye is shaped: the outer edge of the frame keeps
+/// its square corners, which decoders use to locate the symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EyeShape {
+    /// The standard square frame and centre
+    #[default]
+    Square,
+    /// Rounded inner edge and rounded centre
+    Rounded,
+    /// Circular inner edge and circular centre
+    Circle,
+}
+
+impl FromStr for EyeShape {
+    type Err = RqrError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "square" => Ok(EyeShape::Square),
+            "rounded" => Ok(EyeShape::Rounded),
+            "circle" => Ok(EyeShape::Circle),
+            _ => Err(RqrError::InvalidInput(format!(
+                "Eye shape must be square, rounded or circle, got '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for EyeShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            EyeShape::Square => "square",
+            EyeShape::Rounded => "rounded",
+            EyeShape::Circle => "circle",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Visual styling applied on top of the module grid
+///
+/// The default style draws plain square modules and square eyes in the
+/// foreground colour, which matches the unstyled output exactly.
+///
+/// # Examples
+/// ```rust
+/// use rqr::qr::style::{EyeShape, ModuleShape, Style};
+///
+/// let style = Style {
+///     module_shape: ModuleShape::Dot,
+///     eye_shape: EyeShape::Rounded,
+///     eye_color: Some("#e11d48".parse()?),
+/// };
+/// assert!(!style.is_plain());
+/// # Ok::<(), rqr::utils::error::RqrError>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    /// Shape of the data modules
+    pub module_shape: ModuleShape,
+    /// Shape of the three finder patterns
+    pub eye_shape: EyeShape,
+    /// Colour of the finder patterns, the foreground colour when unset
+    pub eye_color: Option<RgbaColor>,
+}
+
+impl Style {
+    /// Whether this is the plain square style
+    pub fn is_plain(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Control point distance that approximates a quarter circle with a cubic Bézier
+const KAPPA: f64 = 0.552_284_75;
+
+/// Path syntax used when converting outlines to vector drawing commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PathSyntax {
+    Svg,
+    Pdf,
+}
+
+/// A rectangle with individually rounded corners, in module units
+///
+/// Radii are given clockwise from the top-left corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Outline {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub radii: [f64; 4],
+}
+
+impl Outline {
+    fn rect(x: f64, y: f64, size: f64, radius: f64) -> Self {
+        Self {
+            x,
+            y,
+            width: size,
+            height: size,
+            radii: [radius; 4],
+        }
+    }
+
+    /// Convert the outline to a closed path, shifted by `(dx, dy)`
+    ///
+    /// Rounded corners become cubic Bézier curves so the same geometry can be
+    /// written to SVG and PDF, which has no arc operator.
+    pub fn to_path(self, dx: f64, dy: f64, syntax: PathSyntax) -> String {
+        let (x, y) = (self.x + dx, self.y + dy);
+        let (right, bottom) = (x + self.width, y + self.height);
+        let [tl, tr, br, bl] = self.radii;
+
+        // Corner point, radius, incoming and outgoing direction, clockwise
+        let corners = [
+            ((right, y), tr, (1.0, 0.0), (0.0, 1.0)),
+            ((right, bottom), br, (0.0, 1.0), (-1.0, 0.0)),
+            ((x, bottom), bl, (-1.0, 0.0), (0.0, -1.0)),
+            ((x, y), tl, (0.0, -1.0), (1.0, 0.0)),
+        ];
+
+        let n = pdf_number;
+        let mut path = match syntax {
+            PathSyntax::Svg => format!("M{} {}", n(x + tl), n(y)),
+            PathSyntax::Pdf => format!("{} {} m\n", n(x + tl), n(y)),
+        };
+        for ((cx, cy), radius, (ix, iy), (ox, oy)) in corners {
+            let entry = (cx - radius * ix, cy - radius * iy);
+            path.push_str(&match syntax {
+                PathSyntax::Svg => format!("L{} {}", n(entry.0), n(entry.1)),
+                PathSyntax::Pdf => format!("{} {} l\n", n(entry.0), n(entry.1)),
+            });
+            if radius <= 0.0 {
+                continue;
+            }
+
+            let exit = (cx + radius * ox, cy + radius * oy);
+            let control = radius * KAPPA;
+            let points = [
+                entry.0 + control * ix,
+                entry.1 + control * iy,
+                exit.0 - control * ox,
+                exit.1 - control * oy,
+                exit.0,
+                exit.1,
+            ]
+            .map(n)
+            .join(" ");
+            path.push_str(&match syntax {
+                PathSyntax::Svg => format!("C{}", points),
+                PathSyntax::Pdf => format!("{} c\n", points),
+            });
+        }
+        path.push_str(match syntax {
+            PathSyntax::Svg => "Z",
+            PathSyntax::Pdf => "h\n",
+        });
+        path
+    }
+
+    /// Whether a point lies inside the outline
+    pub fn contains(&self, px: f64, py: f64) -> bool {
+        if px < self.x || py < self.y || px >= self.x + self.width || py >= self.y + self.height {
+            return false;
+        }
+
+        // Corner centres, clockwise from the top-left
+        let [tl, tr, br, bl] = self.radii;
+        let corners = [
+            (
+                tl,
+                self.x + tl,
+                self.y + tl,
+                px < self.x + tl && py < self.y + tl,
+            ),
+            (
+                tr,
+                self.x + self.width - tr,
+                self.y + tr,
+                px > self.x + self.width - tr && py < self.y + tr,
+            ),
+            (
+                br,
+                self.x + self.width - br,
+                self.y + self.height - br,
+                px > self.x + self.width - br && py > self.y + self.height - br,
+            ),
+            (
+                bl,
+                self.x + bl,
+                self.y + self.height - bl,
+                px < self.x + bl && py > self.y + self.height - bl,
+            ),
+        ];
+
+        corners.iter().all(|&(radius, cx, cy, in_corner)| {
+            !in_corner || radius <= 0.0 || (px - cx).powi(2) + (py - cy).powi(2) <= radius.powi(2)
+        })
+    }
+}
+
+/// A filled group of outlines sharing one colour
+///
+/// Outlines are combined with the even-odd rule, so an outline nested
+/// inside another one punches a hole (used for the eye frames).
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Shape {
+    pub color: RgbaColor,
+    pub outlines: Vec<Outline>,
+}
+
+/// Top-left corners of the finder patterns of a code
+fn finder_origins(qr_code: &Symbol) -> Vec<(usize, usize)> {
+    let width = qr_code.width();
+    match qr_code.symbology() {
+        Symbology::Qr => vec![(0, 0), (width - 7, 0), (0, width - 7)],
+        // The rMQR finder sub pattern is left as plain modules
+        Symbology::Micro | Symbology::Rmqr => vec![(0, 0)],
+        // Data Matrix has an L of solid lines rather than eyes
+        Symbology::DataMatrix => Vec::new(),
+        // The Aztec bullseye sits in the centre, not in the corners
+        Symbology::Aztec => Vec::new(),
+        // PDF417 rows open with start and close with stop patterns
+        Symbology::Pdf417 => Vec::new(),
+        // 1D barcodes are bars and text only
+        Symbology::Code128
+        | Symbology::Ean13
+        | Symbology::UpcA
+        | Symbology::Code39
+        | Symbology::Itf => Vec::new(),
+    }
+}
+
+/// Lay out a styled QR code as shapes in module coordinates
+///
+/// Coordinates start at the top-left corner of the symbol (quiet zone
+/// excluded). The data modules come first, followed by one shape per eye.
+pub(crate) fn layout(qr_code: &Symbol, style: &Style, foreground: RgbaColor) -> Vec<Shape> {
+    let (width, height) = (qr_code.width(), qr_code.height());
+    let colors = qr_code.to_colors();
+    let finders = finder_origins(qr_code);
+
+    let is_dark = |x: isize, y: isize| {
+        x >= 0
+            && y >= 0
+            && (x as usize) < width
+            && (y as usize) < height
+            && colors[y as usize * width + x as usize] == Color::Dark
+    };
+    let in_finder = |x: usize, y: usize| {
+        finders
+            .iter()
+            .any(|&(fx, fy)| x >= fx && x < fx + 7 && y >= fy && y < fy + 7)
+    };
+
+    let mut modules = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            if !is_dark(x as isize, y as isize) || in_finder(x, y) {
+                continue;
+            }
+
+            let (fx, fy) = (x as f64, y as f64);
+            let outline = match style.module_shape {
+                ModuleShape::Square => Outline::rect(fx, fy, 1.0, 0.0),
+                ModuleShape::Dot => Outline::rect(fx + 0.05, fy + 0.05, 0.9, 0.45),
+                ModuleShape::Rounded => Outline::rect(fx + 0.05, fy + 0.05, 0.9, 0.25),
+                ModuleShape::Liquid => {
+                    // Round a corner only when neither neighbour next to it is dark
+                    let (ix, iy) = (x as isize, y as isize);
+                    let up = is_dark(ix, iy - 1);
+                    let down = is_dark(ix, iy + 1);
+                    let left = is_dark(ix - 1, iy);
+                    let right = is_dark(ix + 1, iy);
+                    let radius = |a: bool, b: bool| if a || b { 0.0 } else { 0.5 };
+                    Outline {
+                        x: fx,
+                        y: fy,
+                        width: 1.0,
+                        height: 1.0,
+                        radii: [
+                            radius(up, left),
+                            radius(up, right),
+                            radius(down, right),
+                            radius(down, left),
+                        ],
+                    }
+                }
+            };
+            modules.push(outline);
+        }
+    }
+
+    let mut shapes = vec![Shape {
+        color: foreground,
+        outlines: modules,
+    }];
+
+    // Each eye is a frame (outer minus hole) plus a centre. The outer edge
+    // keeps square corners because decoders locate the eye by them.
+    let [frame_radius, hole_radius, ball_radius] = match style.eye_shape {
+        EyeShape::Square => [0.0, 0.0, 0.0],
+        EyeShape::Rounded => [0.0, 1.5, 1.0],
+        EyeShape::Circle => [0.0, 2.5, 1.5],
+    };
+    for (fx, fy) in finders {
+        let (fx, fy) = (fx as f64, fy as f64);
+        shapes.push(Shape {
+            color: style.eye_color.unwrap_or(foreground),
+            outlines: vec![
+                Outline::rect(fx, fy, 7.0, frame_radius),
+                Outline::rect(fx + 1.0, fy + 1.0, 5.0, hole_radius),
+                Outline::rect(fx + 2.0, fy + 2.0, 3.0, ball_radius),
+            ],
+        });
+    }
+
+    shapes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qr::encoder::QrEncoder;
+
+    fn sample_code() -> Symbol {
+        qrcode::QrCode::new("Style test").unwrap().into()
+    }
+
+    /// Even-odd coverage of a point, as the renderers paint it
+    fn painted(shape: &Shape, x: f64, y: f64) -> bool {
+        shape
+            .outlines
+            .iter()
+            .filter(|outline| outline.contains(x, y))
+            .count()
+            % 2
+            == 1
+    }
+
+    #[test]
+    fn test_module_shape_from_str() {
+        assert_eq!("dot".parse::<ModuleShape>().unwrap(), ModuleShape::Dot);
+        assert_eq!(
+            "LIQUID".parse::<ModuleShape>().unwrap(),
+            ModuleShape::Liquid
+        );
+        assert!("star".parse::<ModuleShape>().is_err());
+
+        for shape in [
+            ModuleShape::Square,
+            ModuleShape::Dot,
+            ModuleShape::Rounded,
+            ModuleShape::Liquid,
+        ] {
+            assert_eq!(shape.to_string().parse::<ModuleShape>().unwrap(), shape);
+        }
+    }
+
+    #[test]
+    fn test_eye_shape_from_str() {
+        assert_eq!("circle".parse::<EyeShape>().unwrap(), EyeShape::Circle);
+        assert!("dot".parse::<EyeShape>().is_err());
+
+        for shape in [EyeShape::Square, EyeShape::Rounded, EyeShape::Circle] {
+            assert_eq!(shape.to_string().parse::<EyeShape>().unwrap(), shape);
+        }
+    }
+
+    #[test]
+    fn test_style_is_plain() {
+        assert!(Style::default().is_plain());
+
+        let style = Style {
+            eye_color: Some(RgbaColor::BLACK),
+            ..Default::default()
+        };
+        assert!(!style.is_plain());
+    }
+
+    #[test]
+    fn test_outline_contains_rounded_corner() {
+        let outline = Outline::rect(0.0, 0.0, 1.0, 0.5);
+        assert!(outline.contains(0.5, 0.5));
+        assert!(!outline.contains(0.02, 0.02));
+        assert!(!outline.contains(1.0, 0.5));
+
+        let square = Outline::rect(0.0, 0.0, 1.0, 0.0);
+        assert!(square.contains(0.02, 0.02));
+    }
+
+    #[test]
+    fn test_shape_even_odd_eye() {
+        let shapes = layout(&sample_code(), &Style::default(), RgbaColor::BLACK);
+        let eye = &shapes[1];
+
+        assert!(painted(eye, 0.5, 0.5)); // frame
+        assert!(!painted(eye, 1.5, 1.5)); // light ring
+        assert!(painted(eye, 3.5, 3.5)); // centre
+    }
+
+    #[test]
+    fn test_layout_matches_plain_modules() {
+        let qr_code = sample_code();
+        let width = qr_code.width();
+        let colors = qr_code.to_colors();
+        let shapes = layout(&qr_code, &Style::default(), RgbaColor::BLACK);
+        assert_eq!(shapes.len(), 4);
+
+        for y in 0..width {
+            for x in 0..width {
+                let (cx, cy) = (x as f64 + 0.5, y as f64 + 0.5);
+                let painted = shapes.iter().any(|shape| painted(shape, cx, cy));
+                assert_eq!(painted, colors[y * width + x] == Color::Dark);
+            }
+        }
+    }
+
+    #[test]
+    fn test_outline_to_path() {
+        let square = Outline::rect(0.0, 0.0, 1.0, 0.0);
+        assert_eq!(
+            square.to_path(2.0, 3.0, PathSyntax::Svg),
+            "M2 3L3 3L3 4L2 4L2 3Z"
+        );
+
+        let dot = Outline::rect(0.0, 0.0, 1.0, 0.5);
+        let svg = dot.to_path(0.0, 0.0, PathSyntax::Svg);
+        assert_eq!(svg.matches('C').count(), 4);
+        let pdf = dot.to_path(0.0, 0.0, PathSyntax::Pdf);
+        assert!(pdf.starts_with("0.5 0 m\n"));
+        assert_eq!(pdf.matches(" c\n").count(), 4);
+        assert!(pdf.ends_with("h\n"));
+    }
+
+    #[test]
+    fn test_layout_rmqr_matches_plain_modules() {
+        let qr_code = QrEncoder::new(600, 2, "M")
+            .unwrap()
+            .with_symbology(Symbology::Rmqr)
+            .encode("Style test")
+            .unwrap();
+        let (width, height) = (qr_code.width(), qr_code.height());
+        let colors = qr_code.to_colors();
+        let shapes = layout(&qr_code, &Style::default(), RgbaColor::BLACK);
+        // Only the finder pattern is drawn as an eye
+        assert_eq!(shapes.len(), 2);
+
+        for y in 0..height {
+            for x in 0..width {
+                let (cx, cy) = (x as f64 + 0.5, y as f64 + 0.5);
+                let painted = shapes.iter().any(|shape| painted(shape, cx, cy));
+                assert_eq!(painted, colors[y * width + x] == Color::Dark);
+            }
+        }
+    }
+
+    #[test]
+    fn test_layout_eye_color() {
+        let red = RgbaColor::new(255, 0, 0, 255);
+        let style = Style {
+            eye_color: Some(red),
+            ..Default::default()
+        };
+        let shapes = layout(&sample_code(), &style, RgbaColor::BLACK);
+
+        assert_eq!(shapes[0].color, RgbaColor::BLACK);
+        assert!(shapes[1..].iter().all(|shape| shape.color == red));
+    }
+
+    #[test]
+    fn test_layout_liquid_rounds_only_exposed_corners() {
+        let style = Style {
+            module_shape: ModuleShape::Liquid,
+            ..Default::default()
+        };
+        let qr_code = sample_code();
+        let width = qr_code.width();
+        let colors = qr_code.to_colors();
+        let dark = |x: f64, y: f64| {
+            x >= 0.0
+                && y >= 0.0
+                && (x as usize) < width
+                && (y as usize) < width
+                && colors[y as usize * width + x as usize] == Color::Dark
+        };
+        let shapes = layout(&qr_code, &style, RgbaColor::BLACK);
+
+        let mut rounded = 0;
+        for outline in &shapes[0].outlines {
+            let (x, y) = (outline.x, outline.y);
+            let exposed = !dark(x, y - 1.0) && !dark(x - 1.0, y);
+            assert_eq!(outline.radii[0] > 0.0, exposed);
+            if exposed {
+                rounded += 1;
+            }
+        }
+        assert!(rounded > 0 && rounded < shapes[0].outlines.len());
+    }
+}
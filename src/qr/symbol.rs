@@ -0,0 +1,786 @@
+use std::{fmt, str::FromStr};
+
+use qrcode::{
+    Color, EcLevel, QrCode, Version,
+    bits::Bits,
+    canvas::{Canvas, MaskPattern},
+    ec,
+};
+use serde::Serialize;
+
+use crate::{
+    qr::{aztec, datamatrix, pdf417, rmqr},
+    utils::error::{Result, RqrError},
+};
+
+/// The eight QR mask patterns, indexed by their mask number
+const QR_MASKS: [MaskPattern; 8] = [
+    MaskPattern::Checkerboard,
+    MaskPattern::HorizontalLines,
+    MaskPattern::VerticalLines,
+    MaskPattern::DiagonalLines,
+    MaskPattern::LargeCheckerboard,
+    MaskPattern::Fields,
+    MaskPattern::Diamonds,
+    MaskPattern::Meadow,
+];
+
+/// The four Micro QR mask patterns, indexed by their mask number
+const MICRO_MASKS: [MaskPattern; 4] = [
+    MaskPattern::HorizontalLines,
+    MaskPattern::LargeCheckerboard,
+    MaskPattern::Diamonds,
+    MaskPattern::Meadow,
+];
+
+/// The family of 2D code or 1D barcode a symbol belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Symbology {
+    /// QR Code, versions 1 to 40
+    #[default]
+    Qr,
+    /// Micro QR Code, versions M1 to M4, with a single finder pattern
+    Micro,
+    /// Rectangular Micro QR Code, R7x43 to R17x139, for long narrow spaces
+    Rmqr,
+    /// Data Matrix ECC 200, 10x10 to 144x144 and rectangles 8x18 to 16x48
+    DataMatrix,
+    /// Aztec Code, compact 15x15 to 27x27 and full-range up to 151x151
+    Aztec,
+    /// PDF417 stacked barcode, 1 to 30 data columns and 3 to 90 rows
+    Pdf417,
+    /// Code 128 1D barcode for any ASCII text
+    Code128,
+    /// EAN-13 1D barcode for 13 digit article numbers
+    Ean13,
+    /// UPC-A 1D barcode for 12 digit product codes
+    UpcA,
+    /// Code 39 1D barcode for digits, upper case letters and a few symbols
+    Code39,
+    /// Interleaved 2 of 5 1D barcode for pairs of digits
+    Itf,
+}
+
+impl FromStr for Symbology {
+    type Err = RqrError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "qr" => Ok(Symbology::Qr),
+            "micro" => Ok(Symbology::Micro),
+            "rmqr" => Ok(Symbology::Rmqr),
+            "datamatrix" => Ok(Symbology::DataMatrix),
+            "aztec" => Ok(Symbology::Aztec),
+            "pdf417" => Ok(Symbology::Pdf417),
+            "code128" => Ok(Symbology::Code128),
+            "ean13" => Ok(Symbology::Ean13),
+            "upca" => Ok(Symbology::UpcA),
+            "code39" => Ok(Symbology::Code39),
+            "itf" => Ok(Symbology::Itf),
+            _ => Err(RqrError::InvalidInput(format!(
+                "Symbology must be qr, micro, rmqr, datamatrix, aztec, pdf417, code128, ean13, \
+                 upca, code39 or itf, got '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Symbology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Symbology::Qr => "qr",
+            Symbology::Micro => "micro",
+            Symbology::Rmqr => "rmqr",
+            Symbology::DataMatrix => "datamatrix",
+            Symbology::Aztec => "aztec",
+            Symbology::Pdf417 => "pdf417",
+            Symbology::Code128 => "code128",
+            Symbology::Ean13 => "ean13",
+            Symbology::UpcA => "upca",
+            Symbology::Code39 => "code39",
+            Symbology::Itf => "itf",
+        };
+        f.write_str(name)
+    }
+}
+
+impl Symbology {
+    /// The name of the symbology as shown to people, e.g. "Data Matrix"
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Symbology::Qr => "QR code",
+            Symbology::Micro => "Micro QR code",
+            Symbology::Rmqr => "rMQR code",
+            Symbology::DataMatrix => "Data Matrix",
+            Symbology::Aztec => "Aztec code",
+            Symbology::Pdf417 => "PDF417",
+            Symbology::Code128 => "Code 128",
+            Symbology::Ean13 => "EAN-13",
+            Symbology::UpcA => "UPC-A",
+            Symbology::Code39 => "Code 39",
+            Symbology::Itf => "ITF",
+        }
+    }
+
+    /// Whether this is a 1D barcode, read across its bars
+    pub fn is_linear(self) -> bool {
+        matches!(
+            self,
+            Symbology::Code128
+                | Symbology::Ean13
+                | Symbology::UpcA
+                | Symbology::Code39
+                | Symbology::Itf
+        )
+    }
+
+    /// The highest version number of the symbology; PDF417 versions count
+    /// data columns and 1D barcodes have none
+    pub fn max_version(self) -> u8 {
+        match self {
+            Symbology::Qr => 40,
+            Symbology::Micro => 4,
+            Symbology::Rmqr => 32,
+            Symbology::DataMatrix => 30,
+            Symbology::Aztec => 36,
+            Symbology::Pdf417 => pdf417::MAX_COLUMNS,
+            _ => 0,
+        }
+    }
+
+    /// Light modules the standard requires around a symbol; Aztec codes,
+    /// found by their central bullseye, need none, while 1D barcodes need
+    /// ten or more at either end
+    pub fn quiet_zone(self) -> usize {
+        match self {
+            Symbology::Qr => 4,
+            Symbology::Micro | Symbology::Rmqr | Symbology::Pdf417 => 2,
+            Symbology::DataMatrix => 1,
+            Symbology::Aztec => 0,
+            Symbology::Ean13 | Symbology::UpcA => 11,
+            Symbology::Code128 | Symbology::Code39 | Symbology::Itf => 10,
+        }
+    }
+
+    /// Margin used when none is given: QR codes get a roomy ten modules,
+    /// the other 2D symbologies, which are picked where space is short,
+    /// only two, and 1D barcodes their quiet zone
+    pub fn default_margin(self) -> u32 {
+        match self {
+            Symbology::Qr => 10,
+            Symbology::Micro
+            | Symbology::Rmqr
+            | Symbology::DataMatrix
+            | Symbology::Aztec
+            | Symbology::Pdf417 => 2,
+            _ => self.quiet_zone() as u32,
+        }
+    }
+
+    /// Whether symbols can be encoded at an error correction level
+    ///
+    /// rMQR only defines levels M and H. Micro QR versions each support a
+    /// different subset, which the encoder checks per version. Data Matrix
+    /// ECC 200 fixes the error correction of each size, so any level is
+    /// accepted and has no effect. Aztec takes each level as a share of
+    /// error correction, from 10% for L to 50% for H, and PDF417 raises its
+    /// recommended security level by one for each level above L. 1D
+    /// barcodes have no error correction and accept any level.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use qrcode::EcLevel;
+    /// use rqr::qr::symbol::Symbology;
+    ///
+    /// assert!(Symbology::Rmqr.supports_error_correction(EcLevel::H));
+    /// assert!(!Symbology::Rmqr.supports_error_correction(EcLevel::Q));
+    /// ```
+    pub fn supports_error_correction(self, ec_level: EcLevel) -> bool {
+        match self {
+            Symbology::Rmqr => matches!(ec_level, EcLevel::M | EcLevel::H),
+            _ => true,
+        }
+    }
+}
+
+/// The size of a symbol, numbered the way its own symbology numbers them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolVersion {
+    /// QR Code version 1 (21x21) to 40 (177x177)
+    Qr(u8),
+    /// Micro QR version M1 to M4
+    Micro(u8),
+    /// rMQR version 1 (R7x43) to 32 (R17x139), flattest first
+    Rmqr(u8),
+    /// Data Matrix size 1 (10x10) to 24 (144x144), or a rectangle from 25
+    /// (8x18) to 30 (16x48)
+    DataMatrix(u8),
+    /// Aztec Code with 1 to 4 layers around a compact bullseye, or 1 to 32
+    /// around a full-range one
+    Aztec { compact: bool, layers: u8 },
+    /// PDF417 with 1 to 30 data columns and 3 to 90 rows
+    Pdf417 { columns: u8, rows: u8 },
+    /// 1D barcodes, which come in a single size
+    Linear,
+}
+
+impl SymbolVersion {
+    /// The version as a single number, as taken by `--version` and
+    /// reported by the decoder: full-range Aztec symbols follow the four
+    /// compact ones, PDF417 symbols count their data columns and 1D
+    /// barcodes are 0
+    pub fn number(self) -> u8 {
+        match self {
+            SymbolVersion::Qr(v)
+            | SymbolVersion::Micro(v)
+            | SymbolVersion::Rmqr(v)
+            | SymbolVersion::DataMatrix(v) => v,
+            SymbolVersion::Aztec {
+                compact: true,
+                layers,
+            } => layers,
+            SymbolVersion::Aztec {
+                compact: false,
+                layers,
+            } => layers + aztec::LAST_COMPACT,
+            SymbolVersion::Pdf417 { columns, .. } => columns,
+            SymbolVersion::Linear => 0,
+        }
+    }
+
+    /// The qrcode version of a QR or Micro QR symbol
+    fn to_qrcode(self) -> Option<Version> {
+        match self {
+            SymbolVersion::Qr(v) => Some(Version::Normal(i16::from(v))),
+            SymbolVersion::Micro(v) => Some(Version::Micro(i16::from(v))),
+            _ => None,
+        }
+    }
+}
+
+impl From<Version> for SymbolVersion {
+    fn from(version: Version) -> Self {
+        match version {
+            Version::Normal(v) => SymbolVersion::Qr(v as u8),
+            Version::Micro(v) => SymbolVersion::Micro(v as u8),
+        }
+    }
+}
+
+/// The version as printed in summaries: `5`, `M3` for Micro QR, `R11x43`
+/// for rMQR, `16x48` for Data Matrix, `19x19 compact` for Aztec, `12x4`
+/// (rows by data columns) for PDF417 or `none` for 1D barcodes
+impl fmt::Display for SymbolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            SymbolVersion::Qr(v) => write!(f, "{}", v),
+            SymbolVersion::Micro(v) => write!(f, "M{}", v),
+            SymbolVersion::Rmqr(v) => f.write_str(&rmqr::label(v)),
+            SymbolVersion::DataMatrix(v) => f.write_str(&datamatrix::label(v)),
+            SymbolVersion::Aztec { .. } => f.write_str(&aztec::label(self.number())),
+            SymbolVersion::Pdf417 { columns, rows } => write!(f, "{}x{}", rows, columns),
+            SymbolVersion::Linear => f.write_str("none"),
+        }
+    }
+}
+
+/// The error correction a symbol carries, in the terms of its symbology
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCorrection {
+    /// QR, Micro QR or rMQR level L, M, Q or H
+    Level(EcLevel),
+    /// Data Matrix ECC 200, whose sizes each fix their error correction
+    Ecc200,
+    /// Aztec error correction, asked for in percent of the data, and the
+    /// data and error correction codewords the symbol was laid out with
+    Aztec {
+        percent: u8,
+        data_words: usize,
+        ec_words: usize,
+    },
+    /// PDF417 security level 0 to 8, adding 2 to 512 error correction
+    /// codewords
+    Pdf417 { security_level: u8 },
+    /// 1D barcodes, whose check characters only catch misreads
+    None,
+}
+
+/// An encoded QR, Micro QR, rMQR, Data Matrix, Aztec or PDF417 symbol, or
+/// a 1D barcode, ready to render
+///
+/// Like `qrcode::QrCode`, but it also records the mask pattern and can be
+/// built with a mask chosen by the caller rather than by penalty score.
+/// rMQR, rectangular Data Matrix and 1D symbols are wider than they are
+/// tall, so renderers must use both `width` and `height`. The modules of a
+/// 1D barcode include its human-readable text beneath the bars.
+///
+/// # Examples
+/// ```rust
+/// use qrcode::{EcLevel, Version, bits::Bits};
+/// use rqr::qr::symbol::Symbol;
+///
+/// let mut bits = Bits::new(Version::Normal(2));
+/// bits.push_optimal_data(b"fixed layout").unwrap();
+/// bits.push_terminator(EcLevel::M).unwrap();
+/// let symbol = Symbol::from_bits(bits, EcLevel::M, Some(5))?;
+/// assert_eq!(symbol.width(), 25);
+/// assert_eq!(symbol.mask(), 5);
+/// # Ok::<(), rqr::utils::error::RqrError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    colors: Vec<Color>,
+    width: usize,
+    height: usize,
+    symbology: Symbology,
+    version: SymbolVersion,
+    error_correction: ErrorCorrection,
+    mask: u8,
+}
+
+impl Symbol {
+    /// Lay out encoded data bits as a symbol
+    ///
+    /// # Arguments
+    /// * `bits` - Data bits with the terminator pushed, sized for their version
+    /// * `ec_level` - Error correction level
+    /// * `mask` - Mask pattern 0-7 (0-3 for Micro QR), or `None` to pick
+    ///   the lowest penalty
+    ///
+    /// # Returns
+    /// Returns the symbol, or an error if the bits do not fit their version
+    /// or the mask is out of range
+    pub fn from_bits(bits: Bits, ec_level: EcLevel, mask: Option<u8>) -> Result<Self> {
+        let version = bits.version();
+        let data = bits.into_bytes();
+        let (encoded, ec_data) = ec::construct_codewords(&data, version, ec_level)
+            .map_err(|e| RqrError::EncodingError(e.to_string()))?;
+
+        let mut canvas = Canvas::new(version, ec_level);
+        canvas.draw_all_functional_patterns();
+        if (version, ec_level) == (Version::Micro(3), EcLevel::L) {
+            // M3-L ends its data in a four-bit codeword, which qrcode only
+            // shortens for M1 and M3-M, so lay the stream out by hand
+            canvas.draw_data(&pack_half_codeword(&encoded, &ec_data), &[]);
+        } else {
+            canvas.draw_data(&encoded, &ec_data);
+        }
+
+        let colors = match mask {
+            Some(mask) => {
+                let (masks, name): (&[MaskPattern], _) = match version {
+                    Version::Normal(_) => (&QR_MASKS, "Mask"),
+                    Version::Micro(_) => (&MICRO_MASKS, "Micro QR mask"),
+                };
+                let pattern = masks.get(usize::from(mask)).ok_or_else(|| {
+                    RqrError::InvalidInput(format!(
+                        "{} must be between 0 and {}, got {}",
+                        name,
+                        masks.len() - 1,
+                        mask
+                    ))
+                })?;
+                canvas.apply_mask(*pattern);
+                canvas.into_colors()
+            }
+            None => canvas.apply_best_mask().into_colors(),
+        };
+        let width = version.width() as usize;
+        // qrcode does not report the mask it picked, so read it back
+        let mask = read_mask(&colors, width, version);
+
+        Ok(Self {
+            colors,
+            width,
+            height: width,
+            symbology: symbology_of(version),
+            version: version.into(),
+            error_correction: ErrorCorrection::Level(ec_level),
+            mask,
+        })
+    }
+
+    /// Wrap modules laid out for a symbology qrcode cannot draw
+    pub(crate) fn from_modules(
+        colors: Vec<Color>,
+        width: usize,
+        height: usize,
+        symbology: Symbology,
+        version: SymbolVersion,
+        error_correction: ErrorCorrection,
+    ) -> Self {
+        Self {
+            colors,
+            width,
+            height,
+            symbology,
+            version,
+            error_correction,
+            mask: 0,
+        }
+    }
+
+    /// Number of modules from left to right
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of modules from top to bottom, which only differs from the
+    /// width for rMQR, rectangular Data Matrix, PDF417 and 1D barcodes
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The symbol version, in the terms of its symbology
+    pub fn version(&self) -> SymbolVersion {
+        self.version
+    }
+
+    /// Whether this is a QR, Micro QR, rMQR, Data Matrix, Aztec or PDF417
+    /// symbol, or which 1D barcode
+    pub fn symbology(&self) -> Symbology {
+        self.symbology
+    }
+
+    /// Light modules the standard requires around the symbol: 4 for QR,
+    /// 2 for Micro QR, rMQR and PDF417, 1 for Data Matrix, none for Aztec,
+    /// 10 or 11 for 1D barcodes
+    pub fn quiet_zone(&self) -> usize {
+        self.symbology().quiet_zone()
+    }
+
+    /// The version as printed in summaries, e.g. `M3` or `R11x43`
+    pub fn version_label(&self) -> String {
+        self.version.to_string()
+    }
+
+    /// The version line of summaries: the version with the size in
+    /// modules and, for QR, Micro QR and rMQR, the mask
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::{encoder::QrEncoder, symbol::Symbology};
+    ///
+    /// let encoder = QrEncoder::new(200, 2, "M")?.with_symbology(Symbology::DataMatrix);
+    /// let symbol = encoder.encode("A1")?;
+    /// assert_eq!(symbol.version_summary(), "10x10 (rows x columns)");
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn version_summary(&self) -> String {
+        let (label, width, height) = (self.version_label(), self.width, self.height);
+        match self.version {
+            // Data Matrix sizes are named rows first
+            SymbolVersion::DataMatrix(_) => format!("{} (rows x columns)", label),
+            SymbolVersion::Aztec { .. } => label,
+            SymbolVersion::Pdf417 { .. } => format!(
+                "{} (rows x data columns, {}x{} modules)",
+                label, width, height
+            ),
+            SymbolVersion::Linear => {
+                format!("none ({}x{} modules, text included)", width, height)
+            }
+            SymbolVersion::Qr(_) | SymbolVersion::Micro(_) | SymbolVersion::Rmqr(_) => format!(
+                "{} ({}x{} modules), mask {}",
+                label, width, height, self.mask
+            ),
+        }
+    }
+
+    /// The error correction line of summaries, e.g. `Q`, `ECC 200`, `23%
+    /// of the data` for Aztec or `security level 3` for PDF417
+    pub fn ec_summary(&self) -> String {
+        match self.error_correction {
+            ErrorCorrection::Level(ec_level) => format!("{:?}", ec_level),
+            ErrorCorrection::Ecc200 => "ECC 200".to_string(),
+            ErrorCorrection::Aztec { percent, .. } => format!("{}% of the data", percent),
+            ErrorCorrection::Pdf417 { security_level } => {
+                format!("security level {}", security_level)
+            }
+            // Check characters only catch misreads, they cannot repair them
+            ErrorCorrection::None => "none".to_string(),
+        }
+    }
+
+    /// The error correction level of a QR, Micro QR or rMQR symbol; `None`
+    /// for the symbologies that have no levels
+    pub fn error_correction_level(&self) -> Option<EcLevel> {
+        match self.error_correction {
+            ErrorCorrection::Level(ec_level) => Some(ec_level),
+            _ => None,
+        }
+    }
+
+    /// The error correction of the symbol, in the terms of its symbology
+    pub fn error_correction(&self) -> ErrorCorrection {
+        self.error_correction
+    }
+
+    /// The mask pattern number, 0-7 (0-3 for Micro QR, always 0 for rMQR,
+    /// which has a single pattern, and for Data Matrix, Aztec, PDF417 and
+    /// 1D barcodes, which have none)
+    pub fn mask(&self) -> u8 {
+        self.mask
+    }
+
+    /// Module colours row by row
+    pub fn to_colors(&self) -> Vec<Color> {
+        self.colors.clone()
+    }
+
+    /// Number of modules that can be wrong before the data is lost, none
+    /// for 1D barcodes
+    pub fn max_allowed_errors(&self) -> usize {
+        match (self.version, self.error_correction) {
+            (_, ErrorCorrection::Aztec { ec_words, .. }) => ec_words / 2,
+            (SymbolVersion::Rmqr(v), ErrorCorrection::Level(ec_level)) => {
+                rmqr::max_allowed_errors(v, ec_level)
+            }
+            (SymbolVersion::DataMatrix(v), _) => datamatrix::max_allowed_errors(v),
+            // Two codewords are kept back to tell a misread symbol from a
+            // correctable one
+            (_, ErrorCorrection::Pdf417 { security_level }) => ((2usize << security_level) - 2) / 2,
+            (version, ErrorCorrection::Level(ec_level)) => version
+                .to_qrcode()
+                .and_then(|version| ec::max_allowed_errors(version, ec_level).ok())
+                .unwrap_or_default(),
+            _ => 0,
+        }
+    }
+}
+
+impl From<QrCode> for Symbol {
+    fn from(qr_code: QrCode) -> Self {
+        let version = qr_code.version();
+        let ec_level = qr_code.error_correction_level();
+        let width = qr_code.width();
+        let colors = qr_code.into_colors();
+        let mask = read_mask(&colors, width, version);
+        Self {
+            colors,
+            width,
+            height: width,
+            symbology: symbology_of(version),
+            version: version.into(),
+            error_correction: ErrorCorrection::Level(ec_level),
+            mask,
+        }
+    }
+}
+
+/// The symbology qrcode's version type stands for
+fn symbology_of(version: Version) -> Symbology {
+    match version {
+        Version::Normal(_) => Symbology::Qr,
+        Version::Micro(_) => Symbology::Micro,
+    }
+}
+
+/// Join the data and error correction codewords of an M3-L symbol into one
+/// bit stream, keeping only the high four bits of the last data codeword
+fn pack_half_codeword(data: &[u8], ec_data: &[u8]) -> Vec<u8> {
+    let (last, full) = data.split_last().unwrap_or((&0, &[]));
+    let bits = full
+        .iter()
+        .flat_map(|&byte| (0..8).rev().map(move |i| (byte >> i) & 1))
+        .chain((4..8).rev().map(|i| (last >> i) & 1))
+        .chain(
+            ec_data
+                .iter()
+                .flat_map(|&byte| (0..8).rev().map(move |i| (byte >> i) & 1)),
+        )
+        .collect::<Vec<_>>();
+    bits.chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .chain(std::iter::repeat(&0))
+                .take(8)
+                .fold(0, |byte, &bit| byte << 1 | bit)
+        })
+        .collect()
+}
+
+/// Read the mask number from the format information beside the top-left
+/// finder pattern
+fn read_mask(colors: &[Color], width: usize, version: Version) -> u8 {
+    let dark = |x: usize, y: usize| colors[y * width + x] == Color::Dark;
+    if let Version::Micro(_) = version {
+        // Most significant bit first: along row 8, then up column 8
+        let cells = (1..=8).map(|x| (x, 8)).chain((1..=7).rev().map(|y| (8, y)));
+        let format = cells.fold(0u16, |bits, (x, y)| bits << 1 | u16::from(dark(x, y))) ^ 0x4445;
+        return ((format >> 10) & 0b11) as u8;
+    }
+    // Most significant bit first: along row 8, then up column 8
+    let cells = (0..=5)
+        .map(|x| (x, 8))
+        .chain([(7, 8), (8, 8), (8, 7)])
+        .chain((0..=5).rev().map(|y| (8, y)));
+    let format = cells.fold(0u16, |bits, (x, y)| bits << 1 | u16::from(dark(x, y))) ^ 0x5412;
+    ((format >> 10) & 0b111) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bits_for(data: &[u8], version: i16, ec_level: EcLevel) -> Bits {
+        let mut bits = Bits::new(Version::Normal(version));
+        bits.push_optimal_data(data).unwrap();
+        bits.push_terminator(ec_level).unwrap();
+        bits
+    }
+
+    #[test]
+    fn test_from_bits_matches_qrcode() {
+        let symbol = Symbol::from_bits(bits_for(b"same", 1, EcLevel::M), EcLevel::M, None).unwrap();
+        let qr_code = QrCode::with_version(b"same", Version::Normal(1), EcLevel::M).unwrap();
+
+        assert_eq!(symbol.to_colors(), qr_code.to_colors());
+        assert_eq!(symbol, Symbol::from(qr_code));
+    }
+
+    #[test]
+    fn test_from_bits_with_each_mask() {
+        let decoder = crate::qr::decoder::QrDecoder::new();
+        let mut layouts = Vec::new();
+        for mask in 0..8 {
+            let symbol =
+                Symbol::from_bits(bits_for(b"mask", 2, EcLevel::Q), EcLevel::Q, Some(mask))
+                    .unwrap();
+            assert_eq!(symbol.mask(), mask);
+
+            let image = crate::qr::encoder::QrEncoder::new(200, 4, "Q")
+                .unwrap()
+                .to_image(&symbol)
+                .unwrap();
+            let decoded = decoder.decode_detailed_from_image(image).unwrap();
+            assert_eq!(decoded[0].mask, mask);
+            assert_eq!(decoded[0].payload, b"mask");
+            layouts.push(symbol.to_colors());
+        }
+        layouts.dedup();
+        assert_eq!(layouts.len(), 8);
+    }
+
+    #[test]
+    fn test_from_bits_rejects_invalid_mask() {
+        let result = Symbol::from_bits(bits_for(b"x", 1, EcLevel::L), EcLevel::L, Some(8));
+        assert!(matches!(result, Err(RqrError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_micro_from_bits_with_each_mask() {
+        let mut layouts = Vec::new();
+        for mask in 0..4 {
+            let mut bits = Bits::new(Version::Micro(2));
+            bits.push_optimal_data(b"0123").unwrap();
+            bits.push_terminator(EcLevel::M).unwrap();
+            let symbol = Symbol::from_bits(bits, EcLevel::M, Some(mask)).unwrap();
+            assert_eq!(symbol.mask(), mask);
+            assert_eq!(symbol.version_label(), "M2");
+            layouts.push(symbol.to_colors());
+        }
+        layouts.dedup();
+        assert_eq!(layouts.len(), 4);
+
+        let mut bits = Bits::new(Version::Micro(2));
+        bits.push_terminator(EcLevel::L).unwrap();
+        let err = Symbol::from_bits(bits, EcLevel::L, Some(4)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid input: Micro QR mask must be between 0 and 3, got 4"
+        );
+    }
+
+    #[test]
+    fn test_summaries() {
+        let symbol = Symbol::from_bits(bits_for(b"x", 1, EcLevel::Q), EcLevel::Q, Some(2)).unwrap();
+        assert_eq!(symbol.ec_summary(), "Q");
+        assert_eq!(symbol.version_summary(), "1 (21x21 modules), mask 2");
+
+        let encoder = || crate::qr::encoder::QrEncoder::new(200, 2, "M").unwrap();
+        let aztec = encoder()
+            .with_symbology(Symbology::Aztec)
+            .encode("Gate 4")
+            .unwrap();
+        assert_eq!(aztec.ec_summary(), "23% of the data");
+        assert_eq!(aztec.version_summary(), "15x15 compact");
+
+        let barcode = encoder()
+            .with_symbology(Symbology::Code39)
+            .encode("A1")
+            .unwrap();
+        assert_eq!(barcode.ec_summary(), "none");
+        assert!(barcode.version_summary().starts_with("none ("));
+    }
+
+    #[test]
+    fn test_pack_half_codeword() {
+        // Only the high nibble of the last data codeword is kept
+        assert_eq!(
+            pack_half_codeword(&[0xab, 0xcd], &[0x12, 0x34]),
+            vec![0xab, 0xc1, 0x23, 0x40]
+        );
+    }
+
+    #[test]
+    fn test_symbology_parse_and_display() {
+        for symbology in [
+            Symbology::Qr,
+            Symbology::Micro,
+            Symbology::Rmqr,
+            Symbology::DataMatrix,
+            Symbology::Aztec,
+            Symbology::Pdf417,
+            Symbology::Code128,
+            Symbology::Ean13,
+            Symbology::UpcA,
+            Symbology::Code39,
+            Symbology::Itf,
+        ] {
+            assert_eq!(
+                symbology.to_string().parse::<Symbology>().unwrap(),
+                symbology
+            );
+        }
+        assert_eq!("MICRO".parse::<Symbology>().unwrap(), Symbology::Micro);
+        assert_eq!("rMQR".parse::<Symbology>().unwrap(), Symbology::Rmqr);
+        assert_eq!(
+            "DataMatrix".parse::<Symbology>().unwrap(),
+            Symbology::DataMatrix
+        );
+        assert_eq!("Aztec".parse::<Symbology>().unwrap(), Symbology::Aztec);
+        assert_eq!("PDF417".parse::<Symbology>().unwrap(), Symbology::Pdf417);
+        assert_eq!("UPCA".parse::<Symbology>().unwrap(), Symbology::UpcA);
+        assert!(Symbology::Itf.is_linear() && !Symbology::Pdf417.is_linear());
+        assert!("maxicode".parse::<Symbology>().is_err());
+        assert_eq!(Symbology::default(), Symbology::Qr);
+        assert_eq!(
+            (Symbology::Qr.quiet_zone(), Symbology::Micro.quiet_zone()),
+            (4, 2)
+        );
+    }
+
+    #[test]
+    fn test_from_qrcode_reads_mask() {
+        for data in ["a", "bb", "Hello, World!", "https://example.com/"] {
+            let qr_code = QrCode::new(data).unwrap();
+            let symbol = Symbol::from(qr_code.clone());
+            let rebuilt = Symbol::from_bits(
+                {
+                    let mut bits = Bits::new(qr_code.version());
+                    bits.push_optimal_data(data.as_bytes()).unwrap();
+                    bits.push_terminator(qr_code.error_correction_level())
+                        .unwrap();
+                    bits
+                },
+                qr_code.error_correction_level(),
+                Some(symbol.mask()),
+            )
+            .unwrap();
+            assert_eq!(rebuilt.to_colors(), qr_code.to_colors());
+        }
+    }
+}
@@ -0,0 +1,573 @@
+use std::{collections::HashMap, fmt, io::Cursor, str::FromStr};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use image::{DynamicImage, ImageFormat, Rgb, RgbImage, Rgba, RgbaImage};
+use qrcode::Color;
+
+use crate::{
+    qr::symbol::Symbol,
+    utils::error::{Result, RqrError},
+};
+
+/// How modules are mapped onto character cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TerminalMode {
+    /// Two modules per cell, stacked with `▀`, `▄` and `█`
+    #[default]
+    Half,
+    /// One module per two cells (`██`), twice as wide and tall as `Half`
+    Full,
+}
+
+impl FromStr for TerminalMode {
+    type Err = RqrError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "half" => Ok(TerminalMode::Half),
+            "full" => Ok(TerminalMode::Full),
+            _ => Err(RqrError::InvalidInput(format!(
+                "Terminal mode must be half or full, got '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for TerminalMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TerminalMode::Half => "half",
+            TerminalMode::Full => "full",
+        };
+        f.write_str(name)
+    }
+}
+
+impl TerminalMode {
+    /// Columns needed to draw a code, quiet zone included
+    ///
+    /// # Arguments
+    /// * `qr_code` - The code to draw
+    pub fn columns(self, qr_code: &Symbol) -> usize {
+        let modules = qr_code.width() + 2 * qr_code.quiet_zone();
+        match self {
+            TerminalMode::Half => modules,
+            TerminalMode::Full => 2 * modules,
+        }
+    }
+
+    /// Pick the mode to draw a code in within the available width
+    ///
+    /// `Full` falls back to the denser `Half` when it is too wide.
+    ///
+    /// # Arguments
+    /// * `qr_code` - The code to draw
+    /// * `available` - Terminal width in columns, unlimited when `None`
+    ///
+    /// # Returns
+    /// Returns the mode to use, or `TerminalTooNarrow` if even `Half` does
+    /// not fit
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::{encoder::QrEncoder, terminal::TerminalMode};
+    ///
+    /// // Version 1 is 21 modules, 29 with the quiet zone
+    /// let qr_code = QrEncoder::new(200, 4, "M")?.encode("hello")?;
+    /// assert_eq!(TerminalMode::Full.fit(&qr_code, Some(40))?, TerminalMode::Half);
+    /// assert_eq!(TerminalMode::Full.fit(&qr_code, None)?, TerminalMode::Full);
+    /// assert!(TerminalMode::Half.fit(&qr_code, Some(20)).is_err());
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn fit(self, qr_code: &Symbol, available: Option<usize>) -> Result<Self> {
+        let Some(available) = available else {
+            return Ok(self);
+        };
+        for mode in [self, TerminalMode::Half] {
+            if mode.columns(qr_code) <= available {
+                return Ok(mode);
+            }
+        }
+        Err(RqrError::TerminalTooNarrow {
+            needed: TerminalMode::Half.columns(qr_code),
+            available,
+        })
+    }
+}
+
+/// Width of the terminal standard output is attached to
+///
+/// Falls back to the `COLUMNS` environment variable when standard output is
+/// not a terminal.
+///
+/// # Returns
+/// Returns the width in columns, or `None` when it cannot be determined
+pub fn terminal_width() -> Option<usize> {
+    if let Some((terminal_size::Width(width), _)) = terminal_size::terminal_size() {
+        return Some(usize::from(width));
+    }
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.trim().parse().ok())
+        .filter(|&columns| columns > 0)
+}
+
+/// Draw a code with block characters, quiet zone included
+///
+/// The quiet zone is the minimum the standard allows: four modules for QR
+/// codes, two for Micro QR.
+///
+/// Dark modules are drawn in the text colour and light ones left blank,
+/// which reads correctly on light backgrounds. `invert` draws the light
+/// modules instead, for dark terminal themes.
+///
+/// # Arguments
+/// * `qr_code` - The code to draw
+/// * `mode` - How modules map onto character cells
+/// * `invert` - Draw light modules instead of dark ones
+///
+/// # Returns
+/// Returns the lines of the drawing joined by newlines, without a trailing
+/// newline
+pub fn render(qr_code: &Symbol, mode: TerminalMode, invert: bool) -> String {
+    let (width, height) = (qr_code.width(), qr_code.height());
+    let quiet_zone = qr_code.quiet_zone();
+    let (columns, rows) = (width + 2 * quiet_zone, height + 2 * quiet_zone);
+    let colors = qr_code.to_colors();
+    // Everything outside the symbol is quiet zone, and therefore light
+    let drawn = |x: usize, y: usize| {
+        let dark = (quiet_zone..quiet_zone + width).contains(&x)
+            && (quiet_zone..quiet_zone + height).contains(&y)
+            && colors[(y - quiet_zone) * width + x - quiet_zone] == Color::Dark;
+        dark != invert
+    };
+
+    let lines: Vec<String> = match mode {
+        TerminalMode::Full => (0..rows)
+            .map(|y| {
+                (0..columns)
+                    .map(|x| if drawn(x, y) { "██" } else { "  " })
+                    .collect()
+            })
+            .collect(),
+        TerminalMode::Half => (0..rows)
+            .step_by(2)
+            .map(|y| {
+                (0..columns)
+                    .map(|x| match (drawn(x, y), drawn(x, y + 1)) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    })
+                    .collect()
+            })
+            .collect(),
+    };
+    lines.join("\n")
+}
+
+/// Escape sequence protocol for drawing a bitmap inline in the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalGraphics {
+    /// DEC Sixel, supported by xterm, foot, WezTerm, mlterm and others
+    Sixel,
+    /// The Kitty graphics protocol, also used by WezTerm and Ghostty
+    Kitty,
+    /// iTerm2 inline images, also used by WezTerm and others
+    Iterm,
+}
+
+impl FromStr for TerminalGraphics {
+    type Err = RqrError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "sixel" => Ok(TerminalGraphics::Sixel),
+            "kitty" => Ok(TerminalGraphics::Kitty),
+            "iterm" => Ok(TerminalGraphics::Iterm),
+            _ => Err(RqrError::InvalidInput(format!(
+                "Terminal graphics must be sixel, kitty or iterm, got '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for TerminalGraphics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TerminalGraphics::Sixel => "sixel",
+            TerminalGraphics::Kitty => "kitty",
+            TerminalGraphics::Iterm => "iterm",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Largest base64 payload the Kitty protocol allows in one escape sequence
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+/// Encode a bitmap as a terminal graphics escape sequence
+///
+/// The image is sent pixel for pixel, so the code on screen is exactly the
+/// `to_image` rendering. Sixel has no transparency, so translucent pixels
+/// are flattened onto white first.
+///
+/// # Arguments
+/// * `image` - The rendered QR code
+/// * `protocol` - The escape sequence protocol to use
+///
+/// # Returns
+/// Returns the escape sequence bytes, or an error if PNG encoding fails
+///
+/// # Examples
+/// ```rust
+/// use rqr::qr::{
+///     encoder::QrEncoder,
+///     terminal::{TerminalGraphics, render_graphics},
+/// };
+///
+/// let encoder = QrEncoder::new(100, 4, "M")?;
+/// let image = encoder.to_image(&encoder.encode("inline")?)?;
+/// let bytes = render_graphics(&image, TerminalGraphics::Kitty)?;
+/// assert!(bytes.starts_with(b"\x1b_Ga=T,f=100"));
+/// # Ok::<(), rqr::utils::error::RqrError>(())
+/// ```
+pub fn render_graphics(image: &DynamicImage, protocol: TerminalGraphics) -> Result<Vec<u8>> {
+    match protocol {
+        TerminalGraphics::Sixel => Ok(sixel(&flatten_to_rgb(image))),
+        TerminalGraphics::Kitty => Ok(kitty(&png_bytes(image)?)),
+        TerminalGraphics::Iterm => Ok(iterm(&png_bytes(image)?)),
+    }
+}
+
+/// Encode an image as PNG in memory
+fn png_bytes(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut png = Cursor::new(Vec::new());
+    image.write_to(&mut png, ImageFormat::Png)?;
+    Ok(png.into_inner())
+}
+
+/// Composite an image onto white and drop its alpha channel
+fn flatten_to_rgb(image: &DynamicImage) -> RgbImage {
+    let mut flattened = RgbaImage::from_pixel(image.width(), image.height(), Rgba([255; 4]));
+    image::imageops::overlay(&mut flattened, &image.to_rgba8(), 0, 0);
+    DynamicImage::ImageRgba8(flattened).to_rgb8()
+}
+
+/// Kitty graphics protocol: PNG data sent in chunks, displayed at the cursor
+fn kitty(png: &[u8]) -> Vec<u8> {
+    let encoded = STANDARD.encode(png);
+    let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(KITTY_CHUNK_SIZE).collect();
+    let mut out = Vec::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        // Only the first chunk carries the keys; q=2 silences the replies
+        if i == 0 {
+            out.extend_from_slice(format!("\x1b_Ga=T,f=100,q=2,m={};", more).as_bytes());
+        } else {
+            out.extend_from_slice(format!("\x1b_Gm={};", more).as_bytes());
+        }
+        out.extend_from_slice(chunk);
+        out.extend_from_slice(b"\x1b\\");
+    }
+    out
+}
+
+/// iTerm2 inline image: the whole PNG in one OSC 1337 sequence
+fn iterm(png: &[u8]) -> Vec<u8> {
+    let mut out = format!(
+        "\x1b]1337;File=inline=1;size={};preserveAspectRatio=1:",
+        png.len()
+    )
+    .into_bytes();
+    out.extend_from_slice(STANDARD.encode(png).as_bytes());
+    out.push(0x07);
+    out
+}
+
+/// DEC Sixel: six-pixel-high bands, one pass per palette colour
+fn sixel(image: &RgbImage) -> Vec<u8> {
+    let (width, height) = image.dimensions();
+    let (palette, indices) = sixel_palette(image);
+
+    let mut out = format!("\x1bP0;1;0q\"1;1;{};{}", width, height).into_bytes();
+    for (i, Rgb([r, g, b])) in palette.iter().enumerate() {
+        // Sixel colour channels are percentages
+        let percent = |c: &u8| (u32::from(*c) * 100 + 127) / 255;
+        out.extend_from_slice(
+            format!("#{};2;{};{};{}", i, percent(r), percent(g), percent(b)).as_bytes(),
+        );
+    }
+
+    for band in (0..height).step_by(6) {
+        let rows = (height - band).min(6);
+        let mut first = true;
+        for color in 0..palette.len() {
+            let sixels: Vec<u8> = (0..width)
+                .map(|x| {
+                    let bits = (0..rows)
+                        .filter(|&dy| indices[((band + dy) * width + x) as usize] == color)
+                        .fold(0, |bits, dy| bits | 1 << dy);
+                    b'?' + bits
+                })
+                .collect();
+            if sixels.iter().all(|&c| c == b'?') {
+                continue;
+            }
+            if !first {
+                out.push(b'$');
+            }
+            first = false;
+            out.extend_from_slice(format!("#{}", color).as_bytes());
+            sixel_run_length(&mut out, &sixels);
+        }
+        out.push(b'-');
+    }
+    out.extend_from_slice(b"\x1b\\");
+    out
+}
+
+/// Build the palette of an image, with each pixel's palette index
+///
+/// QR renderings have a handful of colours; images with more than Sixel's
+/// 256 palette entries (a photo logo) are reduced to a 6x6x6 colour cube.
+fn sixel_palette(image: &RgbImage) -> (Vec<Rgb<u8>>, Vec<usize>) {
+    let mut palette = Vec::new();
+    let mut lookup = HashMap::new();
+    let mut indices = Vec::with_capacity(image.pixels().len());
+    for pixel in image.pixels() {
+        let index = *lookup.entry(*pixel).or_insert_with(|| {
+            palette.push(*pixel);
+            palette.len() - 1
+        });
+        indices.push(index);
+    }
+    if palette.len() <= 256 {
+        return (palette, indices);
+    }
+
+    let level = |c: u8| (usize::from(c) * 5 + 127) / 255;
+    let palette = (0..216)
+        .map(|i| {
+            let value = |l: usize| (l * 51) as u8;
+            Rgb([value(i / 36), value(i / 6 % 6), value(i % 6)])
+        })
+        .collect();
+    let indices = image
+        .pixels()
+        .map(|Rgb([r, g, b])| level(*r) * 36 + level(*g) * 6 + level(*b))
+        .collect();
+    (palette, indices)
+}
+
+/// Append sixel characters, compressing runs with `!<count><char>`
+fn sixel_run_length(out: &mut Vec<u8>, sixels: &[u8]) {
+    for run in sixels.chunk_by(|a, b| a == b) {
+        if run.len() > 3 {
+            out.extend_from_slice(format!("!{}", run.len()).as_bytes());
+            out.push(run[0]);
+        } else {
+            out.extend_from_slice(run);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qr::{encoder::QrEncoder, symbol::Symbology};
+
+    #[test]
+    fn test_terminal_mode_parse_and_display() {
+        for mode in [TerminalMode::Half, TerminalMode::Full] {
+            assert_eq!(mode.to_string().parse::<TerminalMode>().unwrap(), mode);
+        }
+        assert_eq!("FULL".parse::<TerminalMode>().unwrap(), TerminalMode::Full);
+        assert!("ascii".parse::<TerminalMode>().is_err());
+        assert_eq!(TerminalMode::default(), TerminalMode::Half);
+    }
+
+    #[test]
+    fn test_render_full_has_quiet_zone() {
+        let qr_code = Symbol::from(qrcode::QrCode::new(b"quiet").unwrap());
+        let output = render(&qr_code, TerminalMode::Full, false);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines.len(), 21 + 2 * 4);
+        assert!(lines.iter().all(|l| l.chars().count() == 2 * (21 + 2 * 4)));
+        // Quiet zone rows are blank, the first finder row starts after it
+        assert!(lines[..4].iter().all(|l| l.trim().is_empty()));
+        assert!(lines[4].starts_with(&" ".repeat(2 * 4)));
+        assert!(lines[4].contains(&"██".repeat(7)));
+    }
+
+    #[test]
+    fn test_render_micro_has_two_module_quiet_zone() {
+        let qr_code = QrEncoder::new(200, 2, "L")
+            .unwrap()
+            .with_symbology(Symbology::Micro)
+            .encode("1")
+            .unwrap();
+        let output = render(&qr_code, TerminalMode::Full, false);
+        let lines: Vec<&str> = output.lines().collect();
+
+        // M1 is 11 modules wide
+        assert_eq!(lines.len(), 11 + 2 * 2);
+        assert_eq!(TerminalMode::Full.columns(&qr_code), 2 * (11 + 2 * 2));
+        assert!(lines[..2].iter().all(|l| l.trim().is_empty()));
+        assert!(lines[2].starts_with(&format!("    {}", "██".repeat(7))));
+    }
+
+    #[test]
+    fn test_render_rmqr_is_rectangular() {
+        let qr_code = QrEncoder::new(600, 2, "M")
+            .unwrap()
+            .with_symbology(Symbology::Rmqr)
+            .encode("1")
+            .unwrap();
+
+        // R7x43 with its two module quiet zone, two rows per line in half mode
+        let full = render(&qr_code, TerminalMode::Full, false);
+        assert_eq!(full.lines().count(), 7 + 2 * 2);
+        assert!(full.lines().all(|l| l.chars().count() == 2 * (43 + 2 * 2)));
+        let half = render(&qr_code, TerminalMode::Half, false);
+        assert_eq!(half.lines().count(), 6);
+        assert_eq!(TerminalMode::Half.columns(&qr_code), 43 + 2 * 2);
+    }
+
+    #[test]
+    fn test_render_half_is_dense() {
+        let qr_code = Symbol::from(qrcode::QrCode::new(b"dense").unwrap());
+        let output = render(&qr_code, TerminalMode::Half, false);
+        let lines: Vec<&str> = output.lines().collect();
+
+        // 29 module rows pack into 15 lines, the last with a blank lower half
+        assert_eq!(lines.len(), 15);
+        assert!(lines.iter().all(|l| l.chars().count() == 29));
+        assert_eq!(lines[0].trim(), "");
+        assert_eq!(lines[2].chars().nth(4), Some('█'));
+        assert!(lines[14].chars().all(|c| c == ' ' || c == '▀'));
+    }
+
+    #[test]
+    fn test_render_invert_swaps_cells() {
+        let qr_code = Symbol::from(qrcode::QrCode::new(b"invert").unwrap());
+        let normal = render(&qr_code, TerminalMode::Full, false);
+        let inverted = render(&qr_code, TerminalMode::Full, true);
+
+        assert!(inverted.lines().next().unwrap().chars().all(|c| c == '█'));
+        for (a, b) in normal.chars().zip(inverted.chars()) {
+            match a {
+                '\n' => assert_eq!(b, '\n'),
+                ' ' => assert_eq!(b, '█'),
+                _ => assert_eq!(b, ' '),
+            }
+        }
+    }
+
+    #[test]
+    fn test_fit_downgrades_and_fails() {
+        let qr_code = Symbol::from(qrcode::QrCode::new(b"fit").unwrap());
+        assert_eq!(TerminalMode::Full.columns(&qr_code), 58);
+        assert_eq!(
+            TerminalMode::Full.fit(&qr_code, Some(58)).unwrap(),
+            TerminalMode::Full
+        );
+        assert_eq!(
+            TerminalMode::Full.fit(&qr_code, Some(57)).unwrap(),
+            TerminalMode::Half
+        );
+        assert!(matches!(
+            TerminalMode::Half.fit(&qr_code, Some(28)),
+            Err(RqrError::TerminalTooNarrow {
+                needed: 29,
+                available: 28
+            })
+        ));
+    }
+
+    #[test]
+    fn test_terminal_graphics_parse_and_display() {
+        for protocol in [
+            TerminalGraphics::Sixel,
+            TerminalGraphics::Kitty,
+            TerminalGraphics::Iterm,
+        ] {
+            assert_eq!(
+                protocol.to_string().parse::<TerminalGraphics>().unwrap(),
+                protocol
+            );
+        }
+        assert!("png".parse::<TerminalGraphics>().is_err());
+    }
+
+    #[test]
+    fn test_sixel_small_image() {
+        // Two columns, seven rows: black on the left, white on the right,
+        // so the second band holds a single row
+        let image = RgbImage::from_fn(2, 7, |x, _| {
+            if x == 0 {
+                Rgb([0, 0, 0])
+            } else {
+                Rgb([255, 255, 255])
+            }
+        });
+
+        assert_eq!(
+            sixel(&image),
+            b"\x1bP0;1;0q\"1;1;2;7#0;2;0;0;0#1;2;100;100;100#0~?$#1?~-#0@?$#1?@-\x1b\\"
+        );
+    }
+
+    #[test]
+    fn test_sixel_run_length() {
+        let mut out = Vec::new();
+        sixel_run_length(&mut out, b"~~~~~??~");
+        assert_eq!(out, b"!5~??~");
+    }
+
+    #[test]
+    fn test_sixel_palette_reduces_many_colours() {
+        let image = RgbImage::from_fn(32, 32, |x, y| Rgb([(x * 8) as u8, (y * 8) as u8, 7]));
+        let (palette, indices) = sixel_palette(&image);
+
+        assert_eq!(palette.len(), 216);
+        assert!(indices.iter().all(|&i| i < 216));
+        assert_eq!(palette[indices[0]], Rgb([0, 0, 0]));
+    }
+
+    #[test]
+    fn test_kitty_chunks_large_payloads() {
+        let png = vec![0u8; 4000];
+        let out = String::from_utf8(kitty(&png)).unwrap();
+        let chunks: Vec<&str> = out.split_terminator("\x1b\\").collect();
+
+        // 4000 bytes are 5336 base64 characters, two chunks
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].starts_with("\x1b_Ga=T,f=100,q=2,m=1;"));
+        assert!(chunks[1].starts_with("\x1b_Gm=0;"));
+        let payload: String = chunks
+            .iter()
+            .map(|chunk| chunk.split_once(';').unwrap().1)
+            .collect();
+        assert_eq!(STANDARD.decode(payload).unwrap(), png);
+    }
+
+    #[test]
+    fn test_iterm_inline_image_roundtrip() {
+        let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(3, 2, Rgb([10, 20, 30])));
+        let out = render_graphics(&image, TerminalGraphics::Iterm).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let (header, payload) = out.split_once(':').unwrap();
+        assert!(header.starts_with("\x1b]1337;File=inline=1;size="));
+        let png = STANDARD.decode(payload.trim_end_matches('\x07')).unwrap();
+        assert_eq!(
+            image::load_from_memory(&png).unwrap().to_rgb8(),
+            image.to_rgb8()
+        );
+    }
+}
@@ -0,0 +1,482 @@
+use std::{fmt, ops::Range, str::FromStr};
+
+use crate::utils::error::{Result, RqrError};
+
+/// What a redacted password is shown as, whatever its length
+pub const REDACTED: &str = "********";
+
+/// Characters that must be escaped with a backslash in `WIFI:` fields
+const SPECIAL: [char; 5] = ['\\', ';', ',', '"', ':'];
+
+/// The security of a WiFi network
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum WifiAuth {
+    /// WPA or WPA2 Personal
+    #[default]
+    Wpa,
+    /// WPA3 Personal (SAE)
+    Wpa3,
+    /// WEP, only ever read from existing codes
+    Wep,
+    /// An open network without a password
+    Nopass,
+    /// Any other `T:` value, such as `WPA2-EAP`
+    Other(String),
+}
+
+impl WifiAuth {
+    /// The `T:` value written to the payload
+    ///
+    /// WPA3 networks are written as `SAE`, the type Android reads for them.
+    fn code(&self) -> &str {
+        match self {
+            WifiAuth::Wpa => "WPA",
+            WifiAuth::Wpa3 => "SAE",
+            WifiAuth::Wep => "WEP",
+            WifiAuth::Nopass => "nopass",
+            WifiAuth::Other(code) => code,
+        }
+    }
+
+    /// Read a `T:` value, which may be missing for open networks
+    fn from_code(code: &str) -> Self {
+        match code.to_uppercase().as_str() {
+            "WPA" | "WPA2" => WifiAuth::Wpa,
+            "SAE" | "WPA3" => WifiAuth::Wpa3,
+            "WEP" => WifiAuth::Wep,
+            "" | "NOPASS" => WifiAuth::Nopass,
+            _ => WifiAuth::Other(code.to_string()),
+        }
+    }
+}
+
+impl FromStr for WifiAuth {
+    type Err = RqrError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "wpa" => Ok(WifiAuth::Wpa),
+            "wpa3" => Ok(WifiAuth::Wpa3),
+            "nopass" => Ok(WifiAuth::Nopass),
+            _ => Err(RqrError::InvalidInput(format!(
+                "WiFi security must be WPA, WPA3 or nopass, got '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for WifiAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WifiAuth::Wpa3 => f.write_str("WPA3"),
+            auth => f.write_str(auth.code()),
+        }
+    }
+}
+
+/// A WiFi network, as joined by scanning a `WIFI:` code
+///
+/// The payload follows the format phones read:
+/// `WIFI:T:WPA;S:<ssid>;P:<password>;H:true;;`, with `\`, `;`, `,`, `"`
+/// and `:` in the network name and password escaped by a backslash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WifiNetwork {
+    /// The network name
+    pub ssid: String,
+    /// The password, `None` for open networks
+    pub password: Option<String>,
+    /// How the network is secured
+    pub auth: WifiAuth,
+    /// Whether the network does not broadcast its name
+    pub hidden: bool,
+}
+
+impl WifiNetwork {
+    /// Describe a network to encode, checking that phones can join it
+    ///
+    /// # Arguments
+    /// * `ssid` - The network name, 1 to 32 bytes
+    /// * `password` - The password, 8 to 63 characters for WPA (or 64 hex
+    ///   digits), at least 8 for WPA3, none for open networks
+    /// * `auth` - The security, WPA with a password and open without one
+    ///   when unset
+    /// * `hidden` - Whether the network does not broadcast its name
+    ///
+    /// # Returns
+    /// Returns the network, or an error if it could not be joined as given
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::wifi::WifiNetwork;
+    ///
+    /// let network = WifiNetwork::new("Café;Guest", Some("pass:word"), None, false)?;
+    /// assert_eq!(network.payload(), r"WIFI:T:WPA;S:Café\;Guest;P:pass\:word;;");
+    /// # Ok::<(), rqr::utils::error::RqrError>(())
+    /// ```
+    pub fn new(
+        ssid: &str,
+        password: Option<&str>,
+        auth: Option<WifiAuth>,
+        hidden: bool,
+    ) -> Result<Self> {
+        if ssid.is_empty() || ssid.len() > 32 {
+            return Err(RqrError::InvalidInput(format!(
+                "WiFi network names are 1 to 32 bytes, got {}",
+                ssid.len()
+            )));
+        }
+        let auth = auth.unwrap_or(match password {
+            Some(_) => WifiAuth::Wpa,
+            None => WifiAuth::Nopass,
+        });
+        match (&auth, password) {
+            (WifiAuth::Nopass, Some(_)) => {
+                return Err(RqrError::InvalidInput(
+                    "Open (nopass) networks have no password".to_string(),
+                ));
+            }
+            (WifiAuth::Nopass, None) => {}
+            (auth, None) => {
+                return Err(RqrError::InvalidInput(format!(
+                    "{} networks need a password",
+                    auth
+                )));
+            }
+            (WifiAuth::Wpa, Some(password)) => {
+                let passphrase = (8..=63).contains(&password.len())
+                    && password.bytes().all(|b| (b' '..=b'~').contains(&b));
+                let key = password.len() == 64 && password.bytes().all(|b| b.is_ascii_hexdigit());
+                if !passphrase && !key {
+                    return Err(RqrError::InvalidInput(format!(
+                        "WPA passwords are 8 to 63 printable ASCII characters or 64 hex \
+                         digits, got {} characters",
+                        password.chars().count()
+                    )));
+                }
+            }
+            (auth, Some(password)) => {
+                if password.chars().count() < 8 {
+                    return Err(RqrError::InvalidInput(format!(
+                        "{} passwords are at least 8 characters, got {}",
+                        auth,
+                        password.chars().count()
+                    )));
+                }
+            }
+        }
+
+        Ok(Self {
+            ssid: ssid.to_string(),
+            password: password.map(str::to_string),
+            auth,
+            hidden,
+        })
+    }
+
+    /// The text to encode for this network
+    pub fn payload(&self) -> String {
+        let mut payload = format!("WIFI:T:{};S:{};", self.auth.code(), escape(&self.ssid));
+        if let Some(password) = &self.password {
+            payload.push_str(&format!("P:{};", escape(password)));
+        }
+        if self.hidden {
+            payload.push_str("H:true;");
+        }
+        payload.push(';');
+        payload
+    }
+
+    /// Read a `WIFI:` payload
+    ///
+    /// Field names and the `WIFI:` prefix are matched without regard to
+    /// case, unknown fields are skipped and values in double quotes are
+    /// unquoted. A missing `T:` field means an open network.
+    ///
+    /// # Arguments
+    /// * `payload` - Decoded text that may hold a WiFi network
+    ///
+    /// # Returns
+    /// Returns the network, or `None` if the text is not a `WIFI:` payload
+    /// with a network name
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rqr::qr::wifi::{WifiAuth, WifiNetwork};
+    ///
+    /// let network = WifiNetwork::parse(r"WIFI:S:Home\;Net;T:SAE;P:secret123;H:true;;").unwrap();
+    /// assert_eq!(network.ssid, "Home;Net");
+    /// assert_eq!(network.auth, WifiAuth::Wpa3);
+    /// assert!(network.hidden);
+    /// ```
+    pub fn parse(payload: &str) -> Option<Self> {
+        let mut ssid = None;
+        let mut password = None;
+        let mut auth = WifiAuth::Nopass;
+        let mut hidden = false;
+        for (key, value) in fields(payload)? {
+            let value = &payload[value];
+            match key.to_uppercase().as_str() {
+                "S" => ssid = Some(unescape(value)),
+                "P" if !value.is_empty() => password = Some(unescape(value)),
+                "T" => auth = WifiAuth::from_code(&unescape(value)),
+                "H" => hidden = value.eq_ignore_ascii_case("true"),
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            ssid: ssid?,
+            password,
+            auth,
+            hidden,
+        })
+    }
+}
+
+impl fmt::Display for WifiNetwork {
+    /// Describe the network without its password
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "WiFi network \"{}\" ({}", self.ssid, self.auth)?;
+        if self.hidden {
+            f.write_str(", hidden")?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// Hide the password in a `WIFI:` payload
+///
+/// Everything but the password is kept as it was. Other text is returned
+/// unchanged.
+///
+/// # Arguments
+/// * `payload` - Decoded text that may hold a WiFi network
+///
+/// # Returns
+/// Returns the payload with its password replaced by `REDACTED`
+///
+/// # Examples
+/// ```rust
+/// use rqr::qr::wifi::redact;
+///
+/// assert_eq!(redact("WIFI:T:WPA;S:Home;P:hunter22;;"), "WIFI:T:WPA;S:Home;P:********;;");
+/// assert_eq!(redact("Hello"), "Hello");
+/// ```
+pub fn redact(payload: &str) -> String {
+    let password = fields(payload).and_then(|fields| {
+        fields
+            .into_iter()
+            .find(|(key, value)| key.eq_ignore_ascii_case("P") && !value.is_empty())
+    });
+    match password {
+        Some((_, value)) => format!(
+            "{}{}{}",
+            &payload[..value.start],
+            REDACTED,
+            &payload[value.end..]
+        ),
+        None => payload.to_string(),
+    }
+}
+
+/// The names and value ranges of the fields in a `WIFI:` payload
+///
+/// Fields end at unescaped semicolons, and the payload at an empty field.
+fn fields(payload: &str) -> Option<Vec<(&str, Range<usize>)>> {
+    if !payload.get(..5)?.eq_ignore_ascii_case("WIFI:") {
+        return None;
+    }
+
+    let mut fields = Vec::new();
+    let mut start = 5;
+    let mut escaped = false;
+    let mut ended = false;
+    for (i, c) in payload.char_indices().skip(5) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ';' if i == start => {
+                ended = true;
+                break;
+            }
+            ';' => {
+                let (key, _) = payload[start..i].split_once(':')?;
+                fields.push((key, start + key.len() + 1..i));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    // Tolerate a last field without its semicolon
+    if !ended && start < payload.len() {
+        let (key, _) = payload[start..].split_once(':')?;
+        fields.push((key, start + key.len() + 1..payload.len()));
+    }
+    Some(fields)
+}
+
+/// Escape the characters with a meaning in `WIFI:` fields
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if SPECIAL.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Undo `escape`, and remove the double quotes some generators put around
+/// values
+fn unescape(value: &str) -> String {
+    let quoted = value.len() >= 2
+        && value.starts_with('"')
+        && value.ends_with('"')
+        && !value.ends_with("\\\"");
+    let value = if quoted {
+        &value[1..value.len() - 1]
+    } else {
+        value
+    };
+
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_from_str() {
+        assert_eq!("WPA".parse::<WifiAuth>().unwrap(), WifiAuth::Wpa);
+        assert_eq!("wpa3".parse::<WifiAuth>().unwrap(), WifiAuth::Wpa3);
+        assert_eq!("nopass".parse::<WifiAuth>().unwrap(), WifiAuth::Nopass);
+        assert!("WEP".parse::<WifiAuth>().is_err());
+        assert_eq!(WifiAuth::Wpa3.to_string(), "WPA3");
+        assert_eq!(
+            WifiAuth::Other("WPA2-EAP".to_string()).to_string(),
+            "WPA2-EAP"
+        );
+    }
+
+    #[test]
+    fn test_payload_escapes_special_characters() {
+        let network =
+            WifiNetwork::new(r#"My;Net,"1":\"#, Some("p;a,s:s\\w\"d"), None, true).unwrap();
+        assert_eq!(
+            network.payload(),
+            r#"WIFI:T:WPA;S:My\;Net\,\"1\"\:\\;P:p\;a\,s\:s\\w\"d;H:true;;"#
+        );
+
+        let network = WifiNetwork::new("Cafe", None, None, false).unwrap();
+        assert_eq!(network.payload(), "WIFI:T:nopass;S:Cafe;;");
+
+        let network =
+            WifiNetwork::new("Lab", Some("correct horse"), Some(WifiAuth::Wpa3), false).unwrap();
+        assert_eq!(network.payload(), "WIFI:T:SAE;S:Lab;P:correct horse;;");
+    }
+
+    #[test]
+    fn test_new_rejects_unjoinable_networks() {
+        for (ssid, password, auth, message) in [
+            ("", Some("password"), None, "1 to 32 bytes, got 0"),
+            (&"x".repeat(33), Some("password"), None, "got 33"),
+            (
+                "Net",
+                Some("password"),
+                Some(WifiAuth::Nopass),
+                "have no password",
+            ),
+            (
+                "Net",
+                None,
+                Some(WifiAuth::Wpa),
+                "WPA networks need a password",
+            ),
+            ("Net", Some("short"), None, "got 5 characters"),
+            ("Net", Some(&"x".repeat(64)), None, "or 64 hex digits"),
+            (
+                "Net",
+                Some("short"),
+                Some(WifiAuth::Wpa3),
+                "at least 8 characters",
+            ),
+        ] {
+            let error = WifiNetwork::new(ssid, password, auth, false).unwrap_err();
+            assert!(error.to_string().contains(message), "{}", error);
+        }
+
+        // A raw 256-bit key
+        assert!(WifiNetwork::new("Net", Some(&"ab".repeat(32)), None, false).is_ok());
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        for (ssid, password, auth, hidden) in [
+            (
+                r#"My;Net,"1":\"#,
+                Some("p;a,s:s\\w\"d"),
+                WifiAuth::Wpa,
+                true,
+            ),
+            ("Guest", None, WifiAuth::Nopass, false),
+            ("Café 5GHz", Some("correct horse"), WifiAuth::Wpa3, false),
+        ] {
+            let network = WifiNetwork::new(ssid, password, Some(auth.clone()), hidden).unwrap();
+            assert_eq!(WifiNetwork::parse(&network.payload()), Some(network));
+        }
+    }
+
+    #[test]
+    fn test_parse_other_generators() {
+        let network = WifiNetwork::parse(r#"wifi:s:"Home";p:"secret12";t:WEP;x:1"#).unwrap();
+        assert_eq!(network.ssid, "Home");
+        assert_eq!(network.password.as_deref(), Some("secret12"));
+        assert_eq!(network.auth, WifiAuth::Wep);
+
+        let network = WifiNetwork::parse("WIFI:T:WPA2-EAP;S:Corp;E:PEAP;;").unwrap();
+        assert_eq!(network.auth, WifiAuth::Other("WPA2-EAP".to_string()));
+        assert_eq!(network.password, None);
+
+        // Open networks may leave out the type and have an empty password
+        let network = WifiNetwork::parse("WIFI:S:Open;P:;;").unwrap();
+        assert_eq!(network.auth, WifiAuth::Nopass);
+        assert_eq!(network.password, None);
+
+        for text in [
+            "https://example.com",
+            "WIFI:T:WPA;;",
+            "WIFI:T:WPA;junk;;",
+            "WIFI",
+        ] {
+            assert_eq!(WifiNetwork::parse(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_redact() {
+        assert_eq!(
+            redact(r"WIFI:T:WPA;S:Home;P:pa\;ss word;H:true;;"),
+            "WIFI:T:WPA;S:Home;P:********;H:true;;"
+        );
+        assert_eq!(redact("WIFI:S:Home;P:secret12"), "WIFI:S:Home;P:********");
+        assert_eq!(redact("WIFI:T:nopass;S:Home;;"), "WIFI:T:nopass;S:Home;;");
+        assert_eq!(redact("P:secret12;"), "P:secret12;");
+    }
+
+    #[test]
+    fn test_display_leaves_out_password() {
+        let network = WifiNetwork::new("Home", Some("secret123"), None, true).unwrap();
+        assert_eq!(network.to_string(), "WiFi network \"Home\" (WPA, hidden)");
+    }
+}
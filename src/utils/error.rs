@@ -29,6 +29,34 @@ pub enum RqrError {
     /// Requested output format is not supported
     #[error("Output format not supported: {0}")]
     UnsupportedFormat(String),
+
+    /// The server answered with an HTTP error status
+    #[error("HTTP request failed with status {status}: {url}")]
+    HttpStatus { url: String, status: u16 },
+
+    /// The response body is larger than the download limit
+    #[error("Download exceeds the {0} byte limit")]
+    DownloadTooLarge(u64),
+
+    /// The request did not finish within the timeout
+    #[error("Request timed out after {0} seconds")]
+    Timeout(f64),
+
+    /// The server redirected more often than allowed
+    #[error("Too many redirects (limit is {0})")]
+    TooManyRedirects(u32),
+
+    /// The response or data URI holds something other than an image
+    #[error("Expected an image but got '{0}'")]
+    NotAnImage(String),
+
+    /// The connection could not be made or was interrupted
+    #[error("Network error: {0}")]
+    NetworkError(String),
+
+    /// The code is wider than the terminal, even in the densest mode
+    #[error("QR code needs {needed} columns but the terminal is {available} wide")]
+    TerminalTooNarrow { needed: usize, available: usize },
 }
 
 /// Type alias for Results used throughout the rqr tool
@@ -64,6 +92,46 @@ mod tests {
         assert_eq!(error.to_string(), "Output format not supported: gif");
     }
 
+    #[test]
+    fn test_network_error_display() {
+        let error = RqrError::HttpStatus {
+            url: "http://example.com/qr.png".to_string(),
+            status: 404,
+        };
+        assert_eq!(
+            error.to_string(),
+            "HTTP request failed with status 404: http://example.com/qr.png"
+        );
+        assert_eq!(
+            RqrError::DownloadTooLarge(1024).to_string(),
+            "Download exceeds the 1024 byte limit"
+        );
+        assert_eq!(
+            RqrError::Timeout(0.5).to_string(),
+            "Request timed out after 0.5 seconds"
+        );
+        assert_eq!(
+            RqrError::TooManyRedirects(5).to_string(),
+            "Too many redirects (limit is 5)"
+        );
+        assert_eq!(
+            RqrError::NotAnImage("text/html".to_string()).to_string(),
+            "Expected an image but got 'text/html'"
+        );
+    }
+
+    #[test]
+    fn test_terminal_too_narrow_display() {
+        let error = RqrError::TerminalTooNarrow {
+            needed: 29,
+            available: 20,
+        };
+        assert_eq!(
+            error.to_string(),
+            "QR code needs 29 columns but the terminal is 20 wide"
+        );
+    }
+
     #[test]
     fn test_io_error_conversion() {
         let io_err = std::io::Error::new(std::io::ErrorKind::NotFound, "file not found");
@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use ureq::{Agent, Error as UreqError, http::header::CONTENT_TYPE};
+
+use crate::utils::error::{Result, RqrError};
+
+/// Default limit on the size of a downloaded image, 20 MiB
+pub const DEFAULT_MAX_SIZE: u64 = 20 * 1024 * 1024;
+
+/// Default time allowed for the whole download
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default number of redirects followed before giving up
+pub const DEFAULT_MAX_REDIRECTS: u32 = 5;
+
+/// In-process HTTP client for downloading images to decode
+///
+/// Downloads are bounded in size, time and redirects, and only responses
+/// that declare an image content type (or none at all, or the generic
+/// `application/octet-stream`) are accepted, so an HTML error page is
+/// reported as such instead of as an undecodable image.
+///
+/// # Examples
+/// ```rust,no_run
+/// use std::time::Duration;
+///
+/// use rqr::utils::http::HttpFetcher;
+///
+/// let fetcher = HttpFetcher::new()
+///     .with_timeout(Duration::from_secs(10))
+///     .with_max_size(1024 * 1024);
+/// let bytes = fetcher.fetch_image("https://example.com/qr.png")?;
+/// # Ok::<(), rqr::utils::error::RqrError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct HttpFetcher {
+    max_size: u64,
+    timeout: Duration,
+    max_redirects: u32,
+}
+
+impl Default for HttpFetcher {
+    fn default() -> Self {
+        Self {
+            max_size: DEFAULT_MAX_SIZE,
+            timeout: DEFAULT_TIMEOUT,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+        }
+    }
+}
+
+impl HttpFetcher {
+    /// Create a fetcher with the default limits
+    ///
+    /// # Returns
+    /// Returns an `HttpFetcher` allowing 20 MiB, 30 seconds and 5 redirects
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the largest response body accepted, in bytes
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Set the time allowed for the whole request, redirects included
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set how many redirects are followed
+    pub fn with_max_redirects(mut self, max_redirects: u32) -> Self {
+        self.max_redirects = max_redirects;
+        self
+    }
+
+    /// Download an image
+    ///
+    /// # Arguments
+    /// * `url` - The `http://` or `https://` URL of the image
+    ///
+    /// # Returns
+    /// Returns the response body, or an error naming the limit or check that
+    /// failed
+    pub fn fetch_image(&self, url: &str) -> Result<Vec<u8>> {
+        let agent: Agent = Agent::config_builder()
+            .timeout_global(Some(self.timeout))
+            .max_redirects(self.max_redirects)
+            .http_status_as_error(false)
+            .build()
+            .into();
+
+        let mut response = agent.get(url).call().map_err(|e| self.map_error(url, e))?;
+
+        let status = response.status().as_u16();
+        if status >= 400 {
+            return Err(RqrError::HttpStatus {
+                url: url.to_string(),
+                status,
+            });
+        }
+
+        if let Some(content_type) = response.headers().get(CONTENT_TYPE) {
+            let content_type = content_type.to_str().unwrap_or_default();
+            let mime = content_type
+                .split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_lowercase();
+            if !mime.starts_with("image/") && mime != "application/octet-stream" {
+                return Err(RqrError::NotAnImage(content_type.to_string()));
+            }
+        }
+
+        response
+            .body_mut()
+            .with_config()
+            .limit(self.max_size)
+            .read_to_vec()
+            .map_err(|e| self.map_error(url, e))
+    }
+
+    /// Translate a ureq error into the matching `RqrError`
+    fn map_error(&self, url: &str, error: UreqError) -> RqrError {
+        match error {
+            UreqError::Timeout(_) => RqrError::Timeout(self.timeout.as_secs_f64()),
+            UreqError::TooManyRedirects => RqrError::TooManyRedirects(self.max_redirects),
+            UreqError::BodyExceedsLimit(limit) => RqrError::DownloadTooLarge(limit),
+            UreqError::StatusCode(status) => RqrError::HttpStatus {
+                url: url.to_string(),
+                status,
+            },
+            // ureq reports a timeout while reading the body as an I/O error
+            UreqError::Io(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+                RqrError::Timeout(self.timeout.as_secs_f64())
+            }
+            e => RqrError::NetworkError(format!("{}: {}", url, e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fetch_image_connection_refused() {
+        let err = HttpFetcher::new()
+            .fetch_image("http://127.0.0.1:1/qr.png")
+            .unwrap_err();
+        assert!(matches!(err, RqrError::NetworkError(_)));
+    }
+}
@@ -3,6 +3,7 @@
 //! Utility modules including error handling and common types.
 
 pub mod error;
+pub mod http;
 
 /// Test utilities module
 ///
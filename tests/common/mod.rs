@@ -3,9 +3,20 @@
 //! This module provides common helper functions and fixtures used across
 //! the test suite to reduce code duplication and ensure consistency.
 
-use std::path::Path;
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener},
+    path::Path,
+    thread,
+    time::Duration,
+};
 
-use image::DynamicImage;
+use hayro::{
+    PixmapSettings, RenderCache, RenderSettings, hayro_interpret::InterpreterSettings,
+    hayro_syntax::Pdf, vello_cpu::color::palette::css::WHITE,
+};
+use image::{DynamicImage, RgbaImage};
+use resvg::{tiny_skia, usvg};
 use rqr::qr::encoder::QrEncoder;
 use tempfile::TempDir;
 
@@ -76,6 +87,63 @@ pub fn create_qr_image_file_with_params(
         .expect("Failed to save QR image");
 }
 
+/// Rasterizes an SVG document into an image buffer
+///
+/// # Arguments
+/// * `svg` - The SVG document source
+///
+/// # Returns
+/// A `DynamicImage` with the SVG's own width and height
+///
+/// # Panics
+/// Panics if the SVG cannot be parsed or rendered
+pub fn rasterize_svg(svg: &str) -> DynamicImage {
+    let tree = usvg::Tree::from_str(svg, &usvg::Options::default()).expect("Failed to parse SVG");
+    let size = tree.size().to_int_size();
+    let mut pixmap =
+        tiny_skia::Pixmap::new(size.width(), size.height()).expect("Failed to create pixmap");
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+
+    let buffer = RgbaImage::from_raw(size.width(), size.height(), pixmap.take())
+        .expect("Failed to convert pixmap");
+    DynamicImage::ImageRgba8(buffer)
+}
+
+/// Rasterizes a PDF document with hayro, a complete PDF renderer
+///
+/// The first page is rendered onto white, scaled to `width` pixels.
+///
+/// # Returns
+/// A `DynamicImage` of the page, `width` pixels wide
+///
+/// # Panics
+/// Panics if the document cannot be loaded or has no pages
+pub fn rasterize_pdf(pdf: &[u8], width: u32) -> DynamicImage {
+    let pdf = Pdf::new(pdf.to_vec()).expect("Failed to load PDF");
+    let page = pdf.pages().first().expect("PDF has no pages");
+    let scale = width as f32 / page.render_dimensions().0;
+    let pixmap = hayro::render(
+        page,
+        &RenderCache::new(),
+        &InterpreterSettings::default(),
+        &RenderSettings::default(),
+        &PixmapSettings {
+            x_scale: scale,
+            y_scale: scale,
+            bg_color: WHITE,
+        },
+    );
+
+    // The page is opaque, so premultiplied pixels are plain RGBA
+    let buffer = RgbaImage::from_raw(
+        u32::from(pixmap.width()),
+        u32::from(pixmap.height()),
+        pixmap.data_as_u8_slice().to_vec(),
+    )
+    .expect("Failed to convert pixmap");
+    DynamicImage::ImageRgba8(buffer)
+}
+
 /// Returns the binary command for CLI testing
 ///
 /// # Returns
@@ -85,6 +153,39 @@ pub fn cmd() -> assert_cmd::Command {
     assert_cmd::Command::cargo_bin("rqr").expect("Failed to find rqr binary")
 }
 
+/// Compares output with a stored snapshot in `tests/snapshots`
+///
+/// Set `RQR_UPDATE_SNAPSHOTS=1` to write the current output as the new
+/// snapshot instead.
+///
+/// # Arguments
+/// * `name` - File name of the snapshot
+/// * `actual` - The bytes produced by the test
+pub fn assert_snapshot(name: &str, actual: &[u8]) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(name);
+    if std::env::var_os("RQR_UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(path.parent().expect("Snapshot has a directory"))
+            .expect("Failed to create snapshot directory");
+        std::fs::write(&path, actual).expect("Failed to write snapshot");
+        return;
+    }
+    let expected = std::fs::read(&path).unwrap_or_else(|e| {
+        panic!(
+            "Missing snapshot {} ({}), run with RQR_UPDATE_SNAPSHOTS=1 to create it",
+            path.display(),
+            e
+        )
+    });
+    assert!(
+        actual == expected.as_slice(),
+        "Output differs from snapshot {}, run with RQR_UPDATE_SNAPSHOTS=1 to accept it",
+        path.display()
+    );
+}
+
 /// Common test content constants
 pub mod fixtures {
     /// Unicode content with multiple scripts
@@ -106,6 +207,115 @@ pub mod fixtures {
     pub const EMPTY: &str = "";
 }
 
+/// A canned response served by `serve_http`
+pub struct HttpRoute {
+    path: String,
+    status: &'static str,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+    delay: Duration,
+}
+
+impl HttpRoute {
+    /// A `200 OK` response with the given content type and body
+    pub fn ok(path: &str, content_type: &str, body: &[u8]) -> Self {
+        Self {
+            path: path.to_string(),
+            status: "200 OK",
+            headers: vec![("Content-Type", content_type.to_string())],
+            body: body.to_vec(),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// A `302 Found` response pointing at `location`
+    pub fn redirect(path: &str, location: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            status: "302 Found",
+            headers: vec![("Location", location.to_string())],
+            body: Vec::new(),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// Wait before sending the response
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+/// A local HTTP server started by `serve_http`
+pub struct TestServer {
+    addr: SocketAddr,
+}
+
+impl TestServer {
+    /// The URL of `path` on this server
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+}
+
+/// Serves the given routes on a local port until the test process exits
+///
+/// Unknown paths get a `404 Not Found`. Each connection handles a single
+/// request and is then closed.
+///
+/// # Panics
+/// Panics if no local port can be bound
+pub fn serve_http(routes: Vec<HttpRoute>) -> TestServer {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind test server");
+    let addr = listener
+        .local_addr()
+        .expect("Failed to read server address");
+    let routes = std::sync::Arc::new(routes);
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let routes = routes.clone();
+            thread::spawn(move || {
+                let mut reader = BufReader::new(&stream);
+                let mut request_line = String::new();
+                if reader.read_line(&mut request_line).is_err() {
+                    return;
+                }
+                // Skip the headers
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+                    line.clear();
+                }
+
+                let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+                let not_found = HttpRoute {
+                    path: path.to_string(),
+                    status: "404 Not Found",
+                    headers: vec![("Content-Type", "text/plain".to_string())],
+                    body: b"not found".to_vec(),
+                    delay: Duration::ZERO,
+                };
+                let route = routes.iter().find(|r| r.path == path).unwrap_or(&not_found);
+
+                thread::sleep(route.delay);
+                let mut response = format!("HTTP/1.1 {}\r\n", route.status);
+                for (name, value) in &route.headers {
+                    response.push_str(&format!("{}: {}\r\n", name, value));
+                }
+                response.push_str(&format!(
+                    "Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    route.body.len()
+                ));
+                let mut stream = &stream;
+                let _ = stream.write_all(response.as_bytes());
+                let _ = stream.write_all(&route.body);
+            });
+        }
+    });
+
+    TestServer { addr }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
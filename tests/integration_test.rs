@@ -2,13 +2,26 @@
 //!
 //! Tests the complete encode-decode workflow and CLI functionality.
 
-use std::fs;
+use std::{fs, time::Duration};
 
+use base64::Engine;
 use predicates::prelude::*;
+use rqr::{
+    qr::{
+        decoder::QrDecoder,
+        encoder::QrEncoder,
+        style::{EyeShape, ModuleShape, Style},
+        symbol::Symbology,
+    },
+    utils::{error::RqrError, http::HttpFetcher},
+};
 
 mod common;
 
-use common::{cmd, create_qr_image_file, fixtures, temp_dir};
+use common::{
+    HttpRoute, assert_snapshot, cmd, create_qr_image_file, fixtures, rasterize_pdf, rasterize_svg,
+    serve_http, temp_dir,
+};
 
 #[test]
 fn should_display_encode_help_successfully() {
@@ -76,409 +89,1360 @@ fn should_complete_encode_decode_roundtrip() {
 }
 
 #[test]
-fn should_encode_with_custom_size() {
+fn should_complete_svg_encode_decode_roundtrip() {
     let temp_dir = temp_dir();
-    let output_path = temp_dir.path().join("custom_size.png");
+    let output_path = temp_dir.path().join("roundtrip.svg");
 
-    let mut command = cmd();
-    command
+    let mut encode_cmd = cmd();
+    encode_cmd
         .arg("encode")
-        .arg("Size test")
+        .arg("Hello, SVG!")
         .arg("-o")
         .arg(&output_path)
         .arg("-s")
-        .arg("400");
+        .arg("300")
+        .arg("-m")
+        .arg("4");
+    encode_cmd.assert().success();
 
-    command.assert().success();
-    assert!(output_path.exists());
+    let svg = fs::read_to_string(&output_path).unwrap();
+    assert!(svg.contains("width=\"300\" height=\"300\""));
 
-    // Verify image size
-    let img = image::open(&output_path).unwrap();
-    assert_eq!(img.width(), img.height());
+    let image = rasterize_svg(&svg);
+    let results = QrDecoder::new().decode_from_image(image).unwrap();
+    assert_eq!(results, vec!["Hello, SVG!".to_string()]);
 }
 
 #[test]
-fn should_encode_with_all_options() {
+fn should_complete_pdf_encode_decode_roundtrip() {
     let temp_dir = temp_dir();
-    let output_path = temp_dir.path().join("all_options.png");
+    let output_path = temp_dir.path().join("label.pdf");
+
+    let mut encode_cmd = cmd();
+    encode_cmd
+        .arg("encode")
+        .arg("Hello, PDF!")
+        .arg("-o")
+        .arg(&output_path)
+        .arg("-m")
+        .arg("4")
+        .arg("--physical-size")
+        .arg("25mm");
+    encode_cmd
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Size: 25mm"));
+
+    // 25mm for the 21 modules of the symbol, 29 with the margin
+    let pdf = fs::read(&output_path).unwrap();
+    assert!(String::from_utf8_lossy(&pdf).contains("/MediaBox [0 0 97.862767 97.862767]"));
+
+    let results = QrDecoder::new()
+        .decode_from_image(rasterize_pdf(&pdf, 400))
+        .unwrap();
+    assert_eq!(results, vec!["Hello, PDF!".to_string()]);
+}
+
+#[test]
+fn should_rasterize_svg_output_at_any_margin() {
+    for margin in [0, 4, 10] {
+        let encoder = QrEncoder::new(300, margin, "M").unwrap();
+        let qr_code = encoder.encode("SVG roundtrip").unwrap();
+
+        let image = rasterize_svg(&encoder.to_svg_string(&qr_code));
+        assert_eq!((image.width(), image.height()), (300, 300));
+
+        let results = QrDecoder::new().decode_from_image(image).unwrap();
+        assert_eq!(results, vec!["SVG roundtrip".to_string()]);
+    }
+}
+
+#[test]
+fn should_rasterize_pdf_output_at_any_margin() {
+    for margin in [0, 4, 10] {
+        let encoder = QrEncoder::new(200, margin, "M").unwrap();
+        let qr_code = encoder.encode("PDF roundtrip").unwrap();
+
+        let width = (qr_code.width() as u32 + margin * 2) * 8;
+        let image = rasterize_pdf(&encoder.to_pdf_bytes(&qr_code), width);
+        assert_eq!((image.width(), image.height()), (width, width));
+
+        let results = QrDecoder::new().decode_from_image(image).unwrap();
+        assert_eq!(results, vec!["PDF roundtrip".to_string()]);
+    }
+}
+
+/// Every combination of module and eye shape that differs from plain squares
+fn styled() -> Vec<Style> {
+    let mut styles = Vec::new();
+    for module_shape in [
+        ModuleShape::Square,
+        ModuleShape::Dot,
+        ModuleShape::Rounded,
+        ModuleShape::Liquid,
+    ] {
+        for eye_shape in [EyeShape::Square, EyeShape::Rounded, EyeShape::Circle] {
+            styles.push(Style {
+                module_shape,
+                eye_shape,
+                eye_color: None,
+            });
+        }
+    }
+    styles.retain(|style| !style.is_plain());
+    styles
+}
+
+#[test]
+fn should_decode_styled_vector_output() {
+    for style in styled() {
+        let encoder = QrEncoder::new(400, 4, "M").unwrap().with_style(style);
+        let qr_code = encoder.encode("Styled vector").unwrap();
+
+        let svg = encoder.to_svg_string(&qr_code);
+        assert!(svg.contains("fill-rule=\"evenodd\""));
+        assert!(!svg.contains("crispEdges"));
+
+        let pdf = encoder.to_pdf_bytes(&qr_code);
+        for image in [rasterize_svg(&svg), rasterize_pdf(&pdf, 400)] {
+            let results = QrDecoder::new().decode_from_image(image).unwrap();
+            assert_eq!(results, vec!["Styled vector".to_string()], "{:?}", style);
+        }
+    }
+}
+
+#[test]
+fn should_decode_rectangular_rmqr_vector_output() {
+    let encoder = QrEncoder::new(470, 2, "M")
+        .unwrap()
+        .with_symbology(Symbology::Rmqr)
+        .with_physical_size("47mm".parse().unwrap());
+    let symbol = encoder.encode("1").unwrap();
+
+    let image = rasterize_svg(&encoder.to_svg_string(&symbol));
+    assert_eq!((image.width(), image.height()), (470, 110));
+    let pdf = rasterize_pdf(&encoder.to_pdf_bytes(&symbol), 470);
+    assert_eq!((pdf.width(), pdf.height()), (470, 110));
+
+    for image in [image, pdf] {
+        let decoded = QrDecoder::new().decode_detailed_from_image(image).unwrap();
+        assert_eq!(decoded[0].symbology, Symbology::Rmqr);
+    }
+}
+
+#[test]
+fn should_fail_with_invalid_physical_size() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("label.pdf");
 
     let mut command = cmd();
     command
         .arg("encode")
-        .arg("All options test")
+        .arg("Test")
+        .arg("-o")
+        .arg(&output_path)
+        .arg("--physical-size")
+        .arg("25px");
+
+    command.assert().failure();
+    assert!(!output_path.exists());
+}
+
+#[test]
+fn should_encode_with_custom_size() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("custom_size.png");
+
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("Size test")
         .arg("-o")
         .arg(&output_path)
         .arg("-s")
-        .arg("300")
-        .arg("-e")
-        .arg("H")
-        .arg("-m")
-        .arg("15");
+        .arg("400");
 
     command.assert().success();
     assert!(output_path.exists());
+
+    // Verify image size
+    let img = image::open(&output_path).unwrap();
+    assert_eq!(img.width(), img.height());
 }
 
 #[test]
-fn should_encode_all_error_correction_levels() {
+fn should_report_real_image_dimensions() {
     let temp_dir = temp_dir();
 
-    for level in ["L", "M", "Q", "H"] {
-        let output_path = temp_dir.path().join(format!("ec_{}.png", level));
+    for fit in ["pad", "scale"] {
+        let output_path = temp_dir.path().join(format!("{}.png", fit));
 
         let mut command = cmd();
         command
             .arg("encode")
-            .arg("Error correction test")
+            .arg("Fit test")
             .arg("-o")
             .arg(&output_path)
-            .arg("-e")
-            .arg(level);
+            .arg("--fit")
+            .arg(fit);
 
-        command.assert().success();
-        assert!(output_path.exists(), "Failed for level {}", level);
+        command
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Size: 200x200 pixels"));
+
+        let img = image::open(&output_path).unwrap();
+        assert_eq!((img.width(), img.height()), (200, 200));
     }
 }
 
 #[test]
-fn should_output_to_terminal() {
+fn should_fail_when_size_is_not_a_module_multiple() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("exact.png");
+
     let mut command = cmd();
-    command.arg("encode").arg("Terminal output test").arg("-t");
+    command
+        .arg("encode")
+        .arg("Fit test")
+        .arg("-o")
+        .arg(&output_path)
+        .arg("--fit")
+        .arg("exact-modules");
+
+    command
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not a multiple of 41 modules"));
+
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("Fit test")
+        .arg("-o")
+        .arg(&output_path)
+        .arg("--fit")
+        .arg("exact-modules")
+        .arg("-s")
+        .arg("205");
 
     command
         .assert()
         .success()
-        .stdout(predicate::str::contains("Terminal output test"));
+        .stdout(predicate::str::contains("Size: 205x205 pixels"));
 }
 
 #[test]
-fn should_fail_with_invalid_error_correction() {
+fn should_complete_raster_format_roundtrips() {
     let temp_dir = temp_dir();
-    let output_path = temp_dir.path().join("invalid.png");
 
-    let mut command = cmd();
-    command
+    for ext in ["jpg", "webp", "bmp", "gif", "tiff", "ppm"] {
+        let output_path = temp_dir.path().join(format!("roundtrip.{}", ext));
+        let content = format!("Format {}", ext);
+
+        let mut encode_cmd = cmd();
+        encode_cmd
+            .arg("encode")
+            .arg(&content)
+            .arg("-o")
+            .arg(&output_path);
+        encode_cmd.assert().success();
+
+        let mut decode_cmd = cmd();
+        decode_cmd.arg("decode").arg(&output_path);
+        decode_cmd
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(content));
+    }
+}
+
+#[test]
+fn should_warn_when_jpeg_artifacts_break_decoding() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("blurry.jpg");
+
+    let mut encode_cmd = cmd();
+    encode_cmd
         .arg("encode")
-        .arg("Test")
+        .arg("https://example.com/some/long/path?x=12345")
         .arg("-o")
         .arg(&output_path)
-        .arg("-e")
-        .arg("X");
-
-    command.assert().failure();
+        .arg("-s")
+        .arg("100")
+        .arg("-m")
+        .arg("1")
+        .arg("--quality")
+        .arg("1");
+    encode_cmd
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Quality: 1"))
+        .stderr(predicate::str::contains(
+            "JPEG artefacts at quality 1 with 3 pixel modules",
+        ));
 }
 
 #[test]
-fn should_fail_when_file_not_found() {
-    let mut command = cmd();
-    command.arg("decode").arg("/nonexistent/path/file.png");
+fn should_warn_when_quality_is_ignored() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("lossless.png");
 
-    command.assert().failure();
+    let mut encode_cmd = cmd();
+    encode_cmd
+        .arg("encode")
+        .arg("Lossless")
+        .arg("-o")
+        .arg(&output_path)
+        .arg("--quality")
+        .arg("50");
+    encode_cmd
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("--quality only applies to JPEG"));
 }
 
 #[test]
-fn should_fail_with_invalid_image_file() {
+fn should_encode_content_from_stdin() {
     let temp_dir = temp_dir();
-    let invalid_file = temp_dir.path().join("not_an_image.txt");
-    fs::write(&invalid_file, "This is not an image").unwrap();
+    let output_path = temp_dir.path().join("stdin.png");
+    let config = "[Interface]\nAddress = 10.0.0.2/32\n";
 
-    let mut command = cmd();
-    command.arg("decode").arg(&invalid_file);
+    let mut encode_cmd = cmd();
+    encode_cmd
+        .arg("encode")
+        .arg("-")
+        .arg("-o")
+        .arg(&output_path)
+        .write_stdin(config);
+    encode_cmd.assert().success();
 
-    command.assert().failure();
+    let decoder = QrDecoder::new();
+    let results = decoder.decode_from_file(&output_path).unwrap();
+    assert_eq!(
+        results,
+        vec!["[Interface]\nAddress = 10.0.0.2/32".to_string()]
+    );
 }
 
 #[test]
-fn should_encode_unicode_content() {
+fn should_encode_content_from_input_file_keeping_newline() {
     let temp_dir = temp_dir();
-    let output_path = temp_dir.path().join("unicode.png");
+    let input_path = temp_dir.path().join("payload.json");
+    let output_path = temp_dir.path().join("file.png");
+    std::fs::write(&input_path, "{\"id\": 42}\n").unwrap();
 
-    // Encode
     let mut encode_cmd = cmd();
     encode_cmd
         .arg("encode")
-        .arg(fixtures::UNICODE_CONTENT)
+        .arg("--input-file")
+        .arg(&input_path)
+        .arg("--keep-newline")
         .arg("-o")
         .arg(&output_path);
     encode_cmd.assert().success();
 
-    // Decode and verify
-    let mut decode_cmd = cmd();
-    decode_cmd.arg("decode").arg(&output_path);
-    decode_cmd
-        .assert()
-        .success()
-        .stdout(predicate::str::contains(fixtures::UNICODE_CONTENT));
+    let decoder = QrDecoder::new();
+    let results = decoder.decode_from_file(&output_path).unwrap();
+    assert_eq!(results, vec!["{\"id\": 42}\n".to_string()]);
 }
 
 #[test]
-fn should_encode_url_content() {
+fn should_encode_binary_file_exactly() {
     let temp_dir = temp_dir();
-    let output_path = temp_dir.path().join("url.png");
+    let input_path = temp_dir.path().join("key.bin");
+    let output_path = temp_dir.path().join("binary.png");
+    let data: Vec<u8> = (0..=255).rev().collect();
+    std::fs::write(&input_path, &data).unwrap();
 
-    // Encode
     let mut encode_cmd = cmd();
     encode_cmd
         .arg("encode")
-        .arg(fixtures::URL_CONTENT)
+        .arg("--binary")
+        .arg("--input-file")
+        .arg(&input_path)
+        .arg("-s")
+        .arg("400")
         .arg("-o")
         .arg(&output_path);
-    encode_cmd.assert().success();
-
-    // Decode and verify
-    let mut decode_cmd = cmd();
-    decode_cmd.arg("decode").arg(&output_path);
-    decode_cmd
+    encode_cmd
         .assert()
         .success()
-        .stdout(predicate::str::contains(fixtures::URL_CONTENT));
+        .stdout(predicate::str::contains(
+            "Content: 256 bytes of binary data",
+        ));
+
+    let decoder = QrDecoder::new();
+    let payloads = decoder.decode_bytes_from_file(&output_path).unwrap();
+    assert_eq!(payloads, vec![data]);
 }
 
 #[test]
-fn should_encode_long_content() {
+fn should_keep_trailing_newline_in_binary_mode() {
     let temp_dir = temp_dir();
-    let output_path = temp_dir.path().join("long.png");
-
-    let long_content = "a".repeat(500);
+    let output_path = temp_dir.path().join("binary_stdin.png");
 
-    let mut command = cmd();
-    command
+    let mut encode_cmd = cmd();
+    encode_cmd
         .arg("encode")
-        .arg(&long_content)
+        .arg("-")
+        .arg("--binary")
         .arg("-o")
         .arg(&output_path)
-        .arg("-s")
-        .arg("400");
+        .write_stdin(vec![0xde, 0xad, 0xbe, 0xef, b'\n']);
+    encode_cmd.assert().success();
 
-    command.assert().success();
-    assert!(output_path.exists());
+    let decoder = QrDecoder::new();
+    let payloads = decoder.decode_bytes_from_file(&output_path).unwrap();
+    assert_eq!(payloads, vec![vec![0xde, 0xad, 0xbe, 0xef, b'\n']]);
 }
 
 #[test]
-fn should_encode_special_characters() {
+fn should_reject_non_utf8_input_without_binary() {
+    let mut encode_cmd = cmd();
+    encode_cmd
+        .arg("encode")
+        .arg("-")
+        .arg("-t")
+        .write_stdin(vec![0xff, 0xfe]);
+    encode_cmd
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("use --binary"));
+}
+
+#[test]
+fn should_fail_with_content_and_input_file() {
+    let mut encode_cmd = cmd();
+    encode_cmd
+        .arg("encode")
+        .arg("Hello")
+        .arg("--input-file")
+        .arg("payload.txt");
+    encode_cmd
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn should_encode_with_custom_colors() {
     let temp_dir = temp_dir();
-    let output_path = temp_dir.path().join("special.png");
+    let output_path = temp_dir.path().join("colors.png");
 
-    // Encode
     let mut encode_cmd = cmd();
     encode_cmd
         .arg("encode")
-        .arg(fixtures::SPECIAL_CHARS)
+        .arg("Colorful")
         .arg("-o")
-        .arg(&output_path);
+        .arg(&output_path)
+        .arg("--fg")
+        .arg("#1e3a8a")
+        .arg("--bg")
+        .arg("transparent");
     encode_cmd.assert().success();
 
-    // Decode and verify
+    let img = image::open(&output_path).unwrap();
+    assert!(img.color().has_alpha());
+
     let mut decode_cmd = cmd();
     decode_cmd.arg("decode").arg(&output_path);
     decode_cmd
         .assert()
         .success()
-        .stdout(predicate::str::contains(fixtures::SPECIAL_CHARS));
+        .stdout(predicate::str::contains("Colorful"));
 }
 
 #[test]
-fn should_encode_empty_content() {
+fn should_encode_styled_svg() {
     let temp_dir = temp_dir();
-    let output_path = temp_dir.path().join("empty.png");
+    let output_path = temp_dir.path().join("styled.svg");
 
-    let mut command = cmd();
-    command.arg("encode").arg("").arg("-o").arg(&output_path);
+    let mut encode_cmd = cmd();
+    encode_cmd
+        .arg("encode")
+        .arg("Styled")
+        .arg("-o")
+        .arg(&output_path)
+        .arg("-s")
+        .arg("400")
+        .arg("--module-shape")
+        .arg("dot")
+        .arg("--eye-shape")
+        .arg("rounded")
+        .arg("--eye-color")
+        .arg("#be123c");
+    encode_cmd
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Style: dot modules, rounded eyes in #be123cff",
+        ));
+
+    let svg = std::fs::read_to_string(&output_path).unwrap();
+    let results = QrDecoder::new()
+        .decode_from_image(rasterize_svg(&svg))
+        .unwrap();
+    assert_eq!(results, vec!["Styled".to_string()]);
+}
 
-    command.assert().success();
-    assert!(output_path.exists());
+#[test]
+fn should_fail_with_invalid_module_shape() {
+    let mut encode_cmd = cmd();
+    encode_cmd
+        .arg("encode")
+        .arg("Styled")
+        .arg("--module-shape")
+        .arg("star");
+    encode_cmd
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Module shape must be"));
 }
 
 #[test]
-fn should_complete_multiple_encode_decode_cycles() {
+fn should_warn_about_low_contrast_colors() {
     let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("low_contrast.png");
 
-    for i in 0..5 {
-        let output_path = temp_dir.path().join(format!("cycle_{}.png", i));
-        let content = format!("Cycle number {}", i);
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("Low contrast")
+        .arg("-o")
+        .arg(&output_path)
+        .arg("--fg")
+        .arg("#999999")
+        .arg("--bg")
+        .arg("#bbbbbb");
 
-        // Encode
-        let mut encode_cmd = cmd();
-        encode_cmd
-            .arg("encode")
-            .arg(&content)
-            .arg("-o")
-            .arg(&output_path);
-        encode_cmd.assert().success();
+    command
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("low contrast"));
+}
 
-        // Decode
-        let mut decode_cmd = cmd();
-        decode_cmd.arg("decode").arg(&output_path);
-        decode_cmd
-            .assert()
-            .success()
-            .stdout(predicate::str::contains(&content));
-    }
+#[test]
+fn should_refuse_unreadable_colors() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("unreadable.png");
+
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("Unreadable")
+        .arg("-o")
+        .arg(&output_path)
+        .arg("--fg")
+        .arg("#eeeeee");
+
+    command
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unreadable"));
+    assert!(!output_path.exists());
 }
 
 #[test]
-fn should_encode_different_margins() {
+fn should_encode_with_logo_and_raise_error_correction() {
     let temp_dir = temp_dir();
+    let logo_path = temp_dir.path().join("logo.png");
+    let output_path = temp_dir.path().join("branded.png");
+    image::RgbaImage::from_pixel(48, 48, image::Rgba([200, 30, 30, 255]))
+        .save(&logo_path)
+        .unwrap();
 
-    for margin in [0, 5, 10, 20] {
-        let output_path = temp_dir.path().join(format!("margin_{}.png", margin));
+    let mut encode_cmd = cmd();
+    encode_cmd
+        .arg("encode")
+        .arg(fixtures::URL_CONTENT)
+        .arg("-o")
+        .arg(&output_path)
+        .arg("-s")
+        .arg("400")
+        .arg("-m")
+        .arg("4")
+        .arg("--logo")
+        .arg(&logo_path)
+        .arg("--logo-scale")
+        .arg("0.3");
+    encode_cmd
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(raised from M for the logo)"));
+
+    let mut decode_cmd = cmd();
+    decode_cmd.arg("decode").arg(&output_path);
+    decode_cmd
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(fixtures::URL_CONTENT));
+}
+
+#[test]
+fn should_fail_with_oversized_logo() {
+    let temp_dir = temp_dir();
+    let logo_path = temp_dir.path().join("logo.png");
+    let output_path = temp_dir.path().join("branded.png");
+    image::RgbaImage::new(48, 48).save(&logo_path).unwrap();
+
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("Logo")
+        .arg("-o")
+        .arg(&output_path)
+        .arg("--logo")
+        .arg(&logo_path)
+        .arg("--logo-scale")
+        .arg("0.6");
+
+    command
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("exceeds the safe maximum"));
+    assert!(!output_path.exists());
+}
+
+#[test]
+fn should_encode_with_all_options() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("all_options.png");
+
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("All options test")
+        .arg("-o")
+        .arg(&output_path)
+        .arg("-s")
+        .arg("300")
+        .arg("-e")
+        .arg("H")
+        .arg("-m")
+        .arg("15");
+
+    command.assert().success();
+    assert!(output_path.exists());
+}
+
+#[test]
+fn should_encode_all_error_correction_levels() {
+    let temp_dir = temp_dir();
+
+    for level in ["L", "M", "Q", "H"] {
+        let output_path = temp_dir.path().join(format!("ec_{}.png", level));
 
         let mut command = cmd();
         command
             .arg("encode")
-            .arg("Margin test")
+            .arg("Error correction test")
             .arg("-o")
             .arg(&output_path)
-            .arg("-m")
-            .arg(margin.to_string());
+            .arg("-e")
+            .arg(level);
 
         command.assert().success();
-        assert!(output_path.exists(), "Failed with margin {}", margin);
+        assert!(output_path.exists(), "Failed for level {}", level);
     }
 }
 
 #[test]
-fn should_fail_when_no_qr_code_in_image() {
+fn should_encode_with_fixed_version_and_mask() {
     let temp_dir = temp_dir();
-    let blank_image = temp_dir.path().join("blank.png");
-
-    // Create a blank image
-    let img = image::ImageBuffer::from_pixel(100, 100, image::Luma([255u8]));
-    img.save(&blank_image).unwrap();
-
-    let mut command = cmd();
-    command.arg("decode").arg(&blank_image);
+    let decoder = QrDecoder::new();
+
+    // Codes in a series keep the same dimensions whatever their content
+    for (i, content) in ["A1", "A much longer serial number 0000123456"]
+        .iter()
+        .enumerate()
+    {
+        let output_path = temp_dir.path().join(format!("fixed-{}.png", i));
+        let mut command = cmd();
+        command
+            .arg("encode")
+            .arg(content)
+            .arg("--version")
+            .arg("4")
+            .arg("--mask")
+            .arg("6")
+            .arg("-o")
+            .arg(&output_path);
+        command.assert().success().stdout(predicate::str::contains(
+            "Version: 4 (33x33 modules), mask 6",
+        ));
 
-    command.assert().failure();
+        let decoded = decoder.decode_detailed_from_file(&output_path).unwrap();
+        assert_eq!((decoded[0].version, decoded[0].mask), (4, 6));
+        assert_eq!(decoded[0].text.as_deref(), Some(*content));
+    }
 }
 
 #[test]
-fn should_overwrite_existing_file() {
+fn should_encode_with_min_version() {
     let temp_dir = temp_dir();
-    let output_path = temp_dir.path().join("overwrite.png");
+    let output_path = temp_dir.path().join("min.png");
 
-    // First encode
-    let mut command1 = cmd();
-    command1
+    let mut command = cmd();
+    command
         .arg("encode")
-        .arg("First content")
+        .arg("short")
+        .arg("--min-version")
+        .arg("7")
         .arg("-o")
         .arg(&output_path);
-    command1.assert().success();
-
-    let first_modified = fs::metadata(&output_path).unwrap().modified().unwrap();
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Version: 7 (45x45 modules)"));
+}
 
-    // Second encode (overwrite)
-    std::thread::sleep(std::time::Duration::from_millis(100));
+#[test]
+fn should_fail_when_content_exceeds_fixed_version() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("overflow.png");
 
-    let mut command2 = cmd();
-    command2
+    let mut command = cmd();
+    command
         .arg("encode")
-        .arg("Second content")
+        .arg("x".repeat(100))
+        .arg("--version")
+        .arg("2")
         .arg("-o")
         .arg(&output_path);
-    command2.assert().success();
-
-    let second_modified = fs::metadata(&output_path).unwrap().modified().unwrap();
-
-    // File should have been modified
-    assert!(second_modified >= first_modified);
-
-    // Verify new content
-    let mut decode_cmd = cmd();
-    decode_cmd.arg("decode").arg(&output_path);
-    decode_cmd
+    command
         .assert()
-        .success()
-        .stdout(predicate::str::contains("Second content"));
+        .failure()
+        .stderr(predicate::str::contains("does not fit in version 2"));
+    assert!(!output_path.exists());
 }
 
 #[test]
-fn should_fail_when_missing_subcommand() {
+fn should_fail_with_invalid_version_or_mask() {
+    for args in [["--version", "41"], ["--min-version", "0"], ["--mask", "8"]] {
+        let mut command = cmd();
+        command.arg("encode").arg("Test").args(args).arg("-t");
+        command.assert().failure();
+    }
+
     let mut command = cmd();
-    command.assert().failure();
+    command
+        .arg("encode")
+        .arg("Test")
+        .args(["--version", "3", "--min-version", "2"]);
+    command
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
 }
 
 #[test]
-fn should_encode_with_relative_path() {
+fn should_encode_and_decode_micro_qr() {
     let temp_dir = temp_dir();
-    let original_dir = std::env::current_dir().unwrap();
-
-    // Change to temp directory
-    std::env::set_current_dir(&temp_dir).unwrap();
+    let output_path = temp_dir.path().join("micro.png");
 
     let mut command = cmd();
     command
         .arg("encode")
-        .arg("Relative path test")
+        .arg("PART-0042")
+        .arg("--symbology")
+        .arg("micro")
         .arg("-o")
-        .arg("relative.png");
-    command.assert().success();
-
-    assert!(temp_dir.path().join("relative.png").exists());
+        .arg(&output_path);
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Version: M3 (15x15 modules)"))
+        .stdout(predicate::str::contains("Margin: 2 modules"));
 
-    // Restore original directory
-    std::env::set_current_dir(original_dir).unwrap();
+    let mut command = cmd();
+    command
+        .arg("decode")
+        .arg(&output_path)
+        .arg("--format")
+        .arg("json");
+    let output = command.assert().success().get_output().stdout.clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json[0]["symbology"], "micro");
+    assert_eq!(json[0]["version"], 3);
+    assert_eq!(json[0]["payload"], "PART-0042");
 }
 
 #[test]
-fn should_decode_with_relative_path() {
-    let temp_dir = temp_dir();
-    let original_dir = std::env::current_dir().unwrap();
-
-    // Create QR code
-    let output_path = temp_dir.path().join("for_decode.png");
-    create_qr_image_file(&output_path, "Decode relative");
+fn should_draw_micro_qr_in_terminal() {
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("12345")
+        .arg("--symbology")
+        .arg("micro")
+        .arg("-e")
+        .arg("L")
+        .arg("-t")
+        .arg("--terminal-mode")
+        .arg("full");
+    let output = command.assert().success().get_output().stdout.clone();
+    let output = String::from_utf8(output).unwrap();
+
+    // M1 is 11 modules, drawn with a two module quiet zone on each side
+    let lines: Vec<&str> = output.lines().take_while(|l| !l.is_empty()).collect();
+    assert_eq!(lines.len(), 15);
+    assert!(lines.iter().all(|l| l.chars().count() == 30));
+    assert!(lines[2].starts_with(&format!("    {}", "██".repeat(7))));
+}
 
-    // Change to temp directory
-    std::env::set_current_dir(&temp_dir).unwrap();
+#[test]
+fn should_reject_unsupported_micro_qr_settings() {
+    for args in [["-e", "H"], ["--version", "5"], ["--mask", "4"]] {
+        let mut command = cmd();
+        command
+            .arg("encode")
+            .arg("1")
+            .args(["--symbology", "micro"])
+            .args(args)
+            .arg("-t");
+        command
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Micro QR"));
+    }
 
-    // Decode using relative path
-    let mut decode_cmd = cmd();
-    decode_cmd.arg("decode").arg("for_decode.png");
-    decode_cmd
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("x".repeat(40))
+        .args(["--symbology", "micro", "-t"]);
+    command
         .assert()
-        .success()
-        .stdout(predicate::str::contains("Decode relative"));
-
-    // Restore original directory
-    std::env::set_current_dir(original_dir).unwrap();
+        .failure()
+        .stderr(predicate::str::contains("does not fit in versions M1-M4"));
 }
 
 #[test]
-fn should_encode_multiline_content() {
+fn should_encode_and_decode_rmqr() {
     let temp_dir = temp_dir();
-    let output_path = temp_dir.path().join("multiline.png");
+    let output_path = temp_dir.path().join("rmqr.png");
 
     let mut command = cmd();
     command
         .arg("encode")
-        .arg(fixtures::MULTILINE_CONTENT)
+        .arg("CABLE-0042")
+        .arg("--symbology")
+        .arg("rmqr")
+        .arg("--size")
+        .arg("630")
         .arg("-o")
         .arg(&output_path);
-    command.assert().success();
-
-    // Decode and verify
-    let mut decode_cmd = cmd();
-    decode_cmd.arg("decode").arg(&output_path);
-    decode_cmd
+    command
         .assert()
         .success()
-        .stdout(predicate::str::contains("Line 1"));
+        .stdout(predicate::str::contains("Size: 630x110 pixels"))
+        .stdout(predicate::str::contains("Version: R7x59 (59x7 modules)"))
+        .stdout(predicate::str::contains("Margin: 2 modules"));
+
+    let mut command = cmd();
+    command
+        .arg("decode")
+        .arg(&output_path)
+        .arg("--format")
+        .arg("json");
+    let output = command.assert().success().get_output().stdout.clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json[0]["symbology"], "rmqr");
+    assert_eq!(json[0]["version"], 2);
+    assert_eq!(json[0]["payload"], "CABLE-0042");
 }
 
 #[test]
-fn should_encode_content_with_tabs() {
+fn should_encode_rmqr_as_svg_and_pdf() {
     let temp_dir = temp_dir();
-    let output_path = temp_dir.path().join("tabs.png");
 
+    let svg_path = temp_dir.path().join("rmqr.svg");
     let mut command = cmd();
     command
         .arg("encode")
-        .arg(fixtures::TAB_CONTENT)
-        .arg("-o")
-        .arg(&output_path);
-    command.assert().success();
+        .arg("1")
+        .args(["--symbology", "rmqr", "--size", "470", "-o"])
+        .arg(&svg_path);
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Size: 470x110 pixels"));
+    let svg = std::fs::read_to_string(&svg_path).unwrap();
+    assert!(svg.contains("viewBox=\"0 0 47 11\""));
+
+    let pdf_path = temp_dir.path().join("rmqr.pdf");
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("1")
+        .args(["--symbology", "rmqr", "--physical-size", "47mm", "-o"])
+        .arg(&pdf_path);
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Size: 47mm wide"));
+}
+
+#[test]
+fn should_reject_unsupported_rmqr_settings() {
+    for args in [["-e", "Q"], ["--version", "33"], ["--mask", "0"]] {
+        let mut command = cmd();
+        command
+            .arg("encode")
+            .arg("1")
+            .args(["--symbology", "rmqr"])
+            .args(args)
+            .arg("-t");
+        command
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("rMQR"));
+    }
+
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("x".repeat(400))
+        .args(["--symbology", "rmqr", "-t"]);
+    command.assert().failure().stderr(predicate::str::contains(
+        "does not fit in versions R7x43-R17x139",
+    ));
+}
+
+#[test]
+fn should_encode_and_decode_datamatrix() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("datamatrix.png");
+
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("PART-0042")
+        .args(["--symbology", "datamatrix", "-o"])
+        .arg(&output_path);
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Error correction: ECC 200"))
+        .stdout(predicate::str::contains("Version: 14x14 (rows x columns)"))
+        .stdout(predicate::str::contains("Margin: 2 modules"));
+
+    let mut command = cmd();
+    command
+        .arg("decode")
+        .arg(&output_path)
+        .arg("--format")
+        .arg("json");
+    let output = command.assert().success().get_output().stdout.clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json[0]["symbology"], "datamatrix");
+    assert_eq!(json[0]["version"], 3);
+    assert_eq!(json[0]["error_correction"], "ECC200");
+    assert_eq!(json[0]["payload"], "PART-0042");
+}
+
+#[test]
+fn should_encode_rectangular_datamatrix_as_pdf() {
+    let temp_dir = temp_dir();
+    let pdf_path = temp_dir.path().join("datamatrix.pdf");
+
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("12345678")
+        .args(["--symbology", "datamatrix", "--min-version", "25"])
+        .args(["--physical-size", "22mm", "-o"])
+        .arg(&pdf_path);
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Data Matrix saved to:"))
+        .stdout(predicate::str::contains("Size: 22mm wide"))
+        .stdout(predicate::str::contains("Version: 8x18 (rows x columns)"));
+}
+
+#[test]
+fn should_batch_encode_gs1_datamatrix() {
+    let temp_dir = temp_dir();
+    let items = temp_dir.path().join("parts.csv");
+    fs::write(&items, "gtin,lot\n09521234543213,ABC123\n").unwrap();
+
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("--batch")
+        .arg(&items)
+        .args(["--content", "(01){gtin}(10){lot}(21)42"])
+        .args(["--symbology", "datamatrix", "--gs1", "--output"])
+        .arg(temp_dir.path().join("{lot}.png"));
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Encoded 1 of 1 rows"));
+
+    // FNC1 separates the variable length lot number from the serial
+    let mut command = cmd();
+    command
+        .arg("decode")
+        .arg(temp_dir.path().join("ABC123.png"))
+        .args(["--format", "raw"]);
+    command
+        .assert()
+        .success()
+        .stdout(predicate::eq(&b"010952123454321310ABC123\x1d2142"[..]));
+}
+
+#[test]
+fn should_reject_unsupported_datamatrix_settings() {
+    for args in [&["--version", "31"][..], &["--mask", "0"], &["--gs1"]] {
+        let mut command = cmd();
+        command
+            .arg("encode")
+            .arg("1")
+            .args(["--symbology", "datamatrix"])
+            .args(args)
+            .arg("-t");
+        command
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Data Matrix").or(predicate::str::contains("GS1")));
+    }
+
+    let mut command = cmd();
+    command.arg("encode").arg("(01)1").args(["--gs1", "-t"]);
+    command
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("only supported for Data Matrix"));
+}
+
+#[test]
+fn should_encode_and_decode_aztec() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("ticket.png");
+
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("TKT 0421 SEAT 12C")
+        .args(["--symbology", "aztec", "--ec-percent", "60", "-o"])
+        .arg(&output_path);
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Error correction: 60% of the data",
+        ))
+        .stdout(predicate::str::contains("Version: 19x19 compact"))
+        .stdout(predicate::str::contains("Margin: 2 modules"));
+
+    let mut command = cmd();
+    command
+        .arg("decode")
+        .arg(&output_path)
+        .arg("--format")
+        .arg("json");
+    let output = command.assert().success().get_output().stdout.clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json[0]["symbology"], "aztec");
+    assert_eq!(json[0]["version"], 2);
+    assert_eq!(json[0]["error_correction"], "60%");
+    assert_eq!(json[0]["payload"], "TKT 0421 SEAT 12C");
+}
+
+#[test]
+fn should_decode_aztec_alongside_qr_code() {
+    let temp_dir = temp_dir();
+    let qr_path = temp_dir.path().join("qr.png");
+    let aztec_path = temp_dir.path().join("aztec.png");
+    for (content, symbology, path) in [
+        ("https://example.com/trip/42", "qr", &qr_path),
+        ("TKT 0421 SEAT 12C", "aztec", &aztec_path),
+    ] {
+        let mut command = cmd();
+        command
+            .arg("encode")
+            .arg(content)
+            .args(["--symbology", symbology, "-o"])
+            .arg(path);
+        command.assert().success();
+    }
+
+    // The Aztec symbol touches the QR code's quiet zone
+    let mut ticket = image::GrayImage::from_pixel(400, 200, image::Luma([255]));
+    image::imageops::overlay(
+        &mut ticket,
+        &image::open(&qr_path).unwrap().to_luma8(),
+        0,
+        0,
+    );
+    image::imageops::overlay(
+        &mut ticket,
+        &image::open(&aztec_path).unwrap().to_luma8(),
+        200,
+        0,
+    );
+    let ticket_path = temp_dir.path().join("ticket.png");
+    ticket.save(&ticket_path).unwrap();
+
+    let mut command = cmd();
+    command.arg("decode").arg(&ticket_path);
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("https://example.com/trip/42"))
+        .stdout(predicate::str::contains("TKT 0421 SEAT 12C"));
+}
+
+#[test]
+fn should_reject_unsupported_aztec_settings() {
+    for (symbology, args) in [
+        ("aztec", &["--mask", "0"][..]),
+        ("aztec", &["--version", "37"]),
+        ("aztec", &["--ec-percent", "99"]),
+        ("qr", &["--ec-percent", "50"]),
+    ] {
+        let mut command = cmd();
+        command
+            .arg("encode")
+            .arg("1")
+            .args(["--symbology", symbology])
+            .args(args)
+            .arg("-t");
+        command
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Aztec").or(predicate::str::contains("percentage")));
+    }
+}
+
+#[test]
+fn should_output_to_terminal() {
+    let mut command = cmd();
+    command.arg("encode").arg("Terminal output test").arg("-t");
+
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Terminal output test"));
+}
+
+/// Turn half-block terminal output back into an image, dark where drawn
+fn half_blocks_to_image(output: &str, invert: bool) -> image::DynamicImage {
+    let lines: Vec<Vec<char>> = output
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .map(|line| line.chars().collect())
+        .collect();
+    let width = lines[0].len() as u32;
+    let height = 2 * lines.len() as u32;
+    let scale = 8;
+    let image = image::GrayImage::from_fn(width * scale, height * scale, |x, y| {
+        let (column, row) = ((x / scale) as usize, (y / scale) as usize);
+        let cell = lines[row / 2][column];
+        let drawn = match cell {
+            '█' => true,
+            '▀' => row % 2 == 0,
+            '▄' => row % 2 == 1,
+            _ => false,
+        };
+        image::Luma([if drawn != invert { 0 } else { 255 }])
+    });
+    image::DynamicImage::ImageLuma8(image)
+}
+
+#[test]
+fn should_render_scannable_half_block_terminal_output() {
+    let decoder = QrDecoder::new();
+    for invert in [false, true] {
+        let mut command = cmd();
+        command
+            .env_remove("COLUMNS")
+            .arg("encode")
+            .arg("https://example.com/terminal")
+            .arg("-t");
+        if invert {
+            command.arg("--invert");
+        }
+        let output = command.assert().success().get_output().stdout.clone();
+        let output = String::from_utf8(output).unwrap();
+
+        let image = half_blocks_to_image(&output, invert);
+        assert_eq!(
+            decoder.decode_from_image(image).unwrap(),
+            vec!["https://example.com/terminal"]
+        );
+    }
+}
+
+#[test]
+fn should_downgrade_terminal_mode_to_fit_width() {
+    let mut command = cmd();
+    command
+        .env("COLUMNS", "40")
+        .arg("encode")
+        .arg("fit")
+        .arg("-t")
+        .arg("--terminal-mode")
+        .arg("full");
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("▀"))
+        .stderr(predicate::str::contains("using half mode"));
+}
+
+#[test]
+fn should_fail_when_terminal_is_too_narrow() {
+    let mut command = cmd();
+    command
+        .env("COLUMNS", "20")
+        .arg("encode")
+        .arg("narrow")
+        .arg("-t");
+    command
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("TerminalTooNarrow"));
+}
+
+#[test]
+fn should_require_terminal_for_invert() {
+    let mut command = cmd();
+    command.arg("encode").arg("Test").arg("--invert");
+    command.assert().failure();
+}
+
+/// Encode a small fixed code with a terminal graphics protocol
+fn terminal_graphics_output(protocol: &str) -> Vec<u8> {
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("rqr")
+        .arg("-t")
+        .arg("--terminal-graphics")
+        .arg(protocol)
+        .arg("--size")
+        .arg("58")
+        .arg("--margin")
+        .arg("4");
+    command.assert().success().get_output().stdout.clone()
+}
+
+#[test]
+fn should_emit_sixel_graphics() {
+    assert_snapshot(
+        "terminal_graphics_sixel.snap",
+        &terminal_graphics_output("sixel"),
+    );
+}
+
+/// Split the base64 PNG out of terminal graphics output
+///
+/// The compressed bytes depend on the deflate backend, so snapshots hold
+/// the escape-sequence framing with `<png>` in place of the payload, and
+/// the image itself is checked by decoding it.
+fn split_png_payload(output: &[u8]) -> (String, image::DynamicImage, usize) {
+    let text = String::from_utf8(output.to_vec()).unwrap();
+    let start = text.find("iVBORw0KGgo").unwrap();
+    let end = start
+        + text[start..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || "+/=".contains(c)))
+            .unwrap();
+    let png = base64::engine::general_purpose::STANDARD
+        .decode(&text[start..end])
+        .unwrap();
+    let framing = format!("{}<png>{}", &text[..start], &text[end..]);
+    (framing, image::load_from_memory(&png).unwrap(), png.len())
+}
+
+/// Check that a terminal graphics payload is the PNG rendering of the code
+fn assert_rqr_png(image: image::DynamicImage) {
+    assert_eq!((image.width(), image.height()), (58, 58));
+    let decoder = QrDecoder::new();
+    assert_eq!(decoder.decode_from_image(image).unwrap(), vec!["rqr"]);
+}
+
+#[test]
+fn should_emit_kitty_graphics() {
+    let (framing, image, _) = split_png_payload(&terminal_graphics_output("kitty"));
+    assert_snapshot("terminal_graphics_kitty.snap", framing.as_bytes());
+    assert_rqr_png(image);
+}
+
+#[test]
+fn should_emit_iterm_graphics() {
+    let (framing, image, len) = split_png_payload(&terminal_graphics_output("iterm"));
+    // The declared size must match the payload
+    let framing = framing.replacen(&format!("size={};", len), "size=<png length>;", 1);
+    assert_snapshot("terminal_graphics_iterm.snap", framing.as_bytes());
+    assert_rqr_png(image);
+}
+
+#[test]
+fn should_reject_terminal_graphics_with_invert() {
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("Test")
+        .arg("-t")
+        .arg("--terminal-graphics")
+        .arg("kitty")
+        .arg("--invert");
+    command.assert().failure();
+}
+
+#[test]
+fn should_fail_with_invalid_error_correction() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("invalid.png");
+
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("Test")
+        .arg("-o")
+        .arg(&output_path)
+        .arg("-e")
+        .arg("X");
+
+    command.assert().failure();
+}
+
+#[test]
+fn should_fail_when_file_not_found() {
+    let mut command = cmd();
+    command.arg("decode").arg("/nonexistent/path/file.png");
+
+    command.assert().failure();
+}
+
+#[test]
+fn should_fail_with_invalid_image_file() {
+    let temp_dir = temp_dir();
+    let invalid_file = temp_dir.path().join("not_an_image.txt");
+    fs::write(&invalid_file, "This is not an image").unwrap();
+
+    let mut command = cmd();
+    command.arg("decode").arg(&invalid_file);
+
+    command.assert().failure();
+}
+
+#[test]
+fn should_encode_unicode_content() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("unicode.png");
+
+    // Encode
+    let mut encode_cmd = cmd();
+    encode_cmd
+        .arg("encode")
+        .arg(fixtures::UNICODE_CONTENT)
+        .arg("-o")
+        .arg(&output_path);
+    encode_cmd.assert().success();
 
     // Decode and verify
     let mut decode_cmd = cmd();
@@ -486,5 +1450,1168 @@ fn should_encode_content_with_tabs() {
     decode_cmd
         .assert()
         .success()
-        .stdout(predicate::str::contains("Column1"));
+        .stdout(predicate::str::contains(fixtures::UNICODE_CONTENT));
+}
+
+#[test]
+fn should_encode_url_content() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("url.png");
+
+    // Encode
+    let mut encode_cmd = cmd();
+    encode_cmd
+        .arg("encode")
+        .arg(fixtures::URL_CONTENT)
+        .arg("-o")
+        .arg(&output_path);
+    encode_cmd.assert().success();
+
+    // Decode and verify
+    let mut decode_cmd = cmd();
+    decode_cmd.arg("decode").arg(&output_path);
+    decode_cmd
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(fixtures::URL_CONTENT));
+}
+
+#[test]
+fn should_encode_long_content() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("long.png");
+
+    let long_content = "a".repeat(500);
+
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg(&long_content)
+        .arg("-o")
+        .arg(&output_path)
+        .arg("-s")
+        .arg("400");
+
+    command.assert().success();
+    assert!(output_path.exists());
+}
+
+#[test]
+fn should_encode_special_characters() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("special.png");
+
+    // Encode
+    let mut encode_cmd = cmd();
+    encode_cmd
+        .arg("encode")
+        .arg(fixtures::SPECIAL_CHARS)
+        .arg("-o")
+        .arg(&output_path);
+    encode_cmd.assert().success();
+
+    // Decode and verify
+    let mut decode_cmd = cmd();
+    decode_cmd.arg("decode").arg(&output_path);
+    decode_cmd
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(fixtures::SPECIAL_CHARS));
+}
+
+#[test]
+fn should_encode_empty_content() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("empty.png");
+
+    let mut command = cmd();
+    command.arg("encode").arg("").arg("-o").arg(&output_path);
+
+    command.assert().success();
+    assert!(output_path.exists());
+}
+
+#[test]
+fn should_complete_multiple_encode_decode_cycles() {
+    let temp_dir = temp_dir();
+
+    for i in 0..5 {
+        let output_path = temp_dir.path().join(format!("cycle_{}.png", i));
+        let content = format!("Cycle number {}", i);
+
+        // Encode
+        let mut encode_cmd = cmd();
+        encode_cmd
+            .arg("encode")
+            .arg(&content)
+            .arg("-o")
+            .arg(&output_path);
+        encode_cmd.assert().success();
+
+        // Decode
+        let mut decode_cmd = cmd();
+        decode_cmd.arg("decode").arg(&output_path);
+        decode_cmd
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(&content));
+    }
+}
+
+#[test]
+fn should_encode_different_margins() {
+    let temp_dir = temp_dir();
+
+    for margin in [0, 5, 10, 20] {
+        let output_path = temp_dir.path().join(format!("margin_{}.png", margin));
+
+        let mut command = cmd();
+        command
+            .arg("encode")
+            .arg("Margin test")
+            .arg("-o")
+            .arg(&output_path)
+            .arg("-m")
+            .arg(margin.to_string());
+
+        command.assert().success();
+        assert!(output_path.exists(), "Failed with margin {}", margin);
+    }
+}
+
+#[test]
+fn should_fail_when_no_qr_code_in_image() {
+    let temp_dir = temp_dir();
+    let blank_image = temp_dir.path().join("blank.png");
+
+    // Create a blank image
+    let img = image::ImageBuffer::from_pixel(100, 100, image::Luma([255u8]));
+    img.save(&blank_image).unwrap();
+
+    let mut command = cmd();
+    command.arg("decode").arg(&blank_image);
+
+    command.assert().failure();
+}
+
+#[test]
+fn should_overwrite_existing_file() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("overwrite.png");
+
+    // First encode
+    let mut command1 = cmd();
+    command1
+        .arg("encode")
+        .arg("First content")
+        .arg("-o")
+        .arg(&output_path);
+    command1.assert().success();
+
+    let first_modified = fs::metadata(&output_path).unwrap().modified().unwrap();
+
+    // Second encode (overwrite)
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    let mut command2 = cmd();
+    command2
+        .arg("encode")
+        .arg("Second content")
+        .arg("-o")
+        .arg(&output_path);
+    command2.assert().success();
+
+    let second_modified = fs::metadata(&output_path).unwrap().modified().unwrap();
+
+    // File should have been modified
+    assert!(second_modified >= first_modified);
+
+    // Verify new content
+    let mut decode_cmd = cmd();
+    decode_cmd.arg("decode").arg(&output_path);
+    decode_cmd
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Second content"));
+}
+
+#[test]
+fn should_fail_when_missing_subcommand() {
+    let mut command = cmd();
+    command.assert().failure();
+}
+
+#[test]
+fn should_encode_with_relative_path() {
+    let temp_dir = temp_dir();
+    let original_dir = std::env::current_dir().unwrap();
+
+    // Change to temp directory
+    std::env::set_current_dir(&temp_dir).unwrap();
+
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("Relative path test")
+        .arg("-o")
+        .arg("relative.png");
+    command.assert().success();
+
+    assert!(temp_dir.path().join("relative.png").exists());
+
+    // Restore original directory
+    std::env::set_current_dir(original_dir).unwrap();
+}
+
+#[test]
+fn should_decode_with_relative_path() {
+    let temp_dir = temp_dir();
+    let original_dir = std::env::current_dir().unwrap();
+
+    // Create QR code
+    let output_path = temp_dir.path().join("for_decode.png");
+    create_qr_image_file(&output_path, "Decode relative");
+
+    // Change to temp directory
+    std::env::set_current_dir(&temp_dir).unwrap();
+
+    // Decode using relative path
+    let mut decode_cmd = cmd();
+    decode_cmd.arg("decode").arg("for_decode.png");
+    decode_cmd
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Decode relative"));
+
+    // Restore original directory
+    std::env::set_current_dir(original_dir).unwrap();
+}
+
+#[test]
+fn should_encode_multiline_content() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("multiline.png");
+
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg(fixtures::MULTILINE_CONTENT)
+        .arg("-o")
+        .arg(&output_path);
+    command.assert().success();
+
+    // Decode and verify
+    let mut decode_cmd = cmd();
+    decode_cmd.arg("decode").arg(&output_path);
+    decode_cmd
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Line 1"));
+}
+
+#[test]
+fn should_encode_content_with_tabs() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("tabs.png");
+
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg(fixtures::TAB_CONTENT)
+        .arg("-o")
+        .arg(&output_path);
+    command.assert().success();
+
+    // Decode and verify
+    let mut decode_cmd = cmd();
+    decode_cmd.arg("decode").arg(&output_path);
+    decode_cmd
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Column1"));
+}
+
+#[test]
+fn should_print_only_payload_with_raw_format() {
+    let temp_dir = temp_dir();
+    let image_path = temp_dir.path().join("raw.png");
+    create_qr_image_file(&image_path, "Scriptable payload");
+
+    let mut decode_cmd = cmd();
+    decode_cmd
+        .arg("decode")
+        .arg(&image_path)
+        .arg("--format")
+        .arg("raw");
+    decode_cmd
+        .assert()
+        .success()
+        .stdout("Scriptable payload")
+        .stderr(predicate::str::contains("Decoding QR code from"));
+}
+
+#[test]
+fn should_roundtrip_binary_through_raw_format() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("raw_binary.png");
+    let data = vec![0x00, 0x0a, 0xff, 0x80, 0x0d];
+
+    let mut encode_cmd = cmd();
+    encode_cmd
+        .arg("encode")
+        .arg("-")
+        .arg("--binary")
+        .arg("-o")
+        .arg(&output_path)
+        .write_stdin(data.clone());
+    encode_cmd.assert().success();
+
+    let mut decode_cmd = cmd();
+    decode_cmd
+        .arg("decode")
+        .arg(&output_path)
+        .arg("--format")
+        .arg("raw");
+    decode_cmd.assert().success().stdout(data);
+}
+
+#[test]
+fn should_decode_as_json() {
+    let temp_dir = temp_dir();
+    let image_path = temp_dir.path().join("json.png");
+    create_qr_image_file(&image_path, "JSON payload");
+
+    let mut decode_cmd = cmd();
+    decode_cmd
+        .arg("decode")
+        .arg(&image_path)
+        .arg("--format")
+        .arg("json");
+    let output = decode_cmd.assert().success().get_output().stdout.clone();
+
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let records = json.as_array().unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["payload"], "JSON payload");
+    assert_eq!(records[0]["encoding"], "utf-8");
+    assert_eq!(records[0]["error_correction"], "M");
+    assert_eq!(records[0]["source"], image_path.to_str().unwrap());
+}
+
+#[test]
+fn should_decode_as_ndjson() {
+    let temp_dir = temp_dir();
+    let image_path = temp_dir.path().join("ndjson.png");
+    create_qr_image_file(&image_path, "NDJSON payload");
+
+    let mut decode_cmd = cmd();
+    decode_cmd
+        .arg("decode")
+        .arg(&image_path)
+        .arg("-f")
+        .arg("ndjson");
+    let output = decode_cmd.assert().success().get_output().stdout.clone();
+
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 1);
+    let record: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+    assert_eq!(record["payload"], "NDJSON payload");
+}
+
+#[test]
+fn should_fail_with_invalid_decode_format() {
+    let mut command = cmd();
+    command
+        .arg("decode")
+        .arg("qr.png")
+        .arg("--format")
+        .arg("yaml");
+    command
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("text, raw, json or ndjson"));
+}
+
+#[test]
+fn should_decode_from_http_url() {
+    let temp_dir = temp_dir();
+    let image_path = temp_dir.path().join("served.png");
+    create_qr_image_file(&image_path, "Fetched in process");
+    let png = fs::read(&image_path).unwrap();
+
+    let server = serve_http(vec![HttpRoute::ok("/qr.png", "image/png", &png)]);
+
+    let mut decode_cmd = cmd();
+    decode_cmd
+        .arg("decode")
+        .arg(server.url("/qr.png"))
+        .arg("--format")
+        .arg("raw");
+    decode_cmd.assert().success().stdout("Fetched in process");
+}
+
+#[test]
+fn should_fail_with_http_error_status() {
+    let server = serve_http(vec![]);
+
+    let mut decode_cmd = cmd();
+    decode_cmd.arg("decode").arg(server.url("/missing.png"));
+    decode_cmd
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("HttpStatus").and(predicate::str::contains("404")));
+}
+
+#[test]
+fn should_fetch_image_body() {
+    let server = serve_http(vec![HttpRoute::ok("/qr.png", "image/png", b"PNGDATA")]);
+
+    let body = HttpFetcher::new()
+        .fetch_image(&server.url("/qr.png"))
+        .unwrap();
+    assert_eq!(body, b"PNGDATA");
+}
+
+#[test]
+fn should_fetch_octet_stream_and_parameterised_types() {
+    let server = serve_http(vec![
+        HttpRoute::ok("/a", "application/octet-stream", b"A"),
+        HttpRoute::ok("/b", "image/png; charset=binary", b"B"),
+    ]);
+
+    let fetcher = HttpFetcher::new();
+    assert_eq!(fetcher.fetch_image(&server.url("/a")).unwrap(), b"A");
+    assert_eq!(fetcher.fetch_image(&server.url("/b")).unwrap(), b"B");
+}
+
+#[test]
+fn should_reject_html_when_fetching_image() {
+    let server = serve_http(vec![HttpRoute::ok(
+        "/page",
+        "text/html; charset=utf-8",
+        b"<html></html>",
+    )]);
+
+    let err = HttpFetcher::new()
+        .fetch_image(&server.url("/page"))
+        .unwrap_err();
+    assert!(matches!(err, RqrError::NotAnImage(ref t) if t.starts_with("text/html")));
+}
+
+#[test]
+fn should_fail_fetch_with_http_status() {
+    let server = serve_http(vec![]);
+
+    let err = HttpFetcher::new()
+        .fetch_image(&server.url("/missing.png"))
+        .unwrap_err();
+    assert!(matches!(err, RqrError::HttpStatus { status: 404, .. }));
+}
+
+#[test]
+fn should_fail_fetch_when_too_large() {
+    let server = serve_http(vec![HttpRoute::ok("/big.png", "image/png", &[0; 4096])]);
+
+    let err = HttpFetcher::new()
+        .with_max_size(1024)
+        .fetch_image(&server.url("/big.png"))
+        .unwrap_err();
+    assert!(matches!(err, RqrError::DownloadTooLarge(1024)));
+}
+
+#[test]
+fn should_follow_redirects_when_fetching_image() {
+    let server = serve_http(vec![
+        HttpRoute::redirect("/old.png", "/new.png"),
+        HttpRoute::ok("/new.png", "image/png", b"NEW"),
+    ]);
+
+    let body = HttpFetcher::new()
+        .fetch_image(&server.url("/old.png"))
+        .unwrap();
+    assert_eq!(body, b"NEW");
+}
+
+#[test]
+fn should_fail_fetch_after_too_many_redirects() {
+    let server = serve_http(vec![HttpRoute::redirect("/loop", "/loop")]);
+
+    let err = HttpFetcher::new()
+        .with_max_redirects(3)
+        .fetch_image(&server.url("/loop"))
+        .unwrap_err();
+    assert!(matches!(err, RqrError::TooManyRedirects(3)));
+}
+
+#[test]
+fn should_time_out_slow_fetch() {
+    let server = serve_http(vec![
+        HttpRoute::ok("/slow.png", "image/png", b"SLOW").with_delay(Duration::from_secs(2)),
+    ]);
+
+    let err = HttpFetcher::new()
+        .with_timeout(Duration::from_millis(200))
+        .fetch_image(&server.url("/slow.png"))
+        .unwrap_err();
+    assert!(matches!(err, RqrError::Timeout(_)));
+}
+
+#[test]
+fn should_decode_library_url() {
+    let temp_dir = temp_dir();
+    let image_path = temp_dir.path().join("served.png");
+    create_qr_image_file(&image_path, "Served over HTTP");
+    let png = fs::read(&image_path).unwrap();
+
+    let server = serve_http(vec![HttpRoute::ok("/qr.png", "image/png", &png)]);
+
+    let decoder = QrDecoder::new();
+    let results = decoder.decode_from_url(&server.url("/qr.png")).unwrap();
+    assert_eq!(results, vec!["Served over HTTP"]);
+}
+
+#[test]
+fn should_reject_library_url_without_image() {
+    let server = serve_http(vec![HttpRoute::ok("/", "text/html", b"<html></html>")]);
+
+    let decoder = QrDecoder::new();
+    let err = decoder.decode_from_url(&server.url("/")).unwrap_err();
+    assert!(matches!(err, RqrError::NotAnImage(_)));
+}
+
+#[test]
+fn should_decode_image_from_stdin() {
+    let temp_dir = temp_dir();
+    let image_path = temp_dir.path().join("piped.png");
+    create_qr_image_file(&image_path, "Piped screenshot");
+
+    let mut decode_cmd = cmd();
+    decode_cmd
+        .arg("decode")
+        .arg("-")
+        .arg("--format")
+        .arg("raw")
+        .write_stdin(fs::read(&image_path).unwrap());
+    decode_cmd
+        .assert()
+        .success()
+        .stdout("Piped screenshot")
+        .stderr(predicate::str::contains("Decoding QR code from: stdin"));
+}
+
+#[test]
+fn should_fail_with_empty_stdin() {
+    let mut decode_cmd = cmd();
+    decode_cmd.arg("decode").arg("-").write_stdin(Vec::new());
+    decode_cmd
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No image data on stdin"));
+}
+
+#[test]
+fn should_decode_data_uri() {
+    use base64::Engine;
+
+    let temp_dir = temp_dir();
+    let image_path = temp_dir.path().join("inline.png");
+    create_qr_image_file(&image_path, "Inline data");
+    let encoded = base64::engine::general_purpose::STANDARD.encode(fs::read(&image_path).unwrap());
+
+    let mut decode_cmd = cmd();
+    decode_cmd
+        .arg("decode")
+        .arg(format!("data:image/png;base64,{}", encoded))
+        .arg("--format")
+        .arg("json");
+    let output = decode_cmd.assert().success().get_output().stdout.clone();
+
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json[0]["payload"], "Inline data");
+    assert!(
+        json[0]["source"]
+            .as_str()
+            .unwrap()
+            .starts_with("data URI (")
+    );
+}
+
+#[test]
+fn should_decode_file_url() {
+    let temp_dir = temp_dir();
+    let image_path = temp_dir.path().join("file url.png");
+    create_qr_image_file(&image_path, "From a file URL");
+
+    let url = format!(
+        "file://{}",
+        image_path.to_str().unwrap().replace(' ', "%20")
+    );
+    let mut decode_cmd = cmd();
+    decode_cmd.arg("decode").arg(url).arg("--format").arg("raw");
+    decode_cmd.assert().success().stdout("From a file URL");
+}
+
+#[test]
+fn should_batch_decode_directory_with_summary() {
+    let temp_dir = temp_dir();
+    create_qr_image_file(&temp_dir.path().join("one.png"), "Photo one");
+    create_qr_image_file(&temp_dir.path().join("two.png"), "Photo two");
+    fs::create_dir(temp_dir.path().join("nested")).unwrap();
+    create_qr_image_file(
+        &temp_dir.path().join("nested").join("three.png"),
+        "Photo three",
+    );
+
+    let mut decode_cmd = cmd();
+    decode_cmd.arg("decode").arg(temp_dir.path());
+    decode_cmd
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("one.png <==").and(predicate::str::contains("Photo two")))
+        .stdout(predicate::str::contains("Photo three").not())
+        .stderr(predicate::str::contains(
+            "Scanned 2 files: 2 codes found, 0 failed",
+        ));
+
+    let mut decode_cmd = cmd();
+    decode_cmd
+        .arg("decode")
+        .arg(temp_dir.path())
+        .arg("--recursive");
+    decode_cmd
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Photo three"))
+        .stderr(predicate::str::contains(
+            "Scanned 3 files: 3 codes found, 0 failed",
+        ));
+}
+
+#[test]
+fn should_keep_going_and_report_failures_in_batch_json() {
+    let temp_dir = temp_dir();
+    create_qr_image_file(&temp_dir.path().join("a_good.png"), "Readable");
+    fs::write(temp_dir.path().join("b_broken.png"), "not an image").unwrap();
+    create_qr_image_file(&temp_dir.path().join("c_good.png"), "Also readable");
+
+    let pattern = temp_dir.path().join("*.png");
+    let mut decode_cmd = cmd();
+    decode_cmd
+        .arg("decode")
+        .arg(pattern.to_str().unwrap())
+        .arg("--format")
+        .arg("json");
+    let assert = decode_cmd
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to decode"))
+        .stderr(predicate::str::contains(
+            "Scanned 3 files: 2 codes found, 1 failed",
+        ));
+
+    // Fields keep the order of single-file output
+    let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    let field = |name: &str| stdout.find(&format!("\"{}\"", name)).unwrap();
+    assert!(field("source") < field("payload") && field("payload") < field("encoding"));
+
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let records = json.as_array().unwrap();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0]["payload"], "Readable");
+    assert!(
+        records[1]["source"]
+            .as_str()
+            .unwrap()
+            .ends_with("b_broken.png")
+    );
+    assert!(records[1]["error"].is_string());
+    assert_eq!(records[2]["payload"], "Also readable");
+}
+
+#[test]
+fn should_batch_decode_multiple_inputs_as_ndjson() {
+    let temp_dir = temp_dir();
+    let first = temp_dir.path().join("first.png");
+    let second = temp_dir.path().join("second.png");
+    create_qr_image_file(&first, "First input");
+    create_qr_image_file(&second, "Second input");
+
+    let mut decode_cmd = cmd();
+    decode_cmd
+        .arg("decode")
+        .arg(&first)
+        .arg(&second)
+        .arg("-f")
+        .arg("ndjson");
+    let output = decode_cmd.assert().success().get_output().stdout.clone();
+
+    let output = String::from_utf8(output).unwrap();
+    let payloads: Vec<String> = output
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["payload"].to_string())
+        .collect();
+    assert_eq!(payloads, vec!["\"First input\"", "\"Second input\""]);
+}
+
+#[test]
+fn should_fail_when_glob_matches_nothing() {
+    let temp_dir = temp_dir();
+    let pattern = temp_dir.path().join("*.png");
+
+    let mut decode_cmd = cmd();
+    decode_cmd.arg("decode").arg(pattern.to_str().unwrap());
+    decode_cmd
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No files match"));
+}
+
+#[test]
+fn should_report_unexpandable_inputs_and_keep_going() {
+    let temp_dir = temp_dir();
+    let image_path = temp_dir.path().join("good.png");
+    create_qr_image_file(&image_path, "Still decoded");
+    let pattern = temp_dir.path().join("*.gif");
+
+    let mut decode_cmd = cmd();
+    decode_cmd
+        .arg("decode")
+        .arg(pattern.to_str().unwrap())
+        .arg(&image_path)
+        .arg("-f")
+        .arg("ndjson");
+    let assert = decode_cmd
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No files match"))
+        .stderr(predicate::str::contains(
+            "Scanned 2 files: 1 codes found, 1 failed",
+        ));
+
+    let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    let records: Vec<serde_json::Value> = output
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0]["source"], pattern.to_str().unwrap());
+    assert!(records[0]["error"].is_string());
+    assert_eq!(records[1]["payload"], "Still decoded");
+}
+
+#[test]
+fn should_batch_encode_csv_with_templated_outputs() {
+    let temp_dir = temp_dir();
+    let items = temp_dir.path().join("items.csv");
+    fs::write(
+        &items,
+        "sku,url\nA-1,https://example.com/a\nB-2,https://example.com/b\nC-3,https://example.com/c\n",
+    )
+    .unwrap();
+    let output = temp_dir.path().join("out").join("{sku}.svg");
+
+    let mut encode_cmd = cmd();
+    encode_cmd
+        .arg("encode")
+        .arg("--batch")
+        .arg(&items)
+        .arg("--content")
+        .arg("{url}")
+        .arg("--output")
+        .arg(&output);
+    encode_cmd
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Encoded 3 of 3 rows"));
+
+    let svg = fs::read_to_string(temp_dir.path().join("out").join("B-2.svg")).unwrap();
+    let png = rasterize_svg(&svg);
+    let decoder = QrDecoder::new();
+    assert_eq!(
+        decoder.decode_from_image(png).unwrap(),
+        vec!["https://example.com/b"]
+    );
+
+    let manifest = fs::read_to_string(temp_dir.path().join("out").join("manifest.csv")).unwrap();
+    assert_eq!(manifest.lines().count(), 4);
+    assert!(manifest.contains("3,ok,"));
+}
+
+#[test]
+fn should_batch_encode_jsonl_and_report_failed_rows() {
+    let temp_dir = temp_dir();
+    let items = temp_dir.path().join("items.jsonl");
+    fs::write(
+        &items,
+        "{\"id\": 1, \"url\": \"one\"}\n{\"id\": 2}\n{\"id\": 1, \"url\": \"dup\"}\n",
+    )
+    .unwrap();
+    let manifest = temp_dir.path().join("manifest.jsonl");
+
+    let mut encode_cmd = cmd();
+    encode_cmd
+        .arg("encode")
+        .arg("--batch")
+        .arg(&items)
+        .arg("--content")
+        .arg("{url}")
+        .arg("--manifest")
+        .arg(&manifest)
+        .arg("--output")
+        .arg(temp_dir.path().join("tag-{id}.png"));
+    encode_cmd
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Encoded 1 of 3 rows"))
+        .stderr(predicate::str::contains(
+            "Row 2: Invalid input: Row has no column 'url'",
+        ))
+        .stderr(predicate::str::contains("also written by row 1"));
+
+    assert!(temp_dir.path().join("tag-1.png").exists());
+    let statuses: Vec<String> = fs::read_to_string(&manifest)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let entry: serde_json::Value = serde_json::from_str(line).unwrap();
+            entry["status"].as_str().unwrap().to_string()
+        })
+        .collect();
+    assert_eq!(statuses, vec!["ok", "error", "error"]);
+}
+
+#[test]
+fn should_require_content_template_with_batch() {
+    let mut encode_cmd = cmd();
+    encode_cmd.arg("encode").arg("--batch").arg("items.csv");
+    encode_cmd
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--content"));
+}
+
+#[test]
+fn should_encode_and_decode_pdf417() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("licence.png");
+
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("DL 4412-889-031 EXP 2031-08-14")
+        .args(["--symbology", "pdf417", "--columns", "4", "--rows", "12"])
+        .args(["--security-level", "3", "--size", "600", "-o"])
+        .arg(&output_path);
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Error correction: security level 3",
+        ))
+        .stdout(predicate::str::contains(
+            "Version: 12x4 (rows x data columns, 137x36 modules)",
+        ))
+        .stdout(predicate::str::contains("Margin: 2 modules"));
+
+    // The rendering keeps the symbol's wide rectangular shape
+    let image = image::open(&output_path).unwrap();
+    assert_eq!(image.width(), 600);
+    assert!(image.height() < 300);
+
+    let mut command = cmd();
+    command
+        .arg("decode")
+        .arg(&output_path)
+        .arg("--format")
+        .arg("json");
+    let output = command.assert().success().get_output().stdout.clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json[0]["symbology"], "pdf417");
+    assert_eq!(json[0]["version"], 4);
+    assert_eq!(json[0]["error_correction"], "level 3");
+    assert_eq!(json[0]["payload"], "DL 4412-889-031 EXP 2031-08-14");
+}
+
+#[test]
+fn should_round_trip_binary_pdf417() {
+    let temp_dir = temp_dir();
+    let input_path = temp_dir.path().join("label.bin");
+    let output_path = temp_dir.path().join("label.png");
+    let data: Vec<u8> = (0..=255).rev().collect();
+    fs::write(&input_path, &data).unwrap();
+
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("--input-file")
+        .arg(&input_path)
+        .args(["--binary", "--symbology", "pdf417", "--size", "800", "-o"])
+        .arg(&output_path);
+    command.assert().success();
+
+    let mut command = cmd();
+    command
+        .arg("decode")
+        .arg(&output_path)
+        .arg("--format")
+        .arg("raw");
+    let output = command.assert().success().get_output().stdout.clone();
+    assert_eq!(output, data);
+}
+
+#[test]
+fn should_reject_unsupported_pdf417_settings() {
+    for (symbology, args) in [
+        ("pdf417", &["--mask", "0"][..]),
+        ("pdf417", &["--version", "3"]),
+        ("pdf417", &["--columns", "31"]),
+        ("pdf417", &["--rows", "2"]),
+        ("pdf417", &["--security-level", "9"]),
+        ("qr", &["--columns", "4"]),
+    ] {
+        let mut command = cmd();
+        command
+            .arg("encode")
+            .arg("1")
+            .args(["--symbology", symbology])
+            .args(args)
+            .arg("-t");
+        command
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("PDF417").or(predicate::str::contains("between")));
+    }
+}
+
+#[test]
+fn should_encode_and_decode_code128() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("parcel.png");
+
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("PJJ123C")
+        .args(["--symbology", "code128", "--size", "400", "-o"])
+        .arg(&output_path);
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Code 128 saved to:"))
+        .stdout(predicate::str::contains("Error correction: none"))
+        .stdout(predicate::str::contains(
+            "Version: none (112x59 modules, text included)",
+        ))
+        .stdout(predicate::str::contains("Margin: 10 modules"));
+
+    let mut command = cmd();
+    command
+        .arg("decode")
+        .arg(&output_path)
+        .arg("--format")
+        .arg("json");
+    let output = command.assert().success().get_output().stdout.clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json[0]["symbology"], "code128");
+    assert_eq!(json[0]["error_correction"], "none");
+    assert_eq!(json[0]["payload"], "PJJ123C");
+}
+
+#[test]
+fn should_encode_and_decode_retail_and_industrial_barcodes() {
+    let temp_dir = temp_dir();
+    for (symbology, content, expected) in [
+        ("ean13", "400638133393", "4006381333931"),
+        ("upca", "03600029145", "036000291452"),
+        ("code39", "CODE-39 $", "CODE-39 $"),
+        ("itf", "00012345678905", "00012345678905"),
+    ] {
+        let output_path = temp_dir.path().join(format!("{}.png", symbology));
+        let mut command = cmd();
+        command
+            .arg("encode")
+            .arg(content)
+            .args(["--symbology", symbology, "--size", "400", "-o"])
+            .arg(&output_path);
+        command.assert().success();
+
+        let mut command = cmd();
+        command
+            .arg("decode")
+            .arg(&output_path)
+            .arg("--format")
+            .arg("json");
+        let output = command.assert().success().get_output().stdout.clone();
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(json[0]["symbology"], symbology);
+        assert_eq!(json[0]["payload"], expected);
+    }
+}
+
+#[test]
+fn should_filter_decode_by_symbology() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("code.png");
+
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("4006381333931")
+        .args(["--symbology", "ean13", "-o"])
+        .arg(&output_path);
+    command.assert().success();
+
+    let mut command = cmd();
+    command
+        .arg("decode")
+        .arg(&output_path)
+        .args(["--symbology", "ean13"]);
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("4006381333931"));
+
+    let mut command = cmd();
+    command
+        .arg("decode")
+        .arg(&output_path)
+        .args(["--symbology", "qr"]);
+    command
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No qr codes found in image"));
+}
+
+#[test]
+fn should_reject_invalid_barcode_content() {
+    for (symbology, content, message) in [
+        ("ean13", "4006381333932", "check digit should be 1, got 2"),
+        ("upca", "12345", "takes 12 digits"),
+        ("code39", "lower", "Code 39 cannot encode 'l'"),
+        ("itf", "123", "even number of digits"),
+        ("code128", "café", "only encodes ASCII"),
+    ] {
+        let mut command = cmd();
+        command
+            .arg("encode")
+            .arg(content)
+            .args(["--symbology", symbology])
+            .arg("-t");
+        command
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(message));
+    }
+
+    let mut command = cmd();
+    command
+        .arg("encode")
+        .arg("12345678")
+        .args(["--symbology", "itf", "--version", "2", "-t"]);
+    command
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("1D barcodes have no versions"));
+}
+
+#[test]
+fn should_encode_wifi_network_and_decode_it_redacted() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("wifi.png");
+
+    let mut command = cmd();
+    command
+        .args(["wifi", "--ssid", "Office;2F", "--password"])
+        .arg("pa:ss,word;1")
+        .args(["--auth", "WPA3", "--hidden", "-o"])
+        .arg(&output_path);
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Content: WiFi network \"Office;2F\" (WPA3, hidden)",
+        ))
+        .stdout(predicate::str::contains("pa:ss").not());
+
+    let mut command = cmd();
+    command.arg("decode").arg(&output_path);
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("SSID: Office;2F"))
+        .stdout(predicate::str::contains("Security: WPA3"))
+        .stdout(predicate::str::contains("Password: ********"))
+        .stdout(predicate::str::contains("Hidden: yes"))
+        .stdout(predicate::str::contains("pa:ss").not());
+
+    let mut command = cmd();
+    command
+        .arg("decode")
+        .arg(&output_path)
+        .args(["--format", "json", "--show-secrets"]);
+    let output = command.assert().success().get_output().stdout.clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(
+        json[0]["payload"],
+        r"WIFI:T:SAE;S:Office\;2F;P:pa\:ss\,word\;1;H:true;;"
+    );
+    assert_eq!(json[0]["wifi"]["password"], "pa:ss,word;1");
+
+    let mut command = cmd();
+    command
+        .arg("decode")
+        .arg(&output_path)
+        .args(["--format", "raw"]);
+    command.assert().success().stdout(predicate::eq(
+        r"WIFI:T:SAE;S:Office\;2F;P:pa\:ss\,word\;1;H:true;;".as_bytes(),
+    ));
+}
+
+#[test]
+fn should_encode_the_word_wifi_as_plain_content() {
+    let temp_dir = temp_dir();
+    let output_path = temp_dir.path().join("word.png");
+
+    let mut command = cmd();
+    command
+        .args(["encode", "-s", "300", "wifi", "-o"])
+        .arg(&output_path);
+    command
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Content: wifi"));
+
+    let results = QrDecoder::new().decode_from_file(&output_path).unwrap();
+    assert_eq!(results, vec!["wifi".to_string()]);
+}
+
+#[test]
+fn should_reject_invalid_wifi_networks() {
+    for (args, message) in [
+        (&["--ssid", "Home", "--password", "short"][..], "8 to 63"),
+        (
+            &["--ssid", "Home", "--auth", "WPA"],
+            "WPA networks need a password",
+        ),
+        (
+            &[
+                "--ssid",
+                "Home",
+                "--auth",
+                "nopass",
+                "--password",
+                "password1",
+            ],
+            "have no password",
+        ),
+        (&["--ssid", "Home", "--auth", "WEP"], "WPA, WPA3 or nopass"),
+    ] {
+        let mut command = cmd();
+        command.arg("wifi").args(args).arg("-t");
+        command
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(message));
+    }
 }
@@ -55,6 +55,8 @@ proptest! {
         prop_assert_eq!(image.width(), image.height());
         // Image should have reasonable dimensions
         prop_assert!(image.width() > 0);
+        // Padding keeps the requested size exactly
+        prop_assert_eq!(image.width(), size);
     }
 
     /// Property: Various margins should work